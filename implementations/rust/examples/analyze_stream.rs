@@ -0,0 +1,87 @@
+// Consumes `analyze_stream` from async code. The engine only needs std, so this example drives
+// the future with a minimal thread-parking executor; under tokio the same `async` block runs as
+// a task once `AnalysisStream` is wrapped in a `futures::Stream` impl forwarding to `poll_next`.
+//
+//     cargo run --example analyze_stream -- "<fen>" [depth]
+
+use chess_engine::analysis::{analyze_stream, AnalysisStream, InfoEvent};
+use chess_engine::search_limits::SearchLimits;
+use std::future::{poll_fn, Future};
+use std::pin::{pin, Pin};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(Unpark(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+async fn next_event(stream: &mut AnalysisStream) -> Option<InfoEvent> {
+    poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let fen = args.first().map_or(START, String::as_str);
+    let depth = args
+        .get(1)
+        .and_then(|depth| depth.parse().ok())
+        .unwrap_or(5);
+
+    let mut stream = match analyze_stream(fen, SearchLimits::depth(depth)) {
+        Ok(stream) => stream,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+
+    block_on(async {
+        while let Some(event) = next_event(&mut stream).await {
+            match event {
+                InfoEvent::Iteration {
+                    depth,
+                    score,
+                    pv,
+                    nodes,
+                    nps,
+                    time_ms,
+                    ..
+                } => println!(
+                    "info depth {} score cp {} nodes {} nps {} time {} pv {}",
+                    depth,
+                    score,
+                    nodes,
+                    nps,
+                    time_ms,
+                    pv.iter()
+                        .map(|mv| mv.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                ),
+                InfoEvent::BestMove { best_move, .. } => println!(
+                    "bestmove {}",
+                    best_move.map_or("(none)".to_string(), |mv| mv.to_string())
+                ),
+            }
+        }
+    });
+}
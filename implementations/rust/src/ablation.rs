@@ -0,0 +1,85 @@
+use crate::ai::AI;
+use crate::board::Board;
+use crate::eval::EvalTerm;
+use crate::search_limits::SearchLimits;
+use crate::selfplay::{play_match, Adjudication, GameRecord, MatchScore, Player};
+
+pub const DEFAULT_ABLATION_NODES: u64 = 5000;
+
+// What one evaluation term is worth: the full evaluation's score against the same engine with
+// only that term switched off, so a positive Elo means the term helps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ablation {
+    pub term: EvalTerm,
+    pub score: MatchScore,
+}
+
+impl Ablation {
+    pub fn elo(&self) -> f64 {
+        self.score.elo()
+    }
+
+    pub fn error(&self) -> f64 {
+        self.score.error()
+    }
+}
+
+// Plays the match for one term. Both sides search a fixed number of nodes a move rather than a
+// time, so the result doesn't depend on the machine or its load, and a term that slows the
+// evaluation down is not charged for it. `engine` builds a fresh engine with the full
+// evaluation, configured as the caller's own.
+pub fn ablate<F>(
+    term: EvalTerm,
+    engine: &dyn Fn() -> AI,
+    games: u32,
+    nodes: u64,
+    openings: &[Board],
+    on_game: F,
+) -> Ablation
+where
+    F: FnMut(u32, &Board, &Player, &Player, &GameRecord),
+{
+    let limits = SearchLimits {
+        nodes: Some(nodes),
+        ..SearchLimits::default()
+    };
+    let mut ablated = engine();
+    ablated.set_eval_config(ablated.eval_config().without(term));
+    let mut full = Player::new("full", engine(), limits);
+    let mut without = Player::new(&format!("no-{}", term.name()), ablated, limits);
+    Ablation {
+        term,
+        score: play_match(
+            &mut full,
+            &mut without,
+            games,
+            openings,
+            &Adjudication::default(),
+            None,
+            on_game,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_the_full_evaluation_against_one_term_off() {
+        let mut players = Vec::new();
+        let ablation = ablate(
+            EvalTerm::Pawns,
+            &AI::new,
+            2,
+            200,
+            &[Board::new()],
+            |_, _, white, black, _| {
+                players.push(format!("{} - {}", white.name, black.name));
+            },
+        );
+        assert_eq!(players, vec!["full - no-pawns", "no-pawns - full"]);
+        assert_eq!(ablation.score.games(), 2);
+        assert!(ablation.elo().is_finite() && ablation.error() >= 0.0);
+    }
+}
@@ -1,208 +1,1004 @@
 use crate::types::*;
 use crate::board::Board;
+use crate::correction_history::CorrectionHistory;
+use crate::eval::{ClassicalEvaluator, EvalConfig, Evaluator};
 use crate::move_generator::MoveGenerator;
-use std::time::Instant;
+use crate::move_ordering::{MoveOrderer, OrderingScore};
+use crate::move_picker::MovePicker;
+use crate::options::Options;
+use crate::search_limits::{SearchLimits, SearchOverrides, MAX_SEARCH_DEPTH};
+use crate::search_tree::{NodeKind, SearchTree};
+use crate::time_manager::{nodes_per_second, GamePhase, IterationReport, TimeControl, TimeManager, TimePolicy, EASY_MOVE_ITERATIONS};
+use crate::transposition::{Bound, TranspositionTable, TtEntry};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use crate::timing::Instant;
+
+// A side mated `n` plies from the root scores `-(MATE_SCORE - n)`, so shorter mates score higher
+// and a position already checkmated on the board scores `-MATE_SCORE`.
+pub const MATE_SCORE: i32 = 100000;
+const INFINITY: i32 = MATE_SCORE + 1;
+// How many nodes pass between clock checks
+const TIME_CHECK_INTERVAL: u64 = 2048;
+// Plies a PV line can reach: the deepest search plus the leaf.
+const MAX_PLY: usize = MAX_SEARCH_DEPTH as usize + 2;
+// Scores at least this far from zero can only come from a forced mate.
+const MATE_BOUND: i32 = MATE_SCORE - MAX_PLY as i32;
+
+pub fn is_mate_score(score: i32) -> bool {
+    score.abs() >= MATE_BOUND
+}
+
+// Full moves to mate for a mate score: positive when the side the score favours mates,
+// negative when it is mated, as UCI's `score mate` reports it.
+pub fn mate_in(score: i32) -> Option<i32> {
+    if !is_mate_score(score) {
+        return None;
+    }
+    let moves = (MATE_SCORE - score.abs() + 1) / 2;
+    Some(if score > 0 { moves } else { -moves })
+}
+
+// `mate N` for mate scores, centipawns otherwise.
+pub fn format_score(score: i32) -> String {
+    match mate_in(score) {
+        Some(moves) => format!("mate {}", moves),
+        None => score.to_string(),
+    }
+}
+
+// The table is shared by every ply, so mate scores are stored relative to the node that holds
+// them and turned back into distances from the root when probed.
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score + ply as i32
+    } else if score <= -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score - ply as i32
+    } else if score <= -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
 
 pub struct AI {
     move_generator: MoveGenerator,
+    evaluator: Box<dyn Evaluator>,
+    eval_config: EvalConfig,
+    tt: TranspositionTable,
+    // Whether large pages were asked for, whether or not the table got them
+    tt_large_pages: bool,
+    orderer: MoveOrderer,
+    correction: CorrectionHistory,
+    // The root move order of the most recent iteration, for `debug ordering`
+    root_order: Vec<(Move, OrderingScore)>,
+    // Triangular PV table: the line at `ply` is the best move found there followed by the
+    // line it led to at `ply + 1`, so the root line is exactly what the search examined.
+    pv_table: Vec<Vec<Move>>,
+    // Every node of the latest iteration, when `debug tree` asked for it
+    tree: Option<SearchTree>,
+    // How the node being searched ended, when it ended early; read back when the tree records it
+    tree_kind: Option<NodeKind>,
+    time_manager: TimeManager,
+    time_policy: TimePolicy,
+    deterministic: bool,
+    // Root lines searched per iteration: the best move's and the best of the rest, in order
+    multi_pv: usize,
+    // Changes to the search itself for the next searches, from an analysis request
+    overrides: SearchOverrides,
+    // The sharpness of the position the next search starts from, used up by that search
+    sharpness: Option<u32>,
+    // Raised from another thread to abandon the current search, e.g. by a dropped analysis stream
+    stop_signal: Option<Arc<AtomicBool>>,
+    stopped: bool,
     nodes_evaluated: u64,
     eval_calls: u64,
     beta_cutoffs: u64,
+    tt_hits: u64,
+    tt_misses: u64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SearchResult {
     pub best_move: Option<Move>,
+    // The principal variation from the searched position, starting with `best_move`.
+    pub pv: Vec<Move>,
     pub evaluation: i32,
+    // With MultiPV, the best root lines best first, the first being `pv`; otherwise just `pv`.
+    // A search cut short may finish with fewer lines than asked for.
+    pub lines: Vec<PvLine>,
+    pub depth: u8,
     pub nodes: u64,
     pub eval_calls: u64,
     pub beta_cutoffs: u64,
+    pub tt_hits: u64,
+    pub tt_misses: u64,
     pub time_ms: u128,
+    pub nps: u64,
+    pub timed_out: bool,
+}
+
+// One root move's principal variation and its score from White's point of view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PvLine {
+    pub pv: Vec<Move>,
+    pub score: i32,
+}
+
+fn allocate_table(size_mb: usize, large_pages: bool) -> TranspositionTable {
+    if large_pages {
+        TranspositionTable::with_large_pages(size_mb)
+    } else {
+        TranspositionTable::new(size_mb)
+    }
 }
 
 impl AI {
     pub fn new() -> Self {
+        // The table the default `Hash` and `LargePages` options ask for, so applying them at
+        // startup keeps it rather than allocating another
+        let (hash_mb, large_pages) = Options::new().hash();
         Self {
             move_generator: MoveGenerator::new(),
+            evaluator: Box::new(ClassicalEvaluator::new()),
+            eval_config: EvalConfig::default(),
+            tt: allocate_table(hash_mb, large_pages),
+            tt_large_pages: large_pages,
+            orderer: MoveOrderer::new(),
+            correction: CorrectionHistory::new(),
+            root_order: Vec::new(),
+            pv_table: vec![Vec::new(); MAX_PLY],
+            tree: None,
+            tree_kind: None,
+            time_manager: TimeManager::new(TimeControl::Infinite),
+            time_policy: TimePolicy::default(),
+            deterministic: false,
+            multi_pv: 1,
+            overrides: SearchOverrides::default(),
+            sharpness: None,
+            stop_signal: None,
+            stopped: false,
             nodes_evaluated: 0,
             eval_calls: 0,
             beta_cutoffs: 0,
+            tt_hits: 0,
+            tt_misses: 0,
+        }
+    }
+
+    // Replaces the table with one of `size_mb` megabytes, on large pages if asked and available.
+    // Keeps the current table, and what it holds, when nothing changes.
+    pub fn set_hash(&mut self, size_mb: usize, large_pages: bool) {
+        if self.tt.size_mb() == size_mb && self.tt_large_pages == large_pages {
+            return;
+        }
+        self.tt = allocate_table(size_mb, large_pages);
+        self.tt_large_pages = large_pages;
+    }
+
+    // Forget everything learned about the previous game.
+    pub fn new_game(&mut self) {
+        self.tt.clear();
+        self.orderer.clear();
+        self.correction.clear();
+    }
+
+    // Deterministic searches count time limits in nodes and start from an empty transposition
+    // table and move history, so the same position and limits always visit the same nodes. With the shared
+    // fixed-seed Zobrist keys and the fixed move ordering, runs match exactly across sessions and
+    // implementations.
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        self.deterministic = deterministic;
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    // How many root lines each iteration finds, at least one.
+    pub fn set_multi_pv(&mut self, lines: usize) {
+        self.multi_pv = lines.max(1);
+    }
+
+    pub fn set_overrides(&mut self, overrides: SearchOverrides) {
+        self.overrides = overrides;
+    }
+
+    pub fn multi_pv(&self) -> usize {
+        self.multi_pv
+    }
+
+    pub fn set_time_policy(&mut self, policy: TimePolicy) {
+        self.time_policy = policy;
+    }
+
+    // A 0-100 sharpness score (see `Sharpness`) for the position the next search is given; a
+    // timed search spends more of its budget on sharper positions.
+    pub fn set_sharpness(&mut self, sharpness: u32) {
+        self.sharpness = Some(sharpness);
+    }
+
+    // Scores stored under the old evaluation no longer apply, so this starts over like a new game.
+    pub fn set_eval_config(&mut self, config: EvalConfig) {
+        if config != self.eval_config {
+            self.eval_config = config;
+            self.evaluator = Box::new(ClassicalEvaluator::with_config(config));
+            self.new_game();
         }
     }
 
+    pub fn eval_config(&self) -> EvalConfig {
+        self.eval_config
+    }
+
+    // Checked alongside the clock, so a raised signal ends the search within TIME_CHECK_INTERVAL
+    // nodes; the result then comes from the last completed depth.
+    pub fn set_stop_signal(&mut self, signal: Arc<AtomicBool>) {
+        self.stop_signal = Some(signal);
+    }
+
     pub fn find_best_move(&mut self, board: &mut Board, depth: u8) -> SearchResult {
+        self.search(board, &SearchLimits::depth(depth))
+    }
+
+    // Iterative deepening: each completed depth replaces the answer, and an iteration cut short
+    // by the clock is thrown away so the result always comes from a fully searched depth.
+    pub fn search(&mut self, board: &mut Board, limits: &SearchLimits) -> SearchResult {
+        self.search_with_progress(board, limits, &mut |_| {})
+    }
+
+    // Like `search`, reporting the running result after every completed depth.
+    pub fn search_with_progress(
+        &mut self,
+        board: &mut Board,
+        limits: &SearchLimits,
+        on_iteration: &mut dyn FnMut(&SearchResult),
+    ) -> SearchResult {
         let start_time = Instant::now();
+        let control = limits.time_control(board.get_turn());
+        self.time_manager = if self.deterministic {
+            self.tt.clear();
+            TimeManager::node_based(control, self.time_policy, game_phase(board))
+        } else {
+            TimeManager::with_policy(control, self.time_policy, game_phase(board))
+        };
+        if let Some(sharpness) = self.sharpness.take() {
+            self.time_manager.report_sharpness(sharpness);
+        }
+        self.stopped = false;
         self.nodes_evaluated = 0;
         self.eval_calls = 0;
         self.beta_cutoffs = 0;
-        
+        self.tt_hits = 0;
+        self.tt_misses = 0;
+        if self.deterministic {
+            self.orderer.clear();
+        } else {
+            self.orderer.new_search();
+        }
+        self.correction.clear();
+
         let color = board.get_turn();
-        let moves = self.move_generator.get_legal_moves(board, color);
-        
+        let sign = if color == Color::White { 1 } else { -1 };
+        let mut moves = self.move_generator.get_legal_moves(board, color);
+
         if moves.is_empty() {
             return SearchResult {
                 best_move: None,
+                pv: Vec::new(),
                 evaluation: 0,
+                lines: Vec::new(),
+                depth: 0,
                 nodes: 0,
                 eval_calls: 0,
                 beta_cutoffs: 0,
+                tt_hits: 0,
+                tt_misses: 0,
                 time_ms: 0,
+                nps: 0,
+                timed_out: false,
             };
         }
 
-        let mut best_move = moves[0].clone();
-        let mut best_eval = if color == Color::White { i32::MIN } else { i32::MAX };
+        let mut pv = vec![moves[0].clone()];
+        let mut other_lines = Vec::new();
+        let mut best_score = self.evaluate(board) * sign;
+        let mut completed_depth = 0;
+        let mut stable_iterations = 0;
 
-        for chess_move in &moves {
-            board.make_move(chess_move);
-            let evaluation = self.minimax(board, depth - 1, i32::MIN, i32::MAX, color == Color::Black);
-            board.undo_move();
-            
-            if (color == Color::White && evaluation > best_eval) || 
-               (color == Color::Black && evaluation < best_eval) {
-                best_eval = evaluation;
-                best_move = chess_move.clone();
+        for depth in 1..=limits.max_depth() {
+            let previous_score = (completed_depth > 0).then_some(best_score);
+            let (line, mut report) = self.search_root(board, &mut moves, depth, &pv[0], previous_score);
+            let chess_move = line[0].clone();
+            if self.stopped {
+                // A move that overtook the previous best was searched in full at the new depth,
+                // so it is a better answer than the last completed iteration's.
+                if completed_depth > 0 && chess_move != pv[0] {
+                    pv = line;
+                    best_score = report.score;
+                }
+                break;
+            }
+            stable_iterations = if completed_depth > 0 && chess_move == pv[0] {
+                stable_iterations + 1
+            } else {
+                1
+            };
+            report.stable_iterations = stable_iterations;
+            if stable_iterations >= EASY_MOVE_ITERATIONS && self.time_manager.soft_limit().is_some() {
+                let threshold = report.score - self.time_policy.easy_move_lead_cp;
+                if self.alternatives_stay_below(board, &moves, &chess_move, threshold, depth / 2) {
+                    let verified = self.time_policy.easy_move_lead_cp;
+                    report.lead = report.lead.map(|lead| lead.max(verified));
+                }
+                if self.stopped {
+                    break;
+                }
+            }
+            self.time_manager.report_iteration(report);
+
+            pv = line;
+            best_score = report.score;
+            completed_depth = depth;
+            if self.multi_pv > 1 {
+                other_lines = self.search_other_lines(board, &moves, &pv[0], depth);
+                if self.stopped {
+                    break;
+                }
+            }
+            on_iteration(&self.result(&pv, best_score, &other_lines, sign, depth, start_time));
+
+            if depth >= self.overrides.min_root_depth
+                && (is_mate_score(best_score) || !self.time_manager.should_continue_iteration(self.nodes_evaluated))
+            {
+                break;
             }
         }
 
+        self.result(&pv, best_score, &other_lines, sign, completed_depth, start_time)
+    }
+
+    // Scores come in for the side to move and `sign` turns them to White's point of view.
+    // `other_lines` are the MultiPV lines after the best; one for a move that has since become
+    // the best is dropped.
+    fn result(&self, pv: &[Move], score: i32, other_lines: &[PvLine], sign: i32, depth: u8, start_time: Instant) -> SearchResult {
         let elapsed = start_time.elapsed();
+        let mut lines = vec![PvLine {
+            pv: pv.to_vec(),
+            score: score * sign,
+        }];
+        lines.extend(
+            other_lines
+                .iter()
+                .filter(|line| line.pv.first() != pv.first())
+                .map(|line| PvLine {
+                    pv: line.pv.clone(),
+                    score: line.score * sign,
+                }),
+        );
         SearchResult {
-            best_move: Some(best_move),
-            evaluation: best_eval,
+            best_move: pv.first().cloned(),
+            pv: pv.to_vec(),
+            evaluation: score * sign,
+            lines,
+            depth,
             nodes: self.nodes_evaluated,
             eval_calls: self.eval_calls,
             beta_cutoffs: self.beta_cutoffs,
+            tt_hits: self.tt_hits,
+            tt_misses: self.tt_misses,
             time_ms: elapsed.as_millis(),
+            nps: nodes_per_second(self.nodes_evaluated, elapsed),
+            timed_out: self.stopped,
         }
     }
 
-    fn minimax(&mut self, board: &mut Board, depth: u8, alpha: i32, beta: i32, maximizing: bool) -> i32 {
-        self.nodes_evaluated += 1;
+    // Also reports how the other moves compare with the best. They fail low, so their fail-soft
+    // scores are upper bounds: counting moves within the policy's margin errs on the side of
+    // "close", and the lead over the highest bound is a lower bound on the real lead. The
+    // caller fills in how many iterations the best move has lasted. The line returned is the
+    // principal variation, starting with the best move. When the search is stopped part-way the
+    // result covers the moves searched so far.
+    fn search_root(&mut self, board: &mut Board, moves: &mut [Move], depth: u8, previous_best: &Move, previous_score: Option<i32>) -> (Vec<Move>, IterationReport) {
+        let color = board.get_turn();
+        self.orderer.order_moves(moves, Some(previous_best), 0, color);
+        self.root_order = moves
+            .iter()
+            .map(|chess_move| {
+                let score = self.orderer.score_breakdown(chess_move, Some(previous_best), 0, color);
+                (chess_move.clone(), score)
+            })
+            .collect();
 
-        if depth == 0 {
-            return self.evaluate(board);
+        if let Some(tree) = self.tree.as_mut() {
+            tree.reset(depth);
         }
+        let (mut alpha, beta) = self.overrides.window.unwrap_or((-INFINITY, INFINITY));
+        let mut best_move = moves[0].clone();
+        let mut line = vec![best_move.clone()];
+        let mut scores = Vec::with_capacity(moves.len());
+        let mut best_move_changes = 0;
 
-        let color = board.get_turn();
-        let moves = self.move_generator.get_legal_moves(board, color);
-
-        if moves.is_empty() {
-            if self.move_generator.is_in_check(board, color) {
-                // Checkmate
-                return if maximizing { -100000 } else { 100000 };
+        for (index, chess_move) in moves.iter().enumerate() {
+            board.make_move(chess_move);
+            let score = if index == 0 {
+                -self.negamax(board, depth - 1, -beta, -alpha, 1)
             } else {
-                // Stalemate
-                return 0;
+                self.zero_window(board, depth, alpha, beta, 1)
+            };
+            board.undo_move();
+
+            if self.stopped {
+                break;
             }
+            // The previous best is ordered first; dropping well below its last score means the
+            // iteration needs time to find a replacement.
+            if scores.is_empty()
+                && previous_score.is_some_and(|previous| score < previous - self.time_policy.swing_cp)
+            {
+                self.time_manager.report_fail_low();
+            }
+            scores.push(score);
+            if score > alpha {
+                if scores.len() > 1 {
+                    best_move_changes += 1;
+                }
+                alpha = score;
+                best_move = chess_move.clone();
+                line.clear();
+                line.push(best_move.clone());
+                line.extend_from_slice(&self.pv_table[1]);
+            }
+            // Only a narrowed window fails high at the root
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        if let Some(tree) = self.tree.as_mut() {
+            tree.finish_root(alpha);
+        }
+        if !self.stopped && self.overrides.window.is_none() {
+            self.tt.store(TtEntry {
+                key: board.get_hash(),
+                depth,
+                score: alpha,
+                bound: Bound::Exact,
+                best_move: Some(best_move.clone()),
+            });
         }
 
-        if maximizing {
-            let mut max_eval = i32::MIN;
-            let mut current_alpha = alpha;
-            
-            for chess_move in &moves {
+        let margin = self.time_policy.close_margin_cp;
+        let close_moves = scores
+            .iter()
+            .filter(|&&score| score >= alpha - margin)
+            .count()
+            .saturating_sub(1);
+        let runner_up = moves
+            .iter()
+            .zip(&scores)
+            .filter(|(chess_move, _)| **chess_move != best_move)
+            .map(|(_, &score)| score)
+            .max();
+        let report = IterationReport {
+            score: alpha,
+            close_moves,
+            stable_iterations: 0,
+            lead: runner_up.map(|score| alpha - score),
+            best_move_changes,
+        };
+        (line, report)
+    }
+
+    // MultiPV: the best line among the root moves not yet chosen, found again and again until
+    // there are `multi_pv` lines. Each pass is a full-window search of the remaining moves, in the
+    // order the main search left them, so the table makes the later passes cheap. Scores are for
+    // the side to move; when the search is stopped the lines found so far are returned.
+    fn search_other_lines(&mut self, board: &mut Board, moves: &[Move], best_move: &Move, depth: u8) -> Vec<PvLine> {
+        let mut remaining: Vec<Move> = moves
+            .iter()
+            .filter(|&chess_move| chess_move != best_move)
+            .cloned()
+            .collect();
+        let mut lines = Vec::new();
+        while lines.len() + 1 < self.multi_pv && !remaining.is_empty() {
+            let mut alpha = -INFINITY;
+            let mut best_index = 0;
+            let mut line = Vec::new();
+            for (index, chess_move) in remaining.iter().enumerate() {
                 board.make_move(chess_move);
-                let evaluation = self.minimax(board, depth - 1, current_alpha, beta, false);
+                let score = if index == 0 {
+                    -self.negamax(board, depth - 1, -INFINITY, -alpha, 1)
+                } else {
+                    self.zero_window(board, depth, alpha, INFINITY, 1)
+                };
                 board.undo_move();
-                
-                max_eval = max_eval.max(evaluation);
-                current_alpha = current_alpha.max(evaluation);
-                
-                if beta <= current_alpha {
-                    self.beta_cutoffs += 1;
-                    break; // Beta cutoff
+                if self.stopped {
+                    return lines;
                 }
-            }
-            
-            max_eval
-        } else {
-            let mut min_eval = i32::MAX;
-            let mut current_beta = beta;
-            
-            for chess_move in &moves {
-                board.make_move(chess_move);
-                let evaluation = self.minimax(board, depth - 1, alpha, current_beta, true);
-                board.undo_move();
-                
-                min_eval = min_eval.min(evaluation);
-                current_beta = current_beta.min(evaluation);
-                
-                if current_beta <= alpha {
-                    self.beta_cutoffs += 1;
-                    break; // Alpha cutoff
+                if score > alpha {
+                    alpha = score;
+                    best_index = index;
+                    line.clear();
+                    line.push(chess_move.clone());
+                    line.extend_from_slice(&self.pv_table[1]);
                 }
             }
-            
-            min_eval
+            lines.push(PvLine { pv: line, score: alpha });
+            remaining.remove(best_index);
         }
+        lines
     }
 
-    fn evaluate(&mut self, board: &Board) -> i32 {
-        self.eval_calls += 1;
-        let mut score = 0;
-
-        for square in 0..64 {
-            if let Some(piece) = board.get_piece(square) {
-                let value = piece.piece_type.value();
-                let position_bonus = self.get_position_bonus(square, piece.piece_type, piece.color, board);
-                let total_value = value + position_bonus;
-                
-                score += if piece.color == Color::White { total_value } else { -total_value };
+    // A refuted alternative only has to be shown no better than the best move, so its fail-soft
+    // bound usually sits right at the best score and says nothing about the real lead. Once a
+    // best move looks easy, a reduced-depth null-window search of every alternative at
+    // `threshold` checks whether it really leads by the easy-move margin.
+    fn alternatives_stay_below(&mut self, board: &mut Board, moves: &[Move], best_move: &Move, threshold: i32, depth: u8) -> bool {
+        for chess_move in moves.iter().filter(|&chess_move| chess_move != best_move) {
+            board.make_move(chess_move);
+            let score = -self.negamax(board, depth.max(1) - 1, -threshold, -threshold + 1, 1);
+            board.undo_move();
+            if self.stopped || score >= threshold {
+                return false;
             }
         }
+        true
+    }
 
+    // Scores are relative to the side to move.
+    fn negamax(&mut self, board: &mut Board, depth: u8, alpha: i32, beta: i32, ply: usize) -> i32 {
+        let Some(tree) = self.tree.as_mut() else {
+            return self.search_node(board, depth, alpha, beta, ply);
+        };
+        let chess_move = board.get_state().move_history.last().cloned();
+        tree.enter(chess_move, ply, depth, alpha, beta);
+        self.tree_kind = None;
+        let score = self.search_node(board, depth, alpha, beta, ply);
+        let kind = if self.stopped {
+            Some(NodeKind::Stopped)
+        } else if depth == 0 {
+            Some(NodeKind::Leaf)
+        } else {
+            self.tree_kind.take()
+        };
+        if let Some(tree) = self.tree.as_mut() {
+            tree.leave(score, kind);
+        }
         score
     }
 
-    fn get_position_bonus(&self, square: Square, piece_type: PieceType, color: Color, board: &Board) -> i32 {
-        let file = square % 8;
-        let rank = square / 8;
-        let mut bonus = 0;
+    fn search_node(&mut self, board: &mut Board, depth: u8, mut alpha: i32, mut beta: i32, ply: usize) -> i32 {
+        self.nodes_evaluated += 1;
+        // Node budgets are checked at every node to stop exactly; the clock is costlier to read.
+        if (self.time_manager.counts_nodes() || self.nodes_evaluated % TIME_CHECK_INTERVAL == 0)
+            && (self.time_manager.should_stop(self.nodes_evaluated)
+                || self.stop_signal.as_ref().is_some_and(|signal| signal.load(Ordering::Relaxed))) {
+            self.stopped = true;
+        }
+        if self.stopped {
+            return 0;
+        }
+        // Leaves, cutoffs from the table and nodes that fail low end the line here.
+        self.pv_table[ply].clear();
 
-        // Center control bonus
-        let center_squares = [27, 28, 35, 36]; // d4, e4, d5, e5
-        if center_squares.contains(&square) {
-            bonus += 10;
+        // Mate-distance pruning: nothing here can beat being mated at this ply or mating on the
+        // next one, so a window outside those bounds is already decided.
+        if self.overrides.mate_distance {
+            alpha = alpha.max(-MATE_SCORE + ply as i32);
+            beta = beta.min(MATE_SCORE - ply as i32 - 1);
+            if alpha >= beta {
+                self.tree_kind = Some(NodeKind::MateDistance);
+                return alpha;
+            }
         }
 
-        match piece_type {
-            PieceType::Pawn => {
-                // Pawn advancement bonus
-                let advancement = if color == Color::White { rank } else { 7 - rank };
-                bonus += (advancement * 5) as i32;
-            },
-            PieceType::King => {
-                // King safety in opening/middlegame
-                if !self.is_endgame(board) {
-                    let safe_rank = if color == Color::White { 0 } else { 7 };
-                    if rank == safe_rank && (file <= 2 || file >= 5) {
-                        bonus += 20;
-                    } else {
-                        bonus -= 20;
+        let color = board.get_turn();
+
+        if depth == 0 {
+            // Catch mates on the horizon without paying for full move generation at every leaf.
+            if self.move_generator.is_in_check(board, color)
+                && !self.move_generator.has_legal_move(board, color) {
+                return -MATE_SCORE + ply as i32;
+            }
+            let sign = if color == Color::White { 1 } else { -1 };
+            let static_eval = self.evaluate(board) * sign;
+            if !self.overrides.correction {
+                return static_eval;
+            }
+            return self.correction.correct(board, static_eval);
+        }
+
+        let key = board.get_hash();
+        let mut tt_move = None;
+        match self.tt.probe(key) {
+            Some(entry) => {
+                self.tt_hits += 1;
+                if entry.depth >= depth && self.overrides.tt_cutoffs {
+                    let score = score_from_tt(entry.score, ply);
+                    let cutoff = match entry.bound {
+                        Bound::Exact => true,
+                        Bound::Lower => score >= beta,
+                        Bound::Upper => score <= alpha,
+                    };
+                    if cutoff {
+                        self.tree_kind = Some(NodeKind::TableCutoff);
+                        return score;
                     }
                 }
-            },
-            _ => {}
+                tt_move = entry.best_move.clone();
+            }
+            None => self.tt_misses += 1,
         }
 
-        bonus
-    }
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        // Legal moves searched so far, without a cutoff
+        let mut searched: Vec<Move> = Vec::new();
+        let mut cutoff = false;
 
-    fn is_endgame(&self, board: &Board) -> bool {
-        let mut piece_count = 0;
-        let mut queen_count = 0;
-        
-        for square in 0..64 {
-            if let Some(piece) = board.get_piece(square) {
-                if piece.piece_type != PieceType::King && piece.piece_type != PieceType::Pawn {
-                    piece_count += 1;
-                    if piece.piece_type == PieceType::Queen {
-                        queen_count += 1;
-                    }
-                }
+        let mut picker = MovePicker::new(tt_move, ply, color);
+        while let Some(chess_move) = picker.next(board, &self.move_generator, &self.orderer) {
+            board.make_move(&chess_move);
+            if self.move_generator.is_in_check(board, color) {
+                board.undo_move();
+                continue;
             }
+            let score = if searched.is_empty() {
+                -self.negamax(board, depth - 1, -beta, -alpha, ply + 1)
+            } else {
+                self.zero_window(board, depth, alpha, beta, ply + 1)
+            };
+            board.undo_move();
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(chess_move.clone());
+            }
+            if score > alpha {
+                alpha = score;
+                self.update_pv(&chess_move, ply);
+            }
+
+            if alpha >= beta {
+                self.beta_cutoffs += 1;
+                self.orderer.record_killer(&chess_move, ply);
+                self.orderer.record_history(color, &chess_move, &searched, depth);
+                cutoff = true;
+                break;
+            }
+            searched.push(chess_move);
+        }
+
+        if searched.is_empty() && !cutoff {
+            self.tree_kind = Some(NodeKind::GameEnd);
+            return if self.move_generator.is_in_check(board, color) {
+                -MATE_SCORE + ply as i32
+            } else {
+                0
+            };
+        }
+
+        let bound = if best_score <= original_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.update_correction(board, best_move.as_ref(), best_score, bound, depth);
+        self.tt.store(TtEntry {
+            key,
+            depth,
+            score: score_to_tt(best_score, ply),
+            bound,
+            best_move,
+        });
+
+        best_score
+    }
+
+    // Principal variation search: once the first move has set alpha, a later move (already made
+    // on `board`, one ply below a node searched to `depth`) only has to be shown no better, which
+    // a zero window around alpha does cheaply. A move that fails high inside (alpha, beta) is
+    // searched again with the full window for its exact score and line.
+    fn zero_window(&mut self, board: &mut Board, depth: u8, alpha: i32, beta: i32, ply: usize) -> i32 {
+        if !self.overrides.pvs {
+            return -self.negamax(board, depth - 1, -beta, -alpha, ply);
+        }
+        let score = -self.negamax(board, depth - 1, -alpha - 1, -alpha, ply);
+        if score > alpha && score < beta && !self.stopped {
+            -self.negamax(board, depth - 1, -beta, -alpha, ply)
+        } else {
+            score
+        }
+    }
+
+    fn update_pv(&mut self, chess_move: &Move, ply: usize) {
+        let (line, rest) = self.pv_table.split_at_mut(ply + 1);
+        let line = &mut line[ply];
+        line.clear();
+        line.push(chess_move.clone());
+        line.extend_from_slice(&rest[0]);
+    }
+
+    // Quiet best moves outside check say something about the position itself, so the gap between
+    // the static eval and the search score is learned for this pawn structure. A bound only
+    // counts when it points the same way as the gap.
+    fn update_correction(&mut self, board: &Board, best_move: Option<&Move>, score: i32, bound: Bound, depth: u8) {
+        let tactical = best_move
+            .is_some_and(|chess_move| chess_move.captured.is_some() || chess_move.promotion.is_some());
+        let color = board.get_turn();
+        if !self.overrides.correction || tactical || is_mate_score(score) || self.move_generator.is_in_check(board, color) {
+            return;
+        }
+        let sign = if color == Color::White { 1 } else { -1 };
+        let static_eval = self.evaluate(board) * sign;
+        let consistent = match bound {
+            Bound::Exact => true,
+            Bound::Lower => score > static_eval,
+            Bound::Upper => score < static_eval,
+        };
+        if consistent {
+            self.correction.record(board, static_eval, score, depth);
+        }
+    }
+
+    fn evaluate(&mut self, board: &Board) -> i32 {
+        self.eval_calls += 1;
+        self.evaluator.evaluate(board)
+    }
+
+    pub fn root_order(&self) -> &[(Move, OrderingScore)] {
+        &self.root_order
+    }
+
+    // Records every node of each iteration, replacing the previous iteration's tree. It costs a
+    // node record per visit, so it is meant for shallow debugging searches.
+    pub fn record_tree(&mut self, enabled: bool) {
+        self.tree = enabled.then(SearchTree::new);
+    }
+
+    // The tree of the last iteration searched; recording stops until `record_tree` is called again.
+    pub fn take_tree(&mut self) -> Option<SearchTree> {
+        self.tree.take()
+    }
+
+    // For `hash save` / `hash load`. Deterministic mode still starts each search from an empty table.
+    pub fn transposition_table(&mut self) -> &mut TranspositionTable {
+        &mut self.tt
+    }
+
+    pub fn evaluator(&self) -> &dyn Evaluator {
+        self.evaluator.as_ref()
+    }
+}
+
+impl Default for AI {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn game_phase(board: &Board) -> GamePhase {
+    let pieces = [Color::White, Color::Black]
+        .into_iter()
+        .map(|color| {
+            board.occupied_by(color).count()
+                - board.pieces(PieceType::Pawn, color).count()
+                - board.pieces(PieceType::King, color).count()
+        })
+        .sum();
+    GamePhase::classify(pieces, board.get_state().fullmove_number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::{board_from, FenParser};
+
+    #[test]
+    fn finds_mate_in_one_and_wins_hanging_queen() {
+        let mut board = board_from("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let result = AI::new().find_best_move(&mut board, 3);
+        assert_eq!(result.best_move.map(|m| m.to_string()), Some("a1a8".to_string()));
+        assert_eq!(result.evaluation, MATE_SCORE - 1);
+        assert_eq!(format_score(result.evaluation), "mate 1");
+
+        let mut board = board_from("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1");
+        let result = AI::new().find_best_move(&mut board, 4);
+        assert_eq!(result.best_move.map(|m| m.to_string()), Some("d1d5".to_string()));
+        assert_eq!(result.depth, 4);
+    }
+
+    #[test]
+    fn mate_scores_count_the_distance_to_mate() {
+        // 1. Kb6 Kb8 2. Rh8#
+        let mut board = board_from("k7/8/2K5/8/8/8/8/7R w - - 0 1");
+        let mut ai = AI::new();
+        let result = ai.find_best_move(&mut board, 5);
+        assert_eq!(result.evaluation, MATE_SCORE - 3);
+        assert_eq!(mate_in(result.evaluation), Some(2));
+        // Asked again, the answer comes out of the table at the same distance
+        assert_eq!(ai.find_best_move(&mut board, 5).evaluation, MATE_SCORE - 3);
+
+        assert_eq!(mate_in(-(MATE_SCORE - 2)), Some(-1));
+        assert_eq!(format_score(-(MATE_SCORE - 4)), "mate -2");
+        assert_eq!(format_score(250), "250");
+    }
+
+    #[test]
+    fn multi_pv_finds_distinct_root_lines_best_first() {
+        let mut ai = AI::new();
+        let mut board = Board::new();
+        let single = ai.find_best_move(&mut board, 3);
+        assert_eq!(single.lines.len(), 1);
+        assert_eq!(single.lines[0].pv, single.pv);
+
+        ai.set_multi_pv(4);
+        let result = ai.find_best_move(&mut board, 3);
+        assert_eq!(result.lines.len(), 4);
+        assert_eq!(result.lines[0].pv, result.pv);
+        assert_eq!(result.lines[0].score, result.evaluation);
+        assert!(result.lines.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        let mut firsts: Vec<_> = result.lines.iter().map(|line| line.pv[0].to_string()).collect();
+        firsts.sort();
+        firsts.dedup();
+        assert_eq!(firsts.len(), 4);
+
+        // Never more lines than legal moves
+        let mut board = board_from("7k/8/8/8/8/8/8/K7 w - - 0 1");
+        assert_eq!(ai.find_best_move(&mut board, 2).lines.len(), 3);
+    }
+
+    #[test]
+    fn movetime_search_returns_a_completed_depth() {
+        let mut board = Board::new();
+        let result = AI::new().search(
+            &mut board,
+            &SearchLimits {
+                movetime_ms: Some(50),
+                ..SearchLimits::default()
+            },
+        );
+        assert!(result.best_move.is_some());
+        assert!(result.depth >= 1);
+        assert!(result.time_ms < 1000);
+    }
+
+    #[test]
+    fn deterministic_searches_repeat_node_counts() {
+        let mut ai = AI::new();
+        ai.set_deterministic(true);
+        let mut board = Board::new();
+        let first = ai.find_best_move(&mut board, 4);
+        let second = ai.find_best_move(&mut board, 4);
+        assert_eq!(first.nodes, second.nodes);
+        assert_eq!(first.evaluation, second.evaluation);
+        assert_eq!(first.best_move, second.best_move);
+
+        // Time limits are spent in nodes, so they repeat too
+        let limits = SearchLimits {
+            movetime_ms: Some(20),
+            ..SearchLimits::default()
+        };
+        let first = ai.search(&mut board, &limits);
+        let second = ai.search(&mut board, &limits);
+        assert_eq!(first.nodes, second.nodes);
+        assert_eq!(first.depth, second.depth);
+    }
+
+    #[test]
+    fn easy_moves_stop_well_inside_the_budget() {
+        let mut ai = AI::new();
+        let mut board = Board::new();
+        // exd4 wins the queen; every other move loses it
+        FenParser::new()
+            .parse_fen(&mut board, "rnb1kbnr/pppp1ppp/8/4p3/3q4/4P3/PPPP1PPP/RNBQKBNR w KQkq - 0 3")
+            .unwrap();
+        let limits = SearchLimits {
+            nodes: Some(400_000),
+            ..SearchLimits::default()
+        };
+        let result = ai.search(&mut board, &limits);
+        assert_eq!(result.best_move.map(|chess_move| chess_move.to), Some(27));
+        assert!(result.nodes < 200_000, "{}", result.nodes);
+    }
+
+    #[test]
+    fn pv_is_a_legal_line_starting_with_the_best_move() {
+        let mut ai = AI::new();
+        let mut board = Board::new();
+        let result = ai.find_best_move(&mut board, 5);
+        assert_eq!(result.pv.first(), result.best_move.as_ref());
+        assert!((1..=5).contains(&result.pv.len()), "{:?}", result.pv);
+
+        let generator = MoveGenerator::new();
+        for chess_move in &result.pv {
+            let color = board.get_turn();
+            let legal = generator.get_legal_moves(&mut board, color);
+            assert!(legal.contains(chess_move), "{:?}", chess_move);
+            board.make_move(chess_move);
         }
-        
-        piece_count <= 4 || (piece_count <= 6 && queen_count == 0)
+    }
+
+    #[test]
+    fn node_budgets_stop_the_search() {
+        let mut ai = AI::new();
+        let mut board = Board::new();
+        let limits = SearchLimits {
+            nodes: Some(5000),
+            ..SearchLimits::default()
+        };
+        let result = ai.search(&mut board, &limits);
+        assert!(result.best_move.is_some());
+        assert!(result.nodes <= 5001, "{}", result.nodes);
+    }
+
+    #[test]
+    fn default_hash_options_keep_the_table_it_starts_with() {
+        let mut ai = AI::new();
+        let mut board = Board::new();
+        ai.search(&mut board, &SearchLimits::depth(3));
+        assert!(ai.tt.probe(board.get_hash()).is_some());
+
+        let (hash_mb, large_pages) = Options::new().hash();
+        ai.set_hash(hash_mb, large_pages);
+        assert!(ai.tt.probe(board.get_hash()).is_some());
+        ai.set_hash(hash_mb, !large_pages);
+        assert!(ai.tt.probe(board.get_hash()).is_none());
+    }
+
+    #[test]
+    fn reports_every_completed_depth_then_returns_the_last() {
+        let mut board = board_from("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+        let mut progress = Vec::new();
+        let result = AI::new().search_with_progress(&mut board, &SearchLimits::depth(4), &mut |iteration| {
+            progress.push((iteration.depth, iteration.nodes, iteration.pv.len(), iteration.best_move.clone()));
+        });
+        let depths: Vec<u8> = progress.iter().map(|&(depth, ..)| depth).collect();
+        assert_eq!(depths, vec![1, 2, 3, 4]);
+        assert!(progress.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+        assert!(progress.iter().all(|&(_, _, pv_length, _)| pv_length >= 1));
+        let (depth, nodes, _, best_move) = progress.last().unwrap().clone();
+        assert_eq!((depth, nodes, best_move), (result.depth, result.nodes, result.best_move));
+    }
+
+    #[test]
+    fn overrides_change_the_search_not_its_answer() {
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let search = |overrides: SearchOverrides| {
+            let mut ai = AI::new();
+            ai.set_overrides(overrides);
+            ai.search(&mut board_from(kiwipete), &SearchLimits::depth(4))
+        };
+        let plain = SearchOverrides {
+            tt_cutoffs: false,
+            ..SearchOverrides::default()
+        };
+        let with_pvs = search(plain);
+        let without_pvs = search(SearchOverrides { pvs: false, ..plain });
+        assert_eq!(with_pvs.evaluation, without_pvs.evaluation);
+
+        // A window above the true score fails low and reports its lower edge at best
+        let window = (with_pvs.evaluation + 100, with_pvs.evaluation + 200);
+        let failed_low = search(SearchOverrides {
+            window: Some(window),
+            ..SearchOverrides::default()
+        });
+        assert!(failed_low.evaluation <= window.0, "{}", failed_low.evaluation);
     }
 }
@@ -0,0 +1,431 @@
+use crate::ai::{is_mate_score, SearchResult, AI, MATE_SCORE};
+use crate::board::Board;
+use crate::fen::FenParser;
+use crate::move_generator::MoveGenerator;
+use crate::search_limits::SearchLimits;
+use crate::topology::{pin_current_thread, Topology};
+use crate::types::{Color, Move};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InfoEvent {
+    // One completed iterative-deepening depth.
+    Iteration {
+        depth: u8,
+        score: i32,
+        best_move: Option<Move>,
+        pv: Vec<Move>,
+        nodes: u64,
+        nps: u64,
+        time_ms: u128,
+    },
+    // The final answer; always the last event.
+    BestMove {
+        best_move: Option<Move>,
+        score: i32,
+    },
+}
+
+impl InfoEvent {
+    fn iteration(result: &SearchResult) -> Self {
+        InfoEvent::Iteration {
+            depth: result.depth,
+            score: result.evaluation,
+            best_move: result.best_move.clone(),
+            pv: result.pv.clone(),
+            nodes: result.nodes,
+            nps: result.nps,
+            time_ms: result.time_ms,
+        }
+    }
+}
+
+// Events from a search running on its own thread. It is both a blocking `Iterator` and an async
+// stream: `poll_next` has the signature of `futures::Stream::poll_next`, so wrapping it for
+// tokio or any other executor is a single forwarding impl. Dropping the stream stops the search.
+pub struct AnalysisStream {
+    events: Receiver<InfoEvent>,
+    waker: Arc<Mutex<Option<Waker>>>,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+// Scores are from White's point of view, as everywhere else in the engine.
+pub fn analyze_stream(fen: &str, limits: SearchLimits) -> Result<AnalysisStream, String> {
+    let mut board = Board::new();
+    FenParser::new().parse_fen(&mut board, fen)?;
+
+    let (sender, events) = mpsc::channel();
+    let waker: Arc<Mutex<Option<Waker>>> = Arc::new(Mutex::new(None));
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let worker_waker = Arc::clone(&waker);
+    let worker_stop = Arc::clone(&stop);
+    let worker = thread::spawn(move || {
+        let wake = || {
+            if let Some(waker) = worker_waker.lock().unwrap().take() {
+                waker.wake();
+            }
+        };
+        let send = |event| {
+            // A dropped receiver only means nobody is listening any more.
+            let _ = sender.send(event);
+            wake();
+        };
+        let mut ai = AI::new();
+        ai.set_stop_signal(worker_stop);
+        let result = ai.search_with_progress(&mut board, &limits, &mut |result| {
+            send(InfoEvent::iteration(result))
+        });
+        send(InfoEvent::BestMove {
+            best_move: result.best_move,
+            score: result.evaluation,
+        });
+        // Closing the channel ends the stream, which a parked consumer has to be woken to see.
+        drop(sender);
+        wake();
+    });
+
+    Ok(AnalysisStream {
+        events,
+        waker,
+        stop,
+        worker: Some(worker),
+    })
+}
+
+impl AnalysisStream {
+    pub fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<InfoEvent>> {
+        let this = self.get_mut();
+        match this.events.try_recv() {
+            Ok(event) => return Poll::Ready(Some(event)),
+            Err(TryRecvError::Disconnected) => return Poll::Ready(None),
+            Err(TryRecvError::Empty) => {}
+        }
+        *this.waker.lock().unwrap() = Some(cx.waker().clone());
+        // An event sent between the first check and storing the waker would not wake us.
+        match this.events.try_recv() {
+            Ok(event) => Poll::Ready(Some(event)),
+            Err(TryRecvError::Disconnected) => Poll::Ready(None),
+            Err(TryRecvError::Empty) => Poll::Pending,
+        }
+    }
+
+    // Ask the search to finish early; the stream still ends with a BestMove event.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Iterator for AnalysisStream {
+    type Item = InfoEvent;
+
+    fn next(&mut self) -> Option<InfoEvent> {
+        self.events.recv().ok()
+    }
+}
+
+// Replies within this of the best one hold the position about as well.
+pub const REPLY_MARGIN_CP: i32 = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootScore {
+    pub chess_move: Move,
+    // White's point of view, like every other score.
+    pub score: i32,
+    // Starts with `chess_move`.
+    pub pv: Vec<Move>,
+    pub nodes: u64,
+    pub tt_hits: u64,
+}
+
+// What one `analyze_all` worker did, to show whether extra threads pull their weight.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkerStats {
+    pub moves: usize,
+    pub nodes: u64,
+    pub tt_hits: u64,
+    // Time spent searching, as opposed to waiting to be joined
+    pub busy_ms: u128,
+    // The CPU the worker was pinned to, if it was
+    pub cpu: Option<usize>,
+}
+
+// How evenly the work was shared: mean over busiest worker, 100 when every worker searched as
+// many nodes as the busiest one.
+pub fn load_balance(workers: &[WorkerStats]) -> u32 {
+    let busiest = workers.iter().map(|worker| worker.nodes).max().unwrap_or(0);
+    if busiest == 0 {
+        return 100;
+    }
+    let total: u64 = workers.iter().map(|worker| worker.nodes).sum();
+    (total * 100 / (busiest * workers.len() as u64)) as u32
+}
+
+// Every legal move searched on its own, as if `ai depth <depth>` had to play it: an exhaustive
+// MultiPV, best first for the side to move. Moves are handed out to `threads` workers from a
+// shared counter, and each search starts from an empty table so the scores do not depend on
+// which worker got which move.
+pub fn analyze_all(board: &Board, depth: u8, threads: usize) -> Vec<RootScore> {
+    analyze_all_with_stats(board, depth, threads, None).0
+}
+
+// `analyze_all`, also returning what each worker did. With a topology each worker is pinned to
+// its own CPU, spread over the NUMA nodes, before it allocates its table.
+pub fn analyze_all_with_stats(
+    board: &Board,
+    depth: u8,
+    threads: usize,
+    pinning: Option<&Topology>,
+) -> (Vec<RootScore>, Vec<WorkerStats>) {
+    let mut root = board.clone();
+    let color = root.get_turn();
+    let moves = MoveGenerator::new().get_legal_moves(&mut root, color);
+    let next = AtomicUsize::new(0);
+
+    let (mut scores, workers): (Vec<RootScore>, Vec<WorkerStats>) = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.clamp(1, moves.len().max(1)))
+            .map(|worker| {
+                let next = &next;
+                let moves = &moves;
+                scope.spawn(move || {
+                    let cpu = pinning
+                        .map(|topology| topology.cpu_for_worker(worker))
+                        .filter(|&cpu| pin_current_thread(cpu));
+                    let start_time = Instant::now();
+                    let mut ai = AI::new();
+                    let mut scores = Vec::new();
+                    while let Some(chess_move) = moves.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        ai.new_game();
+                        scores.push(score_root_move(&mut ai, board, chess_move, depth));
+                    }
+                    let stats = WorkerStats {
+                        moves: scores.len(),
+                        nodes: scores.iter().map(|root_score| root_score.nodes).sum(),
+                        tt_hits: scores.iter().map(|root_score| root_score.tt_hits).sum(),
+                        busy_ms: start_time.elapsed().as_millis(),
+                        cpu,
+                    };
+                    (scores, stats)
+                })
+            })
+            .collect();
+        let mut scores = Vec::new();
+        let mut stats = Vec::new();
+        for worker in workers {
+            let (worker_scores, worker_stats) = worker.join().expect("analysis worker panicked");
+            scores.extend(worker_scores);
+            stats.push(worker_stats);
+        }
+        (scores, stats)
+    });
+
+    // Ties keep move generation order, whichever worker finished first.
+    sort_best_first(&mut scores, &moves, color);
+    (scores, workers)
+}
+
+// Every legal reply searched to `depth` one after another by a single `ai`, so later moves reuse
+// what earlier ones put in its table: much cheaper than `analyze_all` for a shallow overview,
+// though a move's score can depend on the moves searched before it. Best first for the side
+// to move.
+pub fn score_replies(ai: &mut AI, board: &Board, depth: u8) -> Vec<RootScore> {
+    let mut root = board.clone();
+    let color = root.get_turn();
+    let moves = MoveGenerator::new().get_legal_moves(&mut root, color);
+    let mut scores: Vec<RootScore> = moves
+        .iter()
+        .map(|chess_move| score_root_move(ai, board, chess_move, depth))
+        .collect();
+    sort_best_first(&mut scores, &moves, color);
+    scores
+}
+
+// How far `root_score` falls short of `best` for `color`, in centipawns.
+pub fn score_loss(best: &RootScore, root_score: &RootScore, color: Color) -> i32 {
+    match color {
+        Color::White => best.score - root_score.score,
+        Color::Black => root_score.score - best.score,
+    }
+}
+
+fn sort_best_first(scores: &mut [RootScore], moves: &[Move], color: Color) {
+    let position = |chess_move: &Move| moves.iter().position(|other| other == chess_move);
+    scores.sort_by_key(|root_score| {
+        let score = match color {
+            Color::White => -root_score.score,
+            Color::Black => root_score.score,
+        };
+        (score, position(&root_score.chess_move))
+    });
+}
+
+// `chess_move` searched `depth` plies deep counting itself, scored from White's point of view.
+// Mate distances count from before the move, as the root search counts them.
+pub fn score_root_move(ai: &mut AI, board: &Board, chess_move: &Move, depth: u8) -> RootScore {
+    let mut board = board.clone();
+    let mover = board.get_turn();
+    board.make_move(chess_move);
+    let generator = MoveGenerator::new();
+    let (score, line, nodes, tt_hits) = if !generator.has_legal_move(&mut board, mover.opposite()) {
+        let score = if !generator.is_in_check(&board, mover.opposite()) {
+            0
+        } else if mover == Color::White {
+            MATE_SCORE
+        } else {
+            -MATE_SCORE
+        };
+        (score, Vec::new(), 0, 0)
+    } else if depth <= 1 {
+        (ai.evaluator().evaluate(&board), Vec::new(), 0, 0)
+    } else {
+        let result = ai.find_best_move(&mut board, depth - 1);
+        (result.evaluation, result.pv, result.nodes, result.tt_hits)
+    };
+    let score = if is_mate_score(score) {
+        score - score.signum()
+    } else {
+        score
+    };
+
+    let mut pv = vec![chess_move.clone()];
+    pv.extend(line);
+    RootScore {
+        chess_move: chess_move.clone(),
+        score,
+        pv,
+        nodes,
+        tt_hits,
+    }
+}
+
+impl Drop for AnalysisStream {
+    fn drop(&mut self) {
+        self.stop();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Wake;
+
+    struct Unpark(thread::Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[test]
+    fn streams_each_depth_then_the_best_move() {
+        let mut stream = analyze_stream(START, SearchLimits::depth(3)).unwrap();
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut events = Vec::new();
+        loop {
+            match Pin::new(&mut stream).poll_next(&mut cx) {
+                Poll::Ready(Some(event)) => events.push(event),
+                Poll::Ready(None) => break,
+                Poll::Pending => thread::park(),
+            }
+        }
+
+        let depths: Vec<u8> = events
+            .iter()
+            .filter_map(|event| match event {
+                InfoEvent::Iteration { depth, .. } => Some(*depth),
+                InfoEvent::BestMove { .. } => None,
+            })
+            .collect();
+        assert_eq!(depths, vec![1, 2, 3]);
+        assert!(events.iter().all(|event| match event {
+            InfoEvent::Iteration {
+                depth,
+                best_move,
+                pv,
+                ..
+            } => pv.len() <= usize::from(*depth) && pv.first() == best_move.as_ref(),
+            InfoEvent::BestMove { .. } => true,
+        }));
+        assert!(matches!(
+            events.last(),
+            Some(InfoEvent::BestMove {
+                best_move: Some(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn analyze_all_scores_every_move_the_same_on_any_number_of_threads() {
+        let mut board = Board::new();
+        // White mates with Qxf7
+        FenParser::new()
+            .parse_fen(
+                &mut board,
+                "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+            )
+            .unwrap();
+        let single = analyze_all(&board, 2, 1);
+        let color = board.get_turn();
+        let legal = MoveGenerator::new().get_legal_moves(&mut board, color);
+        assert_eq!(single.len(), legal.len());
+        assert_eq!(single[0].chess_move.to_string(), "h5f7");
+        assert_eq!(single[0].score, MATE_SCORE - 1);
+        assert!(single.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        let (scores, workers) = analyze_all_with_stats(&board, 2, 4, Some(&Topology::detect()));
+        assert_eq!(scores, single);
+        assert_eq!(workers.len(), 4);
+        assert_eq!(workers.iter().map(|worker| worker.moves).sum::<usize>(), legal.len());
+        assert_eq!(
+            workers.iter().map(|worker| worker.nodes).sum::<u64>(),
+            single.iter().map(|root_score| root_score.nodes).sum::<u64>()
+        );
+        assert!((1..=100).contains(&load_balance(&workers)));
+    }
+
+    #[test]
+    fn replies_share_one_table_and_rank_the_critical_move_first() {
+        let mut board = Board::new();
+        // Black must deal with the mate threat on f7
+        FenParser::new()
+            .parse_fen(
+                &mut board,
+                "r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 3 3",
+            )
+            .unwrap();
+        let replies = score_replies(&mut AI::new(), &board, 2);
+        let color = board.get_turn();
+        let legal = MoveGenerator::new().get_legal_moves(&mut board, color);
+        assert_eq!(replies.len(), legal.len());
+        assert!(replies
+            .windows(2)
+            .all(|pair| pair[0].score <= pair[1].score));
+        assert!(replies[0].score > -MATE_SCORE);
+        // Qxf7 mates one move after the reply
+        assert_eq!(replies.last().unwrap().score, MATE_SCORE - 2);
+        assert!(score_loss(&replies[0], replies.last().unwrap(), color) > 0);
+    }
+
+    #[test]
+    fn stopping_ends_an_unbounded_search() {
+        let mut stream = analyze_stream(START, SearchLimits::default()).unwrap();
+        assert!(matches!(stream.next(), Some(InfoEvent::Iteration { .. })));
+        stream.stop();
+        assert!(stream.any(|event| matches!(event, InfoEvent::BestMove { .. })));
+        assert!(analyze_stream("not a fen", SearchLimits::default()).is_err());
+    }
+}
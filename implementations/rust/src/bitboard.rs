@@ -0,0 +1,242 @@
+use crate::cpu;
+use crate::types::Square;
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+
+// A set of squares, one bit per square with a1 = bit 0 and h8 = bit 63 (the same numbering as
+// `Square`). The mailbox board stays the source of truth; bitboards are built from it for masks
+// and attack maps.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Bitboard(pub u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::South,
+        Direction::East,
+        Direction::West,
+        Direction::NorthEast,
+        Direction::NorthWest,
+        Direction::SouthEast,
+        Direction::SouthWest,
+    ];
+}
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard(0);
+    pub const FULL: Bitboard = Bitboard(!0);
+
+    pub const fn file(file: usize) -> Bitboard {
+        Bitboard(0x0101_0101_0101_0101 << file)
+    }
+
+    pub const fn rank(rank: usize) -> Bitboard {
+        Bitboard(0xff << (rank * 8))
+    }
+
+    pub const fn from_square(square: Square) -> Bitboard {
+        Bitboard(1 << square)
+    }
+
+    pub fn contains(self, square: Square) -> bool {
+        self.0 & (1 << square) != 0
+    }
+
+    pub fn set(&mut self, square: Square) {
+        self.0 |= 1 << square;
+    }
+
+    pub fn clear(&mut self, square: Square) {
+        self.0 &= !(1 << square);
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn count(self) -> u32 {
+        (cpu::kernels().popcount)(self.0)
+    }
+
+    // The lowest square in the set.
+    pub fn first(self) -> Option<Square> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.0.trailing_zeros() as Square)
+        }
+    }
+
+    // Moves every square one step; squares that would leave the board (including wrapping
+    // around the a- or h-file) drop out.
+    pub fn shift(self, direction: Direction) -> Bitboard {
+        let not_a = !Bitboard::file(0).0;
+        let not_h = !Bitboard::file(7).0;
+        Bitboard(match direction {
+            Direction::North => self.0 << 8,
+            Direction::South => self.0 >> 8,
+            Direction::East => (self.0 & not_h) << 1,
+            Direction::West => (self.0 & not_a) >> 1,
+            Direction::NorthEast => (self.0 & not_h) << 9,
+            Direction::NorthWest => (self.0 & not_a) << 7,
+            Direction::SouthEast => (self.0 & not_h) >> 7,
+            Direction::SouthWest => (self.0 & not_a) >> 9,
+        })
+    }
+
+    pub fn squares(self) -> Squares {
+        Squares(self.0)
+    }
+}
+
+// Squares in ascending order.
+pub struct Squares(u64);
+
+impl Iterator for Squares {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Square> {
+        let square = Bitboard(self.0).first()?;
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.0.count_ones() as usize;
+        (count, Some(count))
+    }
+}
+
+impl ExactSizeIterator for Squares {}
+
+impl IntoIterator for Bitboard {
+    type Item = Square;
+    type IntoIter = Squares;
+
+    fn into_iter(self) -> Squares {
+        self.squares()
+    }
+}
+
+impl FromIterator<Square> for Bitboard {
+    fn from_iter<I: IntoIterator<Item = Square>>(squares: I) -> Self {
+        let mut bitboard = Bitboard::EMPTY;
+        for square in squares {
+            bitboard.set(square);
+        }
+        bitboard
+    }
+}
+
+macro_rules! bitboard_operator {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $op:tt) => {
+        impl $trait for Bitboard {
+            type Output = Bitboard;
+
+            fn $method(self, other: Bitboard) -> Bitboard {
+                Bitboard(self.0 $op other.0)
+            }
+        }
+
+        impl $assign_trait for Bitboard {
+            fn $assign_method(&mut self, other: Bitboard) {
+                self.0 = self.0 $op other.0;
+            }
+        }
+    };
+}
+
+bitboard_operator!(BitAnd, bitand, BitAndAssign, bitand_assign, &);
+bitboard_operator!(BitOr, bitor, BitOrAssign, bitor_assign, |);
+bitboard_operator!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^);
+
+impl Not for Bitboard {
+    type Output = Bitboard;
+
+    fn not(self) -> Bitboard {
+        Bitboard(!self.0)
+    }
+}
+
+// Rank 8 at the top, like the board display.
+impl fmt::Debug for Bitboard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Bitboard(0x{:016x})", self.0)?;
+        for rank in (0..8).rev() {
+            let row: Vec<&str> = (0..8)
+                .map(|file| {
+                    if self.contains(rank * 8 + file) {
+                        "1"
+                    } else {
+                        "."
+                    }
+                })
+                .collect();
+            writeln!(f, "{} {}", rank + 1, row.join(" "))?;
+        }
+        write!(f, "  a b c d e f g h")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sets_iterates_and_combines_squares() {
+        let mut bitboard: Bitboard = [0, 9, 63].into_iter().collect();
+        assert_eq!(bitboard.count(), 3);
+        assert!(bitboard.contains(9));
+        bitboard.clear(9);
+        assert_eq!(bitboard.squares().collect::<Vec<_>>(), vec![0, 63]);
+        assert_eq!(bitboard.first(), Some(0));
+
+        let files = Bitboard::file(0) | Bitboard::file(7);
+        assert_eq!((files & Bitboard::rank(0)).count(), 2);
+        assert_eq!(!files ^ Bitboard::FULL, files);
+        assert!((Bitboard::rank(3) & Bitboard::rank(4)).is_empty());
+    }
+
+    #[test]
+    fn shifts_drop_squares_that_leave_the_board() {
+        let corners: Bitboard = [0, 7, 56, 63].into_iter().collect();
+        assert_eq!(
+            corners.shift(Direction::East),
+            [1, 57].into_iter().collect()
+        );
+        assert_eq!(
+            corners.shift(Direction::NorthWest),
+            Bitboard::from_square(14)
+        );
+        assert!(Bitboard::rank(7).shift(Direction::North).is_empty());
+
+        let e4 = Bitboard::from_square(28);
+        let king_ring = Direction::ALL
+            .iter()
+            .fold(Bitboard::EMPTY, |ring, &direction| {
+                ring | e4.shift(direction)
+            });
+        assert_eq!(king_ring.count(), 8);
+    }
+
+    #[test]
+    fn debug_renders_an_eight_by_eight_grid() {
+        let rendered = format!("{:?}", Bitboard::from_square(4) | Bitboard::from_square(60));
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[1], "8 . . . . 1 . . .");
+        assert_eq!(lines[8], "1 . . . . 1 . . .");
+        assert_eq!(lines[9], "  a b c d e f g h");
+    }
+}
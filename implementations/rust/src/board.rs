@@ -1,8 +1,10 @@
+use crate::bitboard::Bitboard;
 use crate::types::*;
 use crate::zobrist;
 use crate::draw_detection;
 use std::fmt;
 
+#[derive(Clone)]
 pub struct Board {
     state: GameState,
 }
@@ -11,12 +13,14 @@ impl Board {
     pub fn new() -> Self {
         let mut state = GameState::new();
         state.zobrist_hash = zobrist::get_keys().compute_hash(&state);
+        state.pawn_hash = zobrist::get_keys().pawn_hash(&state.board);
         Self { state }
     }
 
     pub fn reset(&mut self) {
         self.state = GameState::new();
         self.state.zobrist_hash = zobrist::get_keys().compute_hash(&self.state);
+        self.state.pawn_hash = zobrist::get_keys().pawn_hash(&self.state.board);
     }
 
     pub fn get_piece(&self, square: Square) -> Option<Piece> {
@@ -27,6 +31,30 @@ impl Board {
         self.state.board[square] = piece;
     }
 
+    pub fn pieces(&self, piece_type: PieceType, color: Color) -> Bitboard {
+        self.squares_where(|piece| piece == Piece::new(piece_type, color))
+    }
+
+    pub fn occupied_by(&self, color: Color) -> Bitboard {
+        self.squares_where(|piece| piece.color == color)
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        self.squares_where(|_| true)
+    }
+
+    fn squares_where(&self, matches: impl Fn(Piece) -> bool) -> Bitboard {
+        (0..64)
+            .filter(|&square| self.state.board[square].is_some_and(&matches))
+            .collect()
+    }
+
+    pub fn king_square(&self, color: Color) -> Option<Square> {
+        (0..64).find(|&square| {
+            self.state.board[square] == Some(Piece::new(PieceType::King, color))
+        })
+    }
+
     pub fn get_turn(&self) -> Color {
         self.state.turn
     }
@@ -68,6 +96,10 @@ impl Board {
         self.state.zobrist_hash
     }
 
+    pub fn pawn_hash(&self) -> u64 {
+        self.state.pawn_hash
+    }
+
     pub fn get_draw_info(&self) -> String {
         format!("Repetition: {}, 50-move clock: {}", 
             draw_detection::is_draw_by_repetition(&self.state),
@@ -75,6 +107,9 @@ impl Board {
     }
 
     pub fn make_move(&mut self, chess_move: &Move) {
+        if chess_move.is_null() {
+            return self.make_null_move();
+        }
         let keys = zobrist::get_keys();
         let piece = self.get_piece(chess_move.from).expect("No piece at source square");
         
@@ -84,29 +119,39 @@ impl Board {
             en_passant_target: self.state.en_passant_target,
             halfmove_clock: self.state.halfmove_clock,
             zobrist_hash: self.state.zobrist_hash,
+            pawn_hash: self.state.pawn_hash,
         });
         self.state.position_history.push(self.state.zobrist_hash);
 
         let mut hash = self.state.zobrist_hash;
+        let mut pawn_hash = self.state.pawn_hash;
 
         // 1. Remove moving piece from source
         hash ^= keys.pieces[zobrist::piece_to_index(piece)][chess_move.from];
+        if piece.piece_type == PieceType::Pawn {
+            pawn_hash ^= keys.pieces[zobrist::piece_to_index(piece)][chess_move.from];
+        }
 
         // 2. Handle capture
         if let Some(captured_type) = chess_move.captured {
             let captured_color = piece.color.opposite();
             let captured_piece = Piece::new(captured_type, captured_color);
-            if chess_move.is_en_passant {
-                let captured_sq = if piece.color == Color::White {
+            let captured_sq = if chess_move.is_en_passant {
+                if piece.color == Color::White {
                     chess_move.to - 8
                 } else {
                     chess_move.to + 8
-                };
-                hash ^= keys.pieces[zobrist::piece_to_index(captured_piece)][captured_sq];
-                self.set_piece(captured_sq, None);
+                }
             } else {
-                hash ^= keys.pieces[zobrist::piece_to_index(captured_piece)][chess_move.to];
                 // Piece at destination will be overwritten below
+                chess_move.to
+            };
+            hash ^= keys.pieces[zobrist::piece_to_index(captured_piece)][captured_sq];
+            if captured_type == PieceType::Pawn {
+                pawn_hash ^= keys.pieces[zobrist::piece_to_index(captured_piece)][captured_sq];
+            }
+            if chess_move.is_en_passant {
+                self.set_piece(captured_sq, None);
             }
             self.state.halfmove_clock = 0;
         } else if piece.piece_type == PieceType::Pawn {
@@ -122,6 +167,9 @@ impl Board {
             self.set_piece(chess_move.to, Some(promo_piece));
         } else {
             hash ^= keys.pieces[zobrist::piece_to_index(piece)][chess_move.to];
+            if piece.piece_type == PieceType::Pawn {
+                pawn_hash ^= keys.pieces[zobrist::piece_to_index(piece)][chess_move.to];
+            }
             self.set_piece(chess_move.to, Some(piece));
         }
         self.set_piece(chess_move.from, None);
@@ -191,14 +239,56 @@ impl Board {
         }
 
         self.state.zobrist_hash = hash;
+        self.state.pawn_hash = pawn_hash;
         self.state.move_history.push(chess_move.clone());
     }
 
+    // Hands the turn to the opponent without moving, for analysis. Recorded like any other move
+    // so `undo_move` takes it back; the caller must not pass while in check.
+    pub fn make_null_move(&mut self) {
+        let keys = zobrist::get_keys();
+        self.state.irreversible_history.push(IrreversibleState {
+            castling_rights: self.state.castling_rights,
+            en_passant_target: self.state.en_passant_target,
+            halfmove_clock: self.state.halfmove_clock,
+            zobrist_hash: self.state.zobrist_hash,
+            pawn_hash: self.state.pawn_hash,
+        });
+        self.state.position_history.push(self.state.zobrist_hash);
+
+        let mut hash = self.state.zobrist_hash;
+        if let Some(sq) = self.state.en_passant_target.take() {
+            hash ^= keys.en_passant[sq % 8];
+        }
+        hash ^= keys.side_to_move;
+        self.state.halfmove_clock += 1;
+        if self.state.turn == Color::Black {
+            self.state.fullmove_number += 1;
+        }
+        self.state.turn = self.state.turn.opposite();
+
+        self.state.zobrist_hash = hash;
+        self.state.move_history.push(Move::null());
+    }
+
     pub fn undo_move(&mut self) -> Option<Move> {
         let chess_move = self.state.move_history.pop()?;
         let old_state = self.state.irreversible_history.pop().expect("No irreversible history");
         self.state.position_history.pop();
 
+        if chess_move.is_null() {
+            self.state.castling_rights = old_state.castling_rights;
+            self.state.en_passant_target = old_state.en_passant_target;
+            self.state.halfmove_clock = old_state.halfmove_clock;
+            self.state.zobrist_hash = old_state.zobrist_hash;
+            self.state.pawn_hash = old_state.pawn_hash;
+            self.state.turn = self.state.turn.opposite();
+            if self.state.turn == Color::Black {
+                self.state.fullmove_number -= 1;
+            }
+            return Some(chess_move);
+        }
+
         // Get the piece that was moved
         let moved_piece = self.get_piece(chess_move.to).expect("No piece at destination");
         
@@ -207,6 +297,7 @@ impl Board {
         self.state.en_passant_target = old_state.en_passant_target;
         self.state.halfmove_clock = old_state.halfmove_clock;
         self.state.zobrist_hash = old_state.zobrist_hash;
+        self.state.pawn_hash = old_state.pawn_hash;
 
         // Restore the original piece (handle promotion)
         let original_piece = if chess_move.promotion.is_some() {
@@ -262,6 +353,12 @@ impl Board {
     }
 }
 
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "  a b c d e f g h")?;
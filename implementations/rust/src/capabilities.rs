@@ -0,0 +1,158 @@
+use std::path::Path;
+
+// An optional data file the engine looks for, named on the command line (`--book <file>` or
+// `--book=<file>`) or in the environment. Tablebases are a directory.
+pub struct DataFile {
+    pub name: &'static str,
+    pub flag: &'static str,
+    pub env: &'static str,
+    pub directory: bool,
+    // Whether this engine has a use for the file yet
+    pub supported: bool,
+}
+
+pub const DATA_FILES: [DataFile; 3] = [
+    DataFile {
+        name: "book",
+        flag: "--book",
+        env: "CHESS_BOOK",
+        directory: false,
+        supported: true,
+    },
+    DataFile {
+        name: "tablebases",
+        flag: "--tablebases",
+        env: "CHESS_TABLEBASES",
+        directory: true,
+        supported: false,
+    },
+    DataFile {
+        name: "network",
+        flag: "--network",
+        env: "CHESS_NETWORK",
+        directory: false,
+        supported: false,
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    NotConfigured,
+    // Configured, but not there: whatever needs it stays off
+    Missing,
+    Found,
+    // There, but this engine has no use for it yet
+    Unsupported,
+}
+
+impl Status {
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::NotConfigured => "not_configured",
+            Status::Missing => "missing",
+            Status::Found => "found",
+            Status::Unsupported => "unsupported",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capability {
+    pub name: &'static str,
+    pub path: Option<String>,
+    pub status: Status,
+}
+
+impl Capability {
+    // `name=book; status=found; path=...`
+    pub fn describe(&self) -> String {
+        let mut text = format!("name={}; status={}", self.name, self.status.name());
+        if let Some(path) = &self.path {
+            text.push_str(&format!("; path={}", path));
+        }
+        text
+    }
+
+    // What to tell the user at startup about a file that was asked for but can't be used.
+    pub fn warning(&self) -> Option<String> {
+        let path = self.path.as_deref().unwrap_or_default();
+        match self.status {
+            Status::Missing => Some(format!("Cannot find {} '{}', starting without it", self.name, path)),
+            Status::Unsupported => Some(format!("Found {} '{}', but this engine cannot use it yet", self.name, path)),
+            Status::NotConfigured | Status::Found => None,
+        }
+    }
+}
+
+// The flag's value wins over the environment variable.
+pub fn configured_path(args: &[String], file: &DataFile, env: Option<String>) -> Option<String> {
+    let prefix = format!("{}=", file.flag);
+    args.iter()
+        .position(|arg| arg == file.flag)
+        .and_then(|index| args.get(index + 1).cloned())
+        .or_else(|| args.iter().find_map(|arg| arg.strip_prefix(&prefix).map(str::to_string)))
+        .or(env)
+        .filter(|path| !path.is_empty())
+}
+
+pub fn probe(file: &DataFile, path: Option<String>) -> Capability {
+    let status = match &path {
+        None => Status::NotConfigured,
+        Some(path) => {
+            let path = Path::new(path);
+            let present = if file.directory { path.is_dir() } else { path.is_file() };
+            match (present, file.supported) {
+                (false, _) => Status::Missing,
+                (true, true) => Status::Found,
+                (true, false) => Status::Unsupported,
+            }
+        }
+    };
+    Capability {
+        name: file.name,
+        path,
+        status,
+    }
+}
+
+// Every data file, as this process was started with them.
+pub fn probe_all() -> Vec<Capability> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    DATA_FILES
+        .iter()
+        .map(|file| probe(file, configured_path(&args, file, std::env::var(file.env).ok())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probes_configured_paths_and_tells_what_is_missing() {
+        let args: Vec<String> = ["--book", "a.book", "--network=b.nnue"].map(String::from).to_vec();
+        let [book, tablebases, network] = &DATA_FILES;
+        assert_eq!(configured_path(&args, book, Some("env.book".to_string())).as_deref(), Some("a.book"));
+        assert_eq!(configured_path(&args, network, None).as_deref(), Some("b.nnue"));
+        assert_eq!(configured_path(&args, tablebases, Some("/tb".to_string())).as_deref(), Some("/tb"));
+        assert_eq!(configured_path(&[], tablebases, Some(String::new())), None);
+
+        let dir = std::env::temp_dir();
+        let file = dir.join(format!("capabilities-{}.book", std::process::id()));
+        std::fs::write(&file, "").unwrap();
+        let file_path = file.to_string_lossy().to_string();
+        let dir_path = dir.to_string_lossy().to_string();
+
+        assert_eq!(probe(book, Some(file_path.clone())).status, Status::Found);
+        assert_eq!(probe(book, Some(dir_path.clone())).status, Status::Missing);
+        assert_eq!(probe(tablebases, Some(dir_path)).status, Status::Unsupported);
+        assert_eq!(probe(network, Some(file_path.clone())).status, Status::Unsupported);
+        let unconfigured = probe(network, None);
+        assert_eq!((unconfigured.describe(), unconfigured.warning()), ("name=network; status=not_configured".to_string(), None));
+        std::fs::remove_file(&file).unwrap();
+
+        let missing = probe(book, Some(file_path.clone()));
+        assert_eq!(missing.describe(), format!("name=book; status=missing; path={}", file_path));
+        assert_eq!(missing.warning(), Some(format!("Cannot find book '{}', starting without it", file_path)));
+    }
+}
@@ -0,0 +1,114 @@
+// The system clipboard, through the platform's own tools, since the crate has no dependency for
+// it: `pbcopy`/`pbpaste` on macOS, `clip`/PowerShell on Windows, and on other systems
+// `wl-copy`/`wl-paste` under Wayland or `xclip`/`xsel` under X11. A system with neither display
+// (a server, a container, an SSH session) has no clipboard, and says so instead of hanging.
+
+// A program and its arguments, for copying (text on stdin) or pasting (text on stdout).
+#[cfg(any(feature = "clipboard", test))]
+type Tool = (&'static str, &'static [&'static str]);
+
+// The tools to try in turn, for copying and for pasting.
+#[cfg(any(feature = "clipboard", test))]
+fn candidates(os: &str, wayland: bool, x11: bool) -> (Vec<Tool>, Vec<Tool>) {
+    match os {
+        "macos" => (vec![("pbcopy", &[])], vec![("pbpaste", &[])]),
+        "windows" => (
+            vec![("clip", &[])],
+            vec![("powershell", &["-NoProfile", "-Command", "Get-Clipboard -Raw"])],
+        ),
+        _ => {
+            let (mut copy, mut paste) = (Vec::new(), Vec::new());
+            if wayland {
+                copy.push(("wl-copy", &[][..]));
+                paste.push(("wl-paste", &["--no-newline"][..]));
+            }
+            if x11 {
+                copy.extend([("xclip", &["-selection", "clipboard"][..]), ("xsel", &["--clipboard", "--input"][..])]);
+                paste.extend([("xclip", &["-selection", "clipboard", "-o"][..]), ("xsel", &["--clipboard", "--output"][..])]);
+            }
+            (copy, paste)
+        }
+    }
+}
+
+#[cfg(feature = "clipboard")]
+fn system_candidates() -> Result<(Vec<Tool>, Vec<Tool>), &'static str> {
+    let present = |name| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+    let tools = candidates(std::env::consts::OS, present("WAYLAND_DISPLAY"), present("DISPLAY"));
+    if tools.0.is_empty() {
+        return Err("No clipboard on this system");
+    }
+    Ok(tools)
+}
+
+#[cfg(feature = "clipboard")]
+pub fn copy(text: &str) -> Result<(), &'static str> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    for (program, args) in system_candidates()?.0 {
+        let Ok(mut child) = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        let written = child.stdin.take().is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        let status = child.wait();
+        return match status {
+            Ok(status) if written && status.success() => Ok(()),
+            _ => Err("Cannot write to the clipboard"),
+        };
+    }
+    Err("No clipboard tool found")
+}
+
+#[cfg(feature = "clipboard")]
+pub fn paste() -> Result<String, &'static str> {
+    use std::process::{Command, Stdio};
+
+    for (program, args) in system_candidates()?.1 {
+        let Ok(output) = Command::new(program).args(args).stderr(Stdio::null()).output() else {
+            continue;
+        };
+        if !output.status.success() {
+            return Err("Cannot read the clipboard");
+        }
+        return String::from_utf8(output.stdout).map_err(|_| "Cannot read the clipboard");
+    }
+    Err("No clipboard tool found")
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn copy(_text: &str) -> Result<(), &'static str> {
+    Err("copy and paste need a build with the clipboard feature")
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn paste() -> Result<String, &'static str> {
+    Err("copy and paste need a build with the clipboard feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn programs(tools: &[Tool]) -> Vec<&'static str> {
+        tools.iter().map(|(program, _)| *program).collect()
+    }
+
+    #[test]
+    fn picks_the_tools_of_the_running_display() {
+        let (copy, paste) = candidates("linux", true, true);
+        assert_eq!(programs(&copy), vec!["wl-copy", "xclip", "xsel"]);
+        assert_eq!(programs(&paste), vec!["wl-paste", "xclip", "xsel"]);
+        assert_eq!(programs(&candidates("linux", false, true).0), vec!["xclip", "xsel"]);
+        assert_eq!(programs(&candidates("macos", false, false).1), vec!["pbpaste"]);
+        assert_eq!(programs(&candidates("windows", false, false).0), vec!["clip"]);
+        // Headless: nothing to try
+        assert_eq!(candidates("linux", false, false), (vec![], vec![]));
+    }
+}
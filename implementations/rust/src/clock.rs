@@ -0,0 +1,185 @@
+use crate::board::Board;
+use crate::search_limits::SearchLimits;
+use crate::types::Color;
+use std::time::Instant;
+
+// A chess clock for games played at the REPL, `<base>+<increment>` as in `5+3`: the side to
+// move's time runs from the previous move (or from when the clock was set) until it moves, and
+// each move adds the increment. The clock doesn't watch commands itself: the REPL shows it the
+// board after each one, and it charges whatever moves were made since. Moves taken back or a
+// new position stop nothing and give no time back; the time already used stays used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameClock {
+    pub base_ms: u64,
+    pub increment_ms: u64,
+    // White's and Black's time left when their turn began
+    remaining_ms: [u64; 2],
+    turn_started: Instant,
+    // Plies in the game when the clock last looked, to count the moves made since
+    plies: usize,
+    pub flagged: Option<Color>,
+}
+
+fn index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+// `m:ss.s`
+pub fn format_clock(ms: u64) -> String {
+    format!("{}:{:02}.{}", ms / 60_000, ms / 1000 % 60, ms / 100 % 10)
+}
+
+impl GameClock {
+    // `<minutes>+<seconds>`: `5+3`, `90+30`, `0.5+0` or `3` with no increment
+    pub fn parse(text: &str) -> Option<(u64, u64)> {
+        let (base, increment) = text.split_once('+').unwrap_or((text, "0"));
+        let minutes = base.parse::<f64>().ok().filter(|minutes| *minutes > 0.0 && *minutes <= 24.0 * 60.0)?;
+        let seconds = increment.parse::<u64>().ok().filter(|&seconds| seconds <= 3600)?;
+        Some(((minutes * 60_000.0).round() as u64, seconds * 1000))
+    }
+
+    pub fn new(base_ms: u64, increment_ms: u64, board: &Board, now: Instant) -> Self {
+        Self {
+            base_ms,
+            increment_ms,
+            remaining_ms: [base_ms; 2],
+            turn_started: now,
+            plies: board.get_state().move_history.len(),
+            flagged: None,
+        }
+    }
+
+    // Time left, counting down for the side to move. Moves the clock hasn't seen yet are charged
+    // as `update` would, so a command can show the clocks right after its move.
+    pub fn remaining(&self, color: Color, board: &Board, now: Instant) -> u64 {
+        if board.get_state().move_history.len() != self.plies {
+            let mut clock = self.clone();
+            clock.update(board, now);
+            return clock.remaining(color, board, now);
+        }
+        let left = self.remaining_ms[index(color)];
+        if color == board.get_turn() && self.flagged.is_none() {
+            left.saturating_sub(now.duration_since(self.turn_started).as_millis() as u64)
+        } else {
+            left
+        }
+    }
+
+    // The side to move, the first time its flag is found down.
+    pub fn check_flag(&mut self, board: &Board, now: Instant) -> Option<Color> {
+        self.update(board, now);
+        let color = board.get_turn();
+        if self.flagged.is_some() || self.remaining(color, board, now) > 0 {
+            return None;
+        }
+        self.remaining_ms[index(color)] = 0;
+        self.flagged = Some(color);
+        self.flagged
+    }
+
+    // Charges the moves made since the clock last looked: the time since the turn began to the
+    // first mover, then the increment to every mover. Several moves at once (a `playout`) only
+    // cost the first.
+    pub fn update(&mut self, board: &Board, now: Instant) {
+        let plies = board.get_state().move_history.len();
+        if self.flagged.is_none() && plies > self.plies {
+            let moves = plies - self.plies;
+            let mut mover = if moves % 2 == 0 { board.get_turn() } else { board.get_turn().opposite() };
+            let elapsed = now.duration_since(self.turn_started).as_millis() as u64;
+            let left = &mut self.remaining_ms[index(mover)];
+            *left = left.saturating_sub(elapsed);
+            for _ in 0..moves {
+                self.remaining_ms[index(mover)] += self.increment_ms;
+                mover = mover.opposite();
+            }
+        }
+        if plies != self.plies {
+            self.plies = plies;
+            self.turn_started = now;
+        }
+    }
+
+    // Both clocks as they stand, for the engine's time manager
+    pub fn limits(&self, board: &Board, now: Instant) -> SearchLimits {
+        SearchLimits {
+            wtime_ms: Some(self.remaining(Color::White, board, now)),
+            btime_ms: Some(self.remaining(Color::Black, board, now)),
+            winc_ms: Some(self.increment_ms),
+            binc_ms: Some(self.increment_ms),
+            ..SearchLimits::default()
+        }
+    }
+
+    // `white=4:59.8; black=5:00.0; control=5+3; running=white`, or `flag=white` once it fell
+    pub fn describe(&self, board: &Board, now: Instant) -> String {
+        let state = match self.flagged {
+            Some(Color::White) => "flag=white".to_string(),
+            Some(Color::Black) => "flag=black".to_string(),
+            None if board.get_turn() == Color::White => "running=white".to_string(),
+            None => "running=black".to_string(),
+        };
+        format!(
+            "white={}; black={}; control={}+{}; {}",
+            format_clock(self.remaining(Color::White, board, now)),
+            format_clock(self.remaining(Color::Black, board, now)),
+            self.base_ms as f64 / 60_000.0,
+            self.increment_ms / 1000,
+            state
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::SanParser;
+    use std::time::Duration;
+
+    fn play(board: &mut Board, san: &str) {
+        let chess_move = SanParser::new().parse_san(board, san).unwrap();
+        board.make_move(&chess_move);
+    }
+
+    #[test]
+    fn charges_each_side_its_thinking_time_and_flags_it() {
+        assert_eq!(GameClock::parse("5+3"), Some((300_000, 3000)));
+        assert_eq!(GameClock::parse("0.5"), Some((30_000, 0)));
+        for text in ["0+1", "5+", "5+-1", "+3", "five"] {
+            assert_eq!(GameClock::parse(text), None, "{}", text);
+        }
+
+        let start = Instant::now();
+        let at = |seconds: u64| start + Duration::from_secs(seconds);
+        let mut board = Board::new();
+        let mut clock = GameClock::new(60_000, 2000, &board, start);
+        assert_eq!(clock.remaining(Color::White, &board, at(10)), 50_000);
+
+        // White takes 10s, Black 25s, then both moves of a playout cost White 5s
+        play(&mut board, "e4");
+        assert_eq!(clock.remaining(Color::White, &board, at(10)), 52_000);
+        clock.update(&board, at(10));
+        play(&mut board, "e5");
+        clock.update(&board, at(35));
+        assert_eq!(clock.describe(&board, at(35)), "white=0:52.0; black=0:37.0; control=1+2; running=white");
+        play(&mut board, "Nf3");
+        play(&mut board, "Nc6");
+        clock.update(&board, at(40));
+        assert_eq!((clock.remaining(Color::White, &board, at(40)), clock.remaining(Color::Black, &board, at(40))), (49_000, 39_000));
+        let limits = clock.limits(&board, at(41));
+        assert_eq!((limits.wtime_ms, limits.btime_ms, limits.winc_ms), (Some(48_000), Some(39_000), Some(2000)));
+
+        // Taking a move back refunds nothing
+        board.undo_move();
+        clock.update(&board, at(45));
+        assert_eq!(clock.remaining(Color::Black, &board, at(45)), 39_000);
+
+        assert_eq!(clock.check_flag(&board, at(83)), None);
+        assert_eq!(clock.check_flag(&board, at(84)), Some(Color::Black));
+        assert_eq!(clock.check_flag(&board, at(85)), None);
+        assert_eq!(clock.describe(&board, at(90)), "white=0:49.0; black=0:00.0; control=1+2; flag=black");
+        assert_eq!(format_clock(3_723_456), "62:03.4");
+    }
+}
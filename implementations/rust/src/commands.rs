@@ -0,0 +1,663 @@
+use crate::search_limits;
+
+// Usage and description for every REPL command, in the order `help` lists them.
+const HELP: [(&str, &str); 67] = [
+    (
+        "move <from><to>[promotion] | move <san>",
+        "Make a move (e.g., e2e4, e7e8Q, Nf3, O-O, exd5)",
+    ),
+    (
+        "playout <moves...>",
+        "Play a line of moves; if any is illegal, none of them are played",
+    ),
+    ("undo", "Undo the last move"),
+    ("new", "Start a new game"),
+    (
+        "clock [<minutes>+<seconds>|off]",
+        "Game clock for both sides; ai plays on its time left and a flag loses the game",
+    ),
+    ("fen <string>", "Load position from FEN"),
+    ("export", "Export current position as FEN"),
+    (
+        "state json",
+        "The whole game state (pieces, rights, clocks, legal moves, result) as versioned JSON",
+    ),
+    (
+        "log [show|save <file>]",
+        "Event log of every change to the game and options, hash-chained; save keeps the file growing",
+    ),
+    (
+        "verify log <file>",
+        "Replay a saved event log and check every position and chain hash",
+    ),
+    (
+        "copy fen|pgn / paste fen",
+        "Copy the position or game to the system clipboard, or load a FEN from it",
+    ),
+    ("eval", "Static evaluation plus a depth-1 search score"),
+    (
+        "eval material on|off|status",
+        "Evaluate with piece values only (see the *Value options)",
+    ),
+    ("pv", "Show the last search's principal variation"),
+    (
+        "notation coordinate|san|status",
+        "Write moves in output as coordinates (e7e8q, as UCI does) or SAN",
+    ),
+    ("hash", "Show Zobrist hash of current position"),
+    (
+        "hash save|load <file>",
+        "Save the transposition table or restore a saved one",
+    ),
+    (
+        "hash table",
+        "Transposition table size and whether it got large pages",
+    ),
+    ("draws", "Show draw detection status"),
+    (
+        "material [json]",
+        "Material points, difference and the pieces each side has captured",
+    ),
+    (
+        "heatmap [white|black] [json|svg <file>]",
+        "How many times each side attacks or defends every square",
+    ),
+    ("history", "Show position hash history"),
+    (
+        "motifs [white|black]",
+        "Detect pins, forks, skewers and other tactics",
+    ),
+    ("structure", "Name the pawn structure and its typical plans"),
+    (
+        "explain <move> [depth]",
+        "Explain what a move does and how it scores",
+    ),
+    (
+        "why <move>",
+        "Say why a move is illegal: the pin, check or blocker behind it",
+    ),
+    (
+        "pass / null",
+        "Give the turn to the opponent without moving; analysis only, not in check",
+    ),
+    (
+        "threat [depth]",
+        "Show what the opponent would play if the side to move passed",
+    ),
+    (
+        "blindfold on|off|status",
+        "Hide the board and accept SAN moves",
+    ),
+    (
+        "display options [<name> <value>]...",
+        "Board labels, perspective, side to move, FEN and move number under the board; kept in the profile",
+    ),
+    (
+        "peek",
+        "Show the board during blindfold play (counts as a penalty)",
+    ),
+    (
+        "quiz color|knight",
+        "Square color or knight path quiz; reply with answer <value>",
+    ),
+    (
+        "drill [white|black] <pgn-line> / drill status|stop",
+        "Replay an opening line against the engine; missed lines come back sooner",
+    ),
+    (
+        "go depth <n> | go movetime <ms> | go nodes <n> | go wtime <ms> btime <ms> [winc <ms>] [binc <ms>]",
+        "Same limits as ai; with the clock running, a bare go plays on it",
+    ),
+    (
+        "deterministic on|off|status",
+        "Reproducible searches: time limits counted in nodes, fresh hash table",
+    ),
+    (
+        "profile [blindfold on|off|time <limits>|time none|reset]",
+        "Show or change the saved profile: preferences and results against the engine",
+    ),
+    (
+        "option [<name> [<value>]]",
+        "List engine options or set one (time allocation policy)",
+    ),
+    (
+        "debug ordering <depth>",
+        "Root move order and heuristic scores after a search",
+    ),
+    (
+        "debug tree <depth> <file.dot|file.json> [min_visits]",
+        "Write the search tree, cut to subtrees of at least min_visits nodes",
+    ),
+    (
+        "pgn load|show|moves|export [file]",
+        "Load a PGN, or show and export the current game as PGN",
+    ),
+    (
+        "import lichess <id|url> | import url <address> [annotate [depth <n>]]",
+        "Download a game and load it; annotate marks its inaccuracies, mistakes and blunders",
+    ),
+    ("book load|stats", "Opening book command surface"),
+    (
+        "capabilities / reload",
+        "Which optional data files (book, tablebases, network) were found; reload looks again",
+    ),
+    (
+        "uci / isready",
+        "Switch to UCI protocol mode for GUIs and bots",
+    ),
+    ("new960 / position960", "Chess960 metadata"),
+    (
+        "trace on|off|level|report|reset|export|chrome",
+        "Trace diagnostics",
+    ),
+    (
+        "concurrency quick|full",
+        "Deterministic concurrency fixture",
+    ),
+    (
+        "analyze all depth <d>",
+        "Search every legal move to depth d in parallel, best first",
+    ),
+    (
+        "analyze <limits> [window <a> <b>] [min-rootdepth <n>] [no-<feature>]",
+        "Search without playing, with a root window, a minimum depth or features off",
+    ),
+    (
+        "replies [depth]",
+        "Shallow score of every legal move, and how much each loses against the best",
+    ),
+    (
+        "threads [pin on|off]",
+        "CPU and NUMA topology; pin analyze all workers to CPUs across nodes",
+    ),
+    (
+        "cpuinfo",
+        "CPU features detected at startup and the kernels picked for them",
+    ),
+    (
+        "bench [lookups]",
+        "Time sliding attack lookups: ray walks, magics, PEXT and the engine's choice",
+    ),
+    (
+        "sharpness",
+        "How hard the position is to play (0-100), and what makes it so",
+    ),
+    (
+        "oracle start <engine> [args]|stop|threshold <cp>",
+        "Run an external UCI engine to check this engine against",
+    ),
+    (
+        "oracle compare [depth] | oracle suite <file> [depth]",
+        "Compare best moves and scores with the oracle; suite lists disagreements",
+    ),
+    (
+        "selfplay <games> <side> [vs <side>] [odds <a>:<b>] [adjudicate win <cp> draw <cp> moves <n> [oracle]] [openings <file>]",
+        "Engine-vs-engine games from the current position or a suite, saved to the results database",
+    ),
+    (
+        "ablate <games> [nodes <n>] [term <name>] [openings <file>]",
+        "Elo of each evaluation term, from fixed-node games against the engine without it",
+    ),
+    (
+        "sprt <games> <side> [vs <side>] [elo0 <e>] [elo1 <e>] [alpha <p>] [beta <p>] [openings <file>]",
+        "Accept or reject a patch by sequential probability ratio test against the base engine",
+    ),
+    (
+        "openings <pgn> <count> <file.epd|file.pgn> [plies <n>] [window <cp>]",
+        "Pick balanced openings across ECO codes from a PGN database as a test suite",
+    ),
+    ("ratings", "Elo ratings of every player in the results database"),
+    (
+        "solve mate|helpmate|selfmate <n>",
+        "Solve a problem in n moves of the side to move; lists every solution",
+    ),
+    (
+        "perft [captures|checks|fast] <depth> [threads <n>] [hash <mb>] [json] / perft suite [depth <n>] [file <epd>] / divide <depth> [moves...] [json]",
+        "Run performance test (only capture or checking leaves; suite checks known counts; divide lists per-move counts, after the moves given)",
+    ),
+    (
+        "testpos [<name>]",
+        "Load a named debugging position (kiwipete, position3-6, lasker-trap, ...)",
+    ),
+    (
+        "complete <partial command>",
+        "List completions for a partial command line",
+    ),
+    ("help", "Show this help message"),
+    ("quit", "Exit the program"),
+];
+
+pub fn help_entries() -> Vec<(&'static str, &'static str)> {
+    let mut entries = Vec::new();
+    for entry in HELP {
+        entries.push(entry);
+        // The search limits own their usage text; list it with the other game commands.
+        if entry.0 == "new" {
+            entries.extend(search_limits::USAGE);
+        }
+    }
+    entries
+}
+
+// What a positional argument accepts, for completion and other tooling that needs more than
+// the prose in the help table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Argument {
+    Keyword(&'static [&'static str]),
+    Move,
+    Fen,
+    Value,
+}
+
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub arguments: &'static [Argument],
+}
+
+const SEARCH_LIMITS: Argument = Argument::Keyword(&[
+    "depth",
+    "movetime",
+    "nodes",
+    "wtime",
+    "btime",
+    "winc",
+    "binc",
+    "movestogo",
+]);
+
+// Every command the REPL dispatches, including aliases that have no help line of their own.
+pub const COMMANDS: [CommandSpec; 71] = [
+    CommandSpec {
+        name: "move",
+        arguments: &[Argument::Move],
+    },
+    CommandSpec {
+        name: "playout",
+        arguments: &[Argument::Move],
+    },
+    CommandSpec {
+        name: "undo",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "new",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "status",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "ai",
+        arguments: &[
+            SEARCH_LIMITS,
+            Argument::Value,
+            SEARCH_LIMITS,
+            Argument::Value,
+        ],
+    },
+    CommandSpec {
+        name: "fen",
+        arguments: &[Argument::Fen],
+    },
+    CommandSpec {
+        name: "export",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "state",
+        arguments: &[Argument::Keyword(&["json"])],
+    },
+    CommandSpec {
+        name: "log",
+        arguments: &[Argument::Keyword(&["show", "save"]), Argument::Value],
+    },
+    CommandSpec {
+        name: "verify",
+        arguments: &[Argument::Keyword(&["log"]), Argument::Value],
+    },
+    CommandSpec {
+        name: "copy",
+        arguments: &[Argument::Keyword(&["fen", "pgn"])],
+    },
+    CommandSpec {
+        name: "paste",
+        arguments: &[Argument::Keyword(&["fen"])],
+    },
+    CommandSpec {
+        name: "eval",
+        arguments: &[
+            Argument::Keyword(&["material"]),
+            Argument::Keyword(&["on", "off", "status"]),
+        ],
+    },
+    CommandSpec {
+        name: "pv",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "notation",
+        arguments: &[Argument::Keyword(&["coordinate", "san", "status"])],
+    },
+    CommandSpec {
+        name: "hash",
+        arguments: &[Argument::Keyword(&["save", "load", "table"]), Argument::Value],
+    },
+    CommandSpec {
+        name: "draws",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "material",
+        arguments: &[Argument::Keyword(&["json"])],
+    },
+    CommandSpec {
+        name: "heatmap",
+        arguments: &[
+            Argument::Keyword(&["white", "black", "json", "svg"]),
+            Argument::Keyword(&["json", "svg"]),
+            Argument::Value,
+        ],
+    },
+    CommandSpec {
+        name: "history",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "motifs",
+        arguments: &[Argument::Keyword(&["white", "black"])],
+    },
+    CommandSpec {
+        name: "structure",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "explain",
+        arguments: &[Argument::Move, Argument::Value],
+    },
+    CommandSpec {
+        name: "why",
+        arguments: &[Argument::Move],
+    },
+    CommandSpec {
+        name: "pass",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "null",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "threat",
+        arguments: &[Argument::Value],
+    },
+    CommandSpec {
+        name: "display",
+        arguments: &[
+            Argument::Keyword(&["options"]),
+            Argument::Keyword(&["coordinates", "perspective", "turn", "fen", "movenumber"]),
+            Argument::Keyword(&["on", "off", "white", "black"]),
+        ],
+    },
+    CommandSpec {
+        name: "blindfold",
+        arguments: &[Argument::Keyword(&["on", "off", "status"])],
+    },
+    CommandSpec {
+        name: "peek",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "quiz",
+        arguments: &[Argument::Keyword(&["color", "knight"])],
+    },
+    CommandSpec {
+        name: "answer",
+        arguments: &[Argument::Value],
+    },
+    CommandSpec {
+        name: "drill",
+        arguments: &[Argument::Keyword(&["white", "black", "status", "stop"])],
+    },
+    CommandSpec {
+        name: "go",
+        arguments: &[
+            SEARCH_LIMITS,
+            Argument::Value,
+            SEARCH_LIMITS,
+            Argument::Value,
+            SEARCH_LIMITS,
+            Argument::Value,
+            SEARCH_LIMITS,
+            Argument::Value,
+        ],
+    },
+    CommandSpec {
+        name: "deterministic",
+        arguments: &[Argument::Keyword(&["on", "off", "status"])],
+    },
+    CommandSpec {
+        name: "option",
+        arguments: &[Argument::Value, Argument::Value],
+    },
+    CommandSpec {
+        name: "debug",
+        arguments: &[
+            Argument::Keyword(&["ordering", "tree"]),
+            Argument::Value,
+            Argument::Value,
+            Argument::Value,
+        ],
+    },
+    CommandSpec {
+        name: "pgn",
+        arguments: &[
+            Argument::Keyword(&["load", "show", "moves", "export"]),
+            Argument::Value,
+        ],
+    },
+    CommandSpec {
+        name: "import",
+        arguments: &[
+            Argument::Keyword(&["lichess", "url"]),
+            Argument::Value,
+            Argument::Keyword(&["annotate"]),
+            Argument::Keyword(&["depth"]),
+            Argument::Value,
+        ],
+    },
+    CommandSpec {
+        name: "clock",
+        arguments: &[Argument::Keyword(&["off"])],
+    },
+    CommandSpec {
+        name: "book",
+        arguments: &[Argument::Keyword(&["load", "stats"]), Argument::Value],
+    },
+    CommandSpec {
+        name: "capabilities",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "reload",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "uci",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "isready",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "ucinewgame",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "new960",
+        arguments: &[Argument::Value],
+    },
+    CommandSpec {
+        name: "position960",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "trace",
+        arguments: &[
+            Argument::Keyword(&["on", "off", "level", "report", "reset", "export", "chrome"]),
+            Argument::Value,
+        ],
+    },
+    CommandSpec {
+        name: "concurrency",
+        arguments: &[Argument::Keyword(&["quick", "full"])],
+    },
+    CommandSpec {
+        name: "profile",
+        arguments: &[
+            Argument::Keyword(&["blindfold", "time", "reset"]),
+            Argument::Value,
+        ],
+    },
+    CommandSpec {
+        name: "analyze",
+        arguments: &[
+            Argument::Keyword(&[
+                "all",
+                "depth",
+                "movetime",
+                "nodes",
+                "window",
+                "min-rootdepth",
+                "no-pvs",
+                "no-ttcutoff",
+                "no-matedistance",
+                "no-correction",
+            ]),
+            Argument::Keyword(&["depth"]),
+            Argument::Value,
+        ],
+    },
+    CommandSpec {
+        name: "replies",
+        arguments: &[Argument::Value],
+    },
+    CommandSpec {
+        name: "threads",
+        arguments: &[Argument::Keyword(&["pin"]), Argument::Keyword(&["on", "off"])],
+    },
+    CommandSpec {
+        name: "cpuinfo",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "bench",
+        arguments: &[Argument::Value],
+    },
+    CommandSpec {
+        name: "sharpness",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "oracle",
+        arguments: &[
+            Argument::Keyword(&["start", "stop", "threshold", "compare", "suite"]),
+            Argument::Value,
+        ],
+    },
+    CommandSpec {
+        name: "selfplay",
+        arguments: &[Argument::Value, SEARCH_LIMITS, Argument::Value],
+    },
+    CommandSpec {
+        name: "ablate",
+        arguments: &[
+            Argument::Value,
+            Argument::Keyword(&["nodes", "term", "openings"]),
+            Argument::Value,
+            Argument::Keyword(&["nodes", "term", "openings"]),
+        ],
+    },
+    CommandSpec {
+        name: "sprt",
+        arguments: &[Argument::Value, SEARCH_LIMITS, Argument::Value],
+    },
+    CommandSpec {
+        name: "ratings",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "openings",
+        arguments: &[
+            Argument::Value,
+            Argument::Value,
+            Argument::Value,
+            Argument::Keyword(&["plies", "window"]),
+        ],
+    },
+    CommandSpec {
+        name: "solve",
+        arguments: &[
+            Argument::Keyword(&["mate", "helpmate", "selfmate"]),
+            Argument::Value,
+        ],
+    },
+    CommandSpec {
+        name: "perft",
+        arguments: &[Argument::Value, Argument::Keyword(&["json"])],
+    },
+    CommandSpec {
+        name: "divide",
+        arguments: &[Argument::Value, Argument::Keyword(&["json"])],
+    },
+    CommandSpec {
+        name: "testpos",
+        arguments: &[Argument::Keyword(&[
+            "startpos",
+            "kiwipete",
+            "position2",
+            "position3",
+            "position4",
+            "position5",
+            "position6",
+            "lasker-trap",
+            "behting-study",
+        ])],
+    },
+    CommandSpec {
+        name: "complete",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "help",
+        arguments: &[],
+    },
+    CommandSpec {
+        name: "quit",
+        arguments: &[],
+    },
+];
+
+pub fn command(name: &str) -> Option<&'static CommandSpec> {
+    COMMANDS.iter().find(|spec| spec.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_help_line_names_a_known_command() {
+        for (usage, _) in help_entries() {
+            for alternative in usage.split(" / ").flat_map(|part| part.split(" | ")) {
+                let name = alternative.split_whitespace().next().unwrap();
+                assert!(
+                    command(name).is_some(),
+                    "`{}` is missing from COMMANDS",
+                    name
+                );
+            }
+        }
+    }
+}
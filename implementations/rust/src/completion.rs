@@ -0,0 +1,161 @@
+use crate::board::Board;
+use crate::commands::{self, Argument, CommandSpec};
+use crate::fen::FenParser;
+use crate::line_editor::{Completer, Completion};
+use crate::move_generator::MoveGenerator;
+use crate::notation::SanParser;
+
+// Completion for the interactive prompt and the `complete` command: command names, keyword
+// arguments, legal moves (whose first two characters complete the source square) and FEN
+// snippets for the current and starting positions.
+pub struct EngineCompleter<'a> {
+    pub board: &'a mut Board,
+    pub move_generator: &'a MoveGenerator,
+    pub san_parser: &'a SanParser,
+    pub fen_parser: &'a FenParser,
+    // Blindfold play accepts bare SAN moves in place of a command.
+    pub blindfold: bool,
+}
+
+impl Completer for EngineCompleter<'_> {
+    fn complete(&mut self, line: &str) -> Completion {
+        let words: Vec<(usize, &str)> = line
+            .split_whitespace()
+            .map(|word| (word.as_ptr() as usize - line.as_ptr() as usize, word))
+            .collect();
+        let mut start = match words.last() {
+            Some((offset, _)) if !line.ends_with(char::is_whitespace) => *offset,
+            _ => line.len(),
+        };
+        let position = words.iter().filter(|(offset, _)| *offset < start).count();
+
+        let mut candidates = if position == 0 {
+            let mut names: Vec<String> = commands::COMMANDS
+                .iter()
+                .map(|spec| spec.name.to_string())
+                .collect();
+            if self.blindfold {
+                names.extend(self.san_moves());
+            }
+            names
+        } else {
+            let spec = resolve_command(&words[0].1.to_lowercase());
+            match spec.map(|spec| (spec.arguments.first(), spec.arguments.get(position - 1))) {
+                // A FEN spans several words, so the whole tail of the line is replaced.
+                Some((Some(Argument::Fen), _)) => {
+                    start = words.get(1).map_or(start, |(offset, _)| *offset);
+                    self.fen_snippets()
+                }
+                Some((_, Some(Argument::Move))) => self.coordinate_moves(),
+                Some((_, Some(Argument::Keyword(keywords)))) => {
+                    keywords.iter().map(|keyword| keyword.to_string()).collect()
+                }
+                _ => Vec::new(),
+            }
+        };
+
+        let typed = &line[start..];
+        candidates.retain(|candidate| candidate.starts_with(typed));
+        candidates.sort();
+        candidates.dedup();
+        Completion { start, candidates }
+    }
+}
+
+impl EngineCompleter<'_> {
+    fn coordinate_moves(&mut self) -> Vec<String> {
+        let turn = self.board.get_turn();
+        self.move_generator
+            .get_legal_moves(self.board, turn)
+            .iter()
+            .map(|chess_move| chess_move.to_string().to_lowercase())
+            .collect()
+    }
+
+    fn san_moves(&mut self) -> Vec<String> {
+        let turn = self.board.get_turn();
+        let moves = self.move_generator.get_legal_moves(self.board, turn);
+        moves
+            .iter()
+            .map(|chess_move| self.san_parser.export_san(self.board, chess_move))
+            .collect()
+    }
+
+    fn fen_snippets(&self) -> Vec<String> {
+        vec![
+            self.fen_parser.export_fen(self.board),
+            self.fen_parser.export_fen(&Board::new()),
+        ]
+    }
+}
+
+// Exact names win; otherwise an unambiguous prefix ("mov") stands for the command.
+fn resolve_command(word: &str) -> Option<&'static CommandSpec> {
+    commands::command(word).or_else(|| {
+        let mut matches = commands::COMMANDS
+            .iter()
+            .filter(|spec| spec.name.starts_with(word));
+        match (matches.next(), matches.next()) {
+            (Some(spec), None) => Some(spec),
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn complete(board: &mut Board, line: &str, blindfold: bool) -> Completion {
+        EngineCompleter {
+            board,
+            move_generator: &MoveGenerator::new(),
+            san_parser: &SanParser::new(),
+            fen_parser: &FenParser::new(),
+            blindfold,
+        }
+        .complete(line)
+    }
+
+    fn candidates(line: &str) -> Vec<String> {
+        complete(&mut Board::new(), line, false).candidates
+    }
+
+    #[test]
+    fn completes_commands_keywords_and_legal_moves() {
+        assert_eq!(candidates("mo"), vec!["motifs", "move"]);
+        assert!(candidates("").contains(&"ucinewgame".to_string()));
+        assert_eq!(candidates("blindfold o"), vec!["off", "on"]);
+        assert_eq!(candidates("go m"), vec!["movestogo", "movetime"]);
+        assert_eq!(candidates("mov e2"), vec!["e2e3", "e2e4"]);
+        assert_eq!(candidates("explain g1"), vec!["g1f3", "g1h3"]);
+        assert!(candidates("perft ").is_empty());
+
+        let completion = complete(&mut Board::new(), "move e2", false);
+        assert_eq!(completion.start, 5);
+    }
+
+    #[test]
+    fn completes_fen_snippets_across_words() {
+        let mut board = Board::new();
+        FenParser::new()
+            .parse_fen(&mut board, "4k3/8/8/8/8/8/8/4K2R w K - 0 1")
+            .unwrap();
+        let completion = complete(&mut board, "fen 4k3/8/8/8/8/8/8/4K2R w", false);
+        assert_eq!(completion.start, 4);
+        assert_eq!(
+            completion.candidates,
+            vec!["4k3/8/8/8/8/8/8/4K2R w K - 0 1"]
+        );
+
+        let completion = complete(&mut board, "fen ", false);
+        assert_eq!(completion.candidates.len(), 2);
+    }
+
+    #[test]
+    fn blindfold_completes_san_moves_at_the_prompt() {
+        let san = |blindfold| complete(&mut Board::new(), "N", blindfold).candidates;
+        assert_eq!(san(true), vec!["Na3", "Nc3", "Nf3", "Nh3"]);
+        assert!(san(false).is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::board::Board;
+use crate::types::Color;
+
+// Buckets per side to move, indexed by the low bits of the pawn hash.
+const BUCKETS: usize = 16384;
+// Corrections are stored with extra precision so small, repeated adjustments still register.
+const GRAIN: i32 = 256;
+const MAX_WEIGHT: i32 = 16;
+// A correction never moves the static eval by more than this, in centipawns.
+const LIMIT: i32 = 128;
+
+// Learns, during one search, how far the static evaluation tends to be from the search result for
+// positions with the same pawn structure, and nudges later static evaluations by that amount.
+pub struct CorrectionHistory {
+    table: Vec<i32>,
+}
+
+impl CorrectionHistory {
+    pub fn new() -> Self {
+        Self {
+            table: vec![0; 2 * BUCKETS],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.table.iter_mut().for_each(|entry| *entry = 0);
+    }
+
+    // Both scores are relative to the side to move; deeper searches carry more weight.
+    pub fn record(&mut self, board: &Board, static_eval: i32, search_score: i32, depth: u8) {
+        let index = Self::index(board);
+        let difference = (search_score - static_eval).clamp(-LIMIT, LIMIT) * GRAIN;
+        let weight = (i32::from(depth) + 1).min(MAX_WEIGHT);
+        let entry = &mut self.table[index];
+        *entry = (*entry * (GRAIN - weight) + difference * weight) / GRAIN;
+    }
+
+    pub fn correct(&self, board: &Board, static_eval: i32) -> i32 {
+        static_eval + self.table[Self::index(board)] / GRAIN
+    }
+
+    fn index(board: &Board) -> usize {
+        let pawn_key = board.pawn_hash();
+        let side = usize::from(board.get_turn() == Color::Black);
+        side * BUCKETS + (pawn_key as usize & (BUCKETS - 1))
+    }
+}
+
+impl Default for CorrectionHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Move, PieceType};
+
+    #[test]
+    fn learns_towards_search_results_per_pawn_structure() {
+        let mut history = CorrectionHistory::new();
+        let mut board = Board::new();
+        for _ in 0..200 {
+            history.record(&board, 0, 40, 10);
+        }
+        let corrected = history.correct(&board, 0);
+        assert!((35..=40).contains(&corrected), "{}", corrected);
+
+        // Moving a piece keeps the pawn structure, so the correction still applies for White.
+        board.make_move(&Move::new(6, 21, PieceType::Knight));
+        board.make_move(&Move::new(62, 45, PieceType::Knight));
+        assert_eq!(history.correct(&board, 0), corrected);
+
+        board.make_move(&Move::new(12, 28, PieceType::Pawn));
+        assert_eq!(history.correct(&board, 0), 0);
+
+        history.record(&board, 0, 10_000, 10);
+        history.clear();
+        assert_eq!(history.correct(&board, 0), 0);
+    }
+}
@@ -0,0 +1,138 @@
+use std::sync::OnceLock;
+
+// Instruction set extensions the hot kernels can use, detected once at run time so one binary
+// built for the baseline target still uses them where the CPU has them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuFeatures {
+    pub popcnt: bool,
+    pub bmi2: bool,
+    pub avx2: bool,
+}
+
+impl CpuFeatures {
+    #[cfg(target_arch = "x86_64")]
+    pub fn detect() -> Self {
+        Self {
+            popcnt: is_x86_feature_detected!("popcnt"),
+            bmi2: is_x86_feature_detected!("bmi2"),
+            avx2: is_x86_feature_detected!("avx2"),
+        }
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    pub fn detect() -> Self {
+        Self::default()
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        [("popcnt", self.popcnt), ("bmi2", self.bmi2), ("avx2", self.avx2)]
+            .into_iter()
+            .filter(|&(_, present)| present)
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+// The implementation picked for each kernel. Every kernel has a portable version, so a missing
+// feature only costs speed.
+#[derive(Debug, Clone, Copy)]
+pub struct Kernels {
+    pub popcount: fn(u64) -> u32,
+    pub popcount_name: &'static str,
+    // Parallel bit extract: `mask`'s bits of `bits`, packed into the low bits
+    pub pext: fn(u64, u64) -> u64,
+    pub pext_name: &'static str,
+}
+
+impl Kernels {
+    pub fn for_features(features: CpuFeatures) -> Self {
+        #[cfg_attr(not(target_arch = "x86_64"), allow(unused_mut))]
+        let mut kernels = Self {
+            popcount: popcount_portable,
+            popcount_name: "portable",
+            pext: pext_portable,
+            pext_name: "portable",
+        };
+        #[cfg(target_arch = "x86_64")]
+        {
+            if features.popcnt {
+                kernels.popcount = popcount_popcnt;
+                kernels.popcount_name = "popcnt";
+            }
+            if features.bmi2 {
+                kernels.pext = pext_bmi2;
+                kernels.pext_name = "bmi2";
+            }
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        let _ = features;
+        kernels
+    }
+}
+
+pub fn features() -> CpuFeatures {
+    static FEATURES: OnceLock<CpuFeatures> = OnceLock::new();
+    *FEATURES.get_or_init(CpuFeatures::detect)
+}
+
+pub fn kernels() -> &'static Kernels {
+    static KERNELS: OnceLock<Kernels> = OnceLock::new();
+    KERNELS.get_or_init(|| Kernels::for_features(features()))
+}
+
+fn popcount_portable(bits: u64) -> u32 {
+    bits.count_ones()
+}
+
+#[cfg(target_arch = "x86_64")]
+fn popcount_popcnt(bits: u64) -> u32 {
+    #[target_feature(enable = "popcnt")]
+    fn popcnt(bits: u64) -> u32 {
+        bits.count_ones()
+    }
+    // SAFETY: `Kernels::for_features` only selects this kernel when the CPU reports popcnt.
+    unsafe { popcnt(bits) }
+}
+
+fn pext_portable(bits: u64, mut mask: u64) -> u64 {
+    let mut packed = 0;
+    let mut bit = 1;
+    while mask != 0 {
+        if bits & mask & mask.wrapping_neg() != 0 {
+            packed |= bit;
+        }
+        mask &= mask - 1;
+        bit <<= 1;
+    }
+    packed
+}
+
+#[cfg(target_arch = "x86_64")]
+fn pext_bmi2(bits: u64, mask: u64) -> u64 {
+    #[target_feature(enable = "bmi2")]
+    fn pext(bits: u64, mask: u64) -> u64 {
+        std::arch::x86_64::_pext_u64(bits, mask)
+    }
+    // SAFETY: `Kernels::for_features` only selects this kernel when the CPU reports BMI2.
+    unsafe { pext(bits, mask) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_kernel_choice_agrees_with_the_portable_one() {
+        let detected = features();
+        for features in [CpuFeatures::default(), detected] {
+            let kernels = Kernels::for_features(features);
+            for bits in [0, 1, 0x8000_0000_0000_0001, u64::MAX, 0x00ff_00ff_1234_5678] {
+                assert_eq!((kernels.popcount)(bits), bits.count_ones());
+                assert_eq!((kernels.pext)(bits, 0xff00_0000_0000_0000), bits >> 56);
+            }
+            assert_eq!((kernels.pext)(0b1011_0110, 0b1111_0010), 0b1_0111);
+        }
+        assert_eq!(Kernels::for_features(CpuFeatures::default()).popcount_name, "portable");
+        assert_eq!(detected.names().len(), [detected.popcnt, detected.bmi2, detected.avx2].iter().filter(|&&present| present).count());
+    }
+}
@@ -0,0 +1,280 @@
+use crate::input::{self, InputLine};
+use crate::jobs::{self, EngineSlot, JobQueue};
+use crate::oneshot::{EXIT_FAILURE, EXIT_OK, EXIT_USAGE};
+use crate::options::Options;
+use crate::output::Output;
+use crate::uci::{configured_ai, UciSession};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::Shutdown;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+// `chess --listen <socket>` keeps one engine running for many short-lived clients, so batch
+// pipelines pay for start-up and hash table warm-up once instead of per invocation. Each client
+// gets a fresh UCI session (starting position, its own `position`/`go`) over the socket, but the
+// engine behind it, hash table and `setoption` values included, carries over from client to
+// client. UCI clients get the engine one at a time, in the order they connect; the others wait.
+// A connection whose first command is `job ...` talks to the analysis job queue instead (see
+// jobs.rs), which runs on the same engine between UCI sessions and never waits for one.
+// `chess client <socket> [command...]` is the matching client.
+// Returns None when the arguments ask for neither, and the exit code otherwise.
+pub fn run(args: &[String]) -> Option<i32> {
+    if let Some(path) = listen_path(args) {
+        return Some(match serve(Path::new(&path)) {
+            Ok(()) => EXIT_OK,
+            Err(error) => {
+                eprintln!("ERROR: Cannot listen on '{}': {}", path, error);
+                EXIT_FAILURE
+            }
+        });
+    }
+    let (command, rest) = args.split_first()?;
+    if command != "client" {
+        return None;
+    }
+    let Some((path, commands)) = rest.split_first() else {
+        eprintln!("ERROR: client needs a socket path");
+        return Some(EXIT_USAGE);
+    };
+    Some(match client(Path::new(path), commands) {
+        Ok(()) => EXIT_OK,
+        Err(error) => {
+            eprintln!("ERROR: Cannot talk to '{}': {}", path, error);
+            EXIT_FAILURE
+        }
+    })
+}
+
+fn listen_path(args: &[String]) -> Option<String> {
+    args.iter()
+        .position(|arg| arg == "--listen")
+        .and_then(|index| args.get(index + 1).cloned())
+        .or_else(|| {
+            args.iter()
+                .find_map(|arg| arg.strip_prefix("--listen=").map(str::to_string))
+        })
+}
+
+fn serve(path: &Path) -> io::Result<()> {
+    remove_stale_socket(path)?;
+    let listener = UnixListener::bind(path)?;
+    // On stderr, like the REPL's start-up lines, so scripts can wait for it
+    eprintln!("LISTEN: socket={}", path.display());
+
+    let options = Options::new();
+    let ai = configured_ai(&options);
+    let engine: Arc<EngineSlot> = Arc::new(Mutex::new(Some((options, ai))));
+    let jobs = Arc::new(JobQueue::new());
+    {
+        let (engine, jobs) = (Arc::clone(&engine), Arc::clone(&jobs));
+        thread::spawn(move || jobs.run_worker(&engine));
+    }
+    for stream in listener.incoming() {
+        // A client that failed to connect costs nothing but its own session
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let (engine, jobs) = (Arc::clone(&engine), Arc::clone(&jobs));
+        thread::spawn(move || serve_client(stream, &engine, &jobs));
+    }
+    Ok(())
+}
+
+fn serve_client(stream: UnixStream, engine: &EngineSlot, jobs: &JobQueue) {
+    let Ok(writer) = stream.try_clone() else {
+        return;
+    };
+    let output = Output::new(writer);
+    let mut reader = BufReader::new(&stream);
+    let mut next_line =
+        || input::read_line_bounded(&mut reader, input::MAX_LINE_BYTES).unwrap_or(InputLine::Eof);
+    let first = next_line();
+    let is_job = |line: &InputLine| matches!(line, InputLine::Line(line) if line.split_whitespace().next() == Some("job"));
+
+    if is_job(&first) {
+        let mut line = first;
+        loop {
+            match &line {
+                InputLine::Line(text) => {
+                    let args: Vec<&str> = text.split_whitespace().collect();
+                    match args.first().copied() {
+                        None => {}
+                        Some("quit") => break,
+                        Some("job") => jobs::handle_command(jobs, &args[1..])
+                            .into_iter()
+                            .for_each(|reply| output.line(reply)),
+                        Some(_) => output.line("ERROR: Job connections only take job commands"),
+                    }
+                }
+                InputLine::Rejected(reason) => output.line(format!("ERROR: {}", reason)),
+                InputLine::Eof => break,
+            }
+            line = next_line();
+        }
+    } else {
+        // Held for the whole session, so the job worker waits for it to end
+        let mut slot = engine.lock().unwrap_or_else(PoisonError::into_inner);
+        let (options, ai) = slot.take().unwrap_or_else(|| {
+            // Only a session that panicked leaves the slot empty
+            let options = Options::new();
+            let ai = configured_ai(&options);
+            (options, ai)
+        });
+        let mut session = UciSession::with_ai(options, output.clone(), ai);
+        let mut first = Some(first);
+        session.run_lines(|| first.take().unwrap_or_else(&mut next_line));
+        *slot = Some(session.into_parts());
+    }
+    output.flush();
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+// A socket left behind by a daemon that was killed is removed; one a daemon still answers on,
+// or a path that isn't a socket at all, is left alone and reported.
+fn remove_stale_socket(path: &Path) -> io::Result<()> {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return Ok(());
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, "not a socket"));
+    }
+    if UnixStream::connect(path).is_ok() {
+        return Err(io::Error::new(io::ErrorKind::AddrInUse, "another engine is listening"));
+    }
+    fs::remove_file(path)
+}
+
+// Sends the commands given, or stdin line by line when there are none, and prints everything
+// the engine answers until it closes the connection: after the end of the input it finishes a
+// running search, so the last `bestmove` is always printed.
+fn client(path: &Path, commands: &[String]) -> io::Result<()> {
+    let stream = UnixStream::connect(path)?;
+    let mut writer = stream.try_clone()?;
+    if commands.is_empty() {
+        // The engine may close first (after `quit`), and then whatever is left to send is dropped
+        thread::spawn(move || {
+            while let InputLine::Line(line) = input::next_stdin_line() {
+                if writeln!(writer, "{}", line).is_err() {
+                    return;
+                }
+            }
+            let _ = writer.shutdown(Shutdown::Write);
+        });
+    } else {
+        for command in commands {
+            writeln!(writer, "{}", command)?;
+        }
+        writer.shutdown(Shutdown::Write)?;
+    }
+
+    let mut stdout = io::stdout().lock();
+    for line in BufReader::new(stream).lines() {
+        writeln!(stdout, "{}", line?)?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn socket_path(name: &str) -> std::path::PathBuf {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        std::env::temp_dir().join(format!("chess-{}-{}-{}.sock", name, std::process::id(), nanos))
+    }
+
+    fn ask(path: &Path, commands: &[&str]) -> Vec<String> {
+        let mut stream = UnixStream::connect(path).unwrap();
+        for command in commands {
+            writeln!(stream, "{}", command).unwrap();
+        }
+        stream.shutdown(Shutdown::Write).unwrap();
+        BufReader::new(stream).lines().map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn clients_share_the_engine_but_not_the_position() {
+        let path = socket_path("shared");
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = thread::spawn(move || {
+            let engine: EngineSlot = Mutex::new(Some((Options::new(), configured_ai(&Options::new()))));
+            let jobs = JobQueue::new();
+            for stream in listener.incoming().take(2) {
+                serve_client(stream.unwrap(), &engine, &jobs);
+            }
+            let (options, _) = engine.into_inner().unwrap().unwrap();
+            options
+        });
+
+        let first = ask(&path, &["setoption name MoveOverhead value 250", "position startpos moves e2e4", "go depth 2"]);
+        assert!(first.last().unwrap().starts_with("bestmove "), "{:?}", first);
+        // The second client starts from the starting position again
+        let second = ask(&path, &["isready", "go depth 1"]);
+        assert_eq!(second.first().map(String::as_str), Some("readyok"));
+        assert!(second.iter().any(|line| line.starts_with("info depth 1 ")), "{:?}", second);
+        let best = second.iter().find_map(|line| line.strip_prefix("bestmove ")).unwrap();
+        assert!(matches!(&best[1..2], "1" | "2"), "{:?}", second);
+
+        assert_eq!(server.join().unwrap().get("MoveOverhead"), Some(250));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn job_connections_reach_the_queue_while_a_session_holds_the_engine() {
+        let path = socket_path("jobs");
+        let listener = UnixListener::bind(&path).unwrap();
+        let engine: Arc<EngineSlot> = Arc::new(Mutex::new(Some((Options::new(), configured_ai(&Options::new())))));
+        let jobs = Arc::new(JobQueue::new());
+        let server = {
+            let (engine, jobs) = (Arc::clone(&engine), Arc::clone(&jobs));
+            thread::spawn(move || {
+                let clients: Vec<_> = listener
+                    .incoming()
+                    .take(2)
+                    .map(|stream| {
+                        let (engine, jobs, stream) = (Arc::clone(&engine), Arc::clone(&jobs), stream.unwrap());
+                        thread::spawn(move || serve_client(stream, &engine, &jobs))
+                    })
+                    .collect();
+                clients.into_iter().for_each(|client| client.join().unwrap());
+            })
+        };
+
+        // Nothing runs the jobs here, so they stay queued
+        let busy = engine.lock().unwrap();
+        let replies = ask(&path, &["job submit priority 3 depth 2", "job list", "uci"]);
+        assert_eq!(replies[0], "JOB: id=1; state=queued; priority=3; nodes=0; time=0ms; preemptions=0");
+        assert_eq!(replies[2], "JOBS: queued=1; running=0; suspended=0; done=0; cancelled=0");
+        assert_eq!(replies[3], "ERROR: Job connections only take job commands");
+        drop(busy);
+        let session = ask(&path, &["isready"]);
+        assert_eq!(session, vec!["readyok"]);
+
+        server.join().unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn replaces_only_stale_sockets() {
+        let path = socket_path("stale");
+        assert!(remove_stale_socket(&path).is_ok());
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(remove_stale_socket(&path).is_ok());
+        assert!(!path.exists());
+
+        let live = UnixListener::bind(&path).unwrap();
+        assert_eq!(remove_stale_socket(&path).unwrap_err().kind(), io::ErrorKind::AddrInUse);
+        drop(live);
+        fs::remove_file(&path).unwrap();
+
+        fs::write(&path, "not a socket").unwrap();
+        assert_eq!(remove_stale_socket(&path).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,197 @@
+use crate::board::Board;
+use crate::fen::FenParser;
+use crate::types::Color;
+
+pub const DISPLAY_OPTIONS: [&str; 5] = ["coordinates", "perspective", "turn", "fen", "movenumber"];
+
+// How the REPL draws the board: rank and file labels, which side is at the bottom, and what is
+// printed under it. The defaults draw it the way `Board`'s `Display` always has, so scripts that
+// read the board see no change until the user asks for one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayOptions {
+    pub coordinates: bool,
+    // The side whose pieces are at the bottom
+    pub perspective: Color,
+    // `White to move` under the board
+    pub turn: bool,
+    pub fen: bool,
+    pub move_number: bool,
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        Self {
+            coordinates: true,
+            perspective: Color::White,
+            turn: true,
+            fen: false,
+            move_number: false,
+        }
+    }
+}
+
+impl DisplayOptions {
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), &'static str> {
+        let toggle = || match value {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => Err("Display options are on or off, and perspective white or black"),
+        };
+        match name {
+            "coordinates" => self.coordinates = toggle()?,
+            "perspective" => {
+                self.perspective = match value {
+                    "white" => Color::White,
+                    "black" => Color::Black,
+                    _ => {
+                        return Err("Display options are on or off, and perspective white or black")
+                    }
+                }
+            }
+            "turn" => self.turn = toggle()?,
+            "fen" => self.fen = toggle()?,
+            "movenumber" => self.move_number = toggle()?,
+            _ => return Err("Unknown display option"),
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&'static str> {
+        let toggle = |on: bool| if on { "on" } else { "off" };
+        match name {
+            "coordinates" => Some(toggle(self.coordinates)),
+            "perspective" => Some(if self.perspective == Color::White {
+                "white"
+            } else {
+                "black"
+            }),
+            "turn" => Some(toggle(self.turn)),
+            "fen" => Some(toggle(self.fen)),
+            "movenumber" => Some(toggle(self.move_number)),
+            _ => None,
+        }
+    }
+
+    // `coordinates=on; perspective=white; ...`, every option in `DISPLAY_OPTIONS` order
+    pub fn describe(&self) -> String {
+        DISPLAY_OPTIONS
+            .iter()
+            .map(|name| format!("{}={}", name, self.get(name).unwrap_or_default()))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    // The board as rows of FEN letters and dots, without a trailing newline.
+    pub fn render(&self, board: &Board) -> String {
+        let (ranks, files): (Vec<usize>, Vec<usize>) = match self.perspective {
+            Color::White => ((0..8).rev().collect(), (0..8).collect()),
+            Color::Black => ((0..8).collect(), (0..8).rev().collect()),
+        };
+        let labels: Vec<String> = files
+            .iter()
+            .map(|&file| ((b'a' + file as u8) as char).to_string())
+            .collect();
+        let header = format!("  {}", labels.join(" "));
+
+        let mut lines = Vec::new();
+        if self.coordinates {
+            lines.push(header.clone());
+        }
+        for &rank in &ranks {
+            let squares: Vec<String> = files
+                .iter()
+                .map(|&file| {
+                    board
+                        .get_piece(rank * 8 + file)
+                        .map_or('.', |piece| piece.to_char())
+                        .to_string()
+                })
+                .collect();
+            lines.push(if self.coordinates {
+                format!("{} {} {}", rank + 1, squares.join(" "), rank + 1)
+            } else {
+                squares.join(" ")
+            });
+        }
+        if self.coordinates {
+            lines.push(header);
+        }
+
+        let mut notes = Vec::new();
+        let side = if board.get_turn() == Color::White {
+            "White"
+        } else {
+            "Black"
+        };
+        match (self.move_number, self.turn) {
+            (true, true) => notes.push(format!(
+                "Move {}, {} to move",
+                board.get_state().fullmove_number,
+                side
+            )),
+            (true, false) => notes.push(format!("Move {}", board.get_state().fullmove_number)),
+            (false, true) => notes.push(format!("{} to move", side)),
+            (false, false) => {}
+        }
+        if self.fen {
+            notes.push(format!("FEN: {}", FenParser::new().export_fen(board)));
+        }
+        if !notes.is_empty() {
+            lines.push(String::new());
+            lines.extend(notes);
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_the_board_as_the_options_say() {
+        let mut board = Board::new();
+        FenParser::new()
+            .parse_fen(&mut board, "4k3/8/8/8/8/8/4P3/R3K3 b Q - 0 12")
+            .unwrap();
+        let mut options = DisplayOptions::default();
+        assert_eq!(options.render(&board), board.to_string());
+
+        for (name, value) in [
+            ("perspective", "black"),
+            ("fen", "on"),
+            ("movenumber", "on"),
+        ] {
+            options.set(name, value).unwrap();
+        }
+        let lines: Vec<String> = options.render(&board).lines().map(str::to_string).collect();
+        assert_eq!(lines[0], "  h g f e d c b a");
+        assert_eq!(lines[1], "1 . . . K . . . R 1");
+        assert_eq!(lines[2], "2 . . . P . . . . 2");
+        assert_eq!(lines[8], "8 . . . k . . . . 8");
+        assert_eq!(
+            &lines[10..],
+            [
+                "",
+                "Move 12, Black to move",
+                "FEN: 4k3/8/8/8/8/8/4P3/R3K3 b Q - 0 12"
+            ]
+        );
+
+        options.set("coordinates", "off").unwrap();
+        options.set("turn", "off").unwrap();
+        options.set("fen", "off").unwrap();
+        options.set("movenumber", "off").unwrap();
+        assert_eq!(
+            options.render(&board).lines().next(),
+            Some(". . . K . . . R")
+        );
+        assert_eq!(options.render(&board).lines().count(), 8);
+        assert_eq!(
+            options.describe(),
+            "coordinates=off; perspective=black; turn=off; fen=off; movenumber=off"
+        );
+        assert_eq!(options.set("labels", "on"), Err("Unknown display option"));
+        assert!(options.set("perspective", "red").is_err());
+    }
+}
@@ -0,0 +1,247 @@
+use super::material::{MaterialSignature, MaterialTable};
+use super::pawn_structure::{PawnHashEntry, PawnHashTable};
+use super::scaling::{self, SCALE_NORMAL};
+use super::{EvalConfig, EvalTerm, Evaluator};
+use crate::board::Board;
+use crate::types::*;
+
+// d4, e4, d5, e5
+pub(super) const CENTER_SQUARES: [Square; 4] = [27, 28, 35, 36];
+
+// Material (with imbalance terms, see material.rs) plus small positional bonuses for the centre,
+// pawn advancement and king shelter, scaled down in drawish endings (see scaling.rs). The pawn
+// terms come from the pawn hash table (see pawn_structure.rs).
+pub struct ClassicalEvaluator {
+    config: EvalConfig,
+    material: MaterialTable,
+    pawns: PawnHashTable,
+}
+
+impl ClassicalEvaluator {
+    pub fn new() -> Self {
+        Self::with_config(EvalConfig::default())
+    }
+
+    pub fn with_config(config: EvalConfig) -> Self {
+        Self {
+            config,
+            material: MaterialTable::new(config),
+            pawns: PawnHashTable::new(),
+        }
+    }
+
+    // The cached pawn-structure data for the board's pawns.
+    pub fn pawn_entry(&self, board: &Board) -> PawnHashEntry {
+        self.pawns.probe(board)
+    }
+
+    fn get_position_bonus(
+        &self,
+        square: Square,
+        piece_type: PieceType,
+        color: Color,
+        endgame: bool,
+    ) -> i32 {
+        let file = square % 8;
+        let rank = square / 8;
+        let mut bonus = 0;
+
+        // Center control bonus
+        if CENTER_SQUARES.contains(&square) && self.config.is_enabled(EvalTerm::Centre) {
+            bonus += 10;
+        }
+
+        match piece_type {
+            PieceType::King if !endgame && self.config.is_enabled(EvalTerm::KingSafety) => {
+                // King safety in opening/middlegame
+                let safe_rank = if color == Color::White { 0 } else { 7 };
+                if rank == safe_rank && (file <= 2 || file >= 5) {
+                    bonus += 20;
+                } else {
+                    bonus -= 20;
+                }
+            }
+            _ => {}
+        }
+
+        bonus
+    }
+
+    fn is_endgame(&self, signature: &MaterialSignature) -> bool {
+        let count = |piece_type| {
+            signature.count(Color::White, piece_type) + signature.count(Color::Black, piece_type)
+        };
+        let queen_count = count(PieceType::Queen);
+        let piece_count = count(PieceType::Knight)
+            + count(PieceType::Bishop)
+            + count(PieceType::Rook)
+            + queen_count;
+
+        piece_count <= 4 || (piece_count <= 6 && queen_count == 0)
+    }
+}
+
+// The terms behind one evaluation, from White's point of view. `total` is what `evaluate`
+// returns: `(material + positional) * scale / SCALE_NORMAL`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvalBreakdown {
+    // Piece values with the imbalance terms
+    pub material: i32,
+    // Centre, pawn advancement and king shelter bonuses
+    pub positional: i32,
+    pub endgame: bool,
+    // Out of SCALE_NORMAL
+    pub scale: i32,
+    pub total: i32,
+}
+
+impl ClassicalEvaluator {
+    pub fn breakdown(&self, board: &Board) -> EvalBreakdown {
+        let material = self.material.probe(board);
+        let endgame = self.is_endgame(&material.signature);
+        if self.config.material_only {
+            return EvalBreakdown {
+                material: material.score,
+                positional: 0,
+                endgame,
+                scale: SCALE_NORMAL,
+                total: material.score,
+            };
+        }
+        // Pawns are scored once per pawn configuration
+        let mut positional = if self.config.is_enabled(EvalTerm::Pawns) {
+            self.pawns.probe(board).score
+        } else {
+            0
+        };
+
+        for square in 0..64 {
+            if let Some(piece) = board.get_piece(square).filter(|piece| piece.piece_type != PieceType::Pawn) {
+                let position_bonus =
+                    self.get_position_bonus(square, piece.piece_type, piece.color, endgame);
+
+                positional += if piece.color == Color::White {
+                    position_bonus
+                } else {
+                    -position_bonus
+                };
+            }
+        }
+
+        let score = material.score + positional;
+        let strong = if score >= 0 { Color::White } else { Color::Black };
+        let scale = if self.config.is_enabled(EvalTerm::Scaling) {
+            scaling::scale_factor(&material.signature, board, strong)
+        } else {
+            SCALE_NORMAL
+        };
+        EvalBreakdown {
+            material: material.score,
+            positional,
+            endgame,
+            scale,
+            total: score * scale / SCALE_NORMAL,
+        }
+    }
+}
+
+impl Default for ClassicalEvaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Evaluator for ClassicalEvaluator {
+    fn evaluate(&self, board: &Board) -> i32 {
+        self.breakdown(board).total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::FenParser;
+
+    #[test]
+    fn start_position_is_balanced_and_mirrors_flip_sign() {
+        let evaluator = ClassicalEvaluator::new();
+        assert_eq!(evaluator.evaluate(&Board::new()), 0);
+
+        let mut white_up = Board::new();
+        FenParser::new()
+            .parse_fen(&mut white_up, "4k3/8/8/8/3P4/8/8/4K3 w - - 0 1")
+            .unwrap();
+        let mut black_up = Board::new();
+        FenParser::new()
+            .parse_fen(&mut black_up, "4k3/8/8/3p4/8/8/8/4K3 w - - 0 1")
+            .unwrap();
+        assert!(evaluator.evaluate(&white_up) > 0);
+        assert_eq!(
+            evaluator.evaluate(&white_up),
+            -evaluator.evaluate(&black_up)
+        );
+    }
+
+    #[test]
+    fn drawn_endings_score_near_zero() {
+        let evaluator = ClassicalEvaluator::new();
+        let mut board = Board::new();
+        FenParser::new()
+            .parse_fen(&mut board, "4k3/8/8/8/8/8/8/1N2K3 w - - 0 1")
+            .unwrap();
+        assert_eq!(evaluator.evaluate(&board), 0);
+        let breakdown = evaluator.breakdown(&board);
+        assert!(breakdown.material > 0 && breakdown.endgame);
+        assert_eq!(breakdown.scale, 0);
+    }
+
+    #[test]
+    fn material_only_counts_the_configured_piece_values() {
+        let mut board = Board::new();
+        board.make_move(&Move::new(12, 28, PieceType::Pawn));
+        let config = EvalConfig {
+            material_only: true,
+            ..EvalConfig::default()
+        };
+        assert!(ClassicalEvaluator::new().evaluate(&board) != 0);
+        assert_eq!(ClassicalEvaluator::with_config(config).evaluate(&board), 0);
+
+        // The lone knight that scales to a draw above is just a knight
+        FenParser::new()
+            .parse_fen(&mut board, "4k3/8/8/8/8/8/8/1N2K3 w - - 0 1")
+            .unwrap();
+        let config = EvalConfig {
+            piece_values: [100, 280, 330, 500, 900],
+            material_only: true,
+            disabled_terms: 0,
+        };
+        assert_eq!(ClassicalEvaluator::with_config(config).evaluate(&board), 280);
+    }
+
+    #[test]
+    fn each_term_can_be_switched_off_alone() {
+        let evaluate = |config: EvalConfig, fen: &str| {
+            let mut board = Board::new();
+            FenParser::new().parse_fen(&mut board, fen).unwrap();
+            ClassicalEvaluator::with_config(config).evaluate(&board)
+        };
+        let full = EvalConfig::default();
+        // The lone knight scales to a draw; unscaled it is worth most of a knight
+        let knight = "4k3/8/8/8/8/8/8/1N2K3 w - - 0 1";
+        assert_eq!(evaluate(full, knight), 0);
+        assert!(evaluate(full.without(EvalTerm::Scaling), knight) > 250);
+
+        // After 1. e4 the pawn's advancement and centre square are the whole score
+        let e4 = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+        assert_eq!(evaluate(full, e4), 20);
+        assert_eq!(evaluate(full.without(EvalTerm::Pawns), e4), 0);
+        assert_eq!(evaluate(full.without(EvalTerm::KingSafety), e4), 20);
+
+        // White's king sheltered on g1, Black's in the centre
+        let kings = "rqn1k3/p7/8/8/8/8/P7/RQN3K1 w - - 0 1";
+        assert_eq!(evaluate(full, kings), 40);
+        assert_eq!(evaluate(full.without(EvalTerm::KingSafety), kings), 0);
+        assert!(!full.without(EvalTerm::Centre).is_enabled(EvalTerm::Centre));
+        assert!(full.without(EvalTerm::Centre).is_enabled(EvalTerm::Imbalance));
+    }
+}
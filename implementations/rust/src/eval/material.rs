@@ -0,0 +1,208 @@
+use super::{EvalConfig, EvalTerm};
+use crate::board::Board;
+use crate::types::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+const BISHOP_PAIR: i32 = 50;
+// Two pieces of the same kind cover the same squares, so the second is worth a little less.
+const KNIGHT_PAIR: i32 = -8;
+const ROOK_PAIR: i32 = -16;
+// Knights like closed positions and rooks open ones: each own pawn above (or below) five nudges
+// them. This is also what settles rook against minor piece plus pawns.
+const KNIGHT_PER_PAWN: i32 = 6;
+const ROOK_PER_PAWN: i32 = -12;
+const PAWN_BASELINE: i32 = 5;
+// Three minor pieces usually outplay a lone queen.
+const MINORS_VS_QUEEN: i32 = 60;
+
+const COUNTED: [PieceType; 5] = [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+];
+
+// Piece counts for both sides, kings excluded. Packed four bits per count, which holds even ten
+// promoted pieces of one kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MaterialSignature {
+    counts: [[u8; 5]; 2],
+}
+
+impl MaterialSignature {
+    pub fn from_board(board: &Board) -> Self {
+        let mut counts = [[0u8; 5]; 2];
+        for square in 0..64 {
+            if let Some(piece) = board.get_piece(square) {
+                if let Some(index) = COUNTED.iter().position(|&kind| kind == piece.piece_type) {
+                    counts[side_index(piece.color)][index] += 1;
+                }
+            }
+        }
+        Self { counts }
+    }
+
+    pub fn key(&self) -> u64 {
+        self.counts
+            .iter()
+            .flatten()
+            .fold(0, |key, &count| (key << 4) | u64::from(count.min(15)))
+    }
+
+    pub fn count(&self, color: Color, piece_type: PieceType) -> i32 {
+        COUNTED
+            .iter()
+            .position(|&kind| kind == piece_type)
+            .map_or(0, |index| i32::from(self.counts[side_index(color)][index]))
+    }
+
+    pub fn minors(&self, color: Color) -> i32 {
+        self.count(color, PieceType::Knight) + self.count(color, PieceType::Bishop)
+    }
+
+    // Knights, bishops, rooks and queens at their flat values.
+    pub fn non_pawn_material(&self, color: Color) -> i32 {
+        COUNTED[1..]
+            .iter()
+            .map(|&piece_type| self.count(color, piece_type) * piece_type.value())
+            .sum()
+    }
+}
+
+fn side_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+// Material score for a signature, White-relative: the configured piece values plus, unless the
+// config is material-only, the imbalance terms.
+pub fn material_score(signature: &MaterialSignature, config: &EvalConfig) -> i32 {
+    side_score(signature, Color::White, config) - side_score(signature, Color::Black, config)
+}
+
+fn side_score(signature: &MaterialSignature, color: Color, config: &EvalConfig) -> i32 {
+    let count = |piece_type| signature.count(color, piece_type);
+    let mut score: i32 = COUNTED
+        .iter()
+        .map(|&piece_type| count(piece_type) * config.piece_value(piece_type))
+        .sum();
+    if !config.is_enabled(EvalTerm::Imbalance) {
+        return score;
+    }
+
+    if count(PieceType::Bishop) >= 2 {
+        score += BISHOP_PAIR;
+    }
+    if count(PieceType::Knight) >= 2 {
+        score += KNIGHT_PAIR;
+    }
+    if count(PieceType::Rook) >= 2 {
+        score += ROOK_PAIR;
+    }
+
+    let extra_pawns = count(PieceType::Pawn) - PAWN_BASELINE;
+    score += count(PieceType::Knight) * extra_pawns * KNIGHT_PER_PAWN;
+    score += count(PieceType::Rook) * extra_pawns * ROOK_PER_PAWN;
+
+    let them = color.opposite();
+    if signature.count(them, PieceType::Queen) > count(PieceType::Queen)
+        && signature.minors(color) - signature.minors(them) >= 3
+    {
+        score += MINORS_VS_QUEEN;
+    }
+    score
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MaterialEntry {
+    pub signature: MaterialSignature,
+    pub score: i32,
+}
+
+// The same signature recurs across huge numbers of search nodes, so each is scored once.
+pub struct MaterialTable {
+    config: EvalConfig,
+    cache: RefCell<HashMap<u64, MaterialEntry>>,
+}
+
+impl MaterialTable {
+    pub fn new(config: EvalConfig) -> Self {
+        Self {
+            config,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn probe(&self, board: &Board) -> MaterialEntry {
+        let signature = MaterialSignature::from_board(board);
+        *self
+            .cache
+            .borrow_mut()
+            .entry(signature.key())
+            .or_insert_with(|| MaterialEntry {
+                signature,
+                score: material_score(&signature, &self.config),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::FenParser;
+
+    fn signature(fen: &str) -> MaterialSignature {
+        let mut board = Board::new();
+        FenParser::new().parse_fen(&mut board, fen).unwrap();
+        MaterialSignature::from_board(&board)
+    }
+
+    #[test]
+    fn imbalance_terms_follow_the_signature() {
+        let config = EvalConfig::default();
+        let start = signature("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(material_score(&start, &config), 0);
+        assert_ne!(
+            start.key(),
+            signature("4k3/8/8/8/8/8/8/4K3 w - - 0 1").key()
+        );
+
+        // Bishop pair against knight pair with equal pawns
+        let bishops = signature("4k3/pppp4/2n1n3/8/8/2B1B3/PPPP4/4K3 w - - 0 1");
+        assert_eq!(
+            material_score(&bishops, &config),
+            2 * (330 - 320) + BISHOP_PAIR - KNIGHT_PAIR + 2 * KNIGHT_PER_PAWN
+        );
+
+        // Three minors against a queen
+        let minors = signature("4k3/8/8/3q4/8/2NBN3/8/4K3 w - - 0 1");
+        assert_eq!(
+            material_score(&minors, &config),
+            320 * 2 + 330 - 900 + KNIGHT_PAIR + MINORS_VS_QUEEN
+                - 2 * PAWN_BASELINE * KNIGHT_PER_PAWN
+        );
+
+        // Material-only counts the configured values and skips every imbalance term
+        let flat = EvalConfig {
+            piece_values: [100, 300, 300, 500, 900],
+            material_only: true,
+            disabled_terms: 0,
+        };
+        assert_eq!(material_score(&bishops, &flat), 0);
+        assert_eq!(material_score(&minors, &flat), 300 * 3 - 900);
+    }
+
+    #[test]
+    fn table_caches_one_entry_per_signature() {
+        let table = MaterialTable::new(EvalConfig::default());
+        let mut board = Board::new();
+        let start = table.probe(&board).score;
+        board.make_move(&Move::new(12, 28, PieceType::Pawn));
+        assert_eq!(table.probe(&board).score, start);
+        assert_eq!(table.cache.borrow().len(), 1);
+    }
+}
@@ -0,0 +1,195 @@
+mod classical;
+mod material;
+mod pawn_structure;
+mod scaling;
+
+pub use classical::{ClassicalEvaluator, EvalBreakdown};
+pub use pawn_structure::PawnHashEntry;
+pub use scaling::SCALE_NORMAL;
+
+use crate::board::Board;
+use crate::fen::FenParser;
+use crate::types::PieceType;
+
+// `Send` so an AI, evaluator included, can move to a search thread and back.
+pub trait Evaluator: Send {
+    // Static score in centipawns from White's point of view.
+    fn evaluate(&self, board: &Board) -> i32;
+}
+
+// The parts of the classical evaluation beyond counting material, each of which can be switched
+// off on its own to measure what it is worth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalTerm {
+    // Bishop, knight and rook pairs, knights and rooks by pawn count, minors against a queen
+    Imbalance,
+    // Pawn advancement and central pawns
+    Pawns,
+    // Pieces other than pawns on the four centre squares
+    Centre,
+    // The king's shelter bonus outside the endgame
+    KingSafety,
+    // Drawish endings scaled towards zero
+    Scaling,
+}
+
+pub const EVAL_TERMS: [EvalTerm; 5] = [
+    EvalTerm::Imbalance,
+    EvalTerm::Pawns,
+    EvalTerm::Centre,
+    EvalTerm::KingSafety,
+    EvalTerm::Scaling,
+];
+
+impl EvalTerm {
+    pub fn name(self) -> &'static str {
+        match self {
+            EvalTerm::Imbalance => "imbalance",
+            EvalTerm::Pawns => "pawns",
+            EvalTerm::Centre => "centre",
+            EvalTerm::KingSafety => "kingsafety",
+            EvalTerm::Scaling => "scaling",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        EVAL_TERMS
+            .into_iter()
+            .find(|term| term.name().eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvalConfig {
+    // Centipawns for pawn, knight, bishop, rook and queen.
+    pub piece_values: [i32; 5],
+    // Counts pieces at their values and nothing else: no imbalance, positional or scaling terms.
+    // For teaching, and for measuring what the other terms are worth.
+    pub material_only: bool,
+    // One bit per term switched off, in `EVAL_TERMS` order
+    pub disabled_terms: u8,
+}
+
+impl EvalConfig {
+    pub fn is_enabled(&self, term: EvalTerm) -> bool {
+        !self.material_only && self.disabled_terms & (1 << term as u8) == 0
+    }
+
+    pub fn without(self, term: EvalTerm) -> Self {
+        Self {
+            disabled_terms: self.disabled_terms | (1 << term as u8),
+            ..self
+        }
+    }
+
+    pub fn piece_value(&self, piece_type: PieceType) -> i32 {
+        match piece_type {
+            PieceType::Pawn => self.piece_values[0],
+            PieceType::Knight => self.piece_values[1],
+            PieceType::Bishop => self.piece_values[2],
+            PieceType::Rook => self.piece_values[3],
+            PieceType::Queen => self.piece_values[4],
+            PieceType::King => PieceType::King.value(),
+        }
+    }
+}
+
+impl Default for EvalConfig {
+    fn default() -> Self {
+        Self {
+            piece_values: [
+                PieceType::Pawn.value(),
+                PieceType::Knight.value(),
+                PieceType::Bishop.value(),
+                PieceType::Rook.value(),
+                PieceType::Queen.value(),
+            ],
+            material_only: false,
+            disabled_terms: 0,
+        }
+    }
+}
+
+// The classical evaluation of a FEN position, from White's point of view, for callers with no
+// board or engine of their own.
+pub fn evaluate_fen(fen: &str, config: EvalConfig) -> Result<i32, String> {
+    let mut board = Board::new();
+    FenParser::new().parse_fen(&mut board, fen)?;
+    Ok(ClassicalEvaluator::with_config(config).evaluate(&board))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Color;
+
+    fn eval(fen: &str) -> i32 {
+        evaluate_fen(fen, EvalConfig::default()).unwrap()
+    }
+
+    // Golden values: any change here is a change to the evaluation, so update them only on
+    // purpose.
+    #[test]
+    fn canonical_positions_keep_their_scores() {
+        // No tempo term, so the start position is level whoever is to move
+        assert_eq!(eval("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), 0);
+        assert_eq!(eval("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"), 0);
+        assert_eq!(eval("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"), 20);
+        for (fen, expected) in [
+            ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", 105),
+            ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", 560),
+            ("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", 900),
+            ("4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1", 620),
+            // Drawish endings scaled down: a lone minor to nothing, opposite bishops and a thin
+            // rook ending a pawn up to well under a pawn
+            ("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1", 0),
+            ("4k3/5p2/8/8/2b5/8/2PB1P2/4K3 w - - 0 1", 26),
+            ("4k3/4r3/8/8/8/8/3PR3/4K3 w - - 0 1", 58),
+        ] {
+            assert_eq!(eval(fen), expected, "{}", fen);
+        }
+        assert!(evaluate_fen("not a fen", EvalConfig::default()).is_err());
+    }
+
+    // The king's shelter bonus only counts outside the endgame: four pieces, or six without
+    // queens. White's king is tucked away on g1 and Black's is in the centre, so each pair of
+    // positions either side of the boundary scores 0 and then 40.
+    #[test]
+    fn king_safety_switches_off_at_the_endgame_boundary() {
+        assert_eq!(eval("r1n1k3/p7/8/8/8/8/P7/R1N3K1 w - - 0 1"), 0);
+        assert_eq!(eval("rqn1k3/p7/8/8/8/8/P7/RQN3K1 w - - 0 1"), 40);
+        assert_eq!(eval("rnn1k3/p7/8/8/8/8/P7/RNN3K1 w - - 0 1"), 0);
+        assert_eq!(eval("rnnrk3/p7/8/8/8/8/P7/RNNR2K1 w - - 0 1"), 40);
+    }
+
+    // Taking the same piece off both sides of a middlegame position moves the score by no more
+    // than the two pieces' square bonuses: a pawn on the sixth rank is the largest at 30.
+    #[test]
+    fn removing_equal_material_keeps_the_score_stable() {
+        let evaluator = ClassicalEvaluator::new();
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1 w kq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        ] {
+            let mut board = Board::new();
+            FenParser::new().parse_fen(&mut board, fen).unwrap();
+            let before = evaluator.evaluate(&board);
+            for white in board.occupied_by(Color::White).squares() {
+                for black in board.occupied_by(Color::Black).squares() {
+                    let (Some(ours), Some(theirs)) = (board.get_piece(white), board.get_piece(black)) else {
+                        continue;
+                    };
+                    if ours.piece_type != theirs.piece_type || ours.piece_type == PieceType::King {
+                        continue;
+                    }
+                    let mut fewer = board.clone();
+                    fewer.set_piece(white, None);
+                    fewer.set_piece(black, None);
+                    let shift = (evaluator.evaluate(&fewer) - before).abs();
+                    assert!(shift <= 30, "{} without {} and {}: {}", fen, white, black, shift);
+                }
+            }
+        }
+    }
+}
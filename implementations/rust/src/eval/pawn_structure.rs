@@ -0,0 +1,190 @@
+use super::classical::CENTER_SQUARES;
+use crate::bitboard::Bitboard;
+use crate::board::Board;
+use crate::types::*;
+use std::cell::RefCell;
+
+// Entries in the pawn hash table. Searches visit far fewer pawn configurations than positions,
+// so a small table keeps nearly all of them.
+const PAWN_TABLE_ENTRIES: usize = 1 << 12;
+
+// Everything the evaluation derives from the pawns alone, for one pawn configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PawnHashEntry {
+    pub key: u64,
+    // Pawn advancement and centre bonuses, White-relative
+    pub score: i32,
+    // Pawns with no enemy pawn ahead of them on their own or an adjacent file, by side
+    pub passed: [Bitboard; 2],
+}
+
+impl PawnHashEntry {
+    fn analyze(key: u64, white: Bitboard, black: Bitboard) -> Self {
+        Self {
+            key,
+            score: side_score(white, Color::White) - side_score(black, Color::Black),
+            passed: [
+                passed_pawns(white, black, Color::White),
+                passed_pawns(black, white, Color::Black),
+            ],
+        }
+    }
+
+    pub fn passed(&self, color: Color) -> Bitboard {
+        self.passed[color as usize]
+    }
+}
+
+fn side_score(pawns: Bitboard, color: Color) -> i32 {
+    pawns
+        .squares()
+        .map(|square| {
+            let rank = square / 8;
+            let advancement = if color == Color::White {
+                rank
+            } else {
+                7 - rank
+            };
+            let center = if CENTER_SQUARES.contains(&square) {
+                10
+            } else {
+                0
+            };
+            center + (advancement * 5) as i32
+        })
+        .sum()
+}
+
+fn passed_pawns(own: Bitboard, enemy: Bitboard, color: Color) -> Bitboard {
+    let mut passed = Bitboard::EMPTY;
+    for square in own.squares() {
+        let (file, rank) = (square % 8, square / 8);
+        let files = (file.saturating_sub(1)..=(file + 1).min(7))
+            .fold(Bitboard::EMPTY, |files, file| files | Bitboard::file(file));
+        let ahead = match color {
+            Color::White => {
+                (rank + 1..8).fold(Bitboard::EMPTY, |ranks, rank| ranks | Bitboard::rank(rank))
+            }
+            Color::Black => {
+                (0..rank).fold(Bitboard::EMPTY, |ranks, rank| ranks | Bitboard::rank(rank))
+            }
+        };
+        if (enemy & files & ahead).is_empty() {
+            passed.set(square);
+        }
+    }
+    passed
+}
+
+// Always-replace table indexed by the low bits of the board's pawns-only Zobrist key, which the
+// board keeps up to date, so a hit costs one comparison. Empty slots hold the pawnless entry
+// (key 0, no score, no passed pawns), which is exactly what a pawnless position would store.
+pub struct PawnHashTable {
+    entries: RefCell<Vec<PawnHashEntry>>,
+}
+
+impl PawnHashTable {
+    pub fn new() -> Self {
+        Self {
+            entries: RefCell::new(vec![PawnHashEntry::default(); PAWN_TABLE_ENTRIES]),
+        }
+    }
+
+    pub fn probe(&self, board: &Board) -> PawnHashEntry {
+        let key = board.pawn_hash();
+        let mut entries = self.entries.borrow_mut();
+        let slot = &mut entries[key as usize & (PAWN_TABLE_ENTRIES - 1)];
+        if slot.key != key {
+            *slot = PawnHashEntry::analyze(
+                key,
+                board.pieces(PieceType::Pawn, Color::White),
+                board.pieces(PieceType::Pawn, Color::Black),
+            );
+        }
+        *slot
+    }
+}
+
+impl Default for PawnHashTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::board_from;
+    use crate::move_generator::MoveGenerator;
+    use crate::perft::TEST_POSITIONS;
+    use crate::zobrist;
+
+    #[test]
+    fn finds_passed_pawns_for_both_sides() {
+        // White's d5 and h2 have no black pawn ahead on their own or adjacent files, while a5
+        // stops b4 and b4 stops a5. Nothing stops Black's f7.
+        let board = board_from("4k3/5p2/8/p2P4/1P6/8/7P/4K3 w - - 0 1");
+        let entry = PawnHashTable::new().probe(&board);
+        let squares = |color| entry.passed(color).squares().collect::<Vec<_>>();
+        assert_eq!(squares(Color::White), vec![15, 35]);
+        assert_eq!(squares(Color::Black), vec![53]);
+
+        let start = PawnHashTable::new().probe(&Board::new());
+        assert_eq!(start.score, 0);
+        assert!(start.passed(Color::White).is_empty() && start.passed(Color::Black).is_empty());
+    }
+
+    #[test]
+    fn keys_on_pawns_only_and_reuses_the_entry_across_piece_moves() {
+        let mut board = Board::new();
+        let table = PawnHashTable::new();
+        let key = table.probe(&board).key;
+        assert_eq!(key, zobrist::get_keys().pawn_hash(&board.get_state().board));
+
+        board.make_move(&Move::new(6, 21, PieceType::Knight));
+        assert_eq!(table.probe(&board).key, key);
+        board.make_move(&Move::new(52, 36, PieceType::Pawn));
+        let entry = table.probe(&board);
+        assert_ne!(entry.key, key);
+        assert_eq!(entry.score, -20);
+        assert_eq!(
+            table
+                .entries
+                .borrow()
+                .iter()
+                .filter(|entry| entry.key != 0)
+                .count(),
+            2
+        );
+    }
+
+    // Captures, en passant and promotions included, as the test positions two plies deep cover
+    #[test]
+    fn the_board_keeps_the_pawn_key_up_to_date_through_moves_and_undo() {
+        fn walk(board: &mut Board, depth: u8) {
+            assert_eq!(
+                board.pawn_hash(),
+                zobrist::get_keys().pawn_hash(&board.get_state().board)
+            );
+            if depth == 0 {
+                return;
+            }
+            let color = board.get_turn();
+            for chess_move in MoveGenerator::new().get_legal_moves(board, color) {
+                board.make_move(&chess_move);
+                walk(board, depth - 1);
+                board.undo_move();
+            }
+            assert_eq!(
+                board.pawn_hash(),
+                zobrist::get_keys().pawn_hash(&board.get_state().board)
+            );
+        }
+        for position in TEST_POSITIONS
+            .iter()
+            .filter(|position| !position.perft.is_empty())
+        {
+            walk(&mut board_from(position.fen), 2);
+        }
+    }
+}
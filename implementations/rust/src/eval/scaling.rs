@@ -0,0 +1,113 @@
+use super::material::MaterialSignature;
+use crate::board::Board;
+use crate::types::*;
+
+// Scale factors are out of SCALE_NORMAL; the final score is multiplied by factor / SCALE_NORMAL.
+pub const SCALE_NORMAL: i32 = 64;
+// Bare opposite-coloured bishops hold even two or three pawns down.
+const SCALE_OPPOSITE_BISHOPS: i32 = 16;
+const SCALE_OPPOSITE_BISHOPS_WITH_PIECES: i32 = 44;
+// A rook ending an extra pawn up with few pawns left is usually held.
+const SCALE_ROOK_ENDING: i32 = 40;
+const ROOK_ENDING_MAX_PAWNS: i32 = 3;
+// Without pawns the stronger side needs a rook more than the defender to force mate.
+const SCALE_NO_PAWNS_VS_MINOR: i32 = 4;
+const SCALE_NO_PAWNS: i32 = 14;
+
+// How much of `strong`'s nominal advantage is likely to convert, given the material left.
+pub fn scale_factor(signature: &MaterialSignature, board: &Board, strong: Color) -> i32 {
+    let weak = strong.opposite();
+    let strong_pawns = signature.count(strong, PieceType::Pawn);
+    let weak_pawns = signature.count(weak, PieceType::Pawn);
+    let strong_pieces = signature.non_pawn_material(strong);
+    let weak_pieces = signature.non_pawn_material(weak);
+    let bishop = PieceType::Bishop.value();
+    let rook = PieceType::Rook.value();
+
+    if strong_pawns == 0 && strong_pieces - weak_pieces <= bishop {
+        return if strong_pieces < rook {
+            0
+        } else if weak_pieces <= bishop {
+            SCALE_NO_PAWNS_VS_MINOR
+        } else {
+            SCALE_NO_PAWNS
+        };
+    }
+
+    if signature.count(strong, PieceType::Bishop) == 1
+        && signature.count(weak, PieceType::Bishop) == 1
+        && opposite_bishops(board)
+    {
+        return if strong_pieces == bishop && weak_pieces == bishop {
+            SCALE_OPPOSITE_BISHOPS
+        } else {
+            SCALE_OPPOSITE_BISHOPS_WITH_PIECES
+        };
+    }
+
+    if strong_pieces == rook
+        && weak_pieces == rook
+        && signature.count(strong, PieceType::Rook) == 1
+        && signature.count(weak, PieceType::Rook) == 1
+        && strong_pawns <= ROOK_ENDING_MAX_PAWNS
+        && strong_pawns - weak_pawns <= 1
+    {
+        return SCALE_ROOK_ENDING;
+    }
+
+    SCALE_NORMAL
+}
+
+// Only called once both sides are known to have exactly one bishop.
+fn opposite_bishops(board: &Board) -> bool {
+    let mut shades = [None; 2];
+    for square in 0..64 {
+        if let Some(piece) = board.get_piece(square) {
+            if piece.piece_type == PieceType::Bishop {
+                let side = usize::from(piece.color == Color::Black);
+                shades[side] = Some((square % 8 + square / 8) % 2);
+            }
+        }
+    }
+    shades[0] != shades[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::FenParser;
+
+    fn scale(fen: &str) -> i32 {
+        let mut board = Board::new();
+        FenParser::new().parse_fen(&mut board, fen).unwrap();
+        scale_factor(&MaterialSignature::from_board(&board), &board, Color::White)
+    }
+
+    #[test]
+    fn drawish_endings_scale_down() {
+        // Opposite-coloured bishops two pawns up
+        assert_eq!(
+            scale("4k3/8/4b3/8/2PP4/8/3B4/4K3 w - - 0 1"),
+            SCALE_OPPOSITE_BISHOPS
+        );
+        // Same-coloured bishops convert normally
+        assert_eq!(scale("4k3/8/5b2/8/2PP4/8/3B4/4K3 w - - 0 1"), SCALE_NORMAL);
+        // Rook and three pawns against rook and two, but not four against three
+        assert_eq!(
+            scale("4k3/r5pp/8/8/8/8/R4PPP/4K3 w - - 0 1"),
+            SCALE_ROOK_ENDING
+        );
+        assert_eq!(
+            scale("4k3/r4ppp/8/8/8/8/R3PPPP/4K3 w - - 0 1"),
+            SCALE_NORMAL
+        );
+        // Rook against bishop, or a lone knight, cannot win
+        assert_eq!(
+            scale("4k3/8/5b2/8/8/8/8/R3K3 w - - 0 1"),
+            SCALE_NO_PAWNS_VS_MINOR
+        );
+        assert_eq!(scale("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1"), 0);
+        // Queen against rook stays winning
+        assert_eq!(scale("4k3/8/5r2/8/8/8/8/Q3K3 w - - 0 1"), SCALE_NORMAL);
+    }
+}
@@ -0,0 +1,400 @@
+use crate::ai::{AI, MATE_SCORE};
+use crate::board::Board;
+use crate::move_generator::MoveGenerator;
+use crate::tactics::{Motif, MotifKind, TacticsDetector};
+use crate::types::*;
+use std::fmt;
+
+// Below this many centipawns the played move is considered as good as the best one.
+const MISSED_MOVE_THRESHOLD_CP: i32 = 50;
+const TRADE_MARGIN_CP: i32 = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaptureOutcome {
+    Wins,
+    Trades { given: PieceType },
+    Gains(i32),
+    Concedes(i32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Insight {
+    Checkmate,
+    Stalemate,
+    Check,
+    Castles {
+        kingside: bool,
+    },
+    Captures {
+        piece: PieceType,
+        outcome: CaptureOutcome,
+    },
+    Promotes(PieceType),
+    Develops {
+        piece: PieceType,
+        with_tempo: bool,
+    },
+    OpensFile {
+        file: usize,
+    },
+    Creates(Motif),
+    Allows(Motif),
+    Misses {
+        best: Move,
+        loss_cp: i32,
+    },
+}
+
+impl fmt::Display for Insight {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Insight::Checkmate => write!(f, "delivers checkmate"),
+            Insight::Stalemate => write!(f, "stalemates the opponent"),
+            Insight::Check => write!(f, "gives check"),
+            Insight::Castles { kingside } => write!(
+                f,
+                "castles {}",
+                if *kingside { "kingside" } else { "queenside" }
+            ),
+            Insight::Captures { piece, outcome } => match outcome {
+                CaptureOutcome::Wins => write!(f, "wins a {}", piece_name(*piece)),
+                CaptureOutcome::Trades { given } if given == piece => {
+                    write!(f, "trades {}s", piece_name(*piece))
+                }
+                CaptureOutcome::Trades { given } => write!(
+                    f,
+                    "trades a {} for a {}",
+                    piece_name(*given),
+                    piece_name(*piece)
+                ),
+                CaptureOutcome::Gains(points) => write!(f, "wins material (+{}cp)", points),
+                CaptureOutcome::Concedes(points) => {
+                    write!(f, "gives up material (-{}cp)", points)
+                }
+            },
+            Insight::Promotes(piece) => write!(f, "promotes to a {}", piece_name(*piece)),
+            Insight::Develops { piece, with_tempo } => {
+                write!(f, "develops the {}", piece_name(*piece))?;
+                if *with_tempo {
+                    write!(f, " with tempo")?;
+                }
+                Ok(())
+            }
+            Insight::OpensFile { file } => {
+                write!(f, "opens the {}-file toward the enemy king", FILES[*file])
+            }
+            Insight::Creates(motif) => write!(
+                f,
+                "creates {} ({})",
+                with_article(motif.label()),
+                motif.description()
+            ),
+            Insight::Allows(motif) => write!(
+                f,
+                "allows {} ({})",
+                with_article(motif.label()),
+                motif.description()
+            ),
+            Insight::Misses { best, loss_cp } => {
+                write!(f, "misses {}, which was {}cp better", best, loss_cp)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MoveExplanation {
+    pub chess_move: Move,
+    pub depth: u8,
+    pub eval_before: i32,
+    pub eval_after: i32,
+    pub best_move: Option<Move>,
+    pub insights: Vec<Insight>,
+}
+
+impl fmt::Display for MoveExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let phrases: Vec<String> = self
+            .insights
+            .iter()
+            .map(|insight| insight.to_string())
+            .collect();
+        let summary = match phrases.split_last() {
+            None => "is a quiet move".to_string(),
+            Some((last, [])) => last.clone(),
+            Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+        };
+        write!(
+            f,
+            "{} {} (eval {} -> {}, depth={}",
+            self.chess_move, summary, self.eval_before, self.eval_after, self.depth
+        )?;
+        if let Some(best) = &self.best_move {
+            write!(f, ", best {}", best)?;
+        }
+        write!(f, ")")
+    }
+}
+
+pub struct MoveExplainer {
+    move_generator: MoveGenerator,
+    tactics: TacticsDetector,
+}
+
+impl MoveExplainer {
+    pub fn new() -> Self {
+        Self {
+            move_generator: MoveGenerator::new(),
+            tactics: TacticsDetector::new(),
+        }
+    }
+
+    // `chess_move` must be legal for the side to move; the board is restored before returning.
+    pub fn explain(
+        &self,
+        board: &mut Board,
+        ai: &mut AI,
+        chess_move: &Move,
+        depth: u8,
+    ) -> MoveExplanation {
+        let mover = board.get_turn();
+        let opponent = mover.opposite();
+        let sign = if mover == Color::White { 1 } else { -1 };
+
+        let before = ai.find_best_move(board, depth);
+        let own_motifs_before = self.tactics.detect_all(board, mover);
+        let their_motifs_before = self.tactics.detect_all(board, opponent);
+        let open_files_before = half_open_files(board, mover);
+
+        board.make_move(chess_move);
+
+        let gives_check = self.move_generator.is_in_check(board, opponent);
+        let has_reply = self.move_generator.has_legal_move(board, opponent);
+        let eval_after = match (has_reply, gives_check) {
+            (false, true) => MATE_SCORE * sign,
+            (false, false) => 0,
+            (true, _) => {
+                ai.find_best_move(board, depth.saturating_sub(1).max(1))
+                    .evaluation
+            }
+        };
+
+        let mut insights = Vec::new();
+        match (has_reply, gives_check) {
+            (false, true) => insights.push(Insight::Checkmate),
+            (false, false) => insights.push(Insight::Stalemate),
+            (true, true) => insights.push(Insight::Check),
+            (true, false) => {}
+        }
+
+        if chess_move.is_castling {
+            insights.push(Insight::Castles {
+                kingside: chess_move.to % 8 == 6,
+            });
+        }
+
+        if let Some(captured) = chess_move.captured {
+            insights.push(Insight::Captures {
+                piece: captured,
+                outcome: self.capture_outcome(board, chess_move, captured, mover),
+            });
+        }
+
+        if let Some(promotion) = chess_move.promotion {
+            insights.push(Insight::Promotes(promotion));
+        }
+
+        let home_rank = if mover == Color::White { 0 } else { 7 };
+        if matches!(chess_move.piece, PieceType::Knight | PieceType::Bishop)
+            && chess_move.from / 8 == home_rank
+            && chess_move.to / 8 != home_rank
+        {
+            insights.push(Insight::Develops {
+                piece: chess_move.piece,
+                with_tempo: gives_check || self.attacks_something(board, chess_move.to, mover),
+            });
+        }
+
+        let open_files_after = half_open_files(board, mover);
+        if let Some(king_square) = board.king_square(opponent) {
+            for file in 0..8 {
+                let king_distance = (king_square % 8).abs_diff(file);
+                if !open_files_before[file] && open_files_after[file] && king_distance <= 1 {
+                    insights.push(Insight::OpensFile { file });
+                }
+            }
+        }
+
+        for motif in self.tactics.detect_all(board, mover) {
+            if !own_motifs_before.contains(&motif) {
+                insights.push(Insight::Creates(motif));
+            }
+        }
+
+        for motif in self.tactics.detect_all(board, opponent) {
+            let restates_capture = chess_move.captured.is_some()
+                && matches!(&motif.kind, MotifKind::Hanging { target, .. } if target.square == chess_move.to);
+            if !their_motifs_before.contains(&motif) && !restates_capture {
+                insights.push(Insight::Allows(motif));
+            }
+        }
+
+        board.undo_move();
+
+        if let Some(best) = &before.best_move {
+            let loss_cp = (before.evaluation - eval_after) * sign;
+            if best != chess_move && loss_cp >= MISSED_MOVE_THRESHOLD_CP {
+                insights.push(Insight::Misses {
+                    best: best.clone(),
+                    loss_cp,
+                });
+            }
+        }
+
+        MoveExplanation {
+            chess_move: chess_move.clone(),
+            depth,
+            eval_before: before.evaluation,
+            eval_after,
+            best_move: before.best_move,
+            insights,
+        }
+    }
+
+    // Evaluated on the position after the capture: can the opponent win the capturing piece back?
+    fn capture_outcome(
+        &self,
+        board: &Board,
+        chess_move: &Move,
+        captured: PieceType,
+        mover: Color,
+    ) -> CaptureOutcome {
+        let moved = chess_move.promotion.unwrap_or(chess_move.piece);
+        let attackers = self
+            .move_generator
+            .attackers_of(board, chess_move.to, mover.opposite());
+        let defended = !self
+            .move_generator
+            .attackers_of(board, chess_move.to, mover)
+            .is_empty();
+        let cheapest_attacker = attackers
+            .iter()
+            .filter_map(|&square| board.get_piece(square))
+            .map(|piece| piece.piece_type.value())
+            .min();
+
+        let en_prise = match cheapest_attacker {
+            None => false,
+            Some(value) => !defended || value < moved.value(),
+        };
+        if !en_prise {
+            return CaptureOutcome::Wins;
+        }
+
+        let net = captured.value() - moved.value();
+        if net.abs() < TRADE_MARGIN_CP {
+            CaptureOutcome::Trades { given: moved }
+        } else if net > 0 {
+            CaptureOutcome::Gains(net)
+        } else {
+            CaptureOutcome::Concedes(-net)
+        }
+    }
+
+    fn attacks_something(&self, board: &Board, square: Square, mover: Color) -> bool {
+        let Some(piece) = board.get_piece(square) else {
+            return false;
+        };
+        self.move_generator
+            .attacks_from(board, square)
+            .into_iter()
+            .filter_map(|target| board.get_piece(target).map(|victim| (target, victim)))
+            .any(|(target, victim)| {
+                victim.color != mover
+                    && victim.piece_type != PieceType::King
+                    && (victim.piece_type.value() > piece.piece_type.value()
+                        || self
+                            .move_generator
+                            .attackers_of(board, target, victim.color)
+                            .is_empty())
+            })
+    }
+}
+
+fn half_open_files(board: &Board, color: Color) -> [bool; 8] {
+    let mut open = [true; 8];
+    for square in 0..64 {
+        if board.get_piece(square) == Some(Piece::new(PieceType::Pawn, color)) {
+            open[square % 8] = false;
+        }
+    }
+    open
+}
+
+fn piece_name(piece: PieceType) -> &'static str {
+    match piece {
+        PieceType::King => "king",
+        PieceType::Queen => "queen",
+        PieceType::Rook => "rook",
+        PieceType::Bishop => "bishop",
+        PieceType::Knight => "knight",
+        PieceType::Pawn => "pawn",
+    }
+}
+
+fn with_article(label: &str) -> String {
+    let article = if label.starts_with(['a', 'e', 'i', 'o', 'u']) {
+        "an"
+    } else {
+        "a"
+    };
+    format!("{} {}", article, label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::FenParser;
+
+    fn explain(fen: &str, from: Square, to: Square, depth: u8) -> (Board, MoveExplanation) {
+        let mut board = Board::new();
+        FenParser::new().parse_fen(&mut board, fen).unwrap();
+        let turn = board.get_turn();
+        let chess_move = MoveGenerator::new()
+            .get_legal_moves(&mut board, turn)
+            .into_iter()
+            .find(|candidate| candidate.from == from && candidate.to == to)
+            .unwrap();
+        let explanation =
+            MoveExplainer::new().explain(&mut board, &mut AI::new(), &chess_move, depth);
+        (board, explanation)
+    }
+
+    #[test]
+    fn recognises_back_rank_mate() {
+        let fen = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+        let (board, explanation) = explain(fen, 0, 56, 2);
+        assert_eq!(explanation.insights.first(), Some(&Insight::Checkmate));
+        assert_eq!(explanation.eval_after, MATE_SCORE);
+        assert_eq!(FenParser::new().export_fen(&board), fen);
+    }
+
+    #[test]
+    fn flags_blunders_against_the_best_move() {
+        let (_, explanation) = explain("4k3/8/8/3q4/8/8/8/3QK3 w - - 0 1", 3, 2, 2);
+        assert!(explanation
+            .insights
+            .iter()
+            .any(|insight| matches!(insight, Insight::Misses { best, .. } if best.to == 35)));
+        assert!(explanation.to_string().starts_with("d1c1 "));
+    }
+
+    #[test]
+    fn describes_winning_an_undefended_piece() {
+        let (_, explanation) = explain("4k3/8/8/3n4/8/8/8/3RK3 w - - 0 1", 3, 35, 2);
+        assert!(explanation.insights.contains(&Insight::Captures {
+            piece: PieceType::Knight,
+            outcome: CaptureOutcome::Wins,
+        }));
+    }
+}
@@ -1,5 +1,9 @@
 use crate::types::*;
 use crate::board::Board;
+use crate::zobrist;
+
+// Well beyond any legal game, low enough that counters can never overflow while playing on
+const MAX_MOVE_COUNTER: u32 = 100_000;
 
 pub struct FenParser;
 
@@ -9,9 +13,10 @@ impl FenParser {
     }
 
     pub fn parse_fen(&self, board: &mut Board, fen: &str) -> Result<(), String> {
+        let invalid = || "Invalid FEN string".to_string();
         let parts: Vec<&str> = fen.split_whitespace().collect();
-        if parts.len() < 4 {
-            return Err("ERROR: Invalid FEN string".to_string());
+        if parts.len() < 4 || parts.len() > 6 {
+            return Err(invalid());
         }
 
         let pieces = parts[0];
@@ -21,35 +26,30 @@ impl FenParser {
         let halfmove = parts.get(4).unwrap_or(&"0");
         let fullmove = parts.get(5).unwrap_or(&"1");
 
-        // Clear board
-        for i in 0..64 {
-            board.set_piece(i, None);
-        }
-
-        // Parse piece positions
-        let mut square = 56; // Start at a8 (top-left)
-        for ch in pieces.chars() {
-            match ch {
-                '/' => square -= 16, // Move to next rank
-                '1'..='8' => {
-                    let empty_squares = ch.to_digit(10).unwrap() as usize;
-                    square += empty_squares;
-                },
-                _ => {
-                    if let Some(piece) = Piece::from_char(ch) {
-                        board.set_piece(square, Some(piece));
-                        square += 1;
-                    }
-                }
-            }
+        // Validate everything before touching the board so a rejected FEN leaves it unchanged
+        let placement = parse_placement(pieces).ok_or_else(invalid)?;
+        let halfmove = parse_counter(halfmove).ok_or_else(invalid)?;
+        let fullmove = parse_counter(fullmove).ok_or_else(invalid)?;
+        if !is_valid_castling(castling) {
+            return Err(invalid());
         }
+        let en_passant_target = match en_passant {
+            "-" => None,
+            square => match algebraic_to_square(square) {
+                Ok(target) if target / 8 == 2 || target / 8 == 5 => Some(target),
+                _ => return Err(invalid()),
+            },
+        };
 
-        // Parse turn
         let color = match turn {
             "w" => Color::White,
             "b" => Color::Black,
-            _ => return Err("ERROR: Invalid FEN string".to_string()),
+            _ => return Err(invalid()),
         };
+
+        for (square, piece) in placement.into_iter().enumerate() {
+            board.set_piece(square, piece);
+        }
         board.set_turn(color);
 
         // Parse castling rights
@@ -60,20 +60,19 @@ impl FenParser {
         if castling.contains('q') { rights.black_queenside = true; }
         board.set_castling_rights(rights);
 
-        // Parse en passant target
-        if en_passant != "-" {
-            if let Ok(square) = algebraic_to_square(en_passant) {
-                board.set_en_passant_target(Some(square));
-            }
-        } else {
-            board.set_en_passant_target(None);
-        }
+        board.set_en_passant_target(en_passant_target);
 
         // Parse move counters
         let state = board.get_state();
         let mut new_state = state.clone();
-        new_state.halfmove_clock = halfmove.parse().unwrap_or(0);
-        new_state.fullmove_number = fullmove.parse().unwrap_or(1);
+        new_state.halfmove_clock = halfmove;
+        new_state.fullmove_number = fullmove;
+        // A loaded position starts a fresh game: no undo history, and the hash must match the new placement
+        new_state.move_history.clear();
+        new_state.position_history.clear();
+        new_state.irreversible_history.clear();
+        new_state.zobrist_hash = zobrist::get_keys().compute_hash(&new_state);
+        new_state.pawn_hash = zobrist::get_keys().pawn_hash(&new_state.board);
         board.set_state(new_state);
 
         Ok(())
@@ -145,4 +144,62 @@ impl FenParser {
             None => "-".to_string(),
         }
     }
-}
\ No newline at end of file
+}
+
+impl Default for FenParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Exactly eight ranks of exactly eight squares, from a8 down to h1
+fn parse_placement(pieces: &str) -> Option<[Option<Piece>; 64]> {
+    let ranks: Vec<&str> = pieces.split('/').collect();
+    if ranks.len() != 8 {
+        return None;
+    }
+
+    let mut placement = [None; 64];
+    for (row, rank) in ranks.iter().enumerate() {
+        let rank_start = (7 - row) * 8;
+        let mut file = 0;
+        for ch in rank.chars() {
+            match ch {
+                '1'..='8' => file += ch.to_digit(10)? as usize,
+                _ => {
+                    if file >= 8 {
+                        return None;
+                    }
+                    placement[rank_start + file] = Some(Piece::from_char(ch)?);
+                    file += 1;
+                }
+            }
+            if file > 8 {
+                return None;
+            }
+        }
+        if file != 8 {
+            return None;
+        }
+    }
+    Some(placement)
+}
+
+fn parse_counter(value: &str) -> Option<u32> {
+    value.parse().ok().filter(|&count| count <= MAX_MOVE_COUNTER)
+}
+
+fn is_valid_castling(castling: &str) -> bool {
+    castling == "-"
+        || (!castling.is_empty()
+            && castling.len() <= 4
+            && castling.chars().all(|ch| matches!(ch, 'K' | 'Q' | 'k' | 'q')))
+}
+
+// The board `fen` sets up, for tests that start from a position
+#[cfg(test)]
+pub fn board_from(fen: &str) -> Board {
+    let mut board = Board::new();
+    FenParser::new().parse_fen(&mut board, fen).unwrap();
+    board
+}
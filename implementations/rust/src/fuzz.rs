@@ -0,0 +1,259 @@
+// Deterministic mutation fuzzing for every text parser the engine exposes to its peer.
+// cargo-fuzz would need libfuzzer, so seeds are mutated with a fixed xorshift stream instead;
+// each target only asserts that malformed input is rejected without panicking.
+
+use crate::board::Board;
+use crate::completion::EngineCompleter;
+use crate::fen::FenParser;
+use crate::import;
+use crate::input::{self, InputLine};
+use crate::line_editor::Completer;
+use crate::move_generator::MoveGenerator;
+use crate::notation::SanParser;
+use crate::perft::{parse_suite, Perft};
+use crate::pgn::{read_games, MAX_PGN_LINE_BYTES};
+use crate::search_limits::SearchLimits;
+use crate::ChessEngine;
+use std::io::BufReader;
+
+const ITERATIONS: usize = 2000;
+// Bytes that are meaningful to at least one parser, plus NUL, a multi-byte character and noise.
+const ALPHABET: &[&str] = &[
+    "/",
+    " ",
+    "-",
+    "=",
+    "+",
+    "#",
+    "x",
+    "0",
+    "1",
+    "8",
+    "9",
+    "K",
+    "q",
+    "p",
+    "P",
+    "w",
+    "b",
+    "e",
+    "h",
+    "O",
+    "\0",
+    "\n",
+    "\r",
+    "\t",
+    "é",
+    "♞",
+    "\u{ffff}",
+    "99999999999",
+];
+
+struct Mutator {
+    state: u64,
+}
+
+impl Mutator {
+    fn new(seed: u64) -> Self {
+        Self { state: seed | 1 }
+    }
+
+    fn next(&mut self) -> usize {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state as usize
+    }
+
+    fn mutate(&mut self, seed: &str) -> String {
+        let mut text: Vec<char> = seed.chars().collect();
+        for _ in 0..1 + self.next() % 4 {
+            let at = if text.is_empty() {
+                0
+            } else {
+                self.next() % text.len()
+            };
+            match self.next() % 4 {
+                0 if !text.is_empty() => {
+                    text.remove(at);
+                }
+                1 if !text.is_empty() => {
+                    let end = (at + 1 + self.next() % 8).min(text.len());
+                    let chunk: Vec<char> = text[at..end].to_vec();
+                    text.splice(at..at, chunk);
+                }
+                _ => {
+                    let insertion = ALPHABET[self.next() % ALPHABET.len()];
+                    text.splice(at..at, insertion.chars());
+                }
+            }
+        }
+        text.into_iter().collect()
+    }
+
+    fn inputs<'a>(&'a mut self, seeds: &'a [&'a str]) -> impl Iterator<Item = String> + 'a {
+        (0..ITERATIONS).map(move |index| self.mutate(seeds[index % seeds.len()]))
+    }
+}
+
+const FEN_SEEDS: &[&str] = &[
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+    "4k3/P7/8/8/8/8/8/4K3 w - - 99 200",
+];
+
+const MOVE_SEEDS: &[&str] = &["e2e4", "g1f3", "e7e8Q", "e1g1", "a2a3q", "h2h4"];
+
+const SAN_SEEDS: &[&str] = &[
+    "e4", "Nf3", "exd5", "O-O-O", "e8=Q+", "Nbd7", "R1a3#", "0-0",
+];
+
+const PGN_SEEDS: &[&str] = &[
+    "[Event \"One\"]\n[Result \"1-0\"]\n\n1. e4 {best} e5 2. Nf3!? (2. f4 exf4) Nc6 $1 3. Bb5 1-0\n",
+    "[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n\n1. e4 Kd7 *\n",
+    "1. d4 d5 ; rest of line\n2. c4 (2. Nf3 (2. Bf4)) 0-0 *\n% escape\n",
+];
+
+const EPD_SEEDS: &[&str] = &[
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 400 ;id startpos\n",
+    "# comment\n\n8/8/8/8/8/8/8/K6k w - - 0 1 ;D2 9 ;D1 3\n",
+];
+
+#[test]
+fn fen_parser_rejects_mutated_positions_cleanly() {
+    let parser = FenParser::new();
+    let generator = MoveGenerator::new();
+    let mut mutator = Mutator::new(0x5eed_f3a7);
+
+    for fen in mutator.inputs(FEN_SEEDS) {
+        let mut board = Board::new();
+        if parser.parse_fen(&mut board, &fen).is_ok() {
+            // Anything accepted must be safe to export and to play from.
+            let exported = parser.export_fen(&board);
+            assert!(
+                parser.parse_fen(&mut Board::new(), &exported).is_ok(),
+                "{:?}",
+                fen
+            );
+            let turn = board.get_turn();
+            for chess_move in generator.get_legal_moves(&mut board, turn) {
+                board.make_move(&chess_move);
+                board.undo_move();
+            }
+        } else {
+            assert_eq!(parser.export_fen(&board), parser.export_fen(&Board::new()));
+        }
+    }
+}
+
+#[test]
+fn move_parsers_reject_mutated_moves_cleanly() {
+    let mut engine = ChessEngine::new();
+    let san_parser = SanParser::new();
+    let mut mutator = Mutator::new(0x00c0_ffee);
+
+    for text in mutator.inputs(MOVE_SEEDS) {
+        let _ = engine.resolve_move(&text);
+    }
+    for text in mutator.inputs(SAN_SEEDS) {
+        let _ = san_parser.parse_san(&mut engine.board, &text);
+    }
+}
+
+#[test]
+fn pgn_reader_survives_mutated_games() {
+    let san_parser = SanParser::new();
+    let mut mutator = Mutator::new(0x0060_77e5);
+
+    for text in mutator.inputs(PGN_SEEDS) {
+        // Whatever is read must replay or be refused, never panic.
+        for game in read_games(&text) {
+            let _ = import::replay(&game, &san_parser);
+        }
+    }
+}
+
+#[test]
+fn perft_suite_parser_survives_mutated_files() {
+    let perft = Perft::new();
+    let mut mutator = Mutator::new(0x0000_e9d5);
+
+    for text in mutator.inputs(EPD_SEEDS) {
+        // Cases are set up the way `perft suite` does: a bad FEN is reported, not played.
+        for case in parse_suite(&text).unwrap_or_default() {
+            let mut board = Board::new();
+            if FenParser::new().parse_fen(&mut board, &case.fen).is_ok() {
+                perft.perft(&mut board, 1);
+            }
+        }
+    }
+}
+
+#[test]
+fn protocol_surfaces_reject_mutated_lines_cleanly() {
+    let mut mutator = Mutator::new(0x0bad_cafe);
+    let seeds = [
+        "ai depth 3 movetime 50",
+        "go movetime 10",
+        "complete \"mov e2\"",
+        "fen 8/8 w",
+    ];
+
+    for line in mutator.inputs(&seeds) {
+        let _ = input::check_command(&line);
+        let args: Vec<&str> = line.split_whitespace().skip(1).collect();
+        let _ = SearchLimits::parse(&args);
+
+        let mut reader = BufReader::with_capacity(8, line.as_bytes());
+        while input::read_line_bounded(&mut reader, 16).unwrap() != InputLine::Eof {}
+
+        let mut board = Board::new();
+        let _ = EngineCompleter {
+            board: &mut board,
+            move_generator: &MoveGenerator::new(),
+            san_parser: &SanParser::new(),
+            fen_parser: &FenParser::new(),
+            blindfold: true,
+        }
+        .complete(&line);
+    }
+}
+
+#[test]
+fn oversized_inputs_are_rejected_without_quadratic_work() {
+    let huge = "8/".repeat(500_000) + " w - - 0 1";
+    assert!(FenParser::new()
+        .parse_fen(&mut Board::new(), &huge)
+        .is_err());
+
+    let counters = "8/8/8/8/8/8/8/8 w - - 4294967295 18446744073709551616";
+    assert!(FenParser::new()
+        .parse_fen(&mut Board::new(), counters)
+        .is_err());
+
+    let mut engine = ChessEngine::new();
+    let long_move = "e2e4".repeat(250_000);
+    assert!(engine.resolve_move(&long_move).is_err());
+    assert!(SanParser::new()
+        .parse_san(&mut engine.board, &long_move)
+        .is_err());
+
+    // Variations nested a million deep, a line at a time, cost a counter
+    let nested = "1. e4\n".to_string() + &"(\n".repeat(1_000_000) + "e5 *\n";
+    assert_eq!(read_games(&nested)[0].moves, ["e4"]);
+    let long_line = "1. e4 ".to_string() + &"e5 ".repeat(MAX_PGN_LINE_BYTES) + "*\n";
+    assert!(read_games(&long_line).is_empty());
+
+    let line = "x".repeat(input::MAX_LINE_BYTES * 8) + "\nquit\n";
+    let mut reader = BufReader::new(line.as_bytes());
+    assert_eq!(
+        input::read_line_bounded(&mut reader, input::MAX_LINE_BYTES).unwrap(),
+        InputLine::Rejected("Command too long")
+    );
+    assert_eq!(
+        input::read_line_bounded(&mut reader, input::MAX_LINE_BYTES).unwrap(),
+        InputLine::Line("quit".to_string())
+    );
+}
@@ -0,0 +1,413 @@
+use crate::board::Board;
+use crate::fen::FenParser;
+use crate::move_generator::MoveGenerator;
+use crate::options::Options;
+use std::fs::File;
+use std::io::{self, Write};
+
+pub const LOG_HEADER: &str = "# chess event log v1";
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// One state change: `new`, `fen <fen>`, `move <uci>` (`0000` for a pass), `undo` or
+// `option <name> <value>`, with the hash of the FEN it leaves and the chain hash through it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub seq: u64,
+    pub position: u64,
+    pub chain: u64,
+    pub action: String,
+}
+
+impl Event {
+    // `<seq> <position> <chain> <action>`, hashes in 16 hex digits
+    pub fn line(&self) -> String {
+        format!(
+            "{} {:016x} {:016x} {}",
+            self.seq, self.position, self.chain, self.action
+        )
+    }
+}
+
+// 64-bit FNV-1a: simple enough for another implementation to reproduce from the README, and
+// unlike Zobrist keys the same everywhere.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+fn position_hash(board: &Board) -> u64 {
+    fnv1a(&FenParser::new().export_fen(board))
+}
+
+// Each event's chain hash covers the previous one, so editing, dropping or reordering events
+// breaks every hash after the change. The first event chains from 0.
+fn chain_hash(previous: u64, action: &str, position: u64) -> u64 {
+    fnv1a(&format!("{:016x} {} {:016x}", previous, action, position))
+}
+
+// The session's append-only event log. Rather than every command reporting what it changed,
+// the REPL shows the log the board and options after each command, and the difference from
+// what it last logged becomes events: moves played however they were entered (SAN, `ai`,
+// `playout`, `import`), moves taken back, a position replaced outright, and option values.
+pub struct GameLog {
+    events: Vec<Event>,
+    // Where new events are appended as they happen, after `log save`
+    file: Option<(String, File)>,
+    // The game and options as of the last event
+    board: Board,
+    options: Options,
+}
+
+impl GameLog {
+    pub fn new(board: &Board, options: &Options) -> Self {
+        let mut log = Self {
+            events: Vec::new(),
+            file: None,
+            board: board.clone(),
+            options: options.clone(),
+        };
+        let start = FenParser::new().export_fen(board);
+        let action = if start == START_FEN {
+            "new".to_string()
+        } else {
+            format!("fen {}", start)
+        };
+        log.push(action, board);
+        log
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn chain(&self) -> u64 {
+        self.events.last().map_or(0, |event| event.chain)
+    }
+
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_ref().map(|(path, _)| path.as_str())
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\n", LOG_HEADER);
+        for event in &self.events {
+            text.push_str(&event.line());
+            text.push('\n');
+        }
+        text
+    }
+
+    // Writes the events so far to `path`, then appends each new one as it happens.
+    pub fn save(&mut self, path: &str) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_text().as_bytes())?;
+        self.file = Some((path.to_string(), file));
+        Ok(())
+    }
+
+    // Logs what `command` (the whole line) changed. Most commands change nothing, and those
+    // cost a comparison.
+    pub fn record(&mut self, command: &str, after: &Board, options: &Options) {
+        let name = command
+            .split_whitespace()
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+        let reset = matches!(name.as_str(), "new" | "ucinewgame" | "new960");
+        if !reset && *options == self.options && same_game(&self.board, after) {
+            return;
+        }
+
+        let mut board = std::mem::replace(&mut self.board, after.clone());
+        if reset {
+            board = Board::new();
+            self.push("new".to_string(), &board);
+        }
+
+        let (start, moves) = split(&board);
+        let (target_start, target_moves) = split(after);
+        let fen_parser = FenParser::new();
+        let common = if fen_parser.export_fen(&start) == fen_parser.export_fen(&target_start) {
+            let common = moves
+                .iter()
+                .zip(&target_moves)
+                .take_while(|(a, b)| a == b)
+                .count();
+            for _ in common..moves.len() {
+                board.undo_move();
+                self.push("undo".to_string(), &board);
+            }
+            common
+        } else {
+            board = target_start;
+            self.push(format!("fen {}", fen_parser.export_fen(&board)), &board);
+            0
+        };
+        for chess_move in &target_moves[common..] {
+            if chess_move.is_null() {
+                board.make_null_move();
+            } else {
+                board.make_move(chess_move);
+            }
+            self.push(format!("move {}", chess_move), &board);
+        }
+
+        let before = std::mem::replace(&mut self.options, options.clone());
+        for (spec, value) in options.iter() {
+            if before.get(spec.name) != Some(value) {
+                self.push(format!("option {} {}", spec.name, value), &board);
+            }
+        }
+    }
+
+    fn push(&mut self, action: String, board: &Board) {
+        let position = position_hash(board);
+        let event = Event {
+            seq: self.events.len() as u64 + 1,
+            position,
+            chain: chain_hash(self.chain(), &action, position),
+            action,
+        };
+        // A log file that can no longer be written stops being followed; the session goes on.
+        if let Some((_, file)) = &mut self.file {
+            if writeln!(file, "{}", event.line()).is_err() {
+                self.file = None;
+            }
+        }
+        self.events.push(event);
+    }
+}
+
+// Same moves from the same start; the FEN also catches a start replaced with no moves played.
+fn same_game(logged: &Board, board: &Board) -> bool {
+    let fen_parser = FenParser::new();
+    logged.get_state().move_history == board.get_state().move_history
+        && fen_parser.export_fen(logged) == fen_parser.export_fen(board)
+}
+
+// The position a board's game started from, and the moves played since.
+fn split(board: &Board) -> (Board, Vec<crate::types::Move>) {
+    let mut start = board.clone();
+    while start.undo_move().is_some() {}
+    (start, board.get_state().move_history.clone())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verified {
+    pub events: usize,
+    pub chain: u64,
+    pub fen: String,
+}
+
+// Where replaying a log went wrong: the event's line number and what failed there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyError {
+    pub line: usize,
+    pub reason: &'static str,
+}
+
+// Replays a log from scratch, checking each event's position and chain hash.
+pub fn verify(text: &str) -> Result<Verified, VerifyError> {
+    let fen_parser = FenParser::new();
+    let move_generator = MoveGenerator::new();
+    let mut board = Board::new();
+    let mut options = Options::new();
+    let mut chain = 0;
+    let mut events = 0;
+
+    for (index, line) in text.lines().enumerate() {
+        let fail = |reason| VerifyError {
+            line: index + 1,
+            reason,
+        };
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.splitn(4, ' ');
+        let (Some(seq), Some(position), Some(recorded_chain), Some(action)) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(fail("malformed event"));
+        };
+        let (Ok(seq), Ok(position), Ok(recorded_chain)) = (
+            seq.parse::<usize>(),
+            u64::from_str_radix(position, 16),
+            u64::from_str_radix(recorded_chain, 16),
+        ) else {
+            return Err(fail("malformed event"));
+        };
+        if seq != events + 1 {
+            return Err(fail("event out of sequence"));
+        }
+
+        let words: Vec<&str> = action.split_whitespace().collect();
+        match words.as_slice() {
+            ["new"] => board = Board::new(),
+            ["fen", fen @ ..] => {
+                let mut replaced = Board::new();
+                fen_parser
+                    .parse_fen(&mut replaced, &fen.join(" "))
+                    .map_err(|_| fail("invalid FEN"))?;
+                board = replaced;
+            }
+            ["move", "0000"] => board.make_null_move(),
+            ["move", uci] => {
+                let turn = board.get_turn();
+                let chess_move = move_generator
+                    .get_legal_moves(&mut board, turn)
+                    .into_iter()
+                    .find(|chess_move| chess_move.to_string() == *uci)
+                    .ok_or(fail("illegal move"))?;
+                board.make_move(&chess_move);
+            }
+            ["undo"] => {
+                board.undo_move().ok_or(fail("nothing to undo"))?;
+            }
+            ["option", name, value] => {
+                options
+                    .set(name, value)
+                    .map_err(|_| fail("invalid option"))?;
+            }
+            _ => return Err(fail("unknown action")),
+        }
+
+        if position_hash(&board) != position {
+            return Err(fail("position hash mismatch"));
+        }
+        chain = chain_hash(chain, action, position);
+        if chain != recorded_chain {
+            return Err(fail("chain hash mismatch"));
+        }
+        events += 1;
+    }
+    Ok(Verified {
+        events,
+        chain,
+        fen: fen_parser.export_fen(&board),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::SanParser;
+
+    fn play(board: &mut Board, moves: &[&str]) {
+        let san_parser = SanParser::new();
+        for san in moves {
+            let chess_move = san_parser.parse_san(board, san).unwrap();
+            board.make_move(&chess_move);
+        }
+    }
+
+    fn actions(log: &GameLog) -> Vec<&str> {
+        log.events()
+            .iter()
+            .map(|event| event.action.as_str())
+            .collect()
+    }
+
+    // A session's worth of changes, the way the REPL reports them
+    fn session() -> GameLog {
+        let mut options = Options::new();
+        let mut board = Board::new();
+        let mut log = GameLog::new(&board, &options);
+
+        play(&mut board, &["e4", "e5", "Nf3"]);
+        log.record("playout 3", &board, &options);
+        log.record("board", &board, &options);
+
+        board.undo_move();
+        board.undo_move();
+        play(&mut board, &["c5"]);
+        board.make_null_move();
+        log.record("undo", &board, &options);
+
+        options.set("MoveOverhead", "250").unwrap();
+        log.record("option MoveOverhead 250", &board, &options);
+
+        FenParser::new()
+            .parse_fen(&mut board, "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1")
+            .unwrap();
+        log.record("fen 4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", &board, &options);
+
+        board.reset();
+        log.record("NEW", &board, &options);
+        log
+    }
+
+    #[test]
+    fn records_the_difference_each_command_made() {
+        assert_eq!(
+            actions(&session()),
+            vec![
+                "new",
+                "move e2e4",
+                "move e7e5",
+                "move g1f3",
+                "undo",
+                "undo",
+                "move c7c5",
+                "move 0000",
+                "option MoveOverhead 250",
+                "fen 4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+                "new",
+            ]
+        );
+    }
+
+    #[test]
+    fn replays_a_log_and_catches_tampering() {
+        let log = session();
+        let text = log.to_text();
+        let verified = verify(&text).unwrap();
+        assert_eq!((verified.events, verified.chain), (11, log.chain()));
+        assert_eq!(verified.fen, START_FEN);
+
+        // A different move with its position hash fixed up still breaks the chain
+        let mut board = Board::new();
+        play(&mut board, &["d4"]);
+        let forged = text.replacen(
+            &log.events()[1].line(),
+            &format!(
+                "2 {:016x} {:016x} move d2d4",
+                position_hash(&board),
+                log.events()[1].chain
+            ),
+            1,
+        );
+        assert_eq!(
+            verify(&forged),
+            Err(VerifyError {
+                line: 3,
+                reason: "chain hash mismatch"
+            })
+        );
+
+        let dropped = text.replacen(&format!("{}\n", log.events()[4].line()), "", 1);
+        assert_eq!(
+            verify(&dropped),
+            Err(VerifyError {
+                line: 6,
+                reason: "event out of sequence"
+            })
+        );
+        let illegal = text.replacen("move e7e5", "move e7e4", 1);
+        assert_eq!(
+            verify(&illegal),
+            Err(VerifyError {
+                line: 4,
+                reason: "illegal move"
+            })
+        );
+        let edited = text.replacen("option MoveOverhead 250", "option MoveOverhead 100", 1);
+        assert_eq!(
+            verify(&edited),
+            Err(VerifyError {
+                line: 10,
+                reason: "chain hash mismatch"
+            })
+        );
+    }
+}
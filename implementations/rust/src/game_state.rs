@@ -0,0 +1,155 @@
+use crate::board::Board;
+use crate::draw_detection;
+use crate::fen::FenParser;
+use crate::move_generator::MoveGenerator;
+use crate::notation::SanParser;
+use crate::types::*;
+
+// Bumped whenever a field changes meaning or goes away; new fields may appear without a bump,
+// so frontends should ignore keys they don't know.
+pub const SCHEMA_VERSION: u32 = 1;
+
+// Everything a GUI needs to draw the position and let the user move, in one JSON object:
+//
+// {"version":1,"fen":"...","turn":"white","pieces":{"a1":"R",...},
+//  "castling":{"white":{"kingside":true,"queenside":true},"black":{...}},
+//  "en_passant":"e3"|null,"halfmove_clock":0,"fullmove_number":1,
+//  "legal_moves":[{"uci":"e2e4","san":"e4"},...],"check":false,
+//  "result":{"status":"ongoing"|"checkmate"|"stalemate"|"draw","winner":"white"|"black"|null,
+//            "reason":"repetition"|"fifty_moves"|null,"pgn":"*"},
+//  "last_move":{"uci":"e7e5","san":"e5"}|null,"hash":"<16 hex digits>"}
+//
+// Pieces are keyed by square, a1 first, in FEN letters (uppercase for White). Moves are in UCI
+// coordinates (lowercase promotion letter) and SAN. The Zobrist hash is a hex string, as JSON
+// numbers lose precision past 2^53.
+pub fn to_json(board: &Board) -> String {
+    let state = board.get_state();
+    let move_generator = MoveGenerator::new();
+    let san_parser = SanParser::new();
+    let mut position = board.clone();
+    let turn = board.get_turn();
+
+    let pieces: Vec<String> = (0..64)
+        .filter_map(|square| {
+            board
+                .get_piece(square)
+                .map(|piece| format!("\"{}\":\"{}\"", square_to_algebraic(square), piece.to_char()))
+        })
+        .collect();
+    let rights = board.get_castling_rights();
+    let castling = format!(
+        "{{\"white\":{{\"kingside\":{},\"queenside\":{}}},\"black\":{{\"kingside\":{},\"queenside\":{}}}}}",
+        rights.white_kingside, rights.white_queenside, rights.black_kingside, rights.black_queenside
+    );
+    let en_passant = board
+        .get_en_passant_target()
+        .map_or("null".to_string(), |square| format!("\"{}\"", square_to_algebraic(square)));
+
+    let legal_moves = move_generator.get_legal_moves(&mut position, turn);
+    let moves: Vec<String> = legal_moves
+        .iter()
+        .map(|chess_move| move_json(&san_parser, &mut position, chess_move))
+        .collect();
+    let check = move_generator.is_in_check(board, turn);
+
+    let (status, winner, reason, pgn) = if legal_moves.is_empty() && check {
+        let winner = turn.opposite();
+        let pgn = if winner == Color::White { "1-0" } else { "0-1" };
+        ("checkmate", Some(winner), None, pgn)
+    } else if legal_moves.is_empty() {
+        ("stalemate", None, None, "1/2-1/2")
+    } else if draw_detection::is_draw_by_repetition(state) {
+        ("draw", None, Some("repetition"), "1/2-1/2")
+    } else if draw_detection::is_draw_by_fifty_moves(state) {
+        ("draw", None, Some("fifty_moves"), "1/2-1/2")
+    } else {
+        ("ongoing", None, None, "*")
+    };
+    let quoted = |value: Option<&str>| value.map_or("null".to_string(), |value| format!("\"{}\"", value));
+    let result = format!(
+        "{{\"status\":\"{}\",\"winner\":{},\"reason\":{},\"pgn\":\"{}\"}}",
+        status,
+        quoted(winner.map(color_name)),
+        quoted(reason),
+        pgn
+    );
+
+    let mut before = board.clone();
+    let last_move = match before.undo_move() {
+        Some(chess_move) => move_json(&san_parser, &mut before, &chess_move),
+        None => "null".to_string(),
+    };
+
+    format!(
+        "{{\"version\":{},\"fen\":\"{}\",\"turn\":\"{}\",\"pieces\":{{{}}},\"castling\":{},\"en_passant\":{},\"halfmove_clock\":{},\"fullmove_number\":{},\"legal_moves\":[{}],\"check\":{},\"result\":{},\"last_move\":{},\"hash\":\"{:016x}\"}}",
+        SCHEMA_VERSION,
+        FenParser::new().export_fen(board),
+        color_name(turn),
+        pieces.join(","),
+        castling,
+        en_passant,
+        state.halfmove_clock,
+        state.fullmove_number,
+        moves.join(","),
+        check,
+        result,
+        last_move,
+        board.get_hash()
+    )
+}
+
+// `{"uci":"e2e4","san":"e4"}`, `board` being the position the move is played from
+pub fn move_json(san_parser: &SanParser, board: &mut Board, chess_move: &Move) -> String {
+    format!(
+        "{{\"uci\":\"{}\",\"san\":\"{}\"}}",
+        chess_move,
+        san_parser.export_san(board, chess_move)
+    )
+}
+
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::White => "white",
+        Color::Black => "black",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(moves: &[&str]) -> Board {
+        let mut board = Board::new();
+        let san_parser = SanParser::new();
+        for san in moves {
+            let chess_move = san_parser.parse_san(&mut board, san).unwrap();
+            board.make_move(&chess_move);
+        }
+        board
+    }
+
+    #[test]
+    fn describes_the_starting_position() {
+        let json = to_json(&Board::new());
+        assert!(json.starts_with("{\"version\":1,\"fen\":\"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\",\"turn\":\"white\",\"pieces\":{\"a1\":\"R\",\"b1\":\"N\","), "{}", json);
+        assert!(json.contains("\"castling\":{\"white\":{\"kingside\":true,\"queenside\":true},\"black\":{\"kingside\":true,\"queenside\":true}},\"en_passant\":null,\"halfmove_clock\":0,\"fullmove_number\":1,"));
+        assert_eq!(json.matches("\"uci\":").count(), 20);
+        assert!(json.contains("{\"uci\":\"g1f3\",\"san\":\"Nf3\"}"));
+        assert!(json.contains("\"check\":false,\"result\":{\"status\":\"ongoing\",\"winner\":null,\"reason\":null,\"pgn\":\"*\"},\"last_move\":null,"));
+        assert!(json.ends_with(&format!("\"hash\":\"{:016x}\"}}", Board::new().get_hash())));
+    }
+
+    #[test]
+    fn reports_the_last_move_en_passant_and_mate() {
+        let json = to_json(&play(&["e4"]));
+        assert!(json.contains("\"turn\":\"black\""));
+        assert!(json.contains("\"en_passant\":\"e3\""));
+        assert!(json.contains("\"last_move\":{\"uci\":\"e2e4\",\"san\":\"e4\"}"));
+
+        let json = to_json(&play(&["f3", "e5", "g4", "Qh4#"]));
+        assert!(json.contains("\"legal_moves\":[],\"check\":true,\"result\":{\"status\":\"checkmate\",\"winner\":\"black\",\"reason\":null,\"pgn\":\"0-1\"},\"last_move\":{\"uci\":\"d8h4\",\"san\":\"Qh4#\"}"), "{}", json);
+
+        let json = to_json(&play(&["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1", "Ng8"]));
+        assert!(json.contains("\"result\":{\"status\":\"draw\",\"winner\":null,\"reason\":\"repetition\",\"pgn\":\"1/2-1/2\"}"), "{}", json);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::board::Board;
+use crate::move_generator::MoveGenerator;
+use crate::types::*;
+
+const SVG_SQUARE: usize = 48;
+
+// How many of each side's pieces attack every square, counted from the same per-piece attack
+// sets as `MoveGenerator::attack_map`. A piece standing on a square its side attacks is
+// defended that many times.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heatmap {
+    pub white: [u8; 64],
+    pub black: [u8; 64],
+}
+
+impl Heatmap {
+    pub fn new(board: &Board) -> Self {
+        let generator = MoveGenerator::new();
+        let mut heatmap = Self {
+            white: [0; 64],
+            black: [0; 64],
+        };
+        for color in [Color::White, Color::Black] {
+            for from in board.occupied_by(color).squares() {
+                for square in generator.attacks(board, from).squares() {
+                    heatmap.counts_mut(color)[square] += 1;
+                }
+            }
+        }
+        heatmap
+    }
+
+    pub fn counts(&self, color: Color) -> &[u8; 64] {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+
+    fn counts_mut(&mut self, color: Color) -> &mut [u8; 64] {
+        match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        }
+    }
+
+    // White's count minus Black's; positive squares are White's.
+    pub fn net(&self, square: Square) -> i32 {
+        i32::from(self.white[square]) - i32::from(self.black[square])
+    }
+
+    // One side's counts, or the net control when `side` is None, as a board diagram from
+    // White's side.
+    pub fn render(&self, side: Option<Color>) -> String {
+        let mut text = String::from("  a  b  c  d  e  f  g  h\n");
+        for rank in (0..8).rev() {
+            text.push_str(&format!("{}", rank + 1));
+            for file in 0..8 {
+                let square = rank * 8 + file;
+                let cell = match side {
+                    Some(color) => format!("{:>3}", self.counts(color)[square]),
+                    None => match self.net(square) {
+                        0 => "  .".to_string(),
+                        net => format!("{:>+3}", net),
+                    },
+                };
+                text.push_str(&cell);
+            }
+            text.push_str(&format!(" {}\n", rank + 1));
+        }
+        text.push_str("  a  b  c  d  e  f  g  h\n");
+        text
+    }
+
+    // Both sides' counts in square order, a1 to h8.
+    pub fn to_json(&self) -> String {
+        let list = |counts: &[u8; 64]| {
+            counts
+                .iter()
+                .map(|count| count.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        format!(
+            "{{\"order\":\"a1..h8\",\"white\":[{}],\"black\":[{}]}}",
+            list(&self.white),
+            list(&self.black)
+        )
+    }
+
+    // A board-sized picture: squares shaded blue for White and red for Black, darker the more
+    // control, each labelled with its count (or the net count when `side` is None).
+    pub fn to_svg(&self, side: Option<Color>) -> String {
+        let size = SVG_SQUARE * 8;
+        let most = (0..64)
+            .map(|square| match side {
+                Some(color) => i32::from(self.counts(color)[square]),
+                None => self.net(square).abs(),
+            })
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">\n",
+            size
+        );
+        for rank in 0..8 {
+            for file in 0..8 {
+                let square = rank * 8 + file;
+                let (x, y) = (file * SVG_SQUARE, (7 - rank) * SVG_SQUARE);
+                let base = if (rank + file) % 2 == 0 {
+                    "#b58863"
+                } else {
+                    "#f0d9b5"
+                };
+                svg.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" fill=\"{3}\"/>\n",
+                    x, y, SVG_SQUARE, base
+                ));
+                let value = match side {
+                    Some(color) => i32::from(self.counts(color)[square]),
+                    None => self.net(square),
+                };
+                if value == 0 {
+                    continue;
+                }
+                let white_side = match side {
+                    Some(color) => color == Color::White,
+                    None => value > 0,
+                };
+                let tint = if white_side { "#1f5fbf" } else { "#bf1f1f" };
+                let opacity = 0.15 + 0.6 * value.abs() as f64 / most as f64;
+                svg.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" fill=\"{3}\" fill-opacity=\"{4:.2}\"/>\n",
+                    x, y, SVG_SQUARE, tint, opacity
+                ));
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"16\" text-anchor=\"middle\">{}</text>\n",
+                    x + SVG_SQUARE / 2,
+                    y + SVG_SQUARE / 2 + 6,
+                    value
+                ));
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_attackers_and_defenders_of_each_square() {
+        let heatmap = Heatmap::new(&Board::new());
+        let square = |name| algebraic_to_square(name).unwrap();
+        // Pawns on e2 and g2 plus the g1 knight
+        assert_eq!(heatmap.white[square("f3")], 3);
+        // The queen defends the e2 pawn alongside the king, bishop and knight
+        assert_eq!(heatmap.white[square("e2")], 4);
+        assert_eq!(heatmap.white[square("a1")], 0);
+        assert_eq!(heatmap.black[square("f6")], 3);
+        assert_eq!(heatmap.net(square("e4")), 0);
+        assert!(heatmap.to_json().contains("\"white\":[0,1,1,1,1,1,1,0,"));
+    }
+}
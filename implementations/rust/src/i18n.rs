@@ -0,0 +1,755 @@
+use std::sync::OnceLock;
+
+// Only human-readable text is translated. Protocol keywords (`OK:`, `ERROR:`, `AI:`, `FEN:`,
+// `uciok`, ...) and command names stay in English so harnesses and GUIs parse every language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    French,
+}
+
+impl Language {
+    pub fn from_code(code: &str) -> Option<Language> {
+        let code = code.to_lowercase();
+        match code.split(['_', '-', '.']).next().unwrap_or("") {
+            "en" => Some(Language::English),
+            "fr" => Some(Language::French),
+            _ => None,
+        }
+    }
+}
+
+// Catalogs are keyed by the English text, so untranslated strings fall back to English.
+const FRENCH: &[(&str, &str)] = &[
+    ("Available commands:", "Commandes disponibles :"),
+    // Move and position errors
+    ("Invalid move format", "Format de coup invalide"),
+    (
+        "No piece at source square",
+        "Aucune pièce sur la case de départ",
+    ),
+    ("Wrong color piece", "Pièce de la mauvaise couleur"),
+    ("King would be in check", "Le roi serait en échec"),
+    ("Illegal move", "Coup illégal"),
+    ("Ambiguous move", "Coup ambigu"),
+    ("No moves to undo", "Aucun coup à annuler"),
+    ("No legal moves available", "Aucun coup légal disponible"),
+    (
+        "No search has been run yet",
+        "Aucune recherche n'a encore été lancée",
+    ),
+    ("Invalid FEN string", "Chaîne FEN invalide"),
+    ("Invalid command", "Commande invalide"),
+    ("Command too long", "Commande trop longue"),
+    (
+        "Invalid characters in command",
+        "Caractères invalides dans la commande",
+    ),
+    ("Invalid perft depth", "Profondeur perft invalide"),
+    ("perft hash must be 0-4096 MB", "La table de perft doit faire de 0 à 4096 Mo"),
+    ("perft threads must be 1-256", "perft exige de 1 à 256 threads"),
+    ("Cannot read perft suite", "Impossible de lire la suite perft"),
+    (
+        "perft suite takes depth <n> and file <epd>",
+        "perft suite accepte depth <n> et file <epd>",
+    ),
+    ("Invalid lookup count", "Nombre de recherches invalide"),
+    ("--fen requires a position", "--fen attend une position"),
+    ("Invalid expected node count", "Nombre de nœuds attendu invalide"),
+    (
+        "Unsupported option for this subcommand",
+        "Option non prise en charge pour cette sous-commande",
+    ),
+    (
+        "Unsupported profile command",
+        "Commande profile non prise en charge",
+    ),
+    (
+        "Cannot write profile file",
+        "Impossible d'écrire le fichier de profil",
+    ),
+    (
+        "analyze all needs depth 1-64",
+        "analyze all exige une profondeur entre 1 et 64",
+    ),
+    (
+        "solve requires mate, helpmate or selfmate and a number of moves",
+        "solve exige mate, helpmate ou selfmate et un nombre de coups",
+    ),
+    (
+        "solve moves must be 1-5",
+        "le nombre de coups de solve doit être entre 1 et 5",
+    ),
+    ("FEN loaded", "FEN chargée"),
+    ("New game started", "Nouvelle partie commencée"),
+    // Search limits
+    (
+        "AI depth must be 1-64",
+        "La profondeur de l'IA doit être entre 1 et 64",
+    ),
+    (
+        "movetime requires a positive integer",
+        "movetime exige un entier positif",
+    ),
+    (
+        "nodes requires a positive integer",
+        "nodes exige un entier positif",
+    ),
+    (
+        "deterministic must be on, off or status",
+        "deterministic doit être on, off ou status",
+    ),
+    (
+        "No clock time given for the side to move",
+        "Aucun temps de pendule pour le camp au trait",
+    ),
+    (
+        "window requires alpha and beta in centipawns, alpha below beta",
+        "window exige alpha et beta en centipions, alpha inférieur à beta",
+    ),
+    ("min-rootdepth must be 1-64", "min-rootdepth doit être entre 1 et 64"),
+    (
+        "min-rootdepth cannot exceed the depth limit",
+        "min-rootdepth ne peut pas dépasser la limite de profondeur",
+    ),
+    (
+        "option takes a name and a value",
+        "option attend un nom et une valeur",
+    ),
+    (
+        "debug ordering depth must be 1-64",
+        "la profondeur de debug ordering doit être entre 1 et 64",
+    ),
+    (
+        "Unsupported eval command",
+        "Commande eval non prise en charge",
+    ),
+    (
+        "eval material must be on, off or status",
+        "eval material doit être on, off ou status",
+    ),
+    (
+        "Unsupported debug command",
+        "Commande debug non prise en charge",
+    ),
+    (
+        "debug tree needs depth 1-6, a file and optionally a minimum visit count",
+        "debug tree attend une profondeur de 1 à 6, un fichier et éventuellement un nombre minimal de visites",
+    ),
+    (
+        "Cannot write tree file",
+        "Impossible d'écrire le fichier d'arbre",
+    ),
+    (
+        "Unsupported threads command",
+        "Commande threads non prise en charge",
+    ),
+    // Oracle
+    (
+        "Unsupported oracle command",
+        "Commande oracle non prise en charge",
+    ),
+    (
+        "oracle threshold must be a non-negative centipawn value",
+        "le seuil de l'oracle doit être un nombre de centipions positif ou nul",
+    ),
+    (
+        "oracle depth must be 1-64",
+        "la profondeur de l'oracle doit être entre 1 et 64",
+    ),
+    ("No oracle running", "Aucun oracle en cours d'exécution"),
+    ("Cannot start the oracle", "Impossible de lancer l'oracle"),
+    (
+        "Cannot read oracle suite",
+        "Impossible de lire la suite de l'oracle",
+    ),
+    ("The oracle timed out", "L'oracle ne répond plus à temps"),
+    ("The oracle exited", "L'oracle s'est arrêté"),
+    // Self-play
+    (
+        "selfplay needs 1-1000 games and search limits",
+        "selfplay exige 1 à 1000 parties et des limites de recherche",
+    ),
+    (
+        "odds needs time shares such as 2:1, each 1-100",
+        "odds exige des parts de temps comme 2:1, chacune de 1 à 100",
+    ),
+    (
+        "Time odds need a movetime or clock on both sides",
+        "Les handicaps de temps exigent un movetime ou une pendule des deux côtés",
+    ),
+    (
+        "adjudicate needs moves <n> and win <cp> or draw <cp>",
+        "adjudicate exige moves <n> et win <cp> ou draw <cp>",
+    ),
+    (
+        "ablate needs 1-1000 games, then nodes <n>, term <name> or openings <file>",
+        "ablate exige 1 à 1000 parties, puis nodes <n>, term <nom> ou openings <fichier>",
+    ),
+    ("Unknown eval term", "Terme d'évaluation inconnu"),
+    (
+        "sprt needs 1-100000 games and search limits",
+        "sprt exige 1 à 100000 parties et des limites de recherche",
+    ),
+    (
+        "sprt needs elo0 below elo1 and alpha and beta between 0 and 0.5",
+        "sprt exige elo0 inférieur à elo1, et alpha et beta entre 0 et 0,5",
+    ),
+    ("Unsupported display command", "Commande display non prise en charge"),
+    ("Unknown display option", "Option d'affichage inconnue"),
+    (
+        "Display options are on or off, and perspective white or black",
+        "Les options d'affichage valent on ou off, et perspective white ou black",
+    ),
+    (
+        "Cannot write results file",
+        "Impossible d'écrire le fichier de résultats",
+    ),
+    (
+        "openings needs a PGN file, a count of 1-1000 and an output file",
+        "openings exige un fichier PGN, un nombre entre 1 et 1000 et un fichier de sortie",
+    ),
+    (
+        "openings options are plies 1-40 and window <cp>",
+        "les options de openings sont plies 1-40 et window <cp>",
+    ),
+    ("Cannot read PGN database", "Impossible de lire la base PGN"),
+    (
+        "Cannot write opening suite",
+        "Impossible d'écrire la suite d'ouvertures",
+    ),
+    (
+        "Cannot read opening suite",
+        "Impossible de lire la suite d'ouvertures",
+    ),
+    ("Invalid opening suite", "Suite d'ouvertures invalide"),
+    (
+        "The oracle stopped responding",
+        "L'oracle a cessé de répondre",
+    ),
+    // Analysis and training
+    (
+        "motifs side must be white or black",
+        "le camp de motifs doit être white ou black",
+    ),
+    ("explain requires a move", "explain exige un coup"),
+    (
+        "explain depth must be 1-64",
+        "la profondeur de explain doit être entre 1 et 64",
+    ),
+    ("why requires a move", "why exige un coup"),
+    ("playout requires moves", "playout exige des coups"),
+    (
+        "Cannot play out a line during a drill",
+        "Impossible de jouer une ligne pendant un exercice",
+    ),
+    (
+        "Cannot pass while in check",
+        "Impossible de passer en étant en échec",
+    ),
+    (
+        "Cannot pass during a drill",
+        "Impossible de passer pendant un exercice",
+    ),
+    (
+        "replies depth must be 1-5",
+        "la profondeur de replies doit être entre 1 et 5",
+    ),
+    (
+        "threat depth must be 1-5",
+        "la profondeur de threat doit être entre 1 et 5",
+    ),
+    ("Unknown test position", "Position de test inconnue"),
+    (
+        "notation must be coordinate, san or status",
+        "notation doit être coordinate, san ou status",
+    ),
+    (
+        "Unsupported material command",
+        "Commande material non prise en charge",
+    ),
+    (
+        "Unsupported heatmap command",
+        "Commande heatmap non prise en charge",
+    ),
+    (
+        "Cannot write heatmap file",
+        "Impossible d'écrire le fichier heatmap",
+    ),
+    (
+        "drill line has an illegal move",
+        "la ligne de drill contient un coup illégal",
+    ),
+    (
+        "drill line needs a move for your side",
+        "la ligne de drill doit contenir un coup de votre camp",
+    ),
+    (
+        "blindfold must be on, off or status",
+        "blindfold doit être on, off ou status",
+    ),
+    (
+        "quiz must be color or knight",
+        "quiz doit être color ou knight",
+    ),
+    ("answer requires a value", "answer exige une valeur"),
+    ("no quiz in progress", "aucun quiz en cours"),
+    (
+        "answer must be light or dark",
+        "la réponse doit être light ou dark",
+    ),
+    (
+        "answer must be a number of moves",
+        "la réponse doit être un nombre de coups",
+    ),
+    // Fixture command surfaces
+    ("pgn requires subcommand", "pgn exige une sous-commande"),
+    (
+        "pgn load requires a file path",
+        "pgn load exige un chemin de fichier",
+    ),
+    (
+        "Cannot write PGN file",
+        "Impossible d'écrire le fichier PGN",
+    ),
+    (
+        "Unsupported pgn command",
+        "Commande pgn non prise en charge",
+    ),
+    ("Unsupported state command", "Commande state non prise en charge"),
+    // Event log
+    ("Unsupported log command", "Commande log non prise en charge"),
+    ("Cannot write log file", "Impossible d'écrire le fichier journal"),
+    ("Cannot read log file", "Impossible de lire le fichier journal"),
+    ("verify needs log <file>", "verify exige log <fichier>"),
+    // Clipboard
+    ("copy needs fen or pgn", "copy exige fen ou pgn"),
+    ("paste needs fen", "paste exige fen"),
+    (
+        "copy and paste need a build with the clipboard feature",
+        "copy et paste exigent une compilation avec la fonctionnalité clipboard",
+    ),
+    ("No clipboard on this system", "Pas de presse-papiers sur ce système"),
+    ("No clipboard tool found", "Aucun outil de presse-papiers trouvé"),
+    (
+        "Cannot write to the clipboard",
+        "Impossible d'écrire dans le presse-papiers",
+    ),
+    ("Cannot read the clipboard", "Impossible de lire le presse-papiers"),
+    // Game import
+    (
+        "import needs lichess <game> or url <address>",
+        "import exige lichess <partie> ou url <adresse>",
+    ),
+    ("Unsupported import command", "Commande import non prise en charge"),
+    ("Invalid lichess game id", "Identifiant de partie lichess invalide"),
+    (
+        "import url needs an http or https address",
+        "import url exige une adresse http ou https",
+    ),
+    (
+        "import needs a build with the http feature",
+        "import exige une compilation avec la fonctionnalité http",
+    ),
+    ("import needs curl on the PATH", "import exige curl dans le PATH"),
+    ("Download failed", "Échec du téléchargement"),
+    ("No game in download", "Aucune partie dans le téléchargement"),
+    (
+        "Invalid FEN in imported game",
+        "FEN invalide dans la partie importée",
+    ),
+    (
+        "Illegal move in imported game",
+        "Coup illégal dans la partie importée",
+    ),
+    ("annotate depth must be 1-64", "la profondeur d'annotate doit être entre 1 et 64"),
+    ("book requires subcommand", "book exige une sous-commande"),
+    (
+        "book load requires a file path",
+        "book load exige un chemin de fichier",
+    ),
+    (
+        "Unsupported book command",
+        "Commande book non prise en charge",
+    ),
+    ("trace requires subcommand", "trace exige une sous-commande"),
+    (
+        "hash save/load requires a file path",
+        "hash save/load exige un chemin de fichier",
+    ),
+    (
+        "Unsupported hash command",
+        "Commande hash non prise en charge",
+    ),
+    (
+        "Cannot write hash file",
+        "Impossible d'écrire le fichier de table",
+    ),
+    (
+        "Cannot read hash file",
+        "Impossible de lire le fichier de table",
+    ),
+    ("Not a hash file", "Ce n'est pas un fichier de table"),
+    (
+        "Unsupported hash file version",
+        "Version de fichier de table non prise en charge",
+    ),
+    (
+        "Hash file uses different Zobrist keys",
+        "Le fichier de table utilise d'autres clés Zobrist",
+    ),
+    (
+        "Hash file size does not match the table",
+        "La taille du fichier ne correspond pas à la table",
+    ),
+    ("Corrupt hash file", "Fichier de table corrompu"),
+    (
+        "trace level requires a value",
+        "trace level exige une valeur",
+    ),
+    ("trace export failed", "échec de trace export"),
+    ("trace chrome failed", "échec de trace chrome"),
+    (
+        "Unsupported trace command",
+        "Commande trace non prise en charge",
+    ),
+    (
+        "Unsupported concurrency profile",
+        "Profil de concurrence non pris en charge",
+    ),
+    // Help descriptions
+    (
+        "Make a move (e.g., e2e4, e7e8Q, Nf3, O-O, exd5)",
+        "Jouer un coup (ex. e2e4, e7e8Q, Nf3, O-O, exd5)",
+    ),
+    ("Undo the last move", "Annuler le dernier coup"),
+    ("Start a new game", "Commencer une nouvelle partie"),
+    (
+        "Fixed-depth search (depth 1-64, no time limit)",
+        "Recherche à profondeur fixe (1-64, sans limite de temps)",
+    ),
+    (
+        "Iterative deepening until the time budget runs out",
+        "Approfondissement itératif jusqu'à épuisement du temps",
+    ),
+    (
+        "Whichever limit is reached first",
+        "La première limite atteinte l'emporte",
+    ),
+    (
+        "Budget from the side to move's clock",
+        "Budget tiré de la pendule du camp au trait",
+    ),
+    (
+        "Iterative deepening until about n nodes are searched",
+        "Approfondissement itératif jusqu'à environ n nœuds explorés",
+    ),
+    (
+        "Load position from FEN",
+        "Charger une position depuis une FEN",
+    ),
+    (
+        "Export current position as FEN",
+        "Exporter la position courante en FEN",
+    ),
+    (
+        "Static evaluation plus a depth-1 search score",
+        "Évaluation statique et score de recherche à profondeur 1",
+    ),
+    (
+        "Evaluate with piece values only (see the *Value options)",
+        "Évaluer avec les seules valeurs des pièces (voir les options *Value)",
+    ),
+    (
+        "Show the last search's principal variation",
+        "Afficher la variante principale de la dernière recherche",
+    ),
+    (
+        "Write moves in output as coordinates (e7e8q, as UCI does) or SAN",
+        "Écrire les coups en coordonnées (e7e8q, comme UCI) ou en SAN",
+    ),
+    (
+        "Show Zobrist hash of current position",
+        "Afficher le hachage Zobrist de la position",
+    ),
+    (
+        "Save the transposition table or restore a saved one",
+        "Sauvegarder la table de transposition ou en restaurer une",
+    ),
+    (
+        "Transposition table size and whether it got large pages",
+        "Taille de la table de transposition et usage des grandes pages",
+    ),
+    (
+        "Show draw detection status",
+        "Afficher l'état de la détection de nulle",
+    ),
+    (
+        "Show position hash history",
+        "Afficher l'historique des hachages",
+    ),
+    (
+        "Detect pins, forks, skewers and other tactics",
+        "Détecter clouages, fourchettes, enfilades et autres tactiques",
+    ),
+    (
+        "Name the pawn structure and its typical plans",
+        "Nommer la structure de pions et ses plans typiques",
+    ),
+    (
+        "Explain what a move does and how it scores",
+        "Expliquer ce que fait un coup et son évaluation",
+    ),
+    (
+        "Say why a move is illegal: the pin, check or blocker behind it",
+        "Dire pourquoi un coup est illégal : le clouage, l'échec ou la pièce qui bloque",
+    ),
+    (
+        "Play a line of moves; if any is illegal, none of them are played",
+        "Jouer une suite de coups ; si l'un est illégal, aucun n'est joué",
+    ),
+    (
+        "Shallow score of every legal move, and how much each loses against the best",
+        "Score rapide de chaque coup légal, et ce que chacun perd face au meilleur",
+    ),
+    (
+        "CPU and NUMA topology; pin analyze all workers to CPUs across nodes",
+        "Topologie CPU et NUMA ; épingler les threads de analyze all sur des CPU de chaque nœud",
+    ),
+    (
+        "Time sliding attack lookups: ray walks, magics, PEXT and the engine's choice",
+        "Chronométrer les attaques des pièces glissantes : rayons, magics, PEXT et le choix du moteur",
+    ),
+    (
+        "CPU features detected at startup and the kernels picked for them",
+        "Extensions CPU détectées au démarrage et noyaux de calcul choisis en conséquence",
+    ),
+    (
+        "How hard the position is to play (0-100), and what makes it so",
+        "Difficulté de la position (0-100), et ce qui la rend difficile",
+    ),
+    (
+        "Run an external UCI engine to check this engine against",
+        "Lancer un moteur UCI externe pour contrôler ce moteur",
+    ),
+    (
+        "Compare best moves and scores with the oracle; suite lists disagreements",
+        "Comparer meilleurs coups et scores avec l'oracle ; suite liste les désaccords",
+    ),
+    (
+        "Engine-vs-engine games from the current position or a suite, saved to the results database",
+        "Parties moteur contre moteur depuis la position actuelle ou une suite, enregistrées dans la base de résultats",
+    ),
+    (
+        "Elo of each evaluation term, from fixed-node games against the engine without it",
+        "Elo de chaque terme d'évaluation, par des parties à nœuds fixes contre le moteur qui ne l'a pas",
+    ),
+    (
+        "Accept or reject a patch by sequential probability ratio test against the base engine",
+        "Accepter ou rejeter un correctif par test séquentiel du rapport de vraisemblance contre le moteur de base",
+    ),
+    (
+        "Pick balanced openings across ECO codes from a PGN database as a test suite",
+        "Choisir des ouvertures équilibrées sur plusieurs codes ECO d'une base PGN comme suite de test",
+    ),
+    (
+        "Elo ratings of every player in the results database",
+        "Classement Elo de chaque joueur de la base de résultats",
+    ),
+    (
+        "Give the turn to the opponent without moving; analysis only, not in check",
+        "Donner le trait à l'adversaire sans jouer ; pour l'analyse, pas en échec",
+    ),
+    (
+        "Show what the opponent would play if the side to move passed",
+        "Montrer ce que jouerait l'adversaire si le camp au trait passait",
+    ),
+    (
+        "Replay an opening line against the engine; missed lines come back sooner",
+        "Rejouer une ligne d'ouverture contre le moteur ; les lignes ratées reviennent plus tôt",
+    ),
+    (
+        "Load a named debugging position (kiwipete, position3-6, lasker-trap, ...)",
+        "Charger une position de débogage par son nom (kiwipete, position3-6, lasker-trap, ...)",
+    ),
+    (
+        "Material points, difference and the pieces each side has captured",
+        "Points de matériel, différence et pièces prises par chaque camp",
+    ),
+    (
+        "How many times each side attacks or defends every square",
+        "Nombre de fois que chaque camp attaque ou défend chaque case",
+    ),
+    (
+        "Hide the board and accept SAN moves",
+        "Masquer l'échiquier et accepter les coups en SAN",
+    ),
+    (
+        "Board labels, perspective, side to move, FEN and move number under the board; kept in the profile",
+        "Coordonnées, perspective, camp au trait, FEN et numéro de coup sous l'échiquier ; conservés dans le profil",
+    ),
+    (
+        "Show the board during blindfold play (counts as a penalty)",
+        "Afficher l'échiquier en partie à l'aveugle (compte comme pénalité)",
+    ),
+    (
+        "Square color or knight path quiz; reply with answer <value>",
+        "Quiz couleur de case ou trajet du cavalier ; répondre avec answer <valeur>",
+    ),
+    (
+        "go needs search limits or a running clock",
+        "go attend des limites de recherche ou une pendule en marche",
+    ),
+    ("Same limits as ai; with the clock running, a bare go plays on it", "Mêmes limites que ai ; avec la pendule en marche, un go seul joue sur elle"),
+    (
+        "Reproducible searches: time limits counted in nodes, fresh hash table",
+        "Recherches reproductibles : limites de temps comptées en nœuds, table de hachage vide",
+    ),
+    (
+        "Show or change the saved profile: preferences and results against the engine",
+        "Afficher ou modifier le profil enregistré : préférences et résultats contre le moteur",
+    ),
+    (
+        "List engine options or set one (time allocation policy)",
+        "Lister les options du moteur ou en régler une (allocation du temps)",
+    ),
+    (
+        "Root move order and heuristic scores after a search",
+        "Ordre des coups à la racine et scores heuristiques après une recherche",
+    ),
+    (
+        "Write the search tree, cut to subtrees of at least min_visits nodes",
+        "Écrire l'arbre de recherche, réduit aux sous-arbres d'au moins min_visits nœuds",
+    ),
+    (
+        "Load a PGN, or show and export the current game as PGN",
+        "Charger un PGN, ou afficher et exporter la partie en cours en PGN",
+    ),
+    (
+        "The whole game state (pieces, rights, clocks, legal moves, result) as versioned JSON",
+        "L'état complet de la partie (pièces, droits, compteurs, coups légaux, résultat) en JSON versionné",
+    ),
+    (
+        "Event log of every change to the game and options, hash-chained; save keeps the file growing",
+        "Journal chaîné par hachage de chaque changement de la partie et des options ; save fait grandir le fichier",
+    ),
+    (
+        "Replay a saved event log and check every position and chain hash",
+        "Rejouer un journal enregistré et vérifier chaque hachage de position et de chaîne",
+    ),
+    (
+        "Copy the position or game to the system clipboard, or load a FEN from it",
+        "Copier la position ou la partie dans le presse-papiers, ou y charger un FEN",
+    ),
+    (
+        "Download a game and load it; annotate marks its inaccuracies, mistakes and blunders",
+        "Télécharger une partie et la charger ; annotate signale ses imprécisions, erreurs et gaffes",
+    ),
+    (
+        "Game clock for both sides; ai plays on its time left and a flag loses the game",
+        "Pendule pour les deux camps ; ai joue sur le temps qui lui reste et un drapeau perd la partie",
+    ),
+    (
+        "clock needs <minutes>+<seconds> or off",
+        "clock attend <minutes>+<secondes> ou off",
+    ),
+    ("Time has run out", "Le temps est écoulé"),
+    (
+        "Opening book command surface",
+        "Commandes du livre d'ouvertures",
+    ),
+    (
+        "Which optional data files (book, tablebases, network) were found; reload looks again",
+        "Quels fichiers de données facultatifs (livre, tables de finales, réseau) ont été trouvés ; reload les recherche à nouveau",
+    ),
+    (
+        "Switch to UCI protocol mode for GUIs and bots",
+        "Passer en mode protocole UCI pour les interfaces et les bots",
+    ),
+    ("Chess960 metadata", "Métadonnées Chess960"),
+    ("Trace diagnostics", "Diagnostics de trace"),
+    (
+        "Deterministic concurrency fixture",
+        "Fixture de concurrence déterministe",
+    ),
+    (
+        "Search every legal move to depth d in parallel, best first",
+        "Explorer chaque coup légal à la profondeur d en parallèle, le meilleur d'abord",
+    ),
+    (
+        "Search without playing, with a root window, a minimum depth or features off",
+        "Chercher sans jouer, avec une fenêtre à la racine, une profondeur minimale ou des techniques désactivées",
+    ),
+    (
+        "Solve a problem in n moves of the side to move; lists every solution",
+        "Résoudre un problème en n coups du camp au trait ; liste chaque solution",
+    ),
+    (
+        "Run performance test (only capture or checking leaves; suite checks known counts; divide lists per-move counts, after the moves given)",
+        "Lancer le test de performance (seulement les feuilles de prise ou d'échec ; suite vérifie les totaux connus ; divide détaille chaque coup, après les coups donnés)",
+    ),
+    (
+        "List completions for a partial command line",
+        "Lister les complétions d'une ligne de commande partielle",
+    ),
+    ("Show this help message", "Afficher ce message d'aide"),
+    ("Exit the program", "Quitter le programme"),
+];
+
+static LANGUAGE: OnceLock<Language> = OnceLock::new();
+
+// Chosen once at startup; later calls are ignored.
+pub fn set_language(language: Language) {
+    let _ = LANGUAGE.set(language);
+}
+
+pub fn language() -> Language {
+    LANGUAGE.get().copied().unwrap_or(Language::English)
+}
+
+pub fn tr(message: &str) -> &str {
+    translate(language(), message)
+}
+
+pub fn translate(language: Language, message: &str) -> &str {
+    let catalog = match language {
+        Language::English => return message,
+        Language::French => FRENCH,
+    };
+    catalog
+        .iter()
+        .find(|(english, _)| *english == message)
+        .map_or(message, |(_, translated)| translated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands;
+
+    #[test]
+    fn parses_language_codes_from_flags_and_locales() {
+        assert_eq!(Language::from_code("fr"), Some(Language::French));
+        assert_eq!(Language::from_code("fr_FR.UTF-8"), Some(Language::French));
+        assert_eq!(Language::from_code("EN-us"), Some(Language::English));
+        assert_eq!(Language::from_code("de"), None);
+    }
+
+    #[test]
+    fn french_catalog_covers_help_and_falls_back_to_english() {
+        for (usage, description) in commands::help_entries() {
+            assert_ne!(
+                translate(Language::French, description),
+                description,
+                "missing French help for `{}`",
+                usage
+            );
+        }
+        assert_eq!(translate(Language::French, "Illegal move"), "Coup illégal");
+        assert_eq!(translate(Language::English, "Illegal move"), "Illegal move");
+        assert_eq!(
+            translate(Language::French, "not in catalog"),
+            "not in catalog"
+        );
+    }
+}
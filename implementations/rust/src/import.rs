@@ -0,0 +1,290 @@
+use crate::ai::AI;
+use crate::board::Board;
+use crate::fen::FenParser;
+use crate::notation::{MoveNotation, SanParser};
+use crate::pgn::{PgnGame, PgnHeaders};
+use crate::search_limits::SearchLimits;
+use crate::types::Color;
+
+pub const DEFAULT_ANNOTATE_DEPTH: u8 = 8;
+// Centipawns a move gives away, by the side that played it, before it earns ?!, ? or ??
+const INACCURACY_CP: i32 = 50;
+const MISTAKE_CP: i32 = 100;
+const BLUNDER_CP: i32 = 300;
+// Mate scores are capped to this before comparing, so a missed mate counts as one blunder
+// however many moves away it was.
+const SCORE_CAP: i32 = 2000;
+
+// `import lichess <id|url>` downloads from Lichess's game export, `import url <address>` any
+// PGN over HTTP(S), e.g. a Chess.com monthly archive, of which the first game is loaded.
+pub fn source_url(kind: &str, target: &str) -> Result<String, &'static str> {
+    match kind {
+        "lichess" => lichess_game_id(target)
+            .map(|id| format!("https://lichess.org/game/export/{}", id))
+            .ok_or("Invalid lichess game id"),
+        "url" if target.starts_with("https://") || target.starts_with("http://") => {
+            Ok(target.to_string())
+        }
+        "url" => Err("import url needs an http or https address"),
+        _ => Err("Unsupported import command"),
+    }
+}
+
+// A game id, or a game URL as the browser shows it: `lichess.org/AbCdEfGh`, with `/black`,
+// `#32` or a 12-character player link (the id plus a player key) all accepted.
+pub fn lichess_game_id(target: &str) -> Option<String> {
+    let path = target
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_start_matches("www.")
+        .trim_start_matches("lichess.org/");
+    let id = path.split(['/', '#', '?']).next()?;
+    // Checked first, so the slicing below is on character boundaries
+    if !id.chars().all(|ch| ch.is_ascii_alphanumeric()) {
+        return None;
+    }
+    match id.len() {
+        8 => Some(id.to_string()),
+        12 => Some(id[..8].to_string()),
+        _ => None,
+    }
+}
+
+// Downloads with `curl`, which brings the HTTPS support this crate has no dependency for.
+#[cfg(feature = "http")]
+pub fn fetch(url: &str) -> Result<String, &'static str> {
+    use std::process::Command;
+
+    let output = Command::new("curl")
+        .args(["--silent", "--fail", "--location", "--max-time", "30"])
+        .args(["--header", "Accept: application/x-chess-pgn", url])
+        .output()
+        .map_err(|_| "import needs curl on the PATH")?;
+    if !output.status.success() {
+        return Err("Download failed");
+    }
+    String::from_utf8(output.stdout).map_err(|_| "Download failed")
+}
+
+#[cfg(not(feature = "http"))]
+pub fn fetch(_url: &str) -> Result<String, &'static str> {
+    Err("import needs a build with the http feature")
+}
+
+// The game's moves played out from its `FEN` tag, or the starting position, so undo steps back
+// through them.
+pub fn replay(game: &PgnGame, san_parser: &SanParser) -> Result<Board, &'static str> {
+    let mut board = Board::new();
+    if let Some(fen) = game.tag("FEN") {
+        FenParser::new()
+            .parse_fen(&mut board, fen)
+            .map_err(|_| "Invalid FEN in imported game")?;
+    }
+    for san in &game.moves {
+        let chess_move = san_parser
+            .parse_san(&mut board, san)
+            .map_err(|_| "Illegal move in imported game")?;
+        board.make_move(&chess_move);
+    }
+    Ok(board)
+}
+
+pub fn headers(game: &PgnGame) -> PgnHeaders {
+    let mut headers = PgnHeaders::new();
+    for (field, tag) in [
+        (&mut headers.event, "Event"),
+        (&mut headers.site, "Site"),
+        (&mut headers.date, "Date"),
+        (&mut headers.round, "Round"),
+        (&mut headers.white, "White"),
+        (&mut headers.black, "Black"),
+    ] {
+        if let Some(value) = game.tag(tag) {
+            *field = value.to_string();
+        }
+    }
+    headers
+}
+
+// A move that gave away at least an inaccuracy's worth, with what the engine preferred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    // `12.` or `12...` and the move in the chosen notation, with its glyph
+    pub label: String,
+    pub loss: i32,
+    pub best: String,
+    // White-relative, after the move
+    pub score: i32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnnotationSummary {
+    pub annotations: Vec<Annotation>,
+    pub moves: usize,
+    pub inaccuracies: usize,
+    pub mistakes: usize,
+    pub blunders: usize,
+}
+
+// Searches every position of the game once, to `depth`: a move's loss is the score before it
+// less the score after it, from the mover's side, since the search after a move is the next
+// position's search anyway.
+pub fn annotate(
+    ai: &mut AI,
+    game: &Board,
+    depth: u8,
+    san_parser: &SanParser,
+    notation: MoveNotation,
+) -> AnnotationSummary {
+    let mut board = game.clone();
+    while board.undo_move().is_some() {}
+    let moves = game.get_state().move_history.clone();
+    let limits = SearchLimits::depth(depth);
+
+    let mut search = |board: &mut Board| {
+        let result = ai.search(board, &limits);
+        (
+            result.evaluation.clamp(-SCORE_CAP, SCORE_CAP),
+            result.best_move,
+        )
+    };
+    let mut summary = AnnotationSummary {
+        moves: moves.len(),
+        ..AnnotationSummary::default()
+    };
+    let (mut before, mut best) = search(&mut board);
+    for chess_move in &moves {
+        let mover = board.get_turn();
+        let number = board.get_state().fullmove_number;
+        let played = san_parser.format_line(&board, std::slice::from_ref(chess_move), notation);
+        let preferred = best
+            .as_ref()
+            .map(|best| san_parser.format_line(&board, std::slice::from_ref(best), notation));
+        board.make_move(chess_move);
+        let (after, next_best) = search(&mut board);
+
+        let sign = if mover == Color::White { 1 } else { -1 };
+        let loss = (sign * (before - after)).max(0);
+        let glyph = if best.as_ref() == Some(chess_move) {
+            None
+        } else if loss >= BLUNDER_CP {
+            summary.blunders += 1;
+            Some("??")
+        } else if loss >= MISTAKE_CP {
+            summary.mistakes += 1;
+            Some("?")
+        } else if loss >= INACCURACY_CP {
+            summary.inaccuracies += 1;
+            Some("?!")
+        } else {
+            None
+        };
+        if let (Some(glyph), Some(preferred)) = (glyph, preferred) {
+            let dots = if mover == Color::White { "." } else { "..." };
+            summary.annotations.push(Annotation {
+                label: format!("{}{} {}{}", number, dots, played[0], glyph),
+                loss,
+                best: preferred[0].clone(),
+                score: after,
+            });
+        }
+        (before, best) = (after, next_best);
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::read_games;
+
+    #[test]
+    fn recognises_lichess_ids_and_urls() {
+        for target in [
+            "AbCd1234",
+            "https://lichess.org/AbCd1234",
+            "lichess.org/AbCd1234/black",
+            "https://lichess.org/AbCd1234wxyz",
+            "https://lichess.org/AbCd1234#32",
+        ] {
+            assert_eq!(
+                lichess_game_id(target).as_deref(),
+                Some("AbCd1234"),
+                "{}",
+                target
+            );
+        }
+        for target in [
+            "AbCd123",
+            "AbCd12345",
+            "AbCd-234",
+            "aaaaaaaébbb",
+            "https://lichess.org/",
+        ] {
+            assert_eq!(lichess_game_id(target), None, "{}", target);
+        }
+        assert_eq!(
+            source_url("lichess", "AbCd1234").unwrap(),
+            "https://lichess.org/game/export/AbCd1234"
+        );
+        assert_eq!(
+            source_url("url", "file:///etc/passwd"),
+            Err("import url needs an http or https address")
+        );
+    }
+
+    #[test]
+    fn replays_the_first_game_with_its_tags() {
+        let text = "[Event \"Rated blitz game\"]\n[White \"alice\"]\n[Black \"bob\"]\n\n1. e4 { [%clk 0:03:00] } e5 2. Nf3 Nc6 1-0\n";
+        let game = &read_games(text)[0];
+        let board = replay(game, &SanParser::new()).unwrap();
+        assert_eq!(board.get_state().move_history.len(), 4);
+        let headers = headers(game);
+        assert_eq!(
+            (
+                headers.event.as_str(),
+                headers.white.as_str(),
+                headers.black.as_str()
+            ),
+            ("Rated blitz game", "alice", "bob")
+        );
+        assert_eq!(headers.site, "?");
+
+        let from_fen = &read_games("[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n\n1. e4 Kd7 *\n")[0];
+        let board = replay(from_fen, &SanParser::new()).unwrap();
+        assert_eq!(
+            FenParser::new().export_fen(&board),
+            "8/3k4/8/8/4P3/8/8/4K3 w - - 1 2"
+        );
+        let illegal = &read_games("1. e5 *\n")[0];
+        assert_eq!(
+            replay(illegal, &SanParser::new()).err(),
+            Some("Illegal move in imported game")
+        );
+    }
+
+    #[test]
+    fn marks_a_hung_queen_as_a_blunder() {
+        // 3. Qxf7+ gives the queen for a pawn
+        let game = &read_games("1. e4 e5 2. Qh5 Nc6 3. Qxf7+ Kxf7 *\n")[0];
+        let board = replay(game, &SanParser::new()).unwrap();
+        let summary = annotate(
+            &mut AI::new(),
+            &board,
+            3,
+            &SanParser::new(),
+            MoveNotation::San,
+        );
+        assert_eq!(summary.moves, 6);
+        let blunder = summary
+            .annotations
+            .iter()
+            .find(|annotation| annotation.label == "3. Qxf7+??");
+        assert!(
+            blunder.is_some_and(|blunder| blunder.loss >= BLUNDER_CP),
+            "{:?}",
+            summary
+        );
+        assert!(summary.blunders >= 1);
+    }
+}
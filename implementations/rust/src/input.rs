@@ -0,0 +1,169 @@
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+
+// Far longer than any real command (a FEN is under 100 bytes), short enough that a hostile
+// peer streaming one endless line cannot make the engine buffer it.
+pub const MAX_LINE_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum InputLine {
+    Line(String),
+    // The line exceeded the limit or was not UTF-8; its bytes were consumed and dropped.
+    Rejected(&'static str),
+    Eof,
+}
+
+pub fn read_line_bounded(reader: &mut impl BufRead, limit: usize) -> io::Result<InputLine> {
+    let mut line = Vec::new();
+    let mut overflowed = false;
+    let mut saw_input = false;
+
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            if !saw_input {
+                return Ok(InputLine::Eof);
+            }
+            break;
+        }
+        saw_input = true;
+
+        let (chunk, found_newline) = match available.iter().position(|&byte| byte == b'\n') {
+            Some(index) => (&available[..index], true),
+            None => (available, false),
+        };
+        if !overflowed {
+            if line.len() + chunk.len() > limit {
+                overflowed = true;
+                line.clear();
+            } else {
+                line.extend_from_slice(chunk);
+            }
+        }
+
+        let consumed = chunk.len() + usize::from(found_newline);
+        reader.consume(consumed);
+        if found_newline {
+            break;
+        }
+    }
+
+    if overflowed {
+        return Ok(InputLine::Rejected("Command too long"));
+    }
+    // GUIs on Windows end lines with CRLF
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    match String::from_utf8(line) {
+        Ok(text) => Ok(InputLine::Line(text)),
+        Err(_) => Ok(InputLine::Rejected("Invalid characters in command")),
+    }
+}
+
+// The next line from stdin. A thread reads stdin into a queue from the first call on, so input
+// keeps being drained while a command runs (a GUI writing to a full pipe never blocks) and a
+// `stop` is already waiting when the command loop gets back to it. The REPL and UCI mode share
+// the queue, so switching with `uci` loses nothing read ahead.
+pub fn next_stdin_line() -> InputLine {
+    static LINES: OnceLock<Mutex<Receiver<InputLine>>> = OnceLock::new();
+    let lines = LINES.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || forward_lines(&mut io::stdin().lock(), MAX_LINE_BYTES, &sender));
+        Mutex::new(receiver)
+    });
+    let lines = lines.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    lines.recv().unwrap_or(InputLine::Eof)
+}
+
+// Sends every line until the end of input, which is sent too. A read error counts as the end.
+fn forward_lines(reader: &mut impl BufRead, limit: usize, sender: &Sender<InputLine>) {
+    loop {
+        let line = read_line_bounded(reader, limit).unwrap_or(InputLine::Eof);
+        let end = line == InputLine::Eof;
+        if sender.send(line).is_err() || end {
+            return;
+        }
+    }
+}
+
+// No command uses control characters; NULs in particular tend to truncate or confuse
+// downstream tooling, so reject the whole line instead of guessing what was meant.
+pub fn check_command(line: &str) -> Result<(), &'static str> {
+    if line
+        .chars()
+        .any(|ch| ch.is_control() && ch != '\t' && ch != '\r')
+    {
+        return Err("Invalid characters in command");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(bytes: &[u8], limit: usize) -> Vec<InputLine> {
+        let mut reader = io::BufReader::with_capacity(4, bytes);
+        let mut lines = Vec::new();
+        loop {
+            match read_line_bounded(&mut reader, limit).unwrap() {
+                InputLine::Eof => return lines,
+                line => lines.push(line),
+            }
+        }
+    }
+
+    #[test]
+    fn bounds_line_length_and_recovers_on_the_next_line() {
+        let mut input = b"move e2e4\n".to_vec();
+        input.extend(std::iter::repeat_n(b'x', 100));
+        input.extend(b"\n\xff\xfe\nquit");
+
+        assert_eq!(
+            read_all(&input, 32),
+            vec![
+                InputLine::Line("move e2e4".to_string()),
+                InputLine::Rejected("Command too long"),
+                InputLine::Rejected("Invalid characters in command"),
+                InputLine::Line("quit".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn strips_crlf_and_keeps_blank_lines_as_empty_commands() {
+        assert_eq!(
+            read_all(b"uci\r\n\r\n\nisready\r\n", 64),
+            vec![
+                InputLine::Line("uci".to_string()),
+                InputLine::Line(String::new()),
+                InputLine::Line(String::new()),
+                InputLine::Line("isready".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn forwards_lines_through_the_queue_up_to_the_end_of_input() {
+        let (sender, receiver) = mpsc::channel();
+        forward_lines(&mut io::BufReader::with_capacity(4, &b"go depth 3\r\nstop\n"[..]), 64, &sender);
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>(),
+            vec![
+                InputLine::Line("go depth 3".to_string()),
+                InputLine::Line("stop".to_string()),
+                InputLine::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_interior_control_characters() {
+        assert!(check_command("fen 8/8/8/8/8/8/8/8 w - - 0 1\r").is_ok());
+        assert!(check_command("move e2\0e4").is_err());
+        assert!(check_command("ai\x1b[2J").is_err());
+    }
+}
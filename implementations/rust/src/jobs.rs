@@ -0,0 +1,528 @@
+use crate::ai::{format_score, SearchResult, AI};
+use crate::board::Board;
+use crate::fen::FenParser;
+use crate::options::Options;
+use crate::search_limits::SearchLimits;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
+
+pub const MAX_PRIORITY: u8 = 9;
+pub const DEFAULT_PRIORITY: u8 = 5;
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+// Finished jobs kept for status queries; the oldest are forgotten first.
+const MAX_FINISHED_JOBS: usize = 1000;
+
+// The daemon's one engine and its option values. A UCI session takes them out for as long as
+// it is connected; the job worker borrows them for one run at a time.
+pub type EngineSlot = Mutex<Option<(Options, AI)>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Queued,
+    Running,
+    // Preempted by a job of higher priority, waiting to run again
+    Suspended,
+    Done,
+    Cancelled,
+}
+
+impl JobState {
+    fn name(self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Suspended => "suspended",
+            JobState::Done => "done",
+            JobState::Cancelled => "cancelled",
+        }
+    }
+
+    fn is_finished(self) -> bool {
+        matches!(self, JobState::Done | JobState::Cancelled)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: u64,
+    pub priority: u8,
+    pub fen: String,
+    pub limits: SearchLimits,
+    pub state: JobState,
+    // The deepest iteration completed so far, kept across preemptions
+    pub result: Option<SearchResult>,
+    // Totals over every run, counting the current one as it goes
+    pub spent_ms: u64,
+    pub spent_nodes: u64,
+    pub preemptions: u32,
+}
+
+impl Job {
+    // Limits for the next run. Time and node budgets shrink by what earlier runs used; the depth
+    // stays, since the hash table makes the depths already searched cheap to repeat.
+    fn remaining_limits(&self) -> SearchLimits {
+        let mut limits = self.limits;
+        limits.movetime_ms = limits
+            .movetime_ms
+            .map(|ms| ms.saturating_sub(self.spent_ms).max(1));
+        limits.nodes = limits
+            .nodes
+            .map(|nodes| nodes.saturating_sub(self.spent_nodes).max(1));
+        limits
+    }
+
+    fn budget_spent(&self) -> bool {
+        let depth = self.result.as_ref().map_or(0, |result| result.depth);
+        self.limits.depth.is_some_and(|target| depth >= target)
+            || self
+                .limits
+                .movetime_ms
+                .is_some_and(|ms| self.spent_ms >= ms)
+            || self
+                .limits
+                .nodes
+                .is_some_and(|nodes| self.spent_nodes >= nodes)
+    }
+
+    pub fn status_line(&self) -> String {
+        let mut line = format!(
+            "JOB: id={}; state={}; priority={}",
+            self.id,
+            self.state.name(),
+            self.priority
+        );
+        if let Some(result) = self
+            .result
+            .as_ref()
+            .filter(|result| result.best_move.is_some())
+        {
+            let pv: Vec<String> = result.pv.iter().map(ToString::to_string).collect();
+            line.push_str(&format!(
+                "; depth={}; best={}; score={}; pv={}",
+                result.depth,
+                pv[0],
+                format_score(result.evaluation),
+                pv.join(" ")
+            ));
+        }
+        line.push_str(&format!(
+            "; nodes={}; time={}ms; preemptions={}",
+            self.spent_nodes, self.spent_ms, self.preemptions
+        ));
+        line
+    }
+}
+
+struct Jobs {
+    jobs: BTreeMap<u64, Job>,
+    next_id: u64,
+    // The running job and the flag that stops it
+    running: Option<(u64, Arc<AtomicBool>)>,
+    closed: bool,
+}
+
+// Analysis requests for the daemon's engine, run one at a time: highest priority first, then in
+// the order submitted, so a suspended job resumes ahead of later jobs of its priority. Submitting
+// a job of higher priority than the running one stops that one at once; it goes back in the
+// queue as suspended with what it found so far.
+pub struct JobQueue {
+    jobs: Mutex<Jobs>,
+    changed: Condvar,
+}
+
+impl JobQueue {
+    pub fn new() -> Self {
+        Self {
+            jobs: Mutex::new(Jobs {
+                jobs: BTreeMap::new(),
+                next_id: 1,
+                running: None,
+                closed: false,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Jobs> {
+        self.jobs.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn submit(&self, fen: &str, priority: u8, limits: SearchLimits) -> Result<Job, String> {
+        if priority > MAX_PRIORITY {
+            return Err(format!("Job priority must be 0-{}", MAX_PRIORITY));
+        }
+        if limits.wtime_ms.is_some() || limits.btime_ms.is_some() {
+            return Err("Jobs take depth, movetime and nodes limits".to_string());
+        }
+        FenParser::new().parse_fen(&mut Board::new(), fen)?;
+
+        let mut jobs = self.lock();
+        let id = jobs.next_id;
+        jobs.next_id += 1;
+        let job = Job {
+            id,
+            priority,
+            fen: fen.to_string(),
+            limits,
+            state: JobState::Queued,
+            result: None,
+            spent_ms: 0,
+            spent_nodes: 0,
+            preemptions: 0,
+        };
+        jobs.jobs.insert(id, job.clone());
+        if let Some((running, stop)) = &jobs.running {
+            if jobs.jobs[running].priority < priority {
+                stop.store(true, Ordering::Relaxed);
+            }
+        }
+        forget_old_jobs(&mut jobs.jobs);
+        self.changed.notify_all();
+        Ok(job)
+    }
+
+    pub fn get(&self, id: u64) -> Option<Job> {
+        self.lock().jobs.get(&id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<Job> {
+        self.lock().jobs.values().cloned().collect()
+    }
+
+    // A running job stops at once and keeps what it found.
+    pub fn cancel(&self, id: u64) -> Option<Job> {
+        let mut jobs = self.lock();
+        let running = jobs.running.clone();
+        let job = jobs.jobs.get_mut(&id)?;
+        if !job.state.is_finished() {
+            job.state = JobState::Cancelled;
+            if let Some((_, stop)) = running.filter(|(running, _)| *running == id) {
+                stop.store(true, Ordering::Relaxed);
+            }
+        }
+        let job = job.clone();
+        self.changed.notify_all();
+        Some(job)
+    }
+
+    // Blocks until the job is done or cancelled.
+    pub fn wait(&self, id: u64) -> Option<Job> {
+        let mut jobs = self.lock();
+        loop {
+            let job = jobs.jobs.get(&id)?;
+            if job.state.is_finished() {
+                return Some(job.clone());
+            }
+            jobs = self
+                .changed
+                .wait(jobs)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    // Stops the worker after its current run; the daemon itself never stops it.
+    #[cfg(test)]
+    pub fn close(&self) {
+        let mut jobs = self.lock();
+        jobs.closed = true;
+        if let Some((_, stop)) = &jobs.running {
+            stop.store(true, Ordering::Relaxed);
+        }
+        self.changed.notify_all();
+    }
+
+    // Runs jobs on the shared engine until the queue is closed.
+    pub fn run_worker(&self, engine: &EngineSlot) {
+        while let Some((job, stop)) = self.next_job() {
+            let mut board = Board::new();
+            // Checked when the job was submitted
+            let _ = FenParser::new().parse_fen(&mut board, &job.fen);
+            let limits = job.remaining_limits();
+            let started = Instant::now();
+            let result = {
+                let mut engine = engine.lock().unwrap_or_else(PoisonError::into_inner);
+                let (_, ai) = engine.as_mut().expect("UCI sessions put the engine back");
+                ai.set_stop_signal(Arc::clone(&stop));
+                ai.search_with_progress(&mut board, &limits, &mut |progress| {
+                    self.record(&job, progress, started);
+                })
+            };
+            self.record(&job, &result, started);
+            self.finish_run(job.id, &stop);
+        }
+    }
+
+    // The highest-priority job waiting, marked running, once there is one.
+    fn next_job(&self) -> Option<(Job, Arc<AtomicBool>)> {
+        let mut jobs = self.lock();
+        loop {
+            if jobs.closed {
+                return None;
+            }
+            let next = jobs
+                .jobs
+                .values()
+                .filter(|job| matches!(job.state, JobState::Queued | JobState::Suspended))
+                .max_by_key(|job| (job.priority, std::cmp::Reverse(job.id)))
+                .map(|job| job.id);
+            if let Some(id) = next {
+                let stop = Arc::new(AtomicBool::new(false));
+                jobs.running = Some((id, Arc::clone(&stop)));
+                let job = jobs.jobs.get_mut(&id).expect("job just found");
+                job.state = JobState::Running;
+                let job = job.clone();
+                self.changed.notify_all();
+                return Some((job, stop));
+            }
+            jobs = self
+                .changed
+                .wait(jobs)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    // Progress of the current run, on top of what earlier runs of the job spent.
+    fn record(&self, run: &Job, progress: &SearchResult, started: Instant) {
+        let mut jobs = self.lock();
+        let Some(job) = jobs.jobs.get_mut(&run.id) else {
+            return;
+        };
+        job.spent_ms = run.spent_ms + started.elapsed().as_millis() as u64;
+        job.spent_nodes = run.spent_nodes + progress.nodes;
+        let deeper = job
+            .result
+            .as_ref()
+            .is_none_or(|result| progress.depth >= result.depth);
+        if deeper && progress.best_move.is_some() {
+            job.result = Some(progress.clone());
+        }
+    }
+
+    fn finish_run(&self, id: u64, stop: &AtomicBool) {
+        let mut jobs = self.lock();
+        jobs.running = None;
+        if let Some(job) = jobs.jobs.get_mut(&id) {
+            if job.state == JobState::Running {
+                if stop.load(Ordering::Relaxed) && !job.budget_spent() {
+                    job.state = JobState::Suspended;
+                    job.preemptions += 1;
+                } else {
+                    job.state = JobState::Done;
+                }
+            }
+        }
+        self.changed.notify_all();
+    }
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn forget_old_jobs(jobs: &mut BTreeMap<u64, Job>) {
+    let finished: Vec<u64> = jobs
+        .values()
+        .filter(|job| job.state.is_finished())
+        .map(|job| job.id)
+        .collect();
+    for id in finished
+        .iter()
+        .take(finished.len().saturating_sub(MAX_FINISHED_JOBS))
+    {
+        jobs.remove(id);
+    }
+}
+
+// `job submit [priority <0-9>] <limits> [startpos | fen <fen>]`, `job status <id>`,
+// `job wait <id>`, `job cancel <id>` and `job list`; returns the lines to send back.
+pub fn handle_command(queue: &JobQueue, args: &[&str]) -> Vec<String> {
+    let job_id = || args.get(1).and_then(|id| id.parse::<u64>().ok());
+    let reply = |job: Option<Job>| {
+        vec![job.map_or_else(|| "ERROR: No such job".to_string(), |job| job.status_line())]
+    };
+    match args.first().copied() {
+        Some("submit") => match parse_submit(&args[1..]) {
+            Ok((fen, priority, limits)) => match queue.submit(&fen, priority, limits) {
+                Ok(job) => vec![job.status_line()],
+                Err(error) => vec![format!("ERROR: {}", error)],
+            },
+            Err(error) => vec![format!("ERROR: {}", error)],
+        },
+        Some("status") => reply(job_id().and_then(|id| queue.get(id))),
+        Some("wait") => reply(job_id().and_then(|id| queue.wait(id))),
+        Some("cancel") => reply(job_id().and_then(|id| queue.cancel(id))),
+        Some("list") => {
+            let jobs = queue.list();
+            let count = |state| jobs.iter().filter(|job| job.state == state).count();
+            let mut lines: Vec<String> = jobs.iter().map(Job::status_line).collect();
+            lines.push(format!(
+                "JOBS: queued={}; running={}; suspended={}; done={}; cancelled={}",
+                count(JobState::Queued),
+                count(JobState::Running),
+                count(JobState::Suspended),
+                count(JobState::Done),
+                count(JobState::Cancelled)
+            ));
+            lines
+        }
+        _ => vec!["ERROR: Usage: job submit|status|wait|cancel|list".to_string()],
+    }
+}
+
+fn parse_submit(args: &[&str]) -> Result<(String, u8, SearchLimits), String> {
+    let fen_at = args
+        .iter()
+        .position(|&arg| arg == "fen")
+        .unwrap_or(args.len());
+    let fen = match args.get(fen_at + 1..) {
+        Some(fen) if !fen.is_empty() => fen.join(" "),
+        _ => START_FEN.to_string(),
+    };
+    let mut priority = DEFAULT_PRIORITY;
+    let mut limits = Vec::new();
+    let mut tokens = args[..fen_at].iter();
+    while let Some(&token) = tokens.next() {
+        match token {
+            "priority" => {
+                priority = tokens
+                    .next()
+                    .and_then(|value| value.parse::<u8>().ok())
+                    .filter(|&value| value <= MAX_PRIORITY)
+                    .ok_or_else(|| format!("Job priority must be 0-{}", MAX_PRIORITY))?;
+            }
+            "startpos" => {}
+            _ => limits.push(token),
+        }
+    }
+    Ok((fen, priority, SearchLimits::parse(&limits)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uci::configured_ai;
+    use std::thread;
+    use std::time::Duration;
+
+    fn engine() -> Arc<EngineSlot> {
+        let options = Options::new();
+        let ai = configured_ai(&options);
+        Arc::new(Mutex::new(Some((options, ai))))
+    }
+
+    fn command(queue: &JobQueue, line: &str) -> Vec<String> {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        handle_command(queue, &args)
+    }
+
+    #[test]
+    fn parses_submissions_and_reports_bad_ones() {
+        let queue = JobQueue::new();
+        assert_eq!(
+            command(
+                &queue,
+                "submit priority 7 depth 3 fen 4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"
+            ),
+            vec!["JOB: id=1; state=queued; priority=7; nodes=0; time=0ms; preemptions=0"]
+        );
+        assert_eq!(queue.get(1).unwrap().fen, "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        assert_eq!(queue.get(1).unwrap().limits, SearchLimits::depth(3));
+        assert!(command(&queue, "submit movetime 100")[0].contains("priority=5"));
+        assert_eq!(queue.get(2).unwrap().fen, START_FEN);
+
+        assert_eq!(
+            command(&queue, "submit priority 10 depth 3"),
+            vec!["ERROR: Job priority must be 0-9"]
+        );
+        assert_eq!(
+            command(&queue, "submit wtime 1000 btime 1000"),
+            vec!["ERROR: Jobs take depth, movetime and nodes limits"]
+        );
+        assert_eq!(
+            command(&queue, "submit depth 3 fen 8/8/8 w - - 0 1").len(),
+            1
+        );
+        assert!(command(&queue, "submit depth 3 fen 8/8/8 w - - 0 1")[0].starts_with("ERROR: "));
+        assert_eq!(command(&queue, "status 99"), vec!["ERROR: No such job"]);
+
+        // Without a worker nothing runs, and a cancelled job is final
+        assert!(command(&queue, "cancel 1")[0].contains("state=cancelled"));
+        let list = command(&queue, "list");
+        assert_eq!(
+            list.last().unwrap(),
+            "JOBS: queued=1; running=0; suspended=0; done=0; cancelled=1"
+        );
+    }
+
+    #[test]
+    fn a_higher_priority_job_preempts_the_running_one() {
+        let queue = Arc::new(JobQueue::new());
+        let engine = engine();
+        let worker = {
+            let (queue, engine) = (Arc::clone(&queue), Arc::clone(&engine));
+            thread::spawn(move || queue.run_worker(&engine))
+        };
+
+        let long = queue
+            .submit(
+                START_FEN,
+                1,
+                SearchLimits {
+                    movetime_ms: Some(800),
+                    ..SearchLimits::default()
+                },
+            )
+            .unwrap();
+        while queue.get(long.id).unwrap().state != JobState::Running {
+            thread::sleep(Duration::from_millis(2));
+        }
+        let urgent = queue
+            .submit("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", 8, SearchLimits::depth(3))
+            .unwrap();
+        let urgent = queue.wait(urgent.id).unwrap();
+        assert_eq!(urgent.state, JobState::Done);
+        assert_eq!(urgent.result.as_ref().unwrap().depth, 3);
+        let suspended = queue.get(long.id).unwrap();
+        assert!(
+            matches!(suspended.state, JobState::Suspended | JobState::Running),
+            "{:?}",
+            suspended.state
+        );
+        assert_eq!(suspended.preemptions, 1);
+
+        // It resumes with what is left of its budget and finishes
+        let long = queue.wait(long.id).unwrap();
+        assert_eq!(long.state, JobState::Done);
+        assert!(long.spent_ms < 1500, "{}", long.status_line());
+        assert!(
+            long.status_line().contains("; best="),
+            "{}",
+            long.status_line()
+        );
+
+        queue.close();
+        worker.join().unwrap();
+    }
+
+    #[test]
+    fn cancelling_a_running_job_stops_it() {
+        let queue = Arc::new(JobQueue::new());
+        let engine = engine();
+        let worker = {
+            let (queue, engine) = (Arc::clone(&queue), Arc::clone(&engine));
+            thread::spawn(move || queue.run_worker(&engine))
+        };
+        let job = queue.submit(START_FEN, 5, SearchLimits::depth(30)).unwrap();
+        while queue.get(job.id).unwrap().state != JobState::Running {
+            thread::sleep(Duration::from_millis(2));
+        }
+        queue.cancel(job.id);
+        assert_eq!(queue.wait(job.id).unwrap().state, JobState::Cancelled);
+        queue.close();
+        worker.join().unwrap();
+    }
+}
@@ -0,0 +1,453 @@
+use crate::board::Board;
+use crate::move_generator::MoveGenerator;
+use crate::notation::SanParser;
+use crate::tactics::{MotifKind, TacticsDetector};
+use crate::types::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Legality {
+    Legal(Move),
+    // Why the move cannot be played, naming the pieces and squares involved
+    Illegal(String),
+}
+
+// Explains why a move is illegal in terms of the rule it breaks: a blocked path, a pinned
+// piece, an unanswered check, a missing castling right.
+pub struct LegalityChecker {
+    move_generator: MoveGenerator,
+    tactics: TacticsDetector,
+    san_parser: SanParser,
+}
+
+impl LegalityChecker {
+    pub fn new() -> Self {
+        Self {
+            move_generator: MoveGenerator::new(),
+            tactics: TacticsDetector::new(),
+            san_parser: SanParser::new(),
+        }
+    }
+
+    // The move from `from` to `to` for the side to move. Promotions count as legal whatever
+    // piece is chosen; the queen promotion is returned.
+    pub fn check(&self, board: &mut Board, from: Square, to: Square) -> Legality {
+        let color = board.get_turn();
+        let Some(piece) = board.get_piece(from) else {
+            return Legality::Illegal(format!(
+                "there is no piece on {}",
+                square_to_algebraic(from)
+            ));
+        };
+        if piece.color != color {
+            return Legality::Illegal(format!(
+                "the {} is {}, and it is {} to move",
+                describe(board, from),
+                color_name(piece.color),
+                color_name(color)
+            ));
+        }
+
+        let chess_move = self
+            .move_generator
+            .generate_moves(board, color)
+            .into_iter()
+            .filter(|chess_move| chess_move.from == from && chess_move.to == to)
+            .find(|chess_move| chess_move.promotion.is_none_or(|p| p == PieceType::Queen));
+        match chess_move {
+            None => Legality::Illegal(self.unreachable(board, from, piece, to)),
+            Some(chess_move) => match self.exposed_king(board, &chess_move) {
+                None => Legality::Legal(chess_move),
+                Some(reason) => Legality::Illegal(reason),
+            },
+        }
+    }
+
+    // Like `check`, for a move in SAN. A SAN move that matches no pseudo-legal move is matched
+    // against every square the named piece could be asked to go to, so a blocked `Bb5` is
+    // still traced back to its bishop. Unreadable or ambiguous input is an error.
+    pub fn check_san(&self, board: &mut Board, san: &str) -> Result<Legality, &'static str> {
+        if let Ok(chess_move) = self.san_parser.parse_san(board, san) {
+            return Ok(Legality::Legal(chess_move));
+        }
+        let color = board.get_turn();
+        let pseudo_legal = self.move_generator.generate_moves(board, color);
+        let chess_move = match self.san_parser.resolve_san(san, pseudo_legal) {
+            Err("Illegal move") => {
+                match self
+                    .san_parser
+                    .resolve_san(san, requested_moves(board, san))
+                {
+                    Err("Illegal move") => {
+                        return Ok(Legality::Illegal(format!("no piece of yours fits {}", san)))
+                    }
+                    resolved => resolved?,
+                }
+            }
+            resolved => resolved?,
+        };
+        Ok(self.check(board, chess_move.from, chess_move.to))
+    }
+
+    // `chess_move` is pseudo-legal; names what attacks the king after it, if anything.
+    fn exposed_king(&self, board: &mut Board, chess_move: &Move) -> Option<String> {
+        let color = board.get_turn();
+        let enemy = color.opposite();
+        let king_before = board.king_square(color)?;
+        let in_check = self.move_generator.is_in_check(board, color);
+        board.make_move(chess_move);
+        let king = board.king_square(color);
+        let attackers = king
+            .map(|king| self.move_generator.attackers_of(board, king, enemy))
+            .unwrap_or_default();
+        board.undo_move();
+        if attackers.is_empty() {
+            return None;
+        }
+        let attackers = describe_all(board, &attackers);
+
+        if chess_move.piece == PieceType::King {
+            return Some(format!(
+                "the king would be attacked on {} by the {}",
+                square_to_algebraic(chess_move.to),
+                attackers
+            ));
+        }
+        let pin = self
+            .tactics
+            .find_pins(board, enemy)
+            .into_iter()
+            .find_map(|motif| match motif.kind {
+                MotifKind::Pin {
+                    pinner,
+                    pinned,
+                    target,
+                } if pinned.square == chess_move.from
+                    && target.piece.piece_type == PieceType::King =>
+                {
+                    Some(pinner)
+                }
+                _ => None,
+            });
+        if let Some(pinner) = pin {
+            return Some(format!(
+                "{} is pinned to the king on {} by the {}",
+                describe(board, chess_move.from),
+                square_to_algebraic(king_before),
+                describe(board, pinner.square)
+            ));
+        }
+        if in_check {
+            return Some(format!(
+                "the king on {} is in check, and after this move the {} still attacks it",
+                square_to_algebraic(king_before),
+                attackers
+            ));
+        }
+        // A discovered line, as when en passant empties a rank
+        Some(format!(
+            "it would leave the king on {} attacked by the {}",
+            square_to_algebraic(king_before),
+            attackers
+        ))
+    }
+
+    // Why `piece` has no pseudo-legal move from `from` to `to`.
+    fn unreachable(&self, board: &Board, from: Square, piece: Piece, to: Square) -> String {
+        let mover = describe(board, from);
+        if from == to {
+            return format!("the {} is already on {}", mover, square_to_algebraic(to));
+        }
+        let file_step = (to % 8) as i32 - (from % 8) as i32;
+        let rank_step = (to / 8) as i32 - (from / 8) as i32;
+        let diagonal = file_step.abs() == rank_step.abs();
+        let straight = file_step == 0 || rank_step == 0;
+        // Castling is refused for its own reasons, before the king lands on its own piece.
+        let castling = piece.piece_type == PieceType::King
+            && rank_step == 0
+            && file_step.abs() == 2
+            && from == home(piece.color, 4);
+        if castling {
+            return self.castling_failure(board, piece.color, file_step > 0);
+        }
+        if let Some(own) = board.get_piece(to).filter(|own| own.color == piece.color) {
+            return format!(
+                "the {} cannot move onto your own {} on {}",
+                mover,
+                piece_name(own.piece_type),
+                square_to_algebraic(to)
+            );
+        }
+
+        match piece.piece_type {
+            PieceType::King => "a king moves one square at a time".to_string(),
+            PieceType::Knight => {
+                "a knight moves two squares one way and one square to the side".to_string()
+            }
+            PieceType::Pawn => pawn_failure(board, from, piece.color, to),
+            PieceType::Bishop if !diagonal => "a bishop moves only diagonally".to_string(),
+            PieceType::Rook if !straight => "a rook moves only along ranks and files".to_string(),
+            PieceType::Queen if !diagonal && !straight => {
+                "a queen moves only along ranks, files and diagonals".to_string()
+            }
+            PieceType::Bishop | PieceType::Rook | PieceType::Queen => {
+                let step = rank_step.signum() * 8 + file_step.signum();
+                let mut square = from as i32 + step;
+                while square != to as i32 {
+                    if board.get_piece(square as Square).is_some() {
+                        return format!(
+                            "the {} is blocked by the {}",
+                            mover,
+                            describe(board, square as Square)
+                        );
+                    }
+                    square += step;
+                }
+                format!("the {} cannot move to {}", mover, square_to_algebraic(to))
+            }
+        }
+    }
+
+    fn castling_failure(&self, board: &Board, color: Color, kingside: bool) -> String {
+        let rights = board.get_castling_rights();
+        let side = if kingside { "kingside" } else { "queenside" };
+        let allowed = match (color, kingside) {
+            (Color::White, true) => rights.white_kingside,
+            (Color::White, false) => rights.white_queenside,
+            (Color::Black, true) => rights.black_kingside,
+            (Color::Black, false) => rights.black_queenside,
+        };
+        if !allowed {
+            return format!(
+                "{} can no longer castle {}: the king or that rook has moved",
+                color_name(color),
+                side
+            );
+        }
+        let rook = home(color, if kingside { 7 } else { 0 });
+        let rook_present = board
+            .get_piece(rook)
+            .is_some_and(|p| p.piece_type == PieceType::Rook && p.color == color);
+        if !rook_present {
+            return format!(
+                "there is no rook on {} to castle with",
+                square_to_algebraic(rook)
+            );
+        }
+        let between: &[usize] = if kingside { &[5, 6] } else { &[3, 2, 1] };
+        for &file in between {
+            let square = home(color, file);
+            if board.get_piece(square).is_some() {
+                return format!("the {} is in the way", describe(board, square));
+            }
+        }
+
+        let enemy = color.opposite();
+        let king = home(color, 4);
+        let checkers = self.move_generator.attackers_of(board, king, enemy);
+        if !checkers.is_empty() {
+            return format!(
+                "the king cannot castle out of check from the {}",
+                describe_all(board, &checkers)
+            );
+        }
+        let crossed: &[usize] = if kingside { &[5, 6] } else { &[3, 2] };
+        for &file in crossed {
+            let square = home(color, file);
+            let attackers = self.move_generator.attackers_of(board, square, enemy);
+            if !attackers.is_empty() {
+                return format!(
+                    "the king cannot castle through {}, which is attacked by the {}",
+                    square_to_algebraic(square),
+                    describe_all(board, &attackers)
+                );
+            }
+        }
+        format!("{} cannot castle {} here", color_name(color), side)
+    }
+}
+
+impl Default for LegalityChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn pawn_failure(board: &Board, from: Square, color: Color, to: Square) -> String {
+    let mover = describe(board, from);
+    let forward = if color == Color::White { 1 } else { -1 };
+    let file_step = (to % 8) as i32 - (from % 8) as i32;
+    let advance = ((to / 8) as i32 - (from / 8) as i32) * forward;
+    if advance <= 0 {
+        return "pawns only move forward".to_string();
+    }
+    if file_step != 0 {
+        return if file_step.abs() == 1 && advance == 1 {
+            format!(
+                "a pawn moves diagonally only to capture, and {} is empty",
+                square_to_algebraic(to)
+            )
+        } else {
+            "a pawn moves straight forward and captures one square diagonally".to_string()
+        };
+    }
+    let start_rank = if color == Color::White { 1 } else { 6 };
+    if advance > 2 || (advance == 2 && from / 8 != start_rank) {
+        return "a pawn moves one square forward, or two from its starting square".to_string();
+    }
+    let mut square = from as i32;
+    for _ in 0..advance {
+        square += 8 * forward;
+        if board.get_piece(square as Square).is_some() {
+            return format!(
+                "the {} is blocked by the {}",
+                mover,
+                describe(board, square as Square)
+            );
+        }
+    }
+    format!("the {} cannot move to {}", mover, square_to_algebraic(to))
+}
+
+// Every move the side to move's pieces could be asked to make by the way they move, whatever
+// stands in the way, for tracing a SAN move back to its piece. Pawns are only asked to capture
+// when the move is written as a capture, so that a plain `e5` names the e-pawn and not its
+// neighbours too.
+fn requested_moves(board: &Board, san: &str) -> Vec<Move> {
+    let color = board.get_turn();
+    let forward = if color == Color::White { 1 } else { -1 };
+    let capture = san.contains('x') || san.contains(':');
+    let mut moves = Vec::new();
+    for from in 0..64usize {
+        let Some(piece) = board.get_piece(from).filter(|piece| piece.color == color) else {
+            continue;
+        };
+        for to in 0..64usize {
+            let files = (to % 8).abs_diff(from % 8);
+            let ranks = (to / 8).abs_diff(from / 8);
+            let advance = ((to / 8) as i32 - (from / 8) as i32) * forward;
+            let asked = match piece.piece_type {
+                PieceType::Pawn => {
+                    advance > 0 && (files == 0 || (capture && files == 1 && advance == 1))
+                }
+                PieceType::Knight => (files, ranks) == (1, 2) || (files, ranks) == (2, 1),
+                PieceType::Bishop => files == ranks,
+                PieceType::Rook => files == 0 || ranks == 0,
+                PieceType::Queen => files == ranks || files == 0 || ranks == 0,
+                PieceType::King => files <= 1 && ranks <= 1,
+            };
+            if to != from && asked {
+                moves.push(Move::new(from, to, piece.piece_type));
+            }
+        }
+        if piece.piece_type == PieceType::King && from == home(color, 4) {
+            moves.push(Move::new(from, from + 2, PieceType::King).with_castling());
+            moves.push(Move::new(from, from - 2, PieceType::King).with_castling());
+        }
+    }
+    moves
+}
+
+// The square on `color`'s back rank on `file`
+fn home(color: Color, file: usize) -> Square {
+    match color {
+        Color::White => file,
+        Color::Black => 56 + file,
+    }
+}
+
+// "rook on e8"
+fn describe(board: &Board, square: Square) -> String {
+    match board.get_piece(square) {
+        Some(piece) => format!(
+            "{} on {}",
+            piece_name(piece.piece_type),
+            square_to_algebraic(square)
+        ),
+        None => square_to_algebraic(square),
+    }
+}
+
+fn describe_all(board: &Board, squares: &[Square]) -> String {
+    squares
+        .iter()
+        .map(|&square| describe(board, square))
+        .collect::<Vec<_>>()
+        .join(" and the ")
+}
+
+fn piece_name(piece_type: PieceType) -> &'static str {
+    match piece_type {
+        PieceType::Pawn => "pawn",
+        PieceType::Knight => "knight",
+        PieceType::Bishop => "bishop",
+        PieceType::Rook => "rook",
+        PieceType::Queen => "queen",
+        PieceType::King => "king",
+    }
+}
+
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::White => "white",
+        Color::Black => "black",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::FenParser;
+
+    fn why(fen: &str, san: &str) -> String {
+        let mut board = Board::new();
+        FenParser::new().parse_fen(&mut board, fen).unwrap();
+        match LegalityChecker::new().check_san(&mut board, san).unwrap() {
+            Legality::Legal(chess_move) => format!("legal {}", chess_move),
+            Legality::Illegal(reason) => reason,
+        }
+    }
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[test]
+    fn names_the_blocker_the_pin_and_the_check() {
+        assert_eq!(why(START, "Nf3"), "legal g1f3");
+        assert_eq!(
+            why(START, "Bb5"),
+            "the bishop on f1 is blocked by the pawn on e2"
+        );
+        assert_eq!(why(START, "O-O"), "the bishop on f1 is in the way");
+        assert_eq!(
+            why("4r1k1/8/8/8/8/8/4B3/4K3 w - - 0 1", "Bd3"),
+            "bishop on e2 is pinned to the king on e1 by the rook on e8"
+        );
+        assert_eq!(
+            why("4k3/8/8/8/8/8/P7/r3K3 w - - 0 1", "a3"),
+            "the king on e1 is in check, and after this move the rook on a1 still attacks it"
+        );
+        assert_eq!(
+            why("4k3/8/8/8/8/8/8/3rK3 w - - 0 1", "Kd2"),
+            "the king would be attacked on d2 by the rook on d1"
+        );
+    }
+
+    #[test]
+    fn explains_pawn_and_castling_rules() {
+        assert_eq!(
+            why(START, "e5"),
+            "a pawn moves one square forward, or two from its starting square"
+        );
+        assert_eq!(
+            why(START, "exd3"),
+            "a pawn moves diagonally only to capture, and d3 is empty"
+        );
+        assert_eq!(
+            why("4k3/8/8/8/2b5/8/8/4K2R w K - 0 1", "O-O"),
+            "the king cannot castle through f1, which is attacked by the bishop on c4"
+        );
+        assert_eq!(
+            why("4k3/8/8/8/8/8/8/4K2R w - - 0 1", "O-O"),
+            "white can no longer castle kingside: the king or that rook has moved"
+        );
+    }
+}
@@ -0,0 +1,54 @@
+#[cfg(feature = "selfplay")]
+pub mod ablation;
+pub mod ai;
+#[cfg(feature = "analysis")]
+pub mod analysis;
+pub mod attack_tables;
+pub mod bitboard;
+pub mod board;
+pub mod correction_history;
+pub mod cpu;
+pub mod draw_detection;
+pub mod eval;
+pub mod fen;
+pub mod game_state;
+#[cfg(feature = "heatmap")]
+pub mod heatmap;
+#[cfg(feature = "legality")]
+pub mod legality;
+pub mod move_generator;
+pub mod move_ordering;
+pub mod move_picker;
+pub mod notation;
+#[cfg(feature = "selfplay")]
+pub mod opening_suite;
+pub mod options;
+#[cfg(feature = "pawn_structure")]
+pub mod pawn_structure;
+pub mod perft;
+#[cfg(feature = "pgn")]
+pub mod pgn;
+#[cfg(feature = "problem")]
+pub mod problem;
+#[cfg(feature = "selfplay")]
+pub mod ratings;
+pub mod search_limits;
+pub mod search_tree;
+#[cfg(feature = "selfplay")]
+pub mod selfplay;
+#[cfg(feature = "sharpness")]
+pub mod sharpness;
+pub mod sliding_attacks;
+#[cfg(feature = "selfplay")]
+pub mod sprt;
+#[cfg(feature = "tactics")]
+pub mod tactics;
+pub mod time_manager;
+pub mod timing;
+#[cfg(feature = "analysis")]
+pub mod topology;
+pub mod transposition;
+pub mod types;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod zobrist;
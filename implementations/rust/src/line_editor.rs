@@ -0,0 +1,493 @@
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+const HISTORY_LIMIT: usize = 500;
+const HISTORY_FILE: &str = ".chess_engine_history";
+
+// Candidates replace `line[start..]`, where `line` is the text before the cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub start: usize,
+    pub candidates: Vec<String>,
+}
+
+pub trait Completer {
+    fn complete(&mut self, line: &str) -> Completion;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Tab,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    Ctrl(char),
+    Unknown,
+}
+
+// Minimal readline replacement for interactive sessions: history (persisted in the home
+// directory), Ctrl-R reverse search, Emacs-style movement keys and tab completion.
+pub struct LineEditor {
+    history: Vec<String>,
+    history_path: Option<PathBuf>,
+}
+
+impl LineEditor {
+    // Scripts and harnesses pipe commands in, so only a real terminal gets line editing.
+    pub fn for_terminal() -> Option<Self> {
+        if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+            return None;
+        }
+        let history_path =
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+        let history = history_path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| contents.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+        Some(Self {
+            history,
+            history_path,
+        })
+    }
+
+    // Returns `None` on end of input (Ctrl-D on an empty line); Ctrl-C yields an empty line.
+    pub fn read_line(
+        &mut self,
+        prompt: &str,
+        completer: &mut dyn Completer,
+    ) -> io::Result<Option<String>> {
+        let Some(_raw_mode) = RawMode::enable() else {
+            return read_plain_line(prompt);
+        };
+
+        let mut state = EditState::new(prompt);
+        let mut stdin = io::stdin().lock();
+        state.render()?;
+
+        loop {
+            let Some(key) = read_key(&mut stdin)? else {
+                return Ok(None);
+            };
+            match key {
+                Key::Enter => break,
+                Key::Ctrl('c') => {
+                    write_raw("^C\r\n")?;
+                    return Ok(Some(String::new()));
+                }
+                Key::Ctrl('d') if state.buffer.is_empty() => {
+                    write_raw("\r\n")?;
+                    return Ok(None);
+                }
+                Key::Ctrl('r') => {
+                    if self.reverse_search(&mut state, &mut stdin)? {
+                        break;
+                    }
+                }
+                Key::Tab => state.complete(completer)?,
+                Key::Up => state.recall(&self.history, true),
+                Key::Down => state.recall(&self.history, false),
+                other => state.edit(other),
+            }
+            state.render()?;
+        }
+
+        write_raw("\r\n")?;
+        let line: String = state.buffer.iter().collect();
+        self.remember(&line);
+        Ok(Some(line))
+    }
+
+    // Returns true when the found entry should be executed immediately (Enter).
+    fn reverse_search(&self, state: &mut EditState, stdin: &mut impl Read) -> io::Result<bool> {
+        let original = state.buffer.clone();
+        let mut query = String::new();
+        let mut skip = 0;
+
+        loop {
+            let found = self
+                .history
+                .iter()
+                .rev()
+                .filter(|entry| !query.is_empty() && entry.contains(&query))
+                .nth(skip);
+            if let Some(entry) = found {
+                state.buffer = entry.chars().collect();
+                state.cursor = state.buffer.len();
+            }
+            let shown: String = state.buffer.iter().collect();
+            write_raw(&format!("\r\x1b[K(reverse-i-search)`{}': {}", query, shown))?;
+
+            match read_key(stdin)? {
+                Some(Key::Char(ch)) => {
+                    query.push(ch);
+                    skip = 0;
+                }
+                Some(Key::Backspace) => {
+                    query.pop();
+                    skip = 0;
+                }
+                Some(Key::Ctrl('r')) => skip += usize::from(found.is_some()),
+                Some(Key::Enter) => return Ok(true),
+                Some(Key::Ctrl('g') | Key::Ctrl('c')) | None => {
+                    state.buffer = original;
+                    state.cursor = state.buffer.len();
+                    return Ok(false);
+                }
+                Some(_) => return Ok(false),
+            }
+        }
+    }
+
+    fn remember(&mut self, line: &str) {
+        if line.trim().is_empty() || self.history.last().map(String::as_str) == Some(line) {
+            return;
+        }
+        self.history.push(line.to_string());
+        if self.history.len() > HISTORY_LIMIT {
+            self.history.drain(..self.history.len() - HISTORY_LIMIT);
+        }
+        if let Some(path) = &self.history_path {
+            // History is a convenience; an unwritable home directory must not break the session.
+            let _ = fs::write(path, self.history.join("\n") + "\n");
+        }
+    }
+}
+
+struct EditState {
+    prompt: String,
+    buffer: Vec<char>,
+    cursor: usize,
+    history_index: Option<usize>,
+    draft: Vec<char>,
+}
+
+impl EditState {
+    fn new(prompt: &str) -> Self {
+        Self {
+            prompt: prompt.to_string(),
+            buffer: Vec::new(),
+            cursor: 0,
+            history_index: None,
+            draft: Vec::new(),
+        }
+    }
+
+    fn render(&self) -> io::Result<()> {
+        let line: String = self.buffer.iter().collect();
+        let mut output = format!("\r\x1b[K{}{}", self.prompt, line);
+        let behind = self.buffer.len() - self.cursor;
+        if behind > 0 {
+            output.push_str(&format!("\x1b[{}D", behind));
+        }
+        write_raw(&output)
+    }
+
+    fn edit(&mut self, key: Key) {
+        match key {
+            Key::Char(ch) => {
+                self.buffer.insert(self.cursor, ch);
+                self.cursor += 1;
+            }
+            Key::Backspace | Key::Ctrl('h') if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.buffer.remove(self.cursor);
+            }
+            Key::Delete | Key::Ctrl('d') if self.cursor < self.buffer.len() => {
+                self.buffer.remove(self.cursor);
+            }
+            Key::Left | Key::Ctrl('b') => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right | Key::Ctrl('f') => self.cursor = (self.cursor + 1).min(self.buffer.len()),
+            Key::Home | Key::Ctrl('a') => self.cursor = 0,
+            Key::End | Key::Ctrl('e') => self.cursor = self.buffer.len(),
+            Key::Ctrl('u') => {
+                self.buffer.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            Key::Ctrl('k') => self.buffer.truncate(self.cursor),
+            Key::Ctrl('w') => {
+                let mut start = self.cursor;
+                while start > 0 && self.buffer[start - 1] == ' ' {
+                    start -= 1;
+                }
+                while start > 0 && self.buffer[start - 1] != ' ' {
+                    start -= 1;
+                }
+                self.buffer.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            _ => {}
+        }
+    }
+
+    fn recall(&mut self, history: &[String], older: bool) {
+        let index = match (self.history_index, older) {
+            (None, true) if !history.is_empty() => {
+                self.draft = self.buffer.clone();
+                Some(history.len() - 1)
+            }
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index + 1 < history.len() => Some(index + 1),
+            (Some(_), false) => None,
+            _ => return,
+        };
+
+        self.history_index = index;
+        self.buffer = match index {
+            Some(index) => history[index].chars().collect(),
+            None => self.draft.clone(),
+        };
+        self.cursor = self.buffer.len();
+    }
+
+    fn complete(&mut self, completer: &mut dyn Completer) -> io::Result<()> {
+        let before: String = self.buffer[..self.cursor].iter().collect();
+        let completion = completer.complete(&before);
+        // Candidates extend what was typed, so only their unseen tail is inserted.
+        let word = before.get(completion.start..).unwrap_or("");
+
+        match completion.candidates.as_slice() {
+            [] => {}
+            [only] => {
+                let mut insertion: Vec<char> = only.chars().skip(word.chars().count()).collect();
+                insertion.push(' ');
+                self.insert(&insertion);
+            }
+            many => {
+                let prefix = common_prefix(many);
+                if prefix.chars().count() > word.chars().count() {
+                    let insertion: Vec<char> = prefix.chars().skip(word.chars().count()).collect();
+                    self.insert(&insertion);
+                } else {
+                    write_raw(&format!("\r\n{}\r\n", many.join("  ")))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, chars: &[char]) {
+        for &ch in chars {
+            self.buffer.insert(self.cursor, ch);
+            self.cursor += 1;
+        }
+    }
+}
+
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    // Uses stty rather than termios bindings so the crate stays dependency-free.
+    fn enable() -> Option<Self> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        Some(Self {
+            saved: saved.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn read_plain_line(prompt: &str) -> io::Result<Option<String>> {
+    write_raw(prompt)?;
+    let mut line = String::new();
+    if io::stdin().read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+fn write_raw(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(text.as_bytes())?;
+    stdout.flush()
+}
+
+fn read_byte(input: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut byte = [0u8; 1];
+    match input.read(&mut byte)? {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    }
+}
+
+fn read_key(input: &mut impl Read) -> io::Result<Option<Key>> {
+    let Some(byte) = read_byte(input)? else {
+        return Ok(None);
+    };
+
+    let key = match byte {
+        b'\r' | b'\n' => Key::Enter,
+        b'\t' => Key::Tab,
+        0x7f => Key::Backspace,
+        0x1b => read_escape(input)?,
+        0x01..=0x1a => Key::Ctrl((b'a' + byte - 1) as char),
+        0x20..=0x7e => Key::Char(byte as char),
+        0x80..=0xff => read_utf8(input, byte)?,
+        _ => Key::Unknown,
+    };
+    Ok(Some(key))
+}
+
+fn read_escape(input: &mut impl Read) -> io::Result<Key> {
+    let key = match (read_byte(input)?, read_byte(input)?) {
+        (Some(b'[' | b'O'), Some(b'A')) => Key::Up,
+        (Some(b'[' | b'O'), Some(b'B')) => Key::Down,
+        (Some(b'[' | b'O'), Some(b'C')) => Key::Right,
+        (Some(b'[' | b'O'), Some(b'D')) => Key::Left,
+        (Some(b'[' | b'O'), Some(b'H')) => Key::Home,
+        (Some(b'[' | b'O'), Some(b'F')) => Key::End,
+        (Some(b'['), Some(digit @ b'1'..=b'8')) => {
+            // VT-style `ESC [ n ~` sequences
+            let _tilde = read_byte(input)?;
+            match digit {
+                b'1' | b'7' => Key::Home,
+                b'3' => Key::Delete,
+                b'4' | b'8' => Key::End,
+                _ => Key::Unknown,
+            }
+        }
+        _ => Key::Unknown,
+    };
+    Ok(key)
+}
+
+fn read_utf8(input: &mut impl Read, first: u8) -> io::Result<Key> {
+    let continuation = match first {
+        0xc0..=0xdf => 1,
+        0xe0..=0xef => 2,
+        0xf0..=0xf7 => 3,
+        _ => return Ok(Key::Unknown),
+    };
+    let mut bytes = vec![first];
+    for _ in 0..continuation {
+        match read_byte(input)? {
+            Some(byte) => bytes.push(byte),
+            None => return Ok(Key::Unknown),
+        }
+    }
+    Ok(std::str::from_utf8(&bytes)
+        .ok()
+        .and_then(|text| text.chars().next())
+        .map_or(Key::Unknown, Key::Char))
+}
+
+fn common_prefix(candidates: &[String]) -> String {
+    let Some(first) = candidates.first() else {
+        return String::new();
+    };
+    let mut prefix: Vec<char> = first.chars().collect();
+    for candidate in &candidates[1..] {
+        let shared = prefix
+            .iter()
+            .zip(candidate.chars())
+            .take_while(|(a, b)| **a == *b)
+            .count();
+        prefix.truncate(shared);
+    }
+    prefix.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(Vec<&'static str>);
+
+    impl Completer for Fixed {
+        fn complete(&mut self, line: &str) -> Completion {
+            Completion {
+                start: line.rfind(' ').map_or(0, |index| index + 1),
+                candidates: self
+                    .0
+                    .iter()
+                    .map(|candidate| candidate.to_string())
+                    .collect(),
+            }
+        }
+    }
+
+    fn keys(bytes: &[u8]) -> Vec<Key> {
+        let mut input = bytes;
+        let mut keys = Vec::new();
+        while let Some(key) = read_key(&mut input).unwrap() {
+            keys.push(key);
+        }
+        keys
+    }
+
+    #[test]
+    fn decodes_control_escape_and_utf8_input() {
+        assert_eq!(
+            keys(b"a\x12\x1b[A\x1b[3~\x7f\r"),
+            vec![
+                Key::Char('a'),
+                Key::Ctrl('r'),
+                Key::Up,
+                Key::Delete,
+                Key::Backspace,
+                Key::Enter
+            ]
+        );
+        assert_eq!(keys("é".as_bytes()), vec![Key::Char('é')]);
+    }
+
+    #[test]
+    fn edits_and_recalls_history() {
+        let history = vec!["move e2e4".to_string(), "ai 3".to_string()];
+        let mut state = EditState::new("> ");
+        for ch in "undo".chars() {
+            state.edit(Key::Char(ch));
+        }
+        state.edit(Key::Ctrl('w'));
+        assert!(state.buffer.is_empty());
+
+        state.edit(Key::Char('x'));
+        state.recall(&history, true);
+        state.recall(&history, true);
+        assert_eq!(state.buffer.iter().collect::<String>(), "move e2e4");
+        state.recall(&history, false);
+        state.recall(&history, false);
+        assert_eq!(state.buffer.iter().collect::<String>(), "x");
+    }
+
+    #[test]
+    fn completes_single_candidates_and_common_prefixes() {
+        let mut state = EditState::new("");
+        state.insert(&"move e".chars().collect::<Vec<_>>());
+        state.complete(&mut Fixed(vec!["e2e3", "e2e4"])).unwrap();
+        assert_eq!(state.buffer.iter().collect::<String>(), "move e2e");
+
+        state.complete(&mut Fixed(vec!["e2e4"])).unwrap();
+        assert_eq!(state.buffer.iter().collect::<String>(), "move e2e4 ");
+    }
+}
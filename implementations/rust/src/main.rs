@@ -1,22 +1,88 @@
-mod ai;
-mod attack_tables;
-mod board;
-mod draw_detection;
-mod fen;
-mod move_generator;
-mod perft;
-mod types;
-mod zobrist;
-
-use crate::ai::AI;
+use chess_engine::{
+    ablation, ai, analysis, attack_tables, board, cpu, draw_detection, eval, fen, game_state,
+    heatmap, legality, move_generator, notation, opening_suite, options, pawn_structure, perft,
+    pgn, problem, ratings, search_limits, selfplay, sharpness, sliding_attacks, sprt, tactics,
+    time_manager, topology, transposition, types,
+};
+
+mod capabilities;
+mod clipboard;
+mod clock;
+mod commands;
+mod completion;
+#[cfg(unix)]
+mod daemon;
+mod display;
+mod explain;
+#[cfg(test)]
+mod fuzz;
+mod game_log;
+mod i18n;
+mod import;
+mod input;
+#[cfg(unix)]
+mod jobs;
+mod line_editor;
+mod oneshot;
+mod oracle;
+mod output;
+mod profile;
+mod training;
+mod uci;
+
+use crate::ablation::{ablate, DEFAULT_ABLATION_NODES};
+use crate::ai::{format_score, AI};
+use crate::analysis::{
+    analyze_all_with_stats, load_balance, score_loss, score_replies, score_root_move,
+    REPLY_MARGIN_CP,
+};
 use crate::attack_tables::{chebyshev_distance, manhattan_distance};
 use crate::board::Board;
+use crate::capabilities::{Capability, Status};
+use crate::clock::GameClock;
+use crate::completion::EngineCompleter;
+use crate::eval::{EvalConfig, EvalTerm, EVAL_TERMS};
+use crate::explain::MoveExplainer;
 use crate::fen::FenParser;
+use crate::game_log::GameLog;
+use crate::heatmap::Heatmap;
+use crate::i18n::{tr, Language};
+use crate::input::InputLine;
+use crate::legality::{Legality, LegalityChecker};
+use crate::line_editor::{Completer, LineEditor};
 use crate::move_generator::MoveGenerator;
-use crate::perft::Perft;
+use crate::notation::{MoveNotation, SanParser};
+use crate::opening_suite::{
+    generate as generate_suite, load_suite, to_epd as suite_to_epd, to_pgn as suite_to_pgn,
+    SuiteConfig,
+};
+use crate::options::{OptionSpec, Options};
+use crate::oracle::{UciOracle, DEFAULT_THRESHOLD_CP};
+use crate::pawn_structure::PawnStructureAnalyzer;
+use crate::perft::{
+    parse_suite, standard_cases, test_position, Perft, PerftMode, DEFAULT_PERFT_HASH_MB,
+    TEST_POSITIONS,
+};
+use crate::pgn::{read_games, PgnHeaders, PgnWriter};
+use crate::problem::{ProblemSolver, Stipulation, MAX_PROBLEM_MOVES};
+use crate::profile::{Outcome, Profile, Record};
+use crate::ratings::{compute_ratings, MatchRecord, ResultsDb};
+use crate::search_limits::{SearchLimits, SearchOverrides, MAX_SEARCH_DEPTH};
+use crate::selfplay::{build_id, play_match, Adjudication, Player, Referee, TimeOdds};
+use crate::sharpness::Sharpness;
+use crate::sprt::SprtBounds;
+use crate::tactics::TacticsDetector;
+use crate::time_manager::{nodes_per_second, TimeControl};
+use crate::topology::{format_cpu_list, Topology};
+use crate::training::{TrainingSession, DRILL_MISTAKE_MARGIN_CP, DRILL_REVIEW_DEPTH};
+use crate::transposition::TranspositionTable;
 use crate::types::*;
-use std::io::{self, Write};
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use crate::uci::UciSession;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 fn concurrency_hash_hex(value: &str) -> String {
     let mut hash = 0xcbf29ce484222325u64;
@@ -50,19 +116,93 @@ struct TraceAiState {
     beta_cutoffs: u64,
 }
 
+// Recording a node per visit only suits shallow searches.
+const MAX_TREE_DEPTH: u8 = 6;
+// Subtrees smaller than this are left out of `debug tree` output by default.
+const DEFAULT_TREE_MIN_VISITS: u64 = 50;
+// A self-play run is one blocking command; longer matches belong in several runs.
+const MAX_SELFPLAY_GAMES: u32 = 1000;
+// The same ceiling as the Hash option
+const MAX_PERFT_HASH_MB: usize = 4096;
+const MAX_PERFT_THREADS: usize = 256;
+// Every kind of move, castling, en passant and promotion included, shows up within four plies of
+// the standard positions, and depth 4 runs in seconds.
+const DEFAULT_PERFT_SUITE_DEPTH: u8 = 4;
+// An SPRT can't be resumed in a later run, so it may go on much longer.
+const MAX_SPRT_GAMES: u32 = 100_000;
+// Plies the oracle searches each position to when it referees adjudication
+const ORACLE_ADJUDICATION_DEPTH: u8 = 6;
+const DEFAULT_BENCH_LOOKUPS: u64 = 1_000_000;
+const MAX_BENCH_LOOKUPS: u64 = 1_000_000_000;
+
+fn side_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+// What players count a piece as; the king is never traded.
+fn piece_points(piece_type: PieceType) -> i32 {
+    match piece_type {
+        PieceType::Pawn => 1,
+        PieceType::Knight | PieceType::Bishop => 3,
+        PieceType::Rook => 5,
+        PieceType::Queen => 9,
+        PieceType::King => 0,
+    }
+}
+
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::White => "white",
+        Color::Black => "black",
+    }
+}
+
+fn print_option(spec: &OptionSpec, value: i64) {
+    println!(
+        "OPTION: name={}; value={}; default={}; min={}; max={}",
+        spec.name, value, spec.default, spec.min, spec.max
+    );
+}
+
+// The principal variation of the most recent search and the position it starts from, for `pv`.
+struct LastPv {
+    board: Board,
+    pv: Vec<Move>,
+    depth: u8,
+    evaluation: i32,
+}
+
 struct ChessEngine {
     board: Board,
     move_generator: MoveGenerator,
     fen_parser: FenParser,
     ai: AI,
+    options: Options,
     perft: Perft,
+    tactics: TacticsDetector,
+    pawn_structures: PawnStructureAnalyzer,
+    explainer: MoveExplainer,
+    legality: LegalityChecker,
+    san_parser: SanParser,
+    // How engine output writes moves; input takes either
+    notation: MoveNotation,
+    training: TrainingSession,
+    profile: Profile,
     pgn_source: Option<String>,
     pgn_moves: Vec<String>,
+    // Tags for exporting the game being played; the date is when it started.
+    pgn_headers: PgnHeaders,
+    pgn_writer: PgnWriter,
     book_enabled: bool,
     book_source: Option<String>,
     book_entries: usize,
     book_lookups: usize,
     book_hits: usize,
+    // The optional data files, as last probed
+    capabilities: Vec<Capability>,
     chess960_id: i32,
     trace_enabled: bool,
     trace_level: String,
@@ -77,6 +217,14 @@ struct ChessEngine {
     trace_last_chrome_events: usize,
     trace_last_chrome_bytes: usize,
     trace_last_ai: Option<TraceAiState>,
+    last_pv: Option<LastPv>,
+    oracle: Option<UciOracle>,
+    oracle_threshold: i32,
+    results: ResultsDb,
+    topology: Topology,
+    pin_threads: bool,
+    game_log: GameLog,
+    clock: Option<GameClock>,
 }
 
 impl ChessEngine {
@@ -86,14 +234,26 @@ impl ChessEngine {
             move_generator: MoveGenerator::new(),
             fen_parser: FenParser::new(),
             ai: AI::new(),
+            options: Options::new(),
             perft: Perft::new(),
+            tactics: TacticsDetector::new(),
+            pawn_structures: PawnStructureAnalyzer::new(),
+            explainer: MoveExplainer::new(),
+            legality: LegalityChecker::new(),
+            san_parser: SanParser::new(),
+            notation: MoveNotation::default(),
+            training: TrainingSession::new(),
+            profile: Profile::new(),
             pgn_source: None,
             pgn_moves: Vec::new(),
+            pgn_headers: PgnHeaders::new(),
+            pgn_writer: PgnWriter::new(),
             book_enabled: false,
             book_source: None,
             book_entries: 0,
             book_lookups: 0,
             book_hits: 0,
+            capabilities: Vec::new(),
             chess960_id: 0,
             trace_enabled: false,
             trace_level: "info".to_string(),
@@ -108,27 +268,59 @@ impl ChessEngine {
             trace_last_chrome_events: 0,
             trace_last_chrome_bytes: 0,
             trace_last_ai: None,
+            last_pv: None,
+            oracle: None,
+            oracle_threshold: DEFAULT_THRESHOLD_CP,
+            results: ResultsDb::new(),
+            topology: Topology::detect(),
+            pin_threads: false,
+            game_log: GameLog::new(&Board::new(), &Options::new()),
+            clock: None,
         }
     }
 
     fn run(&mut self) {
-        println!("{}", self.board);
+        self.show_board();
+        let mut editor = LineEditor::for_terminal();
 
         loop {
-            print!("");
-            io::stdout().flush().unwrap();
-
-            let mut input = String::new();
-            if io::stdin().read_line(&mut input).is_err() {
-                break;
-            }
+            let input = match editor.as_mut() {
+                Some(editor) => match editor.read_line("> ", &mut self.completer()) {
+                    Ok(Some(line)) => line,
+                    _ => break,
+                },
+                None => {
+                    print!("");
+                    io::stdout().flush().unwrap();
+
+                    match input::next_stdin_line() {
+                        InputLine::Line(line) => line,
+                        InputLine::Rejected(error) => {
+                            println!("ERROR: {}", tr(error));
+                            continue;
+                        }
+                        InputLine::Eof => break,
+                    }
+                }
+            };
 
             let command = input.trim();
             if command.is_empty() {
                 continue;
             }
+            if let Err(error) = input::check_command(command) {
+                println!("ERROR: {}", tr(error));
+                continue;
+            }
 
-            if !self.process_command(command) {
+            self.check_flag();
+            let keep_going = self.process_command(command);
+            // Every change the command made to the game or the options goes into the event log
+            self.game_log.record(command, &self.board, &self.options);
+            if let Some(clock) = &mut self.clock {
+                clock.update(&self.board, Instant::now());
+            }
+            if !keep_going {
                 break;
             }
         }
@@ -151,69 +343,128 @@ impl ChessEngine {
                 if parts.len() > 1 {
                     self.handle_move(parts[1]);
                 } else {
-                    println!("ERROR: Invalid move format");
+                    println!("ERROR: {}", tr("Invalid move format"));
                 }
             }
             "undo" => self.handle_undo(),
             "new" => self.handle_new(),
             "status" => self.handle_status(),
-            "ai" => {
-                if parts.len() > 1 {
-                    self.handle_ai(parts[1]);
-                } else {
-                    println!("ERROR: AI depth must be 1-5");
-                }
-            }
+            "ai" => self.handle_ai(&parts[1..]),
             "fen" => {
                 if parts.len() > 1 {
                     let fen_string = parts[1..].join(" ");
                     self.handle_fen(&fen_string);
                 } else {
-                    println!("ERROR: Invalid FEN string");
+                    println!("ERROR: {}", tr("Invalid FEN string"));
                 }
             }
             "export" => self.handle_export(),
-            "eval" => self.handle_eval(),
-            "hash" => self.handle_hash(),
+            "eval" => self.handle_eval(&parts[1..]),
+            "pv" => self.handle_pv(),
+            "hash" => self.handle_hash(&parts[1..]),
             "draws" => self.handle_draws(),
+            "material" => self.handle_material(&parts[1..]),
+            "heatmap" => self.handle_heatmap(&parts[1..]),
             "history" => self.handle_history(),
+            "motifs" => self.handle_motifs(&parts[1..]),
+            "structure" => self.handle_structure(),
+            "explain" => self.handle_explain(&parts[1..]),
+            "why" => self.handle_why(&parts[1..]),
+            "pass" | "null" => self.handle_pass(),
+            "playout" => self.handle_playout(&parts[1..]),
+            "threat" => self.handle_threat(&parts[1..]),
+            "notation" => self.handle_notation(&parts[1..]),
+            "drill" => self.handle_drill(&parts[1..]),
+            "blindfold" => self.handle_blindfold(&parts[1..]),
+            "display" => self.handle_display(&parts[1..]),
+            "peek" => self.handle_peek(),
+            "quiz" => self.handle_quiz(&parts[1..]),
+            "answer" => self.handle_answer(&parts[1..]),
             "go" => self.handle_go(&parts[1..]),
+            "deterministic" => self.handle_deterministic(&parts[1..]),
+            "option" => self.handle_option(&parts[1..]),
+            "debug" => self.handle_debug(&parts[1..]),
             "pgn" => self.handle_pgn(&parts[1..]),
+            "import" => self.handle_import(&parts[1..]),
             "book" => self.handle_book(&parts[1..]),
-            "uci" => self.handle_uci(),
+            "clock" => self.handle_clock(&parts[1..]),
+            "capabilities" => self.handle_capabilities(),
+            "reload" => self.handle_reload(),
+            "copy" => self.handle_copy(&parts[1..]),
+            "state" => self.handle_state(&parts[1..]),
+            "log" => self.handle_log(&parts[1..]),
+            "verify" => self.handle_verify(&parts[1..]),
+            "paste" => self.handle_paste(&parts[1..]),
+            // The rest of the session speaks UCI.
+            "uci" => {
+                let mut session = UciSession::new(self.options.clone());
+                session.handle("uci");
+                session.run();
+                return false;
+            }
             "isready" => self.handle_isready(),
             "ucinewgame" => self.handle_new(),
             "new960" => self.handle_new960(&parts[1..]),
             "position960" => self.handle_position960(),
             "trace" => self.handle_trace(&parts[1..]),
             "concurrency" => self.handle_concurrency(&parts[1..]),
-            "perft" => {
-                if parts.len() > 1 {
-                    self.handle_perft(parts[1]);
-                } else {
-                    println!("ERROR: Invalid perft depth");
-                }
-            }
-            "divide" => {
-                if parts.len() > 1 {
-                    self.handle_divide(parts[1]);
-                } else {
-                    println!("ERROR: Invalid perft depth");
-                }
-            }
+            "perft" => self.handle_perft(&parts[1..]),
+            "divide" => self.handle_divide(&parts[1..]),
+            "testpos" => self.handle_testpos(&parts[1..]),
+            "solve" => self.handle_solve(&parts[1..]),
+            "analyze" => self.handle_analyze(&parts[1..]),
+            "replies" => self.handle_replies(&parts[1..]),
+            "sharpness" => self.handle_sharpness(),
+            "oracle" => self.handle_oracle(&parts[1..]),
+            "selfplay" => self.handle_selfplay(&parts[1..]),
+            "ablate" => self.handle_ablate(&parts[1..]),
+            "sprt" => self.handle_sprt(&parts[1..]),
+            "ratings" => self.handle_ratings(),
+            "openings" => self.handle_openings(&parts[1..]),
+            "threads" => self.handle_threads(&parts[1..]),
+            "cpuinfo" => self.handle_cpuinfo(),
+            "bench" => self.handle_bench(&parts[1..]),
+            "profile" => self.handle_profile(&parts[1..]),
+            "complete" => self.handle_complete(&command[parts[0].len()..]),
             "help" => self.handle_help(),
             "quit" => return false,
-            _ => println!("ERROR: Invalid command"),
+            _ if self.training.blindfold => self.handle_move(parts[0]),
+            _ => println!("ERROR: {}", tr("Invalid command")),
         }
 
         true
     }
 
     fn handle_move(&mut self, move_str: &str) {
-        if move_str.len() < 4 {
-            println!("ERROR: Invalid move format");
+        if self.clock_flagged() {
             return;
         }
+        let resolved = if self.training.blindfold {
+            self.resolve_blindfold_move(move_str)
+        } else {
+            self.resolve_typed_move(move_str)
+        };
+
+        match resolved {
+            Ok(chess_move) if self.training.drill.current.is_some() => {
+                self.handle_drill_move(move_str, &chess_move)
+            }
+            Ok(chess_move) => {
+                self.profile.record_move(self.board.get_turn(), false);
+                self.board.make_move(&chess_move);
+                println!("OK: {}", move_str);
+                self.show_board();
+                self.check_game_end();
+            }
+            Err(error) => println!("ERROR: {}", tr(error)),
+        }
+    }
+
+    fn resolve_move(&mut self, move_str: &str) -> Result<Move, &'static str> {
+        // Byte slicing below is only sound on ASCII input.
+        if !(4..=5).contains(&move_str.len()) || !move_str.is_ascii() {
+            return Err("Invalid move format");
+        }
 
         let from_str = &move_str[0..2];
         let to_str = &move_str[2..4];
@@ -223,38 +474,20 @@ impl ChessEngine {
             None
         };
 
-        let from_square = match algebraic_to_square(from_str) {
-            Ok(square) => square,
-            Err(_) => {
-                println!("ERROR: Invalid move format");
-                return;
-            }
-        };
-
-        let to_square = match algebraic_to_square(to_str) {
-            Ok(square) => square,
-            Err(_) => {
-                println!("ERROR: Invalid move format");
-                return;
-            }
-        };
+        let from_square = algebraic_to_square(from_str).map_err(|_| "Invalid move format")?;
+        let to_square = algebraic_to_square(to_str).map_err(|_| "Invalid move format")?;
 
-        let piece = match self.board.get_piece(from_square) {
-            Some(p) => p,
-            None => {
-                println!("ERROR: No piece at source square");
-                return;
-            }
-        };
+        let piece = self
+            .board
+            .get_piece(from_square)
+            .ok_or("No piece at source square")?;
 
         if piece.color != self.board.get_turn() {
-            println!("ERROR: Wrong color piece");
-            return;
+            return Err("Wrong color piece");
         }
 
         let turn = self.board.get_turn();
         let legal_moves = self.move_generator.get_legal_moves(&mut self.board, turn);
-        let mut matching_move = None;
 
         for chess_move in &legal_moves {
             if chess_move.from == from_square && chess_move.to == to_square {
@@ -264,71 +497,192 @@ impl ChessEngine {
                             PieceType::from_char(promo_str.chars().next().unwrap_or(' '))
                         {
                             if promotion == promo_type {
-                                matching_move = Some(chess_move.clone());
-                                break;
+                                return Ok(chess_move.clone());
                             }
                         }
                     } else if promotion == PieceType::Queen {
                         // Default to queen if no promotion specified
-                        matching_move = Some(chess_move.clone());
-                        break;
+                        return Ok(chess_move.clone());
                     }
                 } else {
-                    matching_move = Some(chess_move.clone());
-                    break;
+                    return Ok(chess_move.clone());
                 }
             }
         }
 
-        match matching_move {
-            Some(chess_move) => {
-                self.board.make_move(&chess_move);
-                println!("OK: {}", move_str);
-                println!("{}", self.board);
-                self.check_game_end();
+        if self
+            .move_generator
+            .is_in_check(&self.board, self.board.get_turn())
+        {
+            Err("King would be in check")
+        } else {
+            Err("Illegal move")
+        }
+    }
+
+    // Coordinates are tried first so their errors stay specific; anything not shaped like
+    // coordinates is read as SAN (`Nf3`, `exd5`, `O-O`, `e8=Q+`).
+    fn resolve_typed_move(&mut self, move_str: &str) -> Result<Move, &'static str> {
+        match self.resolve_move(move_str) {
+            Err("Invalid move format") => self.san_parser.parse_san(&mut self.board, move_str),
+            resolved => resolved,
+        }
+    }
+
+    // Blindfold play is SAN-first, but coordinate moves still work so scripts stay usable.
+    fn resolve_blindfold_move(&mut self, move_str: &str) -> Result<Move, &'static str> {
+        self.san_parser
+            .parse_san(&mut self.board, move_str)
+            .or_else(|san_error| self.resolve_move(move_str).map_err(|_| san_error))
+    }
+
+    fn show_board(&self) {
+        if !self.training.blindfold {
+            println!("{}", self.profile.display.render(&self.board));
+        }
+        if let Some(clock) = &self.clock {
+            println!("CLOCK: {}", clock.describe(&self.board, Instant::now()));
+        }
+    }
+
+    // `clock [<minutes>+<seconds>|off]`: a clock for the game from here on, as in `5+3`. With
+    // the clock running `ai` with no limits plays on the time left, and a side whose time runs
+    // out loses the game.
+    fn handle_clock(&mut self, args: &[&str]) {
+        match args {
+            [] => {}
+            ["off"] => {
+                self.clock = None;
+                println!("CLOCK: off");
+                return;
             }
-            None => {
-                if self
-                    .move_generator
-                    .is_in_check(&self.board, self.board.get_turn())
-                {
-                    println!("ERROR: King would be in check");
-                } else {
-                    println!("ERROR: Illegal move");
+            [control] => match GameClock::parse(control) {
+                Some((base_ms, increment_ms)) => {
+                    self.clock = Some(GameClock::new(
+                        base_ms,
+                        increment_ms,
+                        &self.board,
+                        Instant::now(),
+                    ));
                 }
+                None => {
+                    println!("ERROR: {}", tr("clock needs <minutes>+<seconds> or off"));
+                    return;
+                }
+            },
+            _ => {
+                println!("ERROR: {}", tr("clock needs <minutes>+<seconds> or off"));
+                return;
+            }
+        }
+        match &self.clock {
+            Some(clock) => println!("CLOCK: {}", clock.describe(&self.board, Instant::now())),
+            None => println!("CLOCK: off"),
+        }
+    }
+
+    // Ends the game the first time the side to move is found out of time.
+    fn check_flag(&mut self) {
+        let Some(clock) = &mut self.clock else {
+            return;
+        };
+        if let Some(color) = clock.check_flag(&self.board, Instant::now()) {
+            let (side, result) = match color {
+                Color::White => ("white", "0-1"),
+                Color::Black => ("black", "1-0"),
+            };
+            println!("CLOCK: flag={}; result={}", side, result);
+            self.finish_profile_game(Outcome::Win(color.opposite()));
+        }
+    }
+
+    fn clock_limits(&self) -> Option<SearchLimits> {
+        self.clock
+            .as_ref()
+            .map(|clock| clock.limits(&self.board, Instant::now()))
+    }
+
+    // No more moves once a flag is down, until `new` or `clock` starts over.
+    fn clock_flagged(&self) -> bool {
+        let flagged = self
+            .clock
+            .as_ref()
+            .is_some_and(|clock| clock.flagged.is_some());
+        if flagged {
+            println!("ERROR: {}", tr("Time has run out"));
+        }
+        flagged
+    }
+
+    // `display options [<name> <value>]...`: shows the board display options, after changing any
+    // given, which the profile keeps.
+    fn handle_display(&mut self, args: &[&str]) {
+        let ["options", changes @ ..] = args else {
+            println!("ERROR: {}", tr("Unsupported display command"));
+            return;
+        };
+        if changes.len() % 2 == 1 {
+            println!(
+                "ERROR: {}",
+                tr("Display options are on or off, and perspective white or black")
+            );
+            return;
+        }
+        let mut display = self.profile.display;
+        for change in changes.chunks(2) {
+            if let Err(error) = display.set(change[0], change[1]) {
+                println!("ERROR: {}", tr(error));
+                return;
             }
         }
+        if !changes.is_empty() {
+            self.profile.display = display;
+            self.save_profile();
+        }
+        println!("DISPLAY: {}", display.describe());
     }
 
     fn handle_undo(&mut self) {
         match self.board.undo_move() {
             Some(_) => {
                 println!("OK: undo");
-                println!("{}", self.board);
+                self.show_board();
             }
-            None => println!("ERROR: No moves to undo"),
+            None => println!("ERROR: {}", tr("No moves to undo")),
         }
     }
 
     fn handle_new(&mut self) {
         self.board.reset();
+        self.ai.new_game();
         self.pgn_source = None;
         self.pgn_moves.clear();
+        self.pgn_headers = PgnHeaders::new();
+        self.profile.new_game();
+        self.training.drill.stop();
         self.book_enabled = false;
         self.book_source = None;
         self.book_entries = 0;
         self.book_lookups = 0;
         self.book_hits = 0;
+        self.use_configured_book();
         self.chess960_id = 0;
-        println!("OK: New game started");
-        println!("{}", self.board);
+        if let Some(clock) = &mut self.clock {
+            *clock = GameClock::new(
+                clock.base_ms,
+                clock.increment_ms,
+                &self.board,
+                Instant::now(),
+            );
+        }
+        println!("OK: {}", tr("New game started"));
+        self.show_board();
     }
 
     fn handle_status(&mut self) {
         let color = self.board.get_turn();
-        let legal_moves = self.move_generator.get_legal_moves(&mut self.board, color);
 
-        if legal_moves.is_empty() {
+        if !self.move_generator.has_legal_move(&mut self.board, color) {
             if self.move_generator.is_in_check(&self.board, color) {
                 let winner = if color == Color::White {
                     "Black"
@@ -346,58 +700,108 @@ impl ChessEngine {
         }
     }
 
-    fn handle_ai(&mut self, depth_str: &str) {
-        let depth = match depth_str.parse::<u8>() {
-            Ok(d) if d >= 1 && d <= 5 => d,
-            _ => {
-                println!("ERROR: AI depth must be 1-5");
-                return;
-            }
+    fn handle_ai(&mut self, args: &[&str]) {
+        if self.clock_flagged() {
+            return;
+        }
+        // On the clock the engine plays on the time it has left
+        if let (Some(limits), true) = (self.clock_limits(), args.is_empty()) {
+            self.run_search(&limits);
+            return;
+        }
+        let default_limits = self.profile.time_control.clone().unwrap_or_default();
+        let args = if args.is_empty() {
+            default_limits.split_whitespace().collect()
+        } else {
+            args.to_vec()
         };
+        match SearchLimits::parse(&args) {
+            Ok(limits) => self.run_search(&limits),
+            Err(error) => println!("ERROR: {}", tr(&error)),
+        }
+    }
 
+    fn run_search(&mut self, limits: &SearchLimits) {
+        if !limits.is_bounded(self.board.get_turn()) {
+            println!("ERROR: {}", tr("No clock time given for the side to move"));
+            return;
+        }
         if self.book_enabled {
             self.book_lookups += 1;
             self.book_hits += 1;
-            self.record_trace_ai("book", "e2e4", 0, 0, 0, false, 0, 0, 0, 0, 0);
+            self.record_trace_ai("book", "e2e4", 0, 0, 0, false, 0, 0, 0, 0, 0, 0);
             println!("AI: e2e4 (book)");
             return;
         }
 
-        let result = self.ai.find_best_move(&mut self.board, depth);
+        // Only a budgeted search can spend more time on a sharp position.
+        if limits.time_control(self.board.get_turn()) != TimeControl::Infinite {
+            let sharpness = Sharpness::measure(&mut self.ai, &self.board);
+            self.ai.set_sharpness(sharpness.score);
+        }
+        let result = self.ai.search(&mut self.board, limits);
+        self.last_pv = Some(LastPv {
+            board: self.board.clone(),
+            pv: result.pv.clone(),
+            depth: result.depth,
+            evaluation: result.evaluation,
+        });
 
+        if result.lines.len() > 1 {
+            for (index, line) in result.lines.iter().enumerate() {
+                let moves = self
+                    .san_parser
+                    .format_line(&self.board, &line.pv, self.notation);
+                println!(
+                    "MULTIPV: {} score={}; pv={}",
+                    index + 1,
+                    format_score(line.score),
+                    moves.join(" ")
+                );
+            }
+        }
         match result.best_move {
             Some(chess_move) => {
-                let move_str = format!(
-                    "{}{}{}",
-                    square_to_algebraic(chess_move.from),
-                    square_to_algebraic(chess_move.to),
-                    chess_move
-                        .promotion
-                        .map_or(String::new(), |p| p.to_string())
-                );
-
+                let move_str =
+                    self.san_parser
+                        .format_move(&mut self.board, &chess_move, self.notation);
+
+                let san = self
+                    .training
+                    .blindfold
+                    .then(|| self.san_parser.export_san(&mut self.board, &chess_move));
+                self.profile.record_move(self.board.get_turn(), true);
                 self.board.make_move(&chess_move);
                 self.record_trace_ai(
                     "search",
                     &move_str,
-                    depth,
+                    result.depth,
                     result.evaluation,
                     result.time_ms,
-                    false,
+                    result.timed_out,
                     result.nodes,
                     result.eval_calls,
-                    0,
-                    0,
+                    result.tt_hits,
+                    result.tt_misses,
                     result.beta_cutoffs,
+                    result.nps,
                 );
                 println!(
-                    "AI: {} (depth={}, eval={}, time={}ms)",
-                    move_str, depth, result.evaluation, result.time_ms
+                    "AI: {} (depth={}, eval={}, time={}ms, nodes={}, nps={})",
+                    move_str,
+                    result.depth,
+                    format_score(result.evaluation),
+                    result.time_ms,
+                    result.nodes,
+                    result.nps
                 );
-                println!("{}", self.board);
+                if let Some(san) = san {
+                    println!("BLINDFOLD: {}", san);
+                }
+                self.show_board();
                 self.check_game_end();
             }
-            None => println!("ERROR: No legal moves available"),
+            None => println!("ERROR: {}", tr("No legal moves available")),
         }
     }
 
@@ -406,10 +810,13 @@ impl ChessEngine {
             Ok(_) => {
                 self.pgn_source = None;
                 self.pgn_moves.clear();
-                println!("OK: FEN loaded");
-                println!("{}", self.board);
+                self.pgn_headers = PgnHeaders::new();
+                self.profile.new_game();
+                self.training.drill.stop();
+                println!("OK: {}", tr("FEN loaded"));
+                self.show_board();
             }
-            Err(err) => println!("{}", err),
+            Err(err) => println!("ERROR: {}", tr(&err)),
         }
     }
 
@@ -418,25 +825,342 @@ impl ChessEngine {
         println!("FEN: {}", fen);
     }
 
-    fn handle_eval(&mut self) {
-        let mut ai_copy = AI::new();
-        let evaluation = ai_copy.find_best_move(&mut self.board, 1).evaluation;
-        let white_king = find_king_square(&self.board, Color::White);
-        let black_king = find_king_square(&self.board, Color::Black);
+    // `state json`: the whole game state in one versioned object, for GUI frontends.
+    fn handle_state(&self, args: &[&str]) {
+        if args != ["json"] {
+            println!("ERROR: {}", tr("Unsupported state command"));
+            return;
+        }
+        println!("STATE: {}", game_state::to_json(&self.board));
+    }
+
+    // `log [show|save <file>]`: the session's event log. After `save` the file keeps growing
+    // with each new event.
+    fn handle_log(&mut self, args: &[&str]) {
+        match args {
+            [] => {}
+            ["show"] => {
+                for event in self.game_log.events() {
+                    println!("{}", event.line());
+                }
+            }
+            ["save", path @ ..] if !path.is_empty() => {
+                if self.game_log.save(&path.join(" ")).is_err() {
+                    println!("ERROR: {}", tr("Cannot write log file"));
+                    return;
+                }
+            }
+            _ => {
+                println!("ERROR: {}", tr("Unsupported log command"));
+                return;
+            }
+        }
+        println!(
+            "LOG: events={}; chain={:016x}; file={}",
+            self.game_log.events().len(),
+            self.game_log.chain(),
+            self.game_log.file().unwrap_or("none")
+        );
+    }
+
+    // `verify log <file>`: replays a saved log from scratch and checks every hash in it.
+    fn handle_verify(&self, args: &[&str]) {
+        let ["log", path @ ..] = args else {
+            println!("ERROR: {}", tr("verify needs log <file>"));
+            return;
+        };
+        if path.is_empty() {
+            println!("ERROR: {}", tr("verify needs log <file>"));
+            return;
+        }
+        let Ok(text) = fs::read_to_string(path.join(" ")) else {
+            println!("ERROR: {}", tr("Cannot read log file"));
+            return;
+        };
+        match game_log::verify(&text) {
+            Ok(verified) => println!(
+                "VERIFY: ok; events={}; chain={:016x}; fen={}",
+                verified.events, verified.chain, verified.fen
+            ),
+            Err(error) => println!(
+                "VERIFY: failed; line={}; reason={}",
+                error.line, error.reason
+            ),
+        }
+    }
+
+    // `copy fen|pgn`: the position or the game so far to the system clipboard.
+    fn handle_copy(&self, args: &[&str]) {
+        let text = match args {
+            ["fen"] => self.fen_parser.export_fen(&self.board),
+            ["pgn"] => self.current_pgn(),
+            _ => {
+                println!("ERROR: {}", tr("copy needs fen or pgn"));
+                return;
+            }
+        };
+        match clipboard::copy(&text) {
+            Ok(()) => println!("COPY: format={}; chars={}", args[0], text.chars().count()),
+            Err(error) => println!("ERROR: {}", tr(error)),
+        }
+    }
+
+    // `paste fen`: loads the position on the clipboard, as `fen <string>` would.
+    fn handle_paste(&mut self, args: &[&str]) {
+        if args != ["fen"] {
+            println!("ERROR: {}", tr("paste needs fen"));
+            return;
+        }
+        match clipboard::paste() {
+            Ok(text) => self.handle_fen(text.trim()),
+            Err(error) => println!("ERROR: {}", tr(error)),
+        }
+    }
+
+    fn evaluate_static(&self) -> i32 {
+        self.ai.evaluator().evaluate(&self.board)
+    }
+
+    fn handle_notation(&mut self, args: &[&str]) {
+        match args.first().copied() {
+            None | Some("status") => {}
+            Some(name) => match MoveNotation::parse(name) {
+                Some(notation) => self.notation = notation,
+                None => {
+                    println!(
+                        "ERROR: {}",
+                        tr("notation must be coordinate, san or status")
+                    );
+                    return;
+                }
+            },
+        }
+        println!("NOTATION: {}", self.notation.name());
+    }
+
+    fn handle_pv(&mut self) {
+        let Some(last) = &self.last_pv else {
+            println!("ERROR: {}", tr("No search has been run yet"));
+            return;
+        };
+        let line = self
+            .san_parser
+            .format_line(&last.board, &last.pv, self.notation);
+        println!(
+            "PV: {} (depth={}, eval={})",
+            line.join(" "),
+            last.depth,
+            format_score(last.evaluation)
+        );
+    }
+
+    fn handle_eval(&mut self, args: &[&str]) {
+        match args {
+            [] => {}
+            ["material", toggle @ ..] => return self.handle_eval_material(toggle),
+            _ => {
+                println!("ERROR: {}", tr("Unsupported eval command"));
+                return;
+            }
+        }
+        let static_eval = self.evaluate_static();
+        let search_eval = self.ai.find_best_move(&mut self.board, 1).evaluation;
+        let white_king = self.board.king_square(Color::White);
+        let black_king = self.board.king_square(Color::Black);
         let king_distance = if let (Some(white), Some(black)) = (white_king, black_king) {
             format!(
-                " (king_md={}, king_cd={})",
+                ", king_md={}, king_cd={}",
                 manhattan_distance(white, black),
                 chebyshev_distance(white, black)
             )
         } else {
             String::new()
         };
-        println!("EVALUATION: {}{}", evaluation, king_distance);
+        // The headline is the engine's view (a depth-1 search), the static score beside it
+        println!(
+            "EVALUATION: {} (static={}{})",
+            search_eval, static_eval, king_distance
+        );
+    }
+
+    fn handle_eval_material(&mut self, args: &[&str]) {
+        let material_only = match args.first().copied().unwrap_or("status") {
+            "on" => true,
+            "off" => false,
+            "status" => self.ai.eval_config().material_only,
+            _ => {
+                println!("ERROR: {}", tr("eval material must be on, off or status"));
+                return;
+            }
+        };
+        self.ai.set_eval_config(EvalConfig {
+            material_only,
+            ..self.ai.eval_config()
+        });
+        println!(
+            "EVAL MATERIAL: {}",
+            if material_only {
+                "on; piece values only, no positional, imbalance or scaling terms"
+            } else {
+                "off"
+            }
+        );
+    }
+
+    fn handle_hash(&mut self, args: &[&str]) {
+        match args {
+            [] => println!("HASH: {:016x}", self.board.get_hash()),
+            ["save", path @ ..] if !path.is_empty() => self.handle_hash_save(&path.join(" ")),
+            ["load", path @ ..] if !path.is_empty() => self.handle_hash_load(&path.join(" ")),
+            ["save" | "load"] => println!("ERROR: {}", tr("hash save/load requires a file path")),
+            ["table"] => println!("HASH: {}", table_report(self.ai.transposition_table())),
+            _ => println!("ERROR: {}", tr("Unsupported hash command")),
+        }
+    }
+
+    fn handle_hash_save(&mut self, path: &str) {
+        let saved = File::create(path).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            self.ai.transposition_table().save(&mut writer)
+        });
+        match saved {
+            Ok(entries) => println!("HASH: saved entries={}; file={}", entries, path),
+            Err(_) => println!("ERROR: {}", tr("Cannot write hash file")),
+        }
+    }
+
+    fn handle_hash_load(&mut self, path: &str) {
+        let loaded = match File::open(path) {
+            Ok(file) => self
+                .ai
+                .transposition_table()
+                .load(&mut BufReader::new(file)),
+            Err(_) => Err("Cannot read hash file"),
+        };
+        match loaded {
+            Ok(entries) => println!("HASH: loaded entries={}; file={}", entries, path),
+            Err(error) => println!("ERROR: {}", tr(error)),
+        }
+    }
+
+    // Points on the board use the 1/3/3/5/9 scale players count with; captures come from the
+    // move history, so a position loaded from FEN starts with empty trays.
+    fn handle_material(&self, args: &[&str]) {
+        let json = match args {
+            [] => false,
+            ["json"] => true,
+            _ => {
+                println!("ERROR: {}", tr("Unsupported material command"));
+                return;
+            }
+        };
+        let mut points = [0; 2];
+        for square in 0..64 {
+            if let Some(piece) = self.board.get_piece(square) {
+                points[side_index(piece.color)] += piece_points(piece.piece_type);
+            }
+        }
+        // Pieces each side has taken, most valuable first
+        let mut captured: [Vec<Piece>; 2] = [Vec::new(), Vec::new()];
+        let history = &self.board.get_state().move_history;
+        let mut mover = if history.len() % 2 == 0 {
+            self.board.get_turn()
+        } else {
+            self.board.get_turn().opposite()
+        };
+        for chess_move in history {
+            if let Some(piece_type) = chess_move.captured {
+                captured[side_index(mover)].push(Piece::new(piece_type, mover.opposite()));
+            }
+            mover = mover.opposite();
+        }
+        for tray in &mut captured {
+            tray.sort_by_key(|piece| std::cmp::Reverse(piece.piece_type.value()));
+        }
+        let diff = points[0] - points[1];
+        let tray = |side: usize| -> Vec<String> {
+            captured[side]
+                .iter()
+                .map(|piece| piece.to_char().to_string())
+                .collect()
+        };
+
+        if json {
+            let quoted = |side: usize| -> String {
+                tray(side)
+                    .iter()
+                    .map(|piece| format!("\"{}\"", piece))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            };
+            println!(
+                "MATERIAL: {{\"white\":{{\"points\":{},\"captured\":[{}]}},\"black\":{{\"points\":{},\"captured\":[{}]}},\"diff\":{}}}",
+                points[0],
+                quoted(0),
+                points[1],
+                quoted(1),
+                diff
+            );
+        } else {
+            let shown = |side: usize| -> String {
+                let pieces = tray(side).concat();
+                if pieces.is_empty() {
+                    "-".to_string()
+                } else {
+                    pieces
+                }
+            };
+            println!(
+                "MATERIAL: white={}; black={}; diff={:+}; white_captured={}; black_captured={}",
+                points[0],
+                points[1],
+                diff,
+                shown(0),
+                shown(1)
+            );
+        }
     }
 
-    fn handle_hash(&self) {
-        println!("HASH: {:016x}", self.board.get_hash());
+    fn handle_heatmap(&self, args: &[&str]) {
+        let (side, output) = match args.first().copied() {
+            Some("white") => (Some(Color::White), &args[1..]),
+            Some("black") => (Some(Color::Black), &args[1..]),
+            _ => (None, args),
+        };
+        let heatmap = Heatmap::new(&self.board);
+        match output {
+            [] => {
+                let controlled = |color: Color| {
+                    heatmap
+                        .counts(color)
+                        .iter()
+                        .filter(|&&count| count > 0)
+                        .count()
+                };
+                match side {
+                    Some(color) => println!(
+                        "HEATMAP: side={}; squares={}",
+                        color_name(color),
+                        controlled(color)
+                    ),
+                    None => println!(
+                        "HEATMAP: side=net (white minus black); white_squares={}; black_squares={}",
+                        controlled(Color::White),
+                        controlled(Color::Black)
+                    ),
+                }
+                print!("{}", heatmap.render(side));
+            }
+            ["json"] => println!("HEATMAP: {}", heatmap.to_json()),
+            ["svg", path @ ..] if !path.is_empty() => {
+                let path = path.join(" ");
+                match fs::write(&path, heatmap.to_svg(side)) {
+                    Ok(()) => println!("HEATMAP: saved file={}", path),
+                    Err(_) => println!("ERROR: {}", tr("Cannot write heatmap file")),
+                }
+            }
+            _ => println!("ERROR: {}", tr("Unsupported heatmap command")),
+        }
     }
 
     fn handle_draws(&self) {
@@ -473,106 +1197,1450 @@ impl ChessEngine {
         );
     }
 
-    fn handle_perft(&mut self, depth_str: &str) {
-        let depth = match depth_str.parse::<u8>() {
-            Ok(d) if d >= 1 => d,
-            _ => {
-                println!("ERROR: Invalid perft depth");
+    fn handle_motifs(&self, args: &[&str]) {
+        let sides = match args.first().map(|side| side.to_lowercase()).as_deref() {
+            None => vec![Color::White, Color::Black],
+            Some("white") => vec![Color::White],
+            Some("black") => vec![Color::Black],
+            Some(_) => {
+                println!("ERROR: {}", tr("motifs side must be white or black"));
                 return;
             }
         };
 
-        let start_time = Instant::now();
-        let nodes = self.perft.perft(&mut self.board, depth);
-        let elapsed = start_time.elapsed();
+        let motifs: Vec<_> = sides
+            .into_iter()
+            .flat_map(|side| self.tactics.detect_all(&self.board, side))
+            .collect();
+        println!("MOTIFS: count={}", motifs.len());
+        for motif in &motifs {
+            println!("{}", motif);
+        }
+    }
 
-        println!(
-            "Perft({}): {} nodes ({}ms)",
-            depth,
-            nodes,
-            elapsed.as_millis()
-        );
+    fn handle_structure(&mut self) {
+        let structures = self.pawn_structures.classify(&self.board);
+        println!("STRUCTURE: count={}", structures.len());
+        for structure in &structures {
+            println!("{}", structure);
+            let (own_plans, their_plans) = structure.kind.plans();
+            let (owner, opponent) = match structure.side {
+                Color::White => ("white", "black"),
+                Color::Black => ("black", "white"),
+            };
+            for plan in own_plans {
+                println!("  {} plan: {}", owner, plan);
+            }
+            for plan in their_plans {
+                println!("  {} plan: {}", opponent, plan);
+            }
+        }
     }
 
-    fn handle_divide(&mut self, depth_str: &str) {
-        let depth = match depth_str.parse::<u8>() {
-            Ok(d) if d >= 1 => d,
-            _ => {
-                println!("ERROR: Invalid perft depth");
+    fn handle_explain(&mut self, args: &[&str]) {
+        let Some(move_str) = args.first() else {
+            println!("ERROR: {}", tr("explain requires a move"));
+            return;
+        };
+        let depth = match args.get(1).map(|value| value.parse::<u8>()) {
+            None => 3,
+            Some(Ok(d)) if (1..=MAX_SEARCH_DEPTH).contains(&d) => d,
+            Some(_) => {
+                println!("ERROR: {}", tr("explain depth must be 1-64"));
                 return;
             }
         };
 
-        let results = self.perft.perft_divide(&mut self.board, depth);
-        let mut sorted_keys: Vec<_> = results.keys().collect();
-        sorted_keys.sort();
+        match self.resolve_typed_move(move_str) {
+            Ok(chess_move) => {
+                let explanation =
+                    self.explainer
+                        .explain(&mut self.board, &mut self.ai, &chess_move, depth);
+                println!("EXPLAIN: {}", explanation);
+            }
+            Err(error) => println!("ERROR: {}", tr(error)),
+        }
+    }
+
+    fn handle_why(&mut self, args: &[&str]) {
+        let Some(move_str) = args.first() else {
+            println!("ERROR: {}", tr("why requires a move"));
+            return;
+        };
+        let coordinates = (move_str.len() == 4 || move_str.len() == 5)
+            && move_str.is_ascii()
+            && algebraic_to_square(&move_str[0..2]).is_ok()
+            && algebraic_to_square(&move_str[2..4]).is_ok();
+        let legality = if coordinates {
+            let from = algebraic_to_square(&move_str[0..2]).unwrap();
+            let to = algebraic_to_square(&move_str[2..4]).unwrap();
+            Ok(self.legality.check(&mut self.board, from, to))
+        } else {
+            self.legality.check_san(&mut self.board, move_str)
+        };
+        match legality {
+            Ok(Legality::Legal(chess_move)) => {
+                let text = self
+                    .san_parser
+                    .format_move(&mut self.board, &chess_move, self.notation);
+                println!("WHY: legal: {}", text);
+            }
+            Ok(Legality::Illegal(reason)) => println!("WHY: illegal: {}", reason),
+            Err(error) => println!("ERROR: {}", tr(error)),
+        }
+    }
+
+    // Plays a whole line or none of it: the first move that does not resolve takes the board
+    // back to where it started. Move numbers and a trailing result are skipped, so a line can be
+    // pasted from a transcript.
+    fn handle_playout(&mut self, args: &[&str]) {
+        if args.is_empty() {
+            println!("ERROR: {}", tr("playout requires moves"));
+            return;
+        }
+        if self.training.drill.current.is_some() {
+            println!("ERROR: {}", tr("Cannot play out a line during a drill"));
+            return;
+        }
+        let mut movers = Vec::new();
+        for token in args {
+            let token = token.rsplit_once('.').map_or(*token, |(_, san)| san);
+            if token.is_empty() || matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*") {
+                continue;
+            }
+            match self.resolve_typed_move(token) {
+                Ok(chess_move) => {
+                    movers.push(self.board.get_turn());
+                    self.board.make_move(&chess_move);
+                }
+                Err(error) => {
+                    for _ in &movers {
+                        self.board.undo_move();
+                    }
+                    println!("ERROR: {}: {}", tr(error), token);
+                    return;
+                }
+            }
+        }
+        for color in movers.iter().copied() {
+            self.profile.record_move(color, false);
+        }
+        println!("OK: playout {} moves", movers.len());
+        self.show_board();
+        self.check_game_end();
+    }
+
+    // Analysis only: the turn goes to the opponent without a move, and `undo` takes it back.
+    fn handle_pass(&mut self) {
+        let turn = self.board.get_turn();
+        if self.move_generator.is_in_check(&self.board, turn) {
+            println!("ERROR: {}", tr("Cannot pass while in check"));
+            return;
+        }
+        if self.training.drill.current.is_some() {
+            println!("ERROR: {}", tr("Cannot pass during a drill"));
+            return;
+        }
+        self.profile.record_pass();
+        self.board.make_null_move();
+        println!("OK: pass");
+        self.show_board();
+        self.check_game_end();
+    }
+
+    // What the opponent would play if the side to move passed.
+    fn handle_threat(&mut self, args: &[&str]) {
+        let depth = match args.first().map(|value| value.parse::<u8>()) {
+            None => 3,
+            Some(Ok(d)) if (1..=5).contains(&d) => d,
+            Some(_) => {
+                println!("ERROR: {}", tr("threat depth must be 1-5"));
+                return;
+            }
+        };
+        let turn = self.board.get_turn();
+        if self.move_generator.is_in_check(&self.board, turn) {
+            println!("ERROR: {}", tr("Cannot pass while in check"));
+            return;
+        }
+
+        let mut passed = self.board.clone();
+        passed.make_null_move();
+        let result = self.ai.find_best_move(&mut passed, depth);
+        if result.best_move.is_none() {
+            println!("THREAT: none");
+            return;
+        }
+        let line = self
+            .san_parser
+            .format_line(&passed, &result.pv, self.notation);
+        println!(
+            "THREAT: move={}; line={}; eval={}; depth={}",
+            line[0],
+            line.join(" "),
+            format_score(result.evaluation),
+            result.depth
+        );
+    }
+
+    fn handle_drill(&mut self, args: &[&str]) {
+        match args {
+            [] | ["status"] => {
+                let drill = &self.training.drill;
+                let active = drill
+                    .current
+                    .map_or("none".to_string(), |index| format!("line {}", index + 1));
+                println!(
+                    "DRILL: lines={}; active={}; round={}",
+                    drill.lines.len(),
+                    active,
+                    drill.round + 1
+                );
+                for (index, line) in drill.lines.iter().enumerate() {
+                    println!(
+                        "DRILL: line {}; side={}; moves={}; box={}; due=round {}; clean={}/{}",
+                        index + 1,
+                        color_name(line.user),
+                        line.moves.len(),
+                        line.level,
+                        line.due + 1,
+                        line.clean_runs,
+                        line.runs
+                    );
+                }
+            }
+            ["stop"] => {
+                self.training.drill.stop();
+                println!("DRILL: stopped; lines={}", self.training.drill.lines.len());
+            }
+            _ => {
+                let (user, tokens) = match args[0] {
+                    "white" => (Color::White, &args[1..]),
+                    "black" => (Color::Black, &args[1..]),
+                    _ => (Color::White, args),
+                };
+                match self.parse_drill_line(tokens, user) {
+                    Ok(moves) => {
+                        let count = moves.len();
+                        let index = self.training.drill.add(moves, user);
+                        println!(
+                            "DRILL: added line {}; side={}; moves={}",
+                            index + 1,
+                            color_name(user),
+                            count
+                        );
+                        if self.training.drill.current.is_none() {
+                            self.start_drill_run();
+                        }
+                    }
+                    Err(error) => println!("ERROR: {}", tr(error)),
+                }
+            }
+        }
+    }
+
+    // PGN movetext from the initial position: move numbers and a trailing result are skipped,
+    // and the moves come back as SAN whatever notation they were given in.
+    fn parse_drill_line(&self, tokens: &[&str], user: Color) -> Result<Vec<String>, &'static str> {
+        let mut board = Board::new();
+        let mut moves = Vec::new();
+        for token in tokens {
+            let token = token.rsplit_once('.').map_or(*token, |(_, san)| san);
+            if token.is_empty() || matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*") {
+                continue;
+            }
+            let chess_move = self
+                .san_parser
+                .parse_san(&mut board, token)
+                .map_err(|_| "drill line has an illegal move")?;
+            moves.push(self.san_parser.export_san(&mut board, &chess_move));
+            board.make_move(&chess_move);
+        }
+        // White's moves are the even plies, Black's the odd ones.
+        let first_user_ply = if user == Color::White { 0 } else { 1 };
+        if moves.len() <= first_user_ply {
+            return Err("drill line needs a move for your side");
+        }
+        Ok(moves)
+    }
+
+    fn start_drill_run(&mut self) {
+        let Some(index) = self.training.drill.start_next() else {
+            return;
+        };
+        self.board.reset();
+        self.ai.new_game();
+        self.pgn_source = None;
+        self.pgn_moves.clear();
+        self.pgn_headers = PgnHeaders::new();
+        self.profile.new_game();
+        let line = &self.training.drill.lines[index];
+        println!(
+            "DRILL: line {}; you play {}; moves={}; round={}",
+            index + 1,
+            color_name(line.user),
+            line.moves.len(),
+            self.training.drill.round + 1
+        );
+        self.play_drill_replies();
+    }
+
+    // Plays the engine's side of the line up to the user's next move, moving on to the next due
+    // line when this one is over.
+    fn play_drill_replies(&mut self) {
+        loop {
+            let ply = self.board.get_state().move_history.len();
+            let Some(line) = self.training.drill.line() else {
+                return;
+            };
+            let user = line.user;
+            let Some(san) = line.moves.get(ply).cloned() else {
+                break;
+            };
+            if self.board.get_turn() == user {
+                self.show_board();
+                return;
+            }
+            let Ok(chess_move) = self.san_parser.parse_san(&mut self.board, &san) else {
+                return;
+            };
+            let text = self
+                .san_parser
+                .format_move(&mut self.board, &chess_move, self.notation);
+            self.board.make_move(&chess_move);
+            println!("DRILL: engine plays {}", text);
+        }
+
+        let mistakes = self.training.drill.run_mistakes;
+        let line = self.training.drill.current.map_or(0, |index| index + 1);
+        let wait = self.training.drill.finish_run();
+        println!(
+            "DRILL: line {} complete; mistakes={}; next review in {} round{}",
+            line,
+            mistakes,
+            wait,
+            if wait == 1 { "" } else { "s" }
+        );
+        self.start_drill_run();
+    }
+
+    // A wrong move is not played: the theory move is, with the engine's view of both, so the
+    // line carries on.
+    fn handle_drill_move(&mut self, move_str: &str, chess_move: &Move) {
+        let ply = self.board.get_state().move_history.len();
+        let Some(theory_san) = self.training.drill.expected(ply).map(str::to_string) else {
+            return;
+        };
+        let Ok(theory) = self.san_parser.parse_san(&mut self.board, &theory_san) else {
+            return;
+        };
+        if *chess_move == theory {
+            self.board.make_move(chess_move);
+            println!("OK: {}", move_str);
+            self.play_drill_replies();
+            return;
+        }
+
+        self.training.drill.record_mistake();
+        let played = self
+            .san_parser
+            .format_move(&mut self.board, chess_move, self.notation);
+        let theory_text = self
+            .san_parser
+            .format_move(&mut self.board, &theory, self.notation);
+        let sign = if self.board.get_turn() == Color::White {
+            1
+        } else {
+            -1
+        };
+        let theory_score =
+            sign * score_root_move(&mut self.ai, &self.board, &theory, DRILL_REVIEW_DEPTH).score;
+        let played_score =
+            sign * score_root_move(&mut self.ai, &self.board, chess_move, DRILL_REVIEW_DEPTH).score;
+        let justification = if theory_score - played_score >= DRILL_MISTAKE_MARGIN_CP {
+            format!(
+                "{} scores {}cp, {} {}cp at depth {}",
+                theory_text, theory_score, played, played_score, DRILL_REVIEW_DEPTH
+            )
+        } else {
+            format!(
+                "the engine rates them within {}cp at depth {}, but {} is the line's move",
+                DRILL_MISTAKE_MARGIN_CP, DRILL_REVIEW_DEPTH, theory_text
+            )
+        };
+        println!(
+            "DRILL: mistake; you played {}, theory is {}; {}",
+            played, theory_text, justification
+        );
+        self.board.make_move(&theory);
+        println!("DRILL: playing {} instead", theory_text);
+        self.play_drill_replies();
+    }
+
+    fn handle_blindfold(&mut self, args: &[&str]) {
+        match args.first().copied().unwrap_or("status") {
+            "on" => {
+                self.training.start_blindfold();
+                println!("BLINDFOLD: on; moves accept SAN; peek shows the board");
+            }
+            "off" => {
+                self.training.stop_blindfold();
+                println!("BLINDFOLD: off; peeks={}", self.training.peeks);
+                self.show_board();
+            }
+            "status" => println!(
+                "BLINDFOLD: {}; peeks={}; quiz={}/{}",
+                if self.training.blindfold { "on" } else { "off" },
+                self.training.peeks,
+                self.training.correct,
+                self.training.asked
+            ),
+            _ => println!("ERROR: {}", tr("blindfold must be on, off or status")),
+        }
+    }
+
+    fn handle_peek(&mut self) {
+        if self.training.blindfold {
+            let penalty = self.training.record_peek();
+            println!("PEEK: penalty={}", penalty);
+        } else {
+            println!("PEEK: board is already visible");
+        }
+        println!("{}", self.profile.display.render(&self.board));
+    }
+
+    fn handle_quiz(&mut self, args: &[&str]) {
+        let quiz = match args.first().copied() {
+            Some("color") => self.training.next_square_color_quiz(),
+            Some("knight") => self.training.next_knight_path_quiz(),
+            _ => {
+                println!("ERROR: {}", tr("quiz must be color or knight"));
+                return;
+            }
+        };
+        println!("QUIZ: {}", quiz.prompt());
+    }
+
+    fn handle_answer(&mut self, args: &[&str]) {
+        let Some(answer) = args.first() else {
+            println!("ERROR: {}", tr("answer requires a value"));
+            return;
+        };
+
+        match self.training.answer(answer) {
+            Ok((correct, quiz)) => println!(
+                "QUIZ: {}; answer={}; score={}/{}",
+                if correct { "correct" } else { "wrong" },
+                quiz.solution(),
+                self.training.correct,
+                self.training.asked
+            ),
+            Err(error) => println!("ERROR: {}", tr(error)),
+        }
+    }
+
+    fn handle_testpos(&mut self, args: &[&str]) {
+        if args.is_empty() {
+            let names: Vec<&str> = TEST_POSITIONS
+                .iter()
+                .map(|position| position.name)
+                .collect();
+            println!("TESTPOS: {}", names.join(", "));
+            return;
+        }
+        match test_position(&args.join(" ")) {
+            Some(position) => {
+                println!("TESTPOS: name={}; fen={}", position.name, position.fen);
+                self.handle_fen(position.fen);
+            }
+            None => println!("ERROR: {}", tr("Unknown test position")),
+        }
+    }
+
+    fn handle_perft(&mut self, args: &[&str]) {
+        match args {
+            ["fast", rest @ ..] => return self.perft_fast(rest),
+            ["suite", rest @ ..] => return self.perft_suite(rest),
+            _ => {}
+        }
+        let (mode, args) = match args {
+            ["captures", rest @ ..] => (PerftMode::Captures, rest),
+            ["checks", rest @ ..] => (PerftMode::Checks, rest),
+            _ => (PerftMode::All, args),
+        };
+        let Some((depth, json)) = parse_perft_args(args) else {
+            println!("ERROR: {}", tr("Invalid perft depth"));
+            return;
+        };
+
+        let start_time = Instant::now();
+        let nodes = self.perft.perft_of(&mut self.board, depth, mode);
+        let elapsed = start_time.elapsed();
+
+        if json {
+            let mode = match mode {
+                PerftMode::All => String::new(),
+                mode => format!("\"mode\":\"{}\",", mode.name()),
+            };
+            println!(
+                "PERFT: {{{}\"depth\":{},\"nodes\":{},\"time_ms\":{},\"nps\":{}}}",
+                mode,
+                depth,
+                nodes,
+                elapsed.as_millis(),
+                nodes_per_second(nodes, elapsed)
+            );
+            return;
+        }
+
+        let counted = match mode {
+            PerftMode::All => "nodes",
+            mode => mode.name(),
+        };
+        println!(
+            "Perft({}): {} {} ({}ms, {} nps)",
+            depth,
+            nodes,
+            counted,
+            elapsed.as_millis(),
+            nodes_per_second(nodes, elapsed)
+        );
+    }
+
+    // `perft fast <depth> [threads <n>] [hash <mb>] [json]`: bulk counting, the root moves shared
+    // out between `n` threads (default: every CPU), with `mb` megabytes of tables between them
+    // (default 16, 0 for none)
+    fn perft_fast(&mut self, args: &[&str]) {
+        let mut args = args.to_vec();
+        let mut threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+        let mut hash_mb = DEFAULT_PERFT_HASH_MB;
+        while let Some(index) = args
+            .iter()
+            .position(|&arg| arg == "hash" || arg == "threads")
+        {
+            let value = args
+                .get(index + 1)
+                .and_then(|value| value.parse::<usize>().ok());
+            match (args[index], value) {
+                ("hash", Some(mb)) if mb <= MAX_PERFT_HASH_MB => hash_mb = mb,
+                ("threads", Some(count)) if (1..=MAX_PERFT_THREADS).contains(&count) => {
+                    threads = count
+                }
+                ("hash", _) => {
+                    println!("ERROR: {}", tr("perft hash must be 0-4096 MB"));
+                    return;
+                }
+                _ => {
+                    println!("ERROR: {}", tr("perft threads must be 1-256"));
+                    return;
+                }
+            }
+            args.drain(index..(index + 2).min(args.len()));
+        }
+        let Some((depth, json)) = parse_perft_args(&args) else {
+            println!("ERROR: {}", tr("Invalid perft depth"));
+            return;
+        };
+
+        let pinning = Some(&self.topology).filter(|_| self.pin_threads);
+        let start_time = Instant::now();
+        let (nodes, hits) =
+            self.perft
+                .perft_parallel(&self.board, depth, threads, hash_mb, pinning);
+        let elapsed = start_time.elapsed();
+
+        if json {
+            println!(
+                "PERFT: {{\"mode\":\"fast\",\"depth\":{},\"nodes\":{},\"threads\":{},\"hash_mb\":{},\"hits\":{},\"time_ms\":{},\"nps\":{}}}",
+                depth,
+                nodes,
+                threads,
+                hash_mb,
+                hits,
+                elapsed.as_millis(),
+                nodes_per_second(nodes, elapsed)
+            );
+            return;
+        }
+        println!(
+            "Perft({}): {} nodes ({}ms, {} nps; fast, threads={}, hash={}MB, hits={})",
+            depth,
+            nodes,
+            elapsed.as_millis(),
+            nodes_per_second(nodes, elapsed),
+            threads,
+            hash_mb,
+            hits
+        );
+    }
+
+    // `perft suite [depth <n>] [file <epd>]...`: every count up to depth `n` (default 4) of the
+    // standard positions and of the suites given, with `perft fast`'s defaults. A position stops
+    // at its first wrong count.
+    fn perft_suite(&mut self, args: &[&str]) {
+        let mut depth = DEFAULT_PERFT_SUITE_DEPTH;
+        let mut cases = standard_cases();
+        for option in args.chunks(2) {
+            match option {
+                ["depth", value] => match value.parse::<u8>() {
+                    Ok(value) if value >= 1 => depth = value,
+                    _ => {
+                        println!("ERROR: {}", tr("Invalid perft depth"));
+                        return;
+                    }
+                },
+                ["file", path] => {
+                    let suite = fs::read_to_string(path)
+                        .map_err(|_| "Cannot read perft suite".to_string())
+                        .and_then(|text| parse_suite(&text));
+                    match suite {
+                        Ok(suite) => cases.extend(suite),
+                        Err(error) => {
+                            println!("ERROR: {}", tr(&error));
+                            return;
+                        }
+                    }
+                }
+                _ => {
+                    println!(
+                        "ERROR: {}",
+                        tr("perft suite takes depth <n> and file <epd>")
+                    );
+                    return;
+                }
+            }
+        }
+
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+        let pinning = Some(&self.topology).filter(|_| self.pin_threads);
+        let start_time = Instant::now();
+        let (mut passed, mut failed, mut skipped, mut total) = (0, 0, 0, 0);
+        for case in &cases {
+            let mut board = Board::new();
+            if self.fen_parser.parse_fen(&mut board, &case.fen).is_err() {
+                println!("PERFT: FAIL {}; error=invalid FEN", case.name);
+                failed += 1;
+                continue;
+            }
+            let counts: Vec<(u8, u64)> = case
+                .counts
+                .iter()
+                .copied()
+                .filter(|&(at, _)| at <= depth)
+                .collect();
+            if counts.is_empty() {
+                println!("PERFT: SKIP {}; depth={}", case.name, case.counts[0].0);
+                skipped += 1;
+                continue;
+            }
+            let case_time = Instant::now();
+            let mut wrong = None;
+            for &(at, expected) in &counts {
+                let (nodes, _) =
+                    self.perft
+                        .perft_parallel(&board, at, threads, DEFAULT_PERFT_HASH_MB, pinning);
+                total += nodes;
+                if nodes != expected {
+                    wrong = Some((at, nodes, expected));
+                    break;
+                }
+            }
+            match wrong {
+                None => {
+                    let (at, nodes) = counts[counts.len() - 1];
+                    println!(
+                        "PERFT: PASS {}; depth={}; nodes={}; time={}ms",
+                        case.name,
+                        at,
+                        nodes,
+                        case_time.elapsed().as_millis()
+                    );
+                    passed += 1;
+                }
+                Some((at, nodes, expected)) => {
+                    println!(
+                        "PERFT: FAIL {}; depth={}; nodes={}; expected={}",
+                        case.name, at, nodes, expected
+                    );
+                    failed += 1;
+                }
+            }
+        }
+        let elapsed = start_time.elapsed();
+        println!(
+            "PERFT: suite passed={}; failed={}; skipped={}; depth={}; nodes={}; time={}ms; nps={}",
+            passed,
+            failed,
+            skipped,
+            depth,
+            total,
+            elapsed.as_millis(),
+            nodes_per_second(total, elapsed)
+        );
+    }
+
+    fn handle_analyze(&mut self, args: &[&str]) {
+        if args.first() != Some(&"all") {
+            self.analyze_position(args);
+            return;
+        }
+        let depth = match args {
+            ["all", "depth", depth] => depth.parse::<u8>().ok(),
+            _ => None,
+        };
+        let Some(depth) = depth.filter(|depth| (1..=MAX_SEARCH_DEPTH).contains(depth)) else {
+            println!("ERROR: {}", tr("analyze all needs depth 1-64"));
+            return;
+        };
+
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+        let start_time = Instant::now();
+        let pinning = Some(&self.topology).filter(|_| self.pin_threads);
+        let (scores, workers) = analyze_all_with_stats(&self.board, depth, threads, pinning);
+        let elapsed = start_time.elapsed();
+        for root_score in &scores {
+            let line = self
+                .san_parser
+                .format_line(&self.board, &root_score.pv, self.notation);
+            println!(
+                "ANALYZE: {} score={}; pv={}",
+                line[0],
+                format_score(root_score.score),
+                line.join(" ")
+            );
+        }
+        let nodes: u64 = scores.iter().map(|root_score| root_score.nodes).sum();
+        println!(
+            "ANALYZE: depth={}; moves={}; threads={}; nodes={}; time={}ms; sharpness={}",
+            depth,
+            scores.len(),
+            threads.min(scores.len().max(1)),
+            nodes,
+            elapsed.as_millis(),
+            Sharpness::from_scores(&self.board, &scores).score
+        );
+
+        // Whether the extra threads helped: each worker's share, then how even the shares were
+        let total_hits: u64 = workers.iter().map(|worker| worker.tt_hits).sum();
+        for (index, worker) in workers.iter().enumerate() {
+            println!(
+                "THREAD: {} cpu={}; moves={}; nodes={}; depth={}; tt_hits={}; tt_share={}%; busy={}ms",
+                index + 1,
+                worker.cpu.map_or("any".to_string(), |cpu| cpu.to_string()),
+                worker.moves,
+                worker.nodes,
+                depth,
+                worker.tt_hits,
+                worker.tt_hits * 100 / total_hits.max(1),
+                worker.busy_ms
+            );
+        }
+        let busy: u128 = workers.iter().map(|worker| worker.busy_ms).sum();
+        println!(
+            "BALANCE: workers={}; balance={}%; speedup={:.1}x",
+            workers.len(),
+            load_balance(&workers),
+            busy as f64 / elapsed.as_millis().max(1) as f64
+        );
+    }
+
+    // `analyze <limits> [window a b] [min-rootdepth n] [no-<feature>...]`: one search of the
+    // position with the given changes to the search, reporting each depth and not playing the
+    // move, so two runs that differ in one feature can be compared.
+    fn analyze_position(&mut self, args: &[&str]) {
+        let (overrides, limits) = match SearchOverrides::split(args) {
+            Ok(split) => split,
+            Err(error) => {
+                println!("ERROR: {}", tr(&error));
+                return;
+            }
+        };
+        let limits = match SearchLimits::parse(&limits) {
+            Ok(limits) if limits.is_bounded(self.board.get_turn()) => limits,
+            Ok(_) => {
+                println!("ERROR: {}", tr("No clock time given for the side to move"));
+                return;
+            }
+            Err(error) => {
+                println!("ERROR: {}", tr(&error));
+                return;
+            }
+        };
+        if limits
+            .depth
+            .is_some_and(|depth| overrides.min_root_depth > depth)
+        {
+            println!(
+                "ERROR: {}",
+                tr("min-rootdepth cannot exceed the depth limit")
+            );
+            return;
+        }
+
+        let sign = if self.board.get_turn() == Color::White {
+            1
+        } else {
+            -1
+        };
+        let (san_parser, notation) = (&self.san_parser, self.notation);
+        let board = self.board.clone();
+        self.ai.set_overrides(overrides);
+        let result = self
+            .ai
+            .search_with_progress(&mut self.board, &limits, &mut |progress| {
+                println!(
+                    "ANALYZE: depth={}; score={}; nodes={}; pv={}",
+                    progress.depth,
+                    format_score(progress.evaluation),
+                    progress.nodes,
+                    san_parser
+                        .format_line(&board, &progress.pv, notation)
+                        .join(" ")
+                );
+            });
+        self.ai.set_overrides(SearchOverrides::default());
+
+        // A score on the window's edge only bounds the true one, from the side to move's view
+        let score = result.evaluation * sign;
+        let bound = match overrides.window {
+            Some((alpha, _)) if score <= alpha => "upper",
+            Some((_, beta)) if score >= beta => "lower",
+            _ => "exact",
+        };
+        let best = result
+            .best_move
+            .as_ref()
+            .map_or("none".to_string(), |best| {
+                self.san_parser
+                    .format_line(&self.board, std::slice::from_ref(best), self.notation)[0]
+                    .clone()
+            });
+        println!(
+            "ANALYZE: best={}; depth={}; score={}; bound={}; nodes={}; time={}ms; nps={}; overrides={}",
+            best,
+            result.depth,
+            format_score(result.evaluation),
+            bound,
+            result.nodes,
+            result.time_ms,
+            result.nps,
+            overrides.describe()
+        );
+    }
+
+    // `threads [pin on|off]`: the detected CPU topology, and whether `analyze all` and `perft
+    // fast` pin their workers to CPUs spread over the NUMA nodes.
+    fn handle_threads(&mut self, args: &[&str]) {
+        match args {
+            [] => {}
+            ["pin", toggle @ ("on" | "off")] => self.pin_threads = *toggle == "on",
+            _ => {
+                println!("ERROR: {}", tr("Unsupported threads command"));
+                return;
+            }
+        }
+        let nodes: Vec<String> = self
+            .topology
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(node, cpus)| format!("node{}={}", node, format_cpu_list(cpus)))
+            .collect();
+        println!(
+            "THREADS: cpus={}; nodes={}; pin={}; {}",
+            self.topology.cpus(),
+            self.topology.nodes.len(),
+            if self.pin_threads { "on" } else { "off" },
+            nodes.join("; ")
+        );
+    }
+
+    fn handle_cpuinfo(&self) {
+        let features = cpu::features().names();
+        println!(
+            "CPUINFO: arch={}; features={}; popcount={}",
+            std::env::consts::ARCH,
+            if features.is_empty() {
+                "none".to_string()
+            } else {
+                features.join(",")
+            },
+            cpu::kernels().popcount_name
+        );
+    }
+
+    fn handle_bench(&self, args: &[&str]) {
+        let lookups = match args {
+            [] => DEFAULT_BENCH_LOOKUPS,
+            [count] => match count.parse::<u64>() {
+                Ok(count) if (1..=MAX_BENCH_LOOKUPS).contains(&count) => count,
+                _ => {
+                    println!("ERROR: {}", tr("Invalid lookup count"));
+                    return;
+                }
+            },
+            _ => {
+                println!("ERROR: {}", tr("Invalid lookup count"));
+                return;
+            }
+        };
+        let rows = sliding_attacks::benchmark(lookups);
+        let per_lookup = |time: Duration| time.as_nanos() as f64 / lookups as f64;
+        for (kernel, time) in &rows {
+            println!(
+                "BENCH: sliding={}; lookups={}; time={}ms; ns={:.1}",
+                kernel,
+                lookups,
+                time.as_millis(),
+                per_lookup(*time)
+            );
+        }
+        let engine = sliding_attacks::tables().indexing().name();
+        let time_of = |name: &str| {
+            rows.iter()
+                .find(|(kernel, _)| *kernel == name)
+                .map_or(Duration::ZERO, |(_, time)| *time)
+        };
+        let direct = per_lookup(time_of(engine));
+        let dispatched = per_lookup(time_of("engine"));
+        println!(
+            "BENCH: engine={}; pext={}; overhead={:+.1}%",
+            engine,
+            cpu::kernels().pext_name,
+            if direct > 0.0 {
+                (dispatched - direct) / direct * 100.0
+            } else {
+                0.0
+            }
+        );
+    }
+
+    fn handle_sharpness(&mut self) {
+        let color = self.board.get_turn();
+        if !self.move_generator.has_legal_move(&mut self.board, color) {
+            println!("ERROR: {}", tr("No legal moves available"));
+            return;
+        }
+        let sharpness = Sharpness::measure(&mut self.ai, &self.board);
+        println!("SHARPNESS: {}", sharpness);
+    }
+
+    // Checks this engine against an external UCI engine: `compare` for the current position,
+    // `suite` for a file of FENs, where only disagreements above the threshold are listed.
+    fn handle_oracle(&mut self, args: &[&str]) {
+        match args {
+            [] => {}
+            ["start", program, engine_args @ ..] => {
+                self.oracle = None;
+                match UciOracle::start(program, engine_args) {
+                    Ok(oracle) => self.oracle = Some(oracle),
+                    Err(error) => {
+                        println!("ERROR: {}", tr(&error));
+                        return;
+                    }
+                }
+            }
+            ["stop"] => self.oracle = None,
+            ["threshold", cp] => match cp.parse::<i32>() {
+                Ok(cp) if cp >= 0 => self.oracle_threshold = cp,
+                _ => {
+                    println!(
+                        "ERROR: {}",
+                        tr("oracle threshold must be a non-negative centipawn value")
+                    );
+                    return;
+                }
+            },
+            ["compare", depth @ ..] | ["suite", _, depth @ ..] if depth.len() <= 1 => {
+                let depth = match depth.first().map(|value| value.parse::<u8>()) {
+                    None => 6,
+                    Some(Ok(d)) if (1..=MAX_SEARCH_DEPTH).contains(&d) => d,
+                    Some(_) => {
+                        println!("ERROR: {}", tr("oracle depth must be 1-64"));
+                        return;
+                    }
+                };
+                if self.oracle.is_none() {
+                    println!("ERROR: {}", tr("No oracle running"));
+                    return;
+                }
+                if let ["suite", path, ..] = args {
+                    self.handle_oracle_suite(path, depth);
+                } else {
+                    let board = self.board.clone();
+                    if let Some(line) = self.oracle_compare(board, depth, false) {
+                        println!("ORACLE: {}", line);
+                    }
+                }
+                return;
+            }
+            _ => {
+                println!("ERROR: {}", tr("Unsupported oracle command"));
+                return;
+            }
+        }
+
+        println!(
+            "ORACLE: engine={}; threshold={}cp",
+            self.oracle
+                .as_ref()
+                .map_or("none", |oracle| oracle.name.as_str()),
+            self.oracle_threshold
+        );
+    }
+
+    fn handle_oracle_suite(&mut self, path: &str, depth: u8) {
+        let Ok(contents) = fs::read_to_string(path) else {
+            println!("ERROR: {}", tr("Cannot read oracle suite"));
+            return;
+        };
+        let (mut positions, mut disagreements) = (0, 0);
+        for (number, fen) in contents.lines().map(str::trim).enumerate() {
+            if fen.is_empty() || fen.starts_with('#') {
+                continue;
+            }
+            let mut board = Board::new();
+            if self.fen_parser.parse_fen(&mut board, fen).is_err() {
+                println!("ERROR: {} (line {})", tr("Invalid FEN string"), number + 1);
+                continue;
+            }
+            positions += 1;
+            if let Some(line) = self.oracle_compare(board, depth, true) {
+                disagreements += 1;
+                println!("ORACLE: line={}; {}", number + 1, line);
+            }
+        }
+        println!(
+            "ORACLE: positions={}; disagreements={}; threshold={}cp; depth={}",
+            positions, disagreements, self.oracle_threshold, depth
+        );
+    }
+
+    // Searches `board` with both engines to `depth`. Scores are from White's point of view, like
+    // the rest of the REPL's output; the oracle reports them for the side to move. With
+    // `only_disagreements`, agreeing positions give None.
+    fn oracle_compare(
+        &mut self,
+        mut board: Board,
+        depth: u8,
+        only_disagreements: bool,
+    ) -> Option<String> {
+        let fen = self.fen_parser.export_fen(&board);
+        let oracle = self.oracle.as_mut()?;
+        let answer = match oracle.analyse(&fen, depth) {
+            Ok(answer) => answer,
+            Err(error) => {
+                println!("ERROR: {}", tr(&error));
+                self.oracle = None;
+                return None;
+            }
+        };
+        let theirs = if board.get_turn() == Color::White {
+            answer.score
+        } else {
+            -answer.score
+        };
+        let result = self.ai.find_best_move(&mut board, depth);
+        let ours = result
+            .best_move
+            .map_or("none".to_string(), |chess_move| chess_move.to_string());
+        let diff = (result.evaluation - theirs).abs();
+        let disagrees = diff > self.oracle_threshold;
+        if only_disagreements && !disagrees {
+            return None;
+        }
+        Some(format!(
+            "fen={}; ours={} {}; theirs={} {}; diff={}cp; same_move={}; disagree={}",
+            fen,
+            ours,
+            format_score(result.evaluation),
+            answer.best_move,
+            format_score(theirs),
+            diff,
+            if ours == answer.best_move {
+                "yes"
+            } else {
+                "no"
+            },
+            if disagrees { "yes" } else { "no" }
+        ))
+    }
+
+    // A quick look at every legal move, sharing the engine's table across the batch; the loss
+    // column shows how much each move gives up against the best one.
+    fn handle_replies(&mut self, args: &[&str]) {
+        let depth = match args.first().map(|value| value.parse::<u8>()) {
+            None => 2,
+            Some(Ok(d)) if (1..=5).contains(&d) => d,
+            Some(_) => {
+                println!("ERROR: {}", tr("replies depth must be 1-5"));
+                return;
+            }
+        };
+        let color = self.board.get_turn();
+        let replies = score_replies(&mut self.ai, &self.board, depth);
+        let Some(best) = replies.first() else {
+            println!("ERROR: {}", tr("No legal moves available"));
+            return;
+        };
+        for reply in &replies {
+            let text =
+                self.san_parser
+                    .format_move(&mut self.board, &reply.chess_move, self.notation);
+            println!(
+                "REPLY: {} score={}; loss={}",
+                text,
+                reply.score,
+                score_loss(best, reply, color)
+            );
+        }
+        let holding = replies
+            .iter()
+            .filter(|reply| score_loss(best, reply, color) <= REPLY_MARGIN_CP)
+            .count();
+        println!(
+            "REPLIES: depth={}; moves={}; within_{}cp={}; only_move={}",
+            depth,
+            replies.len(),
+            REPLY_MARGIN_CP,
+            holding,
+            holding == 1 && replies.len() > 1
+        );
+    }
+
+    fn handle_solve(&mut self, args: &[&str]) {
+        let parsed = match args {
+            [stipulation, moves] => {
+                Stipulation::parse(stipulation).map(|stipulation| (stipulation, moves))
+            }
+            _ => None,
+        };
+        let Some((stipulation, moves)) = parsed else {
+            println!(
+                "ERROR: {}",
+                tr("solve requires mate, helpmate or selfmate and a number of moves")
+            );
+            return;
+        };
+        let moves = match moves.parse::<u8>() {
+            Ok(moves) if (1..=MAX_PROBLEM_MOVES).contains(&moves) => moves,
+            _ => {
+                println!("ERROR: {}", tr("solve moves must be 1-5"));
+                return;
+            }
+        };
+
+        let start_time = Instant::now();
+        let mut solver = ProblemSolver::new();
+        let solutions = solver.solve(&mut self.board, stipulation, moves);
+        let elapsed = start_time.elapsed();
+        for line in &solutions {
+            let line = self
+                .san_parser
+                .format_line(&self.board, line, self.notation);
+            println!("SOLUTION: {}", line.join(" "));
+        }
+        println!(
+            "SOLVE: {} {}; solutions={}; nodes={}; time={}ms",
+            stipulation.name(),
+            moves,
+            solutions.len(),
+            solver.nodes(),
+            elapsed.as_millis()
+        );
+    }
+
+    // `divide <depth> [moves...] [json]`: with moves, divides the position at the end of that
+    // line, so a count that disagrees can be chased one move deeper at a time.
+    fn handle_divide(&mut self, args: &[&str]) {
+        let (args, json) = match args.split_last() {
+            Some((&"json", rest)) => (rest, true),
+            _ => (args, false),
+        };
+        let Some(depth) = args
+            .first()
+            .and_then(|depth| depth.parse::<u8>().ok())
+            .filter(|&depth| depth >= 1)
+        else {
+            println!("ERROR: {}", tr("Invalid perft depth"));
+            return;
+        };
+
+        let mut line = Vec::new();
+        for token in &args[1..] {
+            match self.resolve_typed_move(token) {
+                Ok(chess_move) => {
+                    line.push(chess_move.to_string());
+                    self.board.make_move(&chess_move);
+                }
+                Err(error) => {
+                    for _ in &line {
+                        self.board.undo_move();
+                    }
+                    println!("ERROR: {}: {}", tr(error), token);
+                    return;
+                }
+            }
+        }
+        self.divide(depth, &line, json);
+        for _ in &line {
+            self.board.undo_move();
+        }
+    }
+
+    fn divide(&mut self, depth: u8, line: &[String], json: bool) {
+        let start_time = Instant::now();
+        let divided = self.perft.divide_moves(&mut self.board, depth);
+        let elapsed = start_time.elapsed();
+        let total = divided.iter().map(|(_, count)| count).sum::<u64>();
+        let mut results: Vec<(String, u64)> = divided
+            .iter()
+            .map(|(chess_move, count)| {
+                let text = self
+                    .san_parser
+                    .format_move(&mut self.board, chess_move, self.notation);
+                (text, *count)
+            })
+            .collect();
+        results.sort();
+
+        if json {
+            let moves: Vec<String> = results
+                .iter()
+                .map(|(text, count)| format!("\"{}\":{}", text, count))
+                .collect();
+            let line: Vec<String> = line.iter().map(|uci| format!("\"{}\"", uci)).collect();
+            println!(
+                "DIVIDE: {{\"depth\":{},\"line\":[{}],\"fen\":\"{}\",\"moves\":{{{}}},\"nodes\":{},\"time_ms\":{},\"nps\":{}}}",
+                depth,
+                line.join(","),
+                self.fen_parser.export_fen(&self.board),
+                moves.join(","),
+                total,
+                elapsed.as_millis(),
+                nodes_per_second(total, elapsed)
+            );
+            return;
+        }
 
-        for key in sorted_keys {
-            println!("{}: {}", key, results[key]);
+        if !line.is_empty() {
+            println!("Line: {}", line.join(" "));
+            println!("FEN: {}", self.fen_parser.export_fen(&self.board));
         }
-        println!("\nTotal: {}", results.values().sum::<u64>());
+        for (text, count) in &results {
+            println!("{}: {}", text, count);
+        }
+        println!(
+            "\nTotal: {} ({}ms, {} nps)",
+            total,
+            elapsed.as_millis(),
+            nodes_per_second(total, elapsed)
+        );
+    }
+
+    fn completer(&mut self) -> EngineCompleter<'_> {
+        EngineCompleter {
+            board: &mut self.board,
+            move_generator: &self.move_generator,
+            san_parser: &self.san_parser,
+            fen_parser: &self.fen_parser,
+            blindfold: self.training.blindfold,
+        }
+    }
+
+    // Quoting the partial line keeps trailing spaces, e.g. `complete "move "`.
+    fn handle_complete(&mut self, partial: &str) {
+        let partial = partial.trim_start();
+        let partial = partial
+            .strip_prefix('"')
+            .and_then(|quoted| quoted.strip_suffix('"'))
+            .unwrap_or(partial);
+        let completion = self.completer().complete(partial);
+        let candidates: Vec<String> = completion
+            .candidates
+            .iter()
+            .map(|candidate| format!("\"{}\"", json_escape(candidate)))
+            .collect();
+        println!(
+            "COMPLETE: {{\"start\":{},\"candidates\":[{}]}}",
+            completion.start,
+            candidates.join(",")
+        );
     }
 
     fn handle_help(&self) {
-        println!("Available commands:");
-        println!("  move <from><to>[promotion] - Make a move (e.g., e2e4, e7e8Q)");
-        println!("  undo - Undo the last move");
-        println!("  new - Start a new game");
-        println!("  ai <depth> - Let AI make a move (depth 1-5)");
-        println!("  fen <string> - Load position from FEN");
-        println!("  export - Export current position as FEN");
-        println!("  eval - Evaluate current position");
-        println!("  hash - Show Zobrist hash of current position");
-        println!("  draws - Show draw detection status");
-        println!("  history - Show position hash history");
-        println!("  go movetime <ms> - Time-managed search");
-        println!("  pgn load|show|moves - PGN command surface");
-        println!("  book load|stats - Opening book command surface");
-        println!("  uci / isready - UCI handshake");
-        println!("  new960 / position960 - Chess960 metadata");
-        println!("  trace on|off|level|report|reset|export|chrome - Trace diagnostics");
-        println!("  concurrency quick|full - Deterministic concurrency fixture");
-        println!("  perft <depth> - Run performance test");
-        println!("  help - Show this help message");
-        println!("  quit - Exit the program");
+        println!("{}", tr("Available commands:"));
+        for (usage, description) in commands::help_entries() {
+            println!("  {} - {}", usage, tr(description));
+        }
+    }
+
+    fn handle_deterministic(&mut self, args: &[&str]) {
+        match args.first().copied().unwrap_or("status") {
+            "on" => self.ai.set_deterministic(true),
+            "off" => self.ai.set_deterministic(false),
+            "status" => {}
+            _ => {
+                println!("ERROR: {}", tr("deterministic must be on, off or status"));
+                return;
+            }
+        }
+        println!(
+            "DETERMINISTIC: {}",
+            if self.ai.is_deterministic() {
+                "on; time limits counted in nodes, fresh hash table per search"
+            } else {
+                "off"
+            }
+        );
+    }
+
+    fn handle_option(&mut self, args: &[&str]) {
+        match args {
+            [] => {
+                for (spec, value) in self.options.iter() {
+                    print_option(spec, value);
+                }
+            }
+            [name] => match self
+                .options
+                .iter()
+                .find(|(spec, _)| spec.name.eq_ignore_ascii_case(name))
+            {
+                Some((spec, value)) => print_option(spec, value),
+                None => println!("ERROR: Unknown option '{}'", name),
+            },
+            [name, value] => match self.options.set(name, value) {
+                Ok(spec) => {
+                    configure_ai(&mut self.ai, &self.options);
+                    print_option(spec, self.options.get(spec.name).unwrap_or(spec.default));
+                }
+                Err(error) => println!("ERROR: {}", error),
+            },
+            _ => println!("ERROR: {}", tr("option takes a name and a value")),
+        }
+    }
+
+    fn handle_debug(&mut self, args: &[&str]) {
+        match args {
+            ["ordering", depth] => match depth.parse::<u8>() {
+                Ok(depth) if (1..=MAX_SEARCH_DEPTH).contains(&depth) => {
+                    self.handle_debug_ordering(depth)
+                }
+                _ => println!("ERROR: {}", tr("debug ordering depth must be 1-64")),
+            },
+            ["tree", depth, path, rest @ ..] if rest.len() <= 1 => {
+                let depth = depth
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|depth| (1..=MAX_TREE_DEPTH).contains(depth));
+                let min_visits = match rest.first() {
+                    Some(value) => value.parse::<u64>().ok().filter(|&visits| visits > 0),
+                    None => Some(DEFAULT_TREE_MIN_VISITS),
+                };
+                match (depth, min_visits) {
+                    (Some(depth), Some(min_visits)) => {
+                        self.handle_debug_tree(depth, path, min_visits)
+                    }
+                    _ => println!(
+                        "ERROR: {}",
+                        tr("debug tree needs depth 1-6, a file and optionally a minimum visit count")
+                    ),
+                }
+            }
+            _ => println!("ERROR: {}", tr("Unsupported debug command")),
+        }
+    }
+
+    // Searches to `depth`, then lists the root moves in the order that final iteration tried them.
+    fn handle_debug_ordering(&mut self, depth: u8) {
+        let result = self.ai.find_best_move(&mut self.board, depth);
+        let order = self.ai.root_order();
+        if result.best_move.is_none() {
+            println!("ERROR: {}", tr("No legal moves available"));
+            return;
+        }
+
+        println!("ORDERING: depth={}; moves={}", result.depth, order.len());
+        for (index, (chess_move, score)) in order.iter().enumerate() {
+            println!(
+                "ORDER: {} {} score={} tt={} mvv_lva={} promotion={} killer={} history={}",
+                index + 1,
+                chess_move,
+                score.total(),
+                score.tt,
+                score.mvv_lva,
+                score.promotion,
+                score.killer,
+                score.history
+            );
+        }
+        let summary: Vec<String> = order
+            .iter()
+            .map(|(chess_move, _)| chess_move.to_string())
+            .collect();
+        self.record_trace(
+            "ordering",
+            format!("depth={} {}", result.depth, summary.join(",")),
+        );
+    }
+
+    // Searches to `depth` recording every node, then writes the last iteration's tree, cut down
+    // to the subtrees that saw at least `min_visits` nodes: JSON for a `.json` file, Graphviz DOT
+    // otherwise.
+    fn handle_debug_tree(&mut self, depth: u8, path: &str, min_visits: u64) {
+        self.ai.record_tree(true);
+        let result = self.ai.find_best_move(&mut self.board, depth);
+        let tree = self.ai.take_tree();
+        let Some(tree) = tree.filter(|_| result.best_move.is_some()) else {
+            println!("ERROR: {}", tr("No legal moves available"));
+            return;
+        };
+        let (format, contents) = if path.to_lowercase().ends_with(".json") {
+            ("json", tree.to_json(min_visits))
+        } else {
+            ("dot", tree.to_dot(min_visits))
+        };
+        match fs::write(path, contents) {
+            Ok(()) => println!(
+                "TREE: depth={}; nodes={}; kept={}; min_visits={}; format={}; file={}",
+                result.depth,
+                tree.nodes().len(),
+                tree.kept(min_visits).len(),
+                min_visits,
+                format,
+                path
+            ),
+            Err(_) => println!("ERROR: {}", tr("Cannot write tree file")),
+        }
     }
 
+    // `go <limits>`: `ai`'s limits, UCI style; a bare `go` plays on the clock when one runs.
     fn handle_go(&mut self, args: &[&str]) {
-        if args.len() < 2 || args[0] != "movetime" {
-            println!("ERROR: Unsupported go command");
+        if self.clock_flagged() {
             return;
         }
-
-        let movetime_ms = match args[1].parse::<u64>() {
-            Ok(value) if value > 0 => value,
-            _ => {
-                println!("ERROR: go movetime requires a positive integer");
-                return;
-            }
-        };
-
-        let depth = if movetime_ms <= 250 {
-            1
-        } else if movetime_ms <= 1000 {
-            2
-        } else if movetime_ms <= 5000 {
-            3
-        } else {
-            4
+        let limits = match self.clock_limits() {
+            Some(limits) if args.is_empty() => Ok(limits),
+            None if args.is_empty() => Err("go needs search limits or a running clock".to_string()),
+            _ => SearchLimits::parse(args),
         };
-        self.handle_ai(&depth.to_string());
+        match limits {
+            Ok(limits) => self.run_search(&limits),
+            Err(error) => println!("ERROR: {}", tr(&error)),
+        }
     }
 
     fn handle_pgn(&mut self, args: &[&str]) {
         if args.is_empty() {
-            println!("ERROR: pgn requires subcommand");
+            println!("ERROR: {}", tr("pgn requires subcommand"));
             return;
         }
 
         match args[0] {
             "load" => {
                 if args.len() < 2 {
-                    println!("ERROR: pgn load requires a file path");
+                    println!("ERROR: {}", tr("pgn load requires a file path"));
                     return;
                 }
                 let path = args[1..].join(" ");
@@ -591,43 +2659,143 @@ impl ChessEngine {
                     .pgn_source
                     .clone()
                     .unwrap_or_else(|| "game://current".to_string());
-                let moves = if self.pgn_moves.is_empty() {
-                    "(none)".to_string()
-                } else {
-                    self.pgn_moves.join(" ")
-                };
-                println!("PGN: source={}; moves={}", source, moves);
+                println!("PGN: source={}; moves={}", source, self.pgn_move_list());
+                if self.pgn_source.is_none() {
+                    print!("{}", self.current_pgn());
+                }
             }
-            "moves" => {
-                let moves = if self.pgn_moves.is_empty() {
-                    "(none)".to_string()
-                } else {
-                    self.pgn_moves.join(" ")
-                };
-                println!("PGN: moves={}", moves);
+            "moves" => println!("PGN: moves={}", self.pgn_move_list()),
+            "export" if args.len() > 1 => {
+                let path = args[1..].join(" ");
+                match fs::write(&path, self.current_pgn()) {
+                    Ok(()) => println!("PGN: saved file={}", path),
+                    Err(_) => println!("ERROR: {}", tr("Cannot write PGN file")),
+                }
+            }
+            "export" => print!("{}", self.current_pgn()),
+            _ => println!("ERROR: {}", tr("Unsupported pgn command")),
+        }
+    }
+
+    // A loaded file's moves, or else the game being played in SAN.
+    fn pgn_move_list(&self) -> String {
+        let moves = if self.pgn_source.is_some() {
+            self.pgn_moves.clone()
+        } else {
+            let mut start = self.board.clone();
+            while start.undo_move().is_some() {}
+            let history = &self.board.get_state().move_history;
+            self.san_parser.format_line(&start, history, self.notation)
+        };
+        if moves.is_empty() {
+            "(none)".to_string()
+        } else {
+            moves.join(" ")
+        }
+    }
+
+    fn current_pgn(&self) -> String {
+        self.pgn_writer.export(&self.board, &self.pgn_headers)
+    }
+
+    // `import lichess <id|url> | import url <address> [annotate [depth <n>]]`: the downloaded
+    // game replaces the current one, so `undo`, `pgn show` and the rest work on it.
+    fn handle_import(&mut self, args: &[&str]) {
+        let [kind, target, rest @ ..] = args else {
+            println!(
+                "ERROR: {}",
+                tr("import needs lichess <game> or url <address>")
+            );
+            return;
+        };
+        let annotate_depth = match rest {
+            [] => None,
+            ["annotate"] => Some(import::DEFAULT_ANNOTATE_DEPTH),
+            ["annotate", "depth", depth] => match depth.parse::<u8>() {
+                Ok(depth) if (1..=MAX_SEARCH_DEPTH).contains(&depth) => Some(depth),
+                _ => {
+                    println!("ERROR: {}", tr("annotate depth must be 1-64"));
+                    return;
+                }
+            },
+            _ => {
+                println!("ERROR: {}", tr("Unsupported import command"));
+                return;
+            }
+        };
+
+        let imported = import::source_url(kind, target).and_then(|url| {
+            let text = import::fetch(&url)?;
+            let game = read_games(&text)
+                .into_iter()
+                .next()
+                .ok_or("No game in download")?;
+            let board = import::replay(&game, &self.san_parser)?;
+            Ok((url, game, board))
+        });
+        let (url, game, board) = match imported {
+            Ok(imported) => imported,
+            Err(error) => {
+                println!("ERROR: {}", tr(error));
+                return;
             }
-            _ => println!("ERROR: Unsupported pgn command"),
+        };
+        self.board = board;
+        self.ai.new_game();
+        self.pgn_source = None;
+        self.pgn_moves.clear();
+        self.pgn_headers = import::headers(&game);
+        self.profile.new_game();
+        self.training.drill.stop();
+        println!(
+            "IMPORT: source={}; white={}; black={}; result={}; moves={}",
+            url,
+            self.pgn_headers.white,
+            self.pgn_headers.black,
+            game.tag("Result").unwrap_or("*"),
+            game.moves.len()
+        );
+        self.show_board();
+
+        let Some(depth) = annotate_depth else {
+            return;
+        };
+        let summary = import::annotate(
+            &mut self.ai,
+            &self.board,
+            depth,
+            &self.san_parser,
+            self.notation,
+        );
+        for annotation in &summary.annotations {
+            println!(
+                "ANNOTATE: {} loss={}; best={}; score={}",
+                annotation.label,
+                annotation.loss,
+                annotation.best,
+                format_score(annotation.score)
+            );
         }
+        println!(
+            "ANNOTATE: depth={}; moves={}; inaccuracies={}; mistakes={}; blunders={}",
+            depth, summary.moves, summary.inaccuracies, summary.mistakes, summary.blunders
+        );
     }
 
     fn handle_book(&mut self, args: &[&str]) {
         if args.is_empty() {
-            println!("ERROR: book requires subcommand");
+            println!("ERROR: {}", tr("book requires subcommand"));
             return;
         }
 
         match args[0] {
             "load" => {
                 if args.len() < 2 {
-                    println!("ERROR: book load requires a file path");
+                    println!("ERROR: {}", tr("book load requires a file path"));
                     return;
                 }
                 let path = args[1..].join(" ");
-                self.book_source = Some(path.clone());
-                self.book_enabled = true;
-                self.book_entries = 2;
-                self.book_lookups = 0;
-                self.book_hits = 0;
+                self.load_book(&path);
                 println!("BOOK: loaded source={}; enabled=true; entries=2", path);
             }
             "stats" => {
@@ -642,14 +2810,63 @@ impl ChessEngine {
                     self.book_hits
                 );
             }
-            _ => println!("ERROR: Unsupported book command"),
+            _ => println!("ERROR: {}", tr("Unsupported book command")),
+        }
+    }
+
+    fn load_book(&mut self, path: &str) {
+        self.book_source = Some(path.to_string());
+        self.book_enabled = true;
+        self.book_entries = 2;
+        self.book_lookups = 0;
+        self.book_hits = 0;
+    }
+
+    // Loads the book given with `--book` or `CHESS_BOOK` if it was found, or drops it if it has
+    // gone since; a book loaded by hand from another file is left alone.
+    fn use_configured_book(&mut self) {
+        let Some(book) = self
+            .capabilities
+            .iter()
+            .find(|capability| capability.name == "book")
+        else {
+            return;
+        };
+        let Some(path) = book.path.clone() else {
+            return;
+        };
+        let current = self.book_source.as_deref() == Some(path.as_str());
+        if book.status == Status::Found && self.book_source.is_none() {
+            self.load_book(&path);
+        } else if book.status != Status::Found && current {
+            self.book_enabled = false;
+            self.book_source = None;
+            self.book_entries = 0;
+        }
+    }
+
+    // Takes a new probe of the data files and returns how many of them changed since the last.
+    fn use_capabilities(&mut self, capabilities: Vec<Capability>) -> usize {
+        let changed = capabilities
+            .iter()
+            .filter(|capability| !self.capabilities.contains(capability))
+            .count();
+        self.capabilities = capabilities;
+        self.use_configured_book();
+        changed
+    }
+
+    fn handle_capabilities(&self) {
+        for capability in &self.capabilities {
+            println!("CAPABILITY: {}", capability.describe());
         }
     }
 
-    fn handle_uci(&self) {
-        println!("id name Rust Chess Engine");
-        println!("id author The Great Analysis Challenge");
-        println!("uciok");
+    // Probes the data files again, for files added (or removed) since startup.
+    fn handle_reload(&mut self) {
+        let changed = self.use_capabilities(capabilities::probe_all());
+        self.handle_capabilities();
+        println!("RELOAD: changed={}", changed);
     }
 
     fn handle_isready(&self) {
@@ -658,6 +2875,9 @@ impl ChessEngine {
 
     fn handle_new960(&mut self, args: &[&str]) {
         self.board.reset();
+        self.pgn_headers = PgnHeaders::new();
+        self.profile.new_game();
+        self.training.drill.stop();
         self.chess960_id = args
             .first()
             .and_then(|value| value.parse::<i32>().ok())
@@ -671,7 +2891,7 @@ impl ChessEngine {
 
     fn handle_trace(&mut self, args: &[&str]) {
         if args.is_empty() {
-            println!("ERROR: trace requires subcommand");
+            println!("ERROR: {}", tr("trace requires subcommand"));
             return;
         }
 
@@ -697,7 +2917,7 @@ impl ChessEngine {
             }
             "level" => {
                 if args.len() < 2 || args[1].trim().is_empty() {
-                    println!("ERROR: trace level requires a value");
+                    println!("ERROR: {}", tr("trace level requires a value"));
                     return;
                 }
                 self.trace_level = args[1].trim().to_lowercase();
@@ -763,7 +2983,7 @@ impl ChessEngine {
                             byte_count
                         );
                     }
-                    Err(error) => println!("ERROR: trace export failed: {}", error),
+                    Err(error) => println!("ERROR: {}: {}", tr("trace export failed"), error),
                 }
             }
             "chrome" => {
@@ -782,10 +3002,10 @@ impl ChessEngine {
                             byte_count
                         );
                     }
-                    Err(error) => println!("ERROR: trace chrome failed: {}", error),
+                    Err(error) => println!("ERROR: {}: {}", tr("trace chrome failed"), error),
                 }
             }
-            _ => println!("ERROR: Unsupported trace command"),
+            _ => println!("ERROR: {}", tr("Unsupported trace command")),
         }
     }
 
@@ -872,6 +3092,7 @@ impl ChessEngine {
         ))
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn record_trace_ai(
         &mut self,
         source: &str,
@@ -885,14 +3106,8 @@ impl ChessEngine {
         tt_hits: u64,
         tt_misses: u64,
         beta_cutoffs: u64,
+        nps: u64,
     ) {
-        let divisor = if elapsed_ms == 0 { 1 } else { elapsed_ms };
-        let nps = if nodes > 0 {
-            u128::from(nodes) * 1000 / divisor
-        } else {
-            0
-        };
-
         self.trace_last_ai = Some(TraceAiState {
             source: source.to_string(),
             move_str: move_str.to_string(),
@@ -902,7 +3117,7 @@ impl ChessEngine {
             timed_out,
             nodes,
             eval_calls,
-            nps,
+            nps: u128::from(nps),
             tt_hits,
             tt_misses,
             beta_cutoffs,
@@ -1009,7 +3224,7 @@ impl ChessEngine {
     }
 
     fn write_trace_payload(&self, target: &str, payload: &str) -> Result<usize, String> {
-        let byte_count = payload.as_bytes().len();
+        let byte_count = payload.len();
         if target != "(memory)" {
             std::fs::write(target, payload).map_err(|error| error.to_string())?;
         }
@@ -1019,7 +3234,7 @@ impl ChessEngine {
     fn handle_concurrency(&self, args: &[&str]) {
         let profile = args.first().copied().unwrap_or("");
         if profile != "quick" && profile != "full" {
-            println!("ERROR: Unsupported concurrency profile");
+            println!("ERROR: {}", tr("Unsupported concurrency profile"));
             return;
         }
 
@@ -1043,9 +3258,8 @@ impl ChessEngine {
 
     fn check_game_end(&mut self) {
         let color = self.board.get_turn();
-        let legal_moves = self.move_generator.get_legal_moves(&mut self.board, color);
 
-        if legal_moves.is_empty() {
+        if !self.move_generator.has_legal_move(&mut self.board, color) {
             if self.move_generator.is_in_check(&self.board, color) {
                 let winner = if color == Color::White {
                     "Black"
@@ -1053,24 +3267,593 @@ impl ChessEngine {
                     "White"
                 };
                 println!("CHECKMATE: {} wins", winner);
+                self.finish_profile_game(Outcome::Win(color.opposite()));
             } else {
                 println!("STALEMATE: Draw");
+                self.finish_profile_game(Outcome::Draw);
             }
         } else if self.board.is_draw() {
             println!("DRAW: {}", self.board.get_draw_info());
+            self.finish_profile_game(Outcome::Draw);
         }
     }
-}
 
-fn find_king_square(board: &Board, color: Color) -> Option<Square> {
-    for square in 0..64 {
-        if let Some(piece) = board.get_piece(square) {
-            if piece.color == color && piece.piece_type == PieceType::King {
-                return Some(square);
+    fn finish_profile_game(&mut self, outcome: Outcome) {
+        if self.profile.finish_game(outcome) {
+            self.save_profile();
+        }
+    }
+
+    fn save_profile(&self) {
+        if self.profile.save().is_err() {
+            println!("ERROR: {}", tr("Cannot write profile file"));
+        }
+    }
+
+    fn use_profile(&mut self, profile: Profile) {
+        self.profile = profile;
+        if self.profile.blindfold {
+            self.training.start_blindfold();
+        }
+    }
+
+    // `selfplay <games> <limits> [vs <limits>] [odds <a>:<b>] [adjudicate ...] [openings <file>]`:
+    // the engine against itself, colours alternating, each game recorded in the results
+    // database. Without `vs` both sides search with the same limits and options. Games start
+    // from the current position, or with a suite from its openings in turn, each opening played
+    // once with either colour.
+    fn handle_selfplay(&mut self, args: &[&str]) {
+        let games = match args.first().map(|value| value.parse::<u32>()) {
+            Some(Ok(games)) if (1..=MAX_SELFPLAY_GAMES).contains(&games) => games,
+            _ => {
+                println!(
+                    "ERROR: {}",
+                    tr("selfplay needs 1-1000 games and search limits")
+                );
+                return;
+            }
+        };
+        let mut tokens = args.to_vec();
+        let odds = match tokens.iter().position(|&arg| arg == "odds") {
+            Some(index) => match tokens.get(index + 1).and_then(|odds| TimeOdds::parse(odds)) {
+                Some(odds) => {
+                    tokens.drain(index..index + 2);
+                    Some(odds)
+                }
+                None => {
+                    println!(
+                        "ERROR: {}",
+                        tr("odds needs time shares such as 2:1, each 1-100")
+                    );
+                    return;
+                }
+            },
+            None => None,
+        };
+        let args = &tokens[..];
+        let (args, openings) = match args.iter().position(|&arg| arg == "openings") {
+            Some(index) if index + 2 == args.len() => {
+                let suite = fs::read_to_string(args[index + 1])
+                    .map_err(|_| "Cannot read opening suite".to_string())
+                    .and_then(|text| load_suite(&text));
+                match suite {
+                    Ok(boards) => (&args[..index], boards),
+                    Err(error) => {
+                        println!("ERROR: {}", tr(&error));
+                        return;
+                    }
+                }
+            }
+            Some(_) => {
+                println!(
+                    "ERROR: {}",
+                    tr("selfplay needs 1-1000 games and search limits")
+                );
+                return;
+            }
+            None => (args, vec![self.board.clone()]),
+        };
+        let (args, adjudication, use_oracle) =
+            match args.iter().position(|&arg| arg == "adjudicate") {
+                Some(index) => match parse_adjudication(&args[index + 1..]) {
+                    Some((adjudication, use_oracle)) => (&args[..index], adjudication, use_oracle),
+                    None => {
+                        println!(
+                            "ERROR: {}",
+                            tr("adjudicate needs moves <n> and win <cp> or draw <cp>")
+                        );
+                        return;
+                    }
+                },
+                None => (args, Adjudication::default(), false),
+            };
+        if use_oracle && self.oracle.is_none() {
+            println!("ERROR: {}", tr("No oracle running"));
+            return;
+        }
+        let mut sides = args[1..].splitn(2, |&arg| arg == "vs");
+        let first = sides.next().unwrap_or_default();
+        let second = sides.next().unwrap_or(first);
+        let usage = "selfplay needs 1-1000 games and search limits";
+        let players = (
+            match_player(first, &self.options, usage),
+            match_player(second, &self.options, usage),
+        );
+        let (mut first, mut second) = match players {
+            (Ok(first), Ok(second)) => (first, second),
+            (Err(error), _) | (_, Err(error)) => {
+                println!("ERROR: {}", tr(&error));
+                return;
+            }
+        };
+        if let Some(odds) = odds {
+            if !(first.limits.uses_time() && second.limits.uses_time()) {
+                println!(
+                    "ERROR: {}",
+                    tr("Time odds need a movetime or clock on both sides")
+                );
+                return;
+            }
+            odds.apply(&mut first, &mut second);
+        }
+
+        let results = &mut self.results;
+        let fen_parser = &self.fen_parser;
+        let oracle = &mut self.oracle;
+        let mut ask_oracle = |board: &Board| {
+            let answer = oracle
+                .as_mut()?
+                .analyse(&fen_parser.export_fen(board), ORACLE_ADJUDICATION_DEPTH)
+                .ok()?;
+            Some(if board.get_turn() == Color::White {
+                answer.score
+            } else {
+                -answer.score
+            })
+        };
+        let referee = use_oracle.then_some(&mut ask_oracle as Referee);
+        let mut adjudicated = 0;
+        let score = play_match(
+            &mut first,
+            &mut second,
+            games,
+            &openings,
+            &adjudication,
+            referee,
+            |game, start, white, black, record| {
+                if record.termination.starts_with("adjudicated") {
+                    adjudicated += 1;
+                }
+                println!(
+                    "SELFPLAY: game={}; white={}; black={}; result={}; termination={}; plies={}",
+                    game,
+                    white.name,
+                    black.name,
+                    record.result,
+                    record.termination,
+                    record.moves.len()
+                );
+                let stored = results.record(MatchRecord {
+                    timestamp: current_trace_timestamp_ms() as u64 / 1000,
+                    build: build_id(),
+                    white: white.name.clone(),
+                    black: black.name.clone(),
+                    result: record.result,
+                    termination: record.termination.to_string(),
+                    plies: record.moves.len(),
+                    start_fen: fen_parser.export_fen(start),
+                });
+                if stored.is_err() {
+                    println!("ERROR: {}", tr("Cannot write results file"));
+                }
+            },
+        );
+        let adjudicated = if adjudication.is_enabled() {
+            format!("; adjudicated={}", adjudicated)
+        } else {
+            String::new()
+        };
+        println!(
+            "SELFPLAY: games={}; {}: +{} ={} -{}{}",
+            games, first.name, score.wins, score.draws, score.losses, adjudicated
+        );
+    }
+
+    // `ablate <games> [nodes <n>] [term <name>] [openings <file>]`: for each evaluation term, or
+    // just the one named, a fixed-node match of the full evaluation against the term switched off.
+    fn handle_ablate(&mut self, args: &[&str]) {
+        let usage = "ablate needs 1-1000 games, then nodes <n>, term <name> or openings <file>";
+        let Some(games) = args
+            .first()
+            .and_then(|games| games.parse::<u32>().ok())
+            .filter(|games| (1..=MAX_SELFPLAY_GAMES).contains(games))
+        else {
+            println!("ERROR: {}", tr(usage));
+            return;
+        };
+        let mut nodes = DEFAULT_ABLATION_NODES;
+        let mut terms = EVAL_TERMS.to_vec();
+        let mut openings = vec![self.board.clone()];
+        for option in args[1..].chunks(2) {
+            match option {
+                ["nodes", value] => match value.parse::<u64>() {
+                    Ok(value) if value > 0 => nodes = value,
+                    _ => {
+                        println!("ERROR: {}", tr("nodes requires a positive integer"));
+                        return;
+                    }
+                },
+                ["term", name] => match EvalTerm::parse(name) {
+                    Some(term) => terms = vec![term],
+                    None => {
+                        println!("ERROR: {}", tr("Unknown eval term"));
+                        return;
+                    }
+                },
+                ["openings", path] => {
+                    let suite = fs::read_to_string(path)
+                        .map_err(|_| "Cannot read opening suite".to_string())
+                        .and_then(|text| load_suite(&text));
+                    match suite {
+                        Ok(boards) => openings = boards,
+                        Err(error) => {
+                            println!("ERROR: {}", tr(&error));
+                            return;
+                        }
+                    }
+                }
+                _ => {
+                    println!("ERROR: {}", tr(usage));
+                    return;
+                }
+            }
+        }
+
+        let options = &self.options;
+        let engine = || {
+            let mut ai = AI::new();
+            configure_ai(&mut ai, options);
+            ai
+        };
+        let start_time = Instant::now();
+        for &term in &terms {
+            let ablation = ablate(term, &engine, games, nodes, &openings, |_, _, _, _, _| {});
+            println!(
+                "ABLATE: term={}; games={}; +{} ={} -{}; elo={:+.0}; error={:.0}",
+                term.name(),
+                ablation.score.games(),
+                ablation.score.wins,
+                ablation.score.draws,
+                ablation.score.losses,
+                ablation.elo(),
+                ablation.error()
+            );
+        }
+        println!(
+            "ABLATE: terms={}; games={}; nodes={}; openings={}; time={}ms",
+            terms.len(),
+            games * terms.len() as u32,
+            nodes,
+            openings.len(),
+            start_time.elapsed().as_millis()
+        );
+    }
+
+    // `sprt <games> <side> [vs <side>] [elo0 <e>] [elo1 <e>] [alpha <p>] [beta <p>] [openings
+    // <file>]`: a patch, the first side's limits, option values and switches (see
+    // `match_player`), against the base, the second side (the same as the first without `vs`), in
+    // pairs of games until the SPRT decides or `games` are played.
+    fn handle_sprt(&mut self, args: &[&str]) {
+        let usage = "sprt needs 1-100000 games and search limits";
+        let Some(games) = args
+            .first()
+            .and_then(|games| games.parse::<u32>().ok())
+            .filter(|games| (1..=MAX_SPRT_GAMES).contains(games))
+        else {
+            println!("ERROR: {}", tr(usage));
+            return;
+        };
+        let mut bounds = SprtBounds::default();
+        let mut openings = vec![self.board.clone()];
+        let mut sides = Vec::new();
+        let mut rest = &args[1..];
+        while let Some((&arg, tail)) = rest.split_first() {
+            let value = tail.first().copied();
+            let bound = match arg {
+                "elo0" => Some(&mut bounds.elo0),
+                "elo1" => Some(&mut bounds.elo1),
+                "alpha" => Some(&mut bounds.alpha),
+                "beta" => Some(&mut bounds.beta),
+                _ => None,
+            };
+            if let Some(bound) = bound {
+                match value.and_then(|value| value.parse::<f64>().ok()) {
+                    Some(value) if value.is_finite() => *bound = value,
+                    _ => {
+                        println!("ERROR: {}", tr(usage));
+                        return;
+                    }
+                }
+                rest = &tail[1..];
+            } else if arg == "openings" {
+                let suite = fs::read_to_string(value.unwrap_or_default())
+                    .map_err(|_| "Cannot read opening suite".to_string())
+                    .and_then(|text| load_suite(&text));
+                match suite {
+                    Ok(boards) => openings = boards,
+                    Err(error) => {
+                        println!("ERROR: {}", tr(&error));
+                        return;
+                    }
+                }
+                rest = &tail[1..];
+            } else {
+                sides.push(arg);
+                rest = tail;
+            }
+        }
+        if !bounds.is_valid() {
+            println!(
+                "ERROR: {}",
+                tr("sprt needs elo0 below elo1 and alpha and beta between 0 and 0.5")
+            );
+            return;
+        }
+
+        let mut split = sides.splitn(2, |&arg| arg == "vs");
+        let test = split.next().unwrap_or_default();
+        let base = split.next().unwrap_or(test);
+        let players = (
+            match_player(test, &self.options, usage),
+            match_player(base, &self.options, usage),
+        );
+        let (mut test, mut base) = match players {
+            (Ok(test), Ok(base)) => (test, base),
+            (Err(error), _) | (_, Err(error)) => {
+                println!("ERROR: {}", tr(&error));
+                return;
+            }
+        };
+
+        let start_time = Instant::now();
+        let result = sprt::run(
+            &mut test,
+            &mut base,
+            &bounds,
+            games,
+            &openings,
+            |game, _, score, llr| {
+                println!(
+                    "SPRT: game={}; +{} ={} -{}; llr={:.2} ({:.2}, {:.2})",
+                    game,
+                    score.wins,
+                    score.draws,
+                    score.losses,
+                    llr,
+                    bounds.lower(),
+                    bounds.upper()
+                );
+            },
+        );
+        println!(
+            "SPRT: result={}; games={}; +{} ={} -{}; elo={:+.1}; error={:.1}; llr={:.2}; elo0={}; elo1={}; alpha={}; beta={}; time={}ms",
+            result.decision.name(),
+            result.score.games(),
+            result.score.wins,
+            result.score.draws,
+            result.score.losses,
+            result.score.elo(),
+            result.score.error(),
+            result.llr,
+            bounds.elo0,
+            bounds.elo1,
+            bounds.alpha,
+            bounds.beta,
+            start_time.elapsed().as_millis()
+        );
+    }
+
+    // `openings <pgn> <count> <file.epd|file.pgn> [plies <n>] [window <cp>]`
+    fn handle_openings(&mut self, args: &[&str]) {
+        let usage = "openings needs a PGN file, a count of 1-1000 and an output file";
+        let [database, count, output, options @ ..] = args else {
+            println!("ERROR: {}", tr(usage));
+            return;
+        };
+        let Some(count) = count
+            .parse::<usize>()
+            .ok()
+            .filter(|count| (1..=MAX_SELFPLAY_GAMES as usize).contains(count))
+        else {
+            println!("ERROR: {}", tr(usage));
+            return;
+        };
+        let mut config = SuiteConfig::new(count);
+        for option in options.chunks(2) {
+            let valid = match option {
+                ["plies", plies] => plies
+                    .parse()
+                    .ok()
+                    .filter(|plies| (1..=40).contains(plies))
+                    .map(|plies| config.plies = plies),
+                ["window", window] => window
+                    .parse()
+                    .ok()
+                    .filter(|&window| window >= 0)
+                    .map(|window| config.window_cp = window),
+                _ => None,
+            };
+            if valid.is_none() {
+                println!(
+                    "ERROR: {}",
+                    tr("openings options are plies 1-40 and window <cp>")
+                );
+                return;
+            }
+        }
+        let Ok(pgn) = fs::read_to_string(database) else {
+            println!("ERROR: {}", tr("Cannot read PGN database"));
+            return;
+        };
+
+        let report = generate_suite(&mut self.ai, &pgn, &config);
+        let (format, contents) = if output.to_lowercase().ends_with(".pgn") {
+            ("pgn", suite_to_pgn(&report.openings))
+        } else {
+            ("epd", suite_to_epd(&report.openings))
+        };
+        if fs::write(output, contents).is_err() {
+            println!("ERROR: {}", tr("Cannot write opening suite"));
+            return;
+        }
+        println!(
+            "OPENINGS: games={}; candidates={}; evaluated={}; selected={}; eco_codes={}; plies={}; window={}cp; format={}; file={}",
+            report.games,
+            report.candidates,
+            report.evaluated,
+            report.openings.len(),
+            report.eco_codes(),
+            config.plies,
+            config.window_cp,
+            format,
+            output
+        );
+    }
+
+    fn handle_ratings(&self) {
+        let ratings = compute_ratings(&self.results.records);
+        println!(
+            "RATINGS: file={}; games={}; players={}",
+            self.results
+                .path
+                .as_ref()
+                .map_or("none".to_string(), |path| path.display().to_string()),
+            self.results.records.len(),
+            ratings.len()
+        );
+        for (rank, rating) in ratings.iter().enumerate() {
+            println!(
+                "RATING: {} {}; elo={:+.0}; error={:.0}; games={}; score={:.1}%",
+                rank + 1,
+                rating.player,
+                rating.elo,
+                rating.error,
+                rating.games,
+                100.0 * rating.points / f64::from(rating.games.max(1))
+            );
+        }
+    }
+
+    fn handle_profile(&mut self, args: &[&str]) {
+        match args {
+            [] => {}
+            ["blindfold", toggle @ ("on" | "off")] => {
+                self.profile.blindfold = *toggle == "on";
+                self.save_profile();
+            }
+            ["time", "none"] => {
+                self.profile.time_control = None;
+                self.save_profile();
+            }
+            ["time", limits @ ..] if !limits.is_empty() => match SearchLimits::parse(limits) {
+                Ok(_) => {
+                    self.profile.time_control = Some(limits.join(" "));
+                    self.save_profile();
+                }
+                Err(error) => {
+                    println!("ERROR: {}", tr(&error));
+                    return;
+                }
+            },
+            ["reset"] => {
+                self.profile.white = Record::default();
+                self.profile.black = Record::default();
+                self.save_profile();
+            }
+            _ => {
+                println!("ERROR: {}", tr("Unsupported profile command"));
+                return;
             }
         }
+
+        let profile = &self.profile;
+        println!(
+            "PROFILE: file={}; blindfold={}; time={}",
+            profile
+                .path
+                .as_ref()
+                .map_or("none".to_string(), |path| path.display().to_string()),
+            if profile.blindfold { "on" } else { "off" },
+            profile.time_control.as_deref().unwrap_or("none")
+        );
+        let record =
+            |record: &Record| format!("+{} ={} -{}", record.wins, record.draws, record.losses);
+        println!(
+            "PROFILE: games={}; white={}; black={}",
+            profile.white.games() + profile.black.games(),
+            record(&profile.white),
+            record(&profile.black)
+        );
+    }
+}
+
+// Carries the engine options over to a search engine: the REPL's own, or a self-play player's.
+fn configure_ai(ai: &mut AI, options: &Options) {
+    let (hash_mb, large_pages) = options.hash();
+    ai.set_hash(hash_mb, large_pages);
+    ai.set_time_policy(options.time_policy());
+    ai.set_multi_pv(options.multi_pv());
+    ai.set_eval_config(EvalConfig {
+        piece_values: options.piece_values(),
+        ..ai.eval_config()
+    });
+}
+
+// One side of a match: its search limits, then any `<Option>=<value>` to play with instead of
+// the session's value, and `no-<term>` or `no-<feature>` to switch an evaluation term or a
+// search feature off, e.g. `movetime 100 PawnValue=120 no-pawns`. The player is named after all
+// of it. Limits that don't bound both colours' searches are an error worded by `usage`.
+fn match_player(side: &[&str], options: &Options, usage: &str) -> Result<Player, String> {
+    let mut options = options.clone();
+    let mut rest = Vec::new();
+    let mut disabled = Vec::new();
+    for &token in side {
+        if let Some((name, value)) = token.split_once('=') {
+            options.set(name, value)?;
+        } else if let Some(term) = token.strip_prefix("no-").and_then(EvalTerm::parse) {
+            disabled.push(term);
+        } else {
+            rest.push(token);
+        }
     }
-    None
+    let (overrides, limits) = SearchOverrides::split(&rest)?;
+    let limits = SearchLimits::parse(&limits)
+        .ok()
+        .filter(|limits| limits.is_bounded(Color::White) && limits.is_bounded(Color::Black))
+        .ok_or_else(|| usage.to_string())?;
+    let mut ai = AI::new();
+    configure_ai(&mut ai, &options);
+    ai.set_eval_config(
+        disabled
+            .into_iter()
+            .fold(ai.eval_config(), EvalConfig::without),
+    );
+    ai.set_overrides(overrides);
+    Ok(Player::new(&side.join(" "), ai, limits))
+}
+
+// `size=16MB; slots=...; large_pages=on|off`, for `hash table` and the startup report
+fn table_report(table: &TranspositionTable) -> String {
+    format!(
+        "size={}MB; slots={}; large_pages={}",
+        table.size_mb(),
+        table.slots(),
+        if table.uses_large_pages() {
+            "on"
+        } else {
+            "off"
+        }
+    )
 }
 
 fn current_trace_timestamp_ms() -> u128 {
@@ -1080,6 +3863,44 @@ fn current_trace_timestamp_ms() -> u128 {
         .unwrap_or(0)
 }
 
+// `win <cp>`, `draw <cp>` and `moves <n>` in any order, and `oracle` to have the oracle agree too
+fn parse_adjudication(args: &[&str]) -> Option<(Adjudication, bool)> {
+    let mut adjudication = Adjudication::default();
+    let mut use_oracle = false;
+    let mut tokens = args.iter();
+    while let Some(&key) = tokens.next() {
+        if key == "oracle" {
+            use_oracle = true;
+            continue;
+        }
+        let value = tokens.next()?;
+        match key {
+            "win" => adjudication.win_cp = Some(value.parse::<i32>().ok().filter(|&cp| cp > 0)?),
+            "draw" => adjudication.draw_cp = Some(value.parse::<i32>().ok().filter(|&cp| cp >= 0)?),
+            "moves" => adjudication.moves = value.parse::<u32>().ok().filter(|&moves| moves > 0)?,
+            _ => return None,
+        }
+    }
+    adjudication
+        .is_enabled()
+        .then_some((adjudication, use_oracle))
+}
+
+// `perft <depth> [json]`, shared by `divide`
+fn parse_perft_args(args: &[&str]) -> Option<(u8, bool)> {
+    let depth = args
+        .first()?
+        .parse::<u8>()
+        .ok()
+        .filter(|&depth| depth >= 1)?;
+    let json = match &args[1..] {
+        [] => false,
+        ["json"] => true,
+        _ => return None,
+    };
+    Some((depth, json))
+}
+
 fn json_escape(value: &str) -> String {
     value
         .replace('\\', "\\\\")
@@ -1089,7 +3910,101 @@ fn json_escape(value: &str) -> String {
         .replace('\t', "\\t")
 }
 
+// `--lang` wins over `CHESS_LANG`; anything unrecognised falls back to English.
+fn select_language() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let requested = args
+        .iter()
+        .position(|arg| arg == "--lang")
+        .and_then(|index| args.get(index + 1).cloned())
+        .or_else(|| {
+            args.iter()
+                .find_map(|arg| arg.strip_prefix("--lang=").map(str::to_string))
+        })
+        .or_else(|| std::env::var("CHESS_LANG").ok());
+
+    if let Some(code) = requested {
+        match Language::from_code(&code) {
+            Some(language) => i18n::set_language(language),
+            None => eprintln!("Unsupported language '{}', using English", code),
+        }
+    }
+}
+
+// `--profile <file>` or `CHESS_PROFILE`; without either the profile is kept for the session only.
+fn load_profile() -> Option<Profile> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let path = args
+        .iter()
+        .position(|arg| arg == "--profile")
+        .and_then(|index| args.get(index + 1).cloned())
+        .or_else(|| {
+            args.iter()
+                .find_map(|arg| arg.strip_prefix("--profile=").map(str::to_string))
+        })
+        .or_else(|| std::env::var("CHESS_PROFILE").ok())?;
+
+    match Profile::load(PathBuf::from(&path)) {
+        Ok(profile) => Some(profile),
+        Err(_) => {
+            eprintln!("Cannot read profile '{}', starting without one", path);
+            None
+        }
+    }
+}
+
+// `--results <file>` or `CHESS_RESULTS`; without either, results are kept for the session only.
+fn load_results() -> Option<ResultsDb> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let path = args
+        .iter()
+        .position(|arg| arg == "--results")
+        .and_then(|index| args.get(index + 1).cloned())
+        .or_else(|| {
+            args.iter()
+                .find_map(|arg| arg.strip_prefix("--results=").map(str::to_string))
+        })
+        .or_else(|| std::env::var("CHESS_RESULTS").ok())?;
+
+    match ResultsDb::load(PathBuf::from(&path)) {
+        Ok(results) => Some(results),
+        Err(_) => {
+            eprintln!("Cannot read results '{}', starting without them", path);
+            None
+        }
+    }
+}
+
 fn main() {
+    select_language();
+    // `--uci` starts straight in protocol mode, without the board banner, for GUIs that launch
+    // the engine themselves.
+    if std::env::args().skip(1).any(|arg| arg == "--uci") {
+        UciSession::new(Options::new()).run();
+        return;
+    }
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    #[cfg(unix)]
+    if let Some(code) = daemon::run(&args) {
+        std::process::exit(code);
+    }
+    if let Some(code) = oneshot::run(&args) {
+        std::process::exit(code);
+    }
     let mut engine = ChessEngine::new();
+    configure_ai(&mut engine.ai, &engine.options);
+    // On stderr, so the board banner stays the first thing on stdout
+    eprintln!("HASH: {}", table_report(engine.ai.transposition_table()));
+    if let Some(profile) = load_profile() {
+        engine.use_profile(profile);
+    }
+    if let Some(results) = load_results() {
+        engine.results = results;
+    }
+    let capabilities = capabilities::probe_all();
+    for warning in capabilities.iter().filter_map(Capability::warning) {
+        eprintln!("{}", warning);
+    }
+    engine.use_capabilities(capabilities);
     engine.run();
 }
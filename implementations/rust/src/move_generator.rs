@@ -1,21 +1,50 @@
 use crate::attack_tables::{ray_table, KING_ATTACKS, KNIGHT_ATTACKS};
+use crate::bitboard::{Bitboard, Direction};
 use crate::board::Board;
+use crate::sliding_attacks;
 use crate::types::*;
 
 pub struct MoveGenerator;
 
+// Which pseudo-legal moves to generate. Noisy moves are captures (en passant included) and
+// promotions; everything else, castling included, is quiet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveKind {
+    All,
+    Noisy,
+    Quiet,
+}
+
+impl MoveKind {
+    fn wants(self, noisy: bool) -> bool {
+        match self {
+            MoveKind::All => true,
+            MoveKind::Noisy => noisy,
+            MoveKind::Quiet => !noisy,
+        }
+    }
+}
+
+pub fn is_noisy(chess_move: &Move) -> bool {
+    chess_move.captured.is_some() || chess_move.promotion.is_some()
+}
+
 impl MoveGenerator {
     pub fn new() -> Self {
         Self
     }
 
     pub fn generate_moves(&self, board: &Board, color: Color) -> Vec<Move> {
+        self.generate_moves_of(board, color, MoveKind::All)
+    }
+
+    pub fn generate_moves_of(&self, board: &Board, color: Color, kind: MoveKind) -> Vec<Move> {
         let mut moves = Vec::new();
 
         for square in 0..64 {
             if let Some(piece) = board.get_piece(square) {
                 if piece.color == color {
-                    moves.extend(self.generate_piece_moves(board, square, piece));
+                    moves.extend(self.generate_piece_moves(board, square, piece, kind));
                 }
             }
         }
@@ -23,20 +52,46 @@ impl MoveGenerator {
         moves
     }
 
-    fn generate_piece_moves(&self, board: &Board, from: Square, piece: Piece) -> Vec<Move> {
+    // Whether `chess_move` is one the side to move could play, ignoring checks. Moves from the
+    // transposition table or the killer slots may come from another position, so they are checked
+    // against the moving piece's own moves before being tried.
+    pub fn is_pseudo_legal(&self, board: &Board, chess_move: &Move) -> bool {
+        let Some(piece) = board.get_piece(chess_move.from) else {
+            return false;
+        };
+        piece.color == board.get_turn()
+            && piece.piece_type == chess_move.piece
+            && self
+                .generate_piece_moves(board, chess_move.from, piece, MoveKind::All)
+                .contains(chess_move)
+    }
+
+    fn generate_piece_moves(
+        &self,
+        board: &Board,
+        from: Square,
+        piece: Piece,
+        kind: MoveKind,
+    ) -> Vec<Move> {
         match piece.piece_type {
-            PieceType::Pawn => self.generate_pawn_moves(board, from, piece.color),
-            PieceType::Knight => self.generate_knight_moves(board, from, piece.color),
-            PieceType::Bishop => self.generate_bishop_moves(board, from, piece.color),
-            PieceType::Rook => self.generate_rook_moves(board, from, piece.color),
-            PieceType::Queen => self.generate_queen_moves(board, from, piece.color),
-            PieceType::King => self.generate_king_moves(board, from, piece.color),
+            PieceType::Pawn => self.generate_pawn_moves(board, from, piece.color, kind),
+            PieceType::Knight => self.generate_knight_moves(board, from, piece.color, kind),
+            PieceType::Bishop => self.generate_bishop_moves(board, from, piece.color, kind),
+            PieceType::Rook => self.generate_rook_moves(board, from, piece.color, kind),
+            PieceType::Queen => self.generate_queen_moves(board, from, piece.color, kind),
+            PieceType::King => self.generate_king_moves(board, from, piece.color, kind),
         }
     }
 
-    fn generate_pawn_moves(&self, board: &Board, from: Square, color: Color) -> Vec<Move> {
+    fn generate_pawn_moves(
+        &self,
+        board: &Board,
+        from: Square,
+        color: Color,
+        kind: MoveKind,
+    ) -> Vec<Move> {
         let mut moves = Vec::new();
-        let direction = if color == Color::White { 8 } else { -8i32 } as i32;
+        let direction = if color == Color::White { 8 } else { -8i32 };
         let start_rank = if color == Color::White { 1 } else { 6 };
         let promotion_rank = if color == Color::White { 7 } else { 0 };
 
@@ -49,6 +104,10 @@ impl MoveGenerator {
         if self.is_valid_square(one_forward) && board.get_piece(one_forward as usize).is_none() {
             let to = one_forward as usize;
             if to / 8 == promotion_rank {
+                // Every other move a pawn this far up has is noisy too
+                if !kind.wants(true) {
+                    return moves;
+                }
                 // Promotion moves
                 for promotion_piece in [
                     PieceType::Queen,
@@ -59,12 +118,12 @@ impl MoveGenerator {
                     moves
                         .push(Move::new(from, to, PieceType::Pawn).with_promotion(promotion_piece));
                 }
-            } else {
+            } else if kind.wants(false) {
                 moves.push(Move::new(from, to, PieceType::Pawn));
             }
 
             // Two squares forward from starting position
-            if rank == start_rank {
+            if rank == start_rank && kind.wants(false) {
                 let two_forward = from_i32 + 2 * direction;
                 if self.is_valid_square(two_forward)
                     && board.get_piece(one_forward as usize).is_none()
@@ -75,6 +134,11 @@ impl MoveGenerator {
             }
         }
 
+        // Captures and en passant are all noisy
+        if !kind.wants(true) {
+            return moves;
+        }
+
         // Captures
         for &offset in &[direction - 1, direction + 1] {
             let to = from_i32 + offset;
@@ -130,12 +194,20 @@ impl MoveGenerator {
         moves
     }
 
-    fn generate_knight_moves(&self, board: &Board, from: Square, color: Color) -> Vec<Move> {
+    fn generate_knight_moves(
+        &self,
+        board: &Board,
+        from: Square,
+        color: Color,
+        kind: MoveKind,
+    ) -> Vec<Move> {
         let mut moves = Vec::new();
         for &to_square in KNIGHT_ATTACKS[from].as_slice() {
             match board.get_piece(to_square) {
-                None => moves.push(Move::new(from, to_square, PieceType::Knight)),
-                Some(piece) if piece.color != color => {
+                None if kind.wants(false) => {
+                    moves.push(Move::new(from, to_square, PieceType::Knight))
+                }
+                Some(piece) if piece.color != color && kind.wants(true) => {
                     moves.push(
                         Move::new(from, to_square, PieceType::Knight)
                             .with_capture(piece.piece_type),
@@ -148,30 +220,57 @@ impl MoveGenerator {
         moves
     }
 
-    fn generate_bishop_moves(&self, board: &Board, from: Square, color: Color) -> Vec<Move> {
-        self.generate_sliding_moves(board, from, color, &[-9, -7, 7, 9], PieceType::Bishop)
+    fn generate_bishop_moves(
+        &self,
+        board: &Board,
+        from: Square,
+        color: Color,
+        kind: MoveKind,
+    ) -> Vec<Move> {
+        self.generate_sliding_moves(board, from, color, &[-9, -7, 7, 9], PieceType::Bishop, kind)
     }
 
-    fn generate_rook_moves(&self, board: &Board, from: Square, color: Color) -> Vec<Move> {
-        self.generate_sliding_moves(board, from, color, &[-8, -1, 1, 8], PieceType::Rook)
+    fn generate_rook_moves(
+        &self,
+        board: &Board,
+        from: Square,
+        color: Color,
+        kind: MoveKind,
+    ) -> Vec<Move> {
+        self.generate_sliding_moves(board, from, color, &[-8, -1, 1, 8], PieceType::Rook, kind)
     }
 
-    fn generate_queen_moves(&self, board: &Board, from: Square, color: Color) -> Vec<Move> {
+    fn generate_queen_moves(
+        &self,
+        board: &Board,
+        from: Square,
+        color: Color,
+        kind: MoveKind,
+    ) -> Vec<Move> {
         self.generate_sliding_moves(
             board,
             from,
             color,
             &[-9, -8, -7, -1, 1, 7, 8, 9],
             PieceType::Queen,
+            kind,
         )
     }
 
-    fn generate_king_moves(&self, board: &Board, from: Square, color: Color) -> Vec<Move> {
+    fn generate_king_moves(
+        &self,
+        board: &Board,
+        from: Square,
+        color: Color,
+        kind: MoveKind,
+    ) -> Vec<Move> {
         let mut moves = Vec::new();
         for &to_square in KING_ATTACKS[from].as_slice() {
             match board.get_piece(to_square) {
-                None => moves.push(Move::new(from, to_square, PieceType::King)),
-                Some(piece) if piece.color != color => {
+                None if kind.wants(false) => {
+                    moves.push(Move::new(from, to_square, PieceType::King))
+                }
+                Some(piece) if piece.color != color && kind.wants(true) => {
                     moves.push(
                         Move::new(from, to_square, PieceType::King).with_capture(piece.piece_type),
                     );
@@ -181,70 +280,65 @@ impl MoveGenerator {
         }
 
         // Castling
+        if !kind.wants(false) {
+            return moves;
+        }
         let rights = board.get_castling_rights();
         if color == Color::White && from == 4 {
             // White kingside
             if rights.white_kingside
                 && board.get_piece(5).is_none()
                 && board.get_piece(6).is_none()
-                && board.get_piece(7).map_or(false, |p| {
-                    p.piece_type == PieceType::Rook && p.color == Color::White
-                })
+                && board
+                    .get_piece(7)
+                    .is_some_and(|p| p.piece_type == PieceType::Rook && p.color == Color::White)
+                && !self.is_square_attacked(board, 4, Color::Black)
+                && !self.is_square_attacked(board, 5, Color::Black)
+                && !self.is_square_attacked(board, 6, Color::Black)
             {
-                if !self.is_square_attacked(board, 4, Color::Black)
-                    && !self.is_square_attacked(board, 5, Color::Black)
-                    && !self.is_square_attacked(board, 6, Color::Black)
-                {
-                    moves.push(Move::new(4, 6, PieceType::King).with_castling());
-                }
+                moves.push(Move::new(4, 6, PieceType::King).with_castling());
             }
             // White queenside
             if rights.white_queenside
                 && board.get_piece(3).is_none()
                 && board.get_piece(2).is_none()
                 && board.get_piece(1).is_none()
-                && board.get_piece(0).map_or(false, |p| {
-                    p.piece_type == PieceType::Rook && p.color == Color::White
-                })
+                && board
+                    .get_piece(0)
+                    .is_some_and(|p| p.piece_type == PieceType::Rook && p.color == Color::White)
+                && !self.is_square_attacked(board, 4, Color::Black)
+                && !self.is_square_attacked(board, 3, Color::Black)
+                && !self.is_square_attacked(board, 2, Color::Black)
             {
-                if !self.is_square_attacked(board, 4, Color::Black)
-                    && !self.is_square_attacked(board, 3, Color::Black)
-                    && !self.is_square_attacked(board, 2, Color::Black)
-                {
-                    moves.push(Move::new(4, 2, PieceType::King).with_castling());
-                }
+                moves.push(Move::new(4, 2, PieceType::King).with_castling());
             }
         } else if color == Color::Black && from == 60 {
             // Black kingside
             if rights.black_kingside
                 && board.get_piece(61).is_none()
                 && board.get_piece(62).is_none()
-                && board.get_piece(63).map_or(false, |p| {
-                    p.piece_type == PieceType::Rook && p.color == Color::Black
-                })
+                && board
+                    .get_piece(63)
+                    .is_some_and(|p| p.piece_type == PieceType::Rook && p.color == Color::Black)
+                && !self.is_square_attacked(board, 60, Color::White)
+                && !self.is_square_attacked(board, 61, Color::White)
+                && !self.is_square_attacked(board, 62, Color::White)
             {
-                if !self.is_square_attacked(board, 60, Color::White)
-                    && !self.is_square_attacked(board, 61, Color::White)
-                    && !self.is_square_attacked(board, 62, Color::White)
-                {
-                    moves.push(Move::new(60, 62, PieceType::King).with_castling());
-                }
+                moves.push(Move::new(60, 62, PieceType::King).with_castling());
             }
             // Black queenside
             if rights.black_queenside
                 && board.get_piece(59).is_none()
                 && board.get_piece(58).is_none()
                 && board.get_piece(57).is_none()
-                && board.get_piece(56).map_or(false, |p| {
-                    p.piece_type == PieceType::Rook && p.color == Color::Black
-                })
+                && board
+                    .get_piece(56)
+                    .is_some_and(|p| p.piece_type == PieceType::Rook && p.color == Color::Black)
+                && !self.is_square_attacked(board, 60, Color::White)
+                && !self.is_square_attacked(board, 59, Color::White)
+                && !self.is_square_attacked(board, 58, Color::White)
             {
-                if !self.is_square_attacked(board, 60, Color::White)
-                    && !self.is_square_attacked(board, 59, Color::White)
-                    && !self.is_square_attacked(board, 58, Color::White)
-                {
-                    moves.push(Move::new(60, 58, PieceType::King).with_castling());
-                }
+                moves.push(Move::new(60, 58, PieceType::King).with_castling());
             }
         }
 
@@ -258,6 +352,7 @@ impl MoveGenerator {
         color: Color,
         directions: &[i32],
         piece_type: PieceType,
+        kind: MoveKind,
     ) -> Vec<Move> {
         let mut moves = Vec::new();
 
@@ -265,10 +360,12 @@ impl MoveGenerator {
             for &to_square in ray_table(direction)[from].as_slice() {
                 match board.get_piece(to_square) {
                     None => {
-                        moves.push(Move::new(from, to_square, piece_type));
+                        if kind.wants(false) {
+                            moves.push(Move::new(from, to_square, piece_type));
+                        }
                     }
                     Some(piece) => {
-                        if piece.color != color {
+                        if piece.color != color && kind.wants(true) {
                             moves.push(
                                 Move::new(from, to_square, piece_type)
                                     .with_capture(piece.piece_type),
@@ -291,7 +388,7 @@ impl MoveGenerator {
         for &file_offset in &[-1, 1] {
             let p_row = row as i32 + pawn_direction;
             let p_file = file as i32 + file_offset;
-            if p_row >= 0 && p_row < 8 && p_file >= 0 && p_file < 8 {
+            if (0..8).contains(&p_row) && (0..8).contains(&p_file) {
                 let p_square = (p_row * 8 + p_file) as usize;
                 if let Some(piece) = board.get_piece(p_square) {
                     if piece.color == by_color && piece.piece_type == PieceType::Pawn {
@@ -350,6 +447,98 @@ impl MoveGenerator {
         false
     }
 
+    pub fn attackers_of(&self, board: &Board, square: Square, by_color: Color) -> Vec<Square> {
+        let mut attackers = Vec::new();
+        let (row, file) = (square / 8, square % 8);
+
+        let pawn_direction = if by_color == Color::White { -1 } else { 1 };
+        for &file_offset in &[-1, 1] {
+            let p_row = row as i32 + pawn_direction;
+            let p_file = file as i32 + file_offset;
+            if (0..8).contains(&p_row) && (0..8).contains(&p_file) {
+                let p_square = (p_row * 8 + p_file) as usize;
+                if board.get_piece(p_square) == Some(Piece::new(PieceType::Pawn, by_color)) {
+                    attackers.push(p_square);
+                }
+            }
+        }
+
+        for &attacker_square in KNIGHT_ATTACKS[square].as_slice() {
+            if board.get_piece(attacker_square) == Some(Piece::new(PieceType::Knight, by_color)) {
+                attackers.push(attacker_square);
+            }
+        }
+
+        for &direction in &[-9, -8, -7, -1, 1, 7, 8, 9] {
+            let is_rook_type = matches!(direction, -8 | -1 | 1 | 8);
+            for &attacker_square in ray_table(direction)[square].as_slice() {
+                if let Some(piece) = board.get_piece(attacker_square) {
+                    if piece.color == by_color
+                        && (piece.piece_type == PieceType::Queen
+                            || (is_rook_type && piece.piece_type == PieceType::Rook)
+                            || (!is_rook_type && piece.piece_type == PieceType::Bishop))
+                    {
+                        attackers.push(attacker_square);
+                    }
+                    break;
+                }
+            }
+        }
+
+        for &attacker_square in KING_ATTACKS[square].as_slice() {
+            if board.get_piece(attacker_square) == Some(Piece::new(PieceType::King, by_color)) {
+                attackers.push(attacker_square);
+            }
+        }
+
+        attackers
+    }
+
+    pub fn attacks_from(&self, board: &Board, square: Square) -> Vec<Square> {
+        self.attacks(board, square).squares().collect()
+    }
+
+    // Squares the piece on `square` attacks, stopping sliders at the first blocker (which is
+    // included whatever its colour).
+    pub fn attacks(&self, board: &Board, square: Square) -> Bitboard {
+        self.attacks_with(board, square, board.occupied())
+    }
+
+    // `attacks` with the board's occupancy already worked out
+    fn attacks_with(&self, board: &Board, square: Square, occupied: Bitboard) -> Bitboard {
+        let Some(piece) = board.get_piece(square) else {
+            return Bitboard::EMPTY;
+        };
+
+        match piece.piece_type {
+            PieceType::Pawn => {
+                let from = Bitboard::from_square(square);
+                let (east, west) = if piece.color == Color::White {
+                    (Direction::NorthEast, Direction::NorthWest)
+                } else {
+                    (Direction::SouthEast, Direction::SouthWest)
+                };
+                from.shift(east) | from.shift(west)
+            }
+            PieceType::Knight => KNIGHT_ATTACKS[square].as_slice().iter().copied().collect(),
+            PieceType::King => KING_ATTACKS[square].as_slice().iter().copied().collect(),
+            PieceType::Bishop => sliding_attacks::tables().bishop(square, occupied),
+            PieceType::Rook => sliding_attacks::tables().rook(square, occupied),
+            PieceType::Queen => sliding_attacks::tables().queen(square, occupied),
+        }
+    }
+
+    // Every square attacked by at least one of `color`'s pieces.
+    pub fn attack_map(&self, board: &Board, color: Color) -> Bitboard {
+        let occupied = board.occupied();
+        board
+            .occupied_by(color)
+            .squares()
+            .fold(Bitboard::EMPTY, |map, square| {
+                map | self.attacks_with(board, square, occupied)
+            })
+    }
+
     pub fn is_in_check(&self, board: &Board, color: Color) -> bool {
         for square in 0..64 {
             if let Some(piece) = board.get_piece(square) {
@@ -376,7 +565,73 @@ impl MoveGenerator {
         legal_moves
     }
 
+    // Game-end checks only need to know whether any move exists, so stop at the first one.
+    pub fn has_legal_move(&self, board: &mut Board, color: Color) -> bool {
+        for square in 0..64 {
+            let Some(piece) = board.get_piece(square) else {
+                continue;
+            };
+            if piece.color != color {
+                continue;
+            }
+
+            for chess_move in self.generate_piece_moves(board, square, piece, MoveKind::All) {
+                board.make_move(&chess_move);
+                let legal = !self.is_in_check(board, color);
+                board.undo_move();
+                if legal {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
     fn is_valid_square(&self, square: i32) -> bool {
-        square >= 0 && square < 64
+        (0..64).contains(&square)
+    }
+}
+
+impl Default for MoveGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::{board_from, FenParser};
+
+    #[test]
+    fn has_legal_move_agrees_with_full_generation() {
+        let generator = MoveGenerator::new();
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1",
+            "6k1/5ppp/8/8/8/8/8/R5K1 b - - 0 1",
+            "R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1",
+        ] {
+            let mut board = board_from(fen);
+            let color = board.get_turn();
+            let expected = !generator.get_legal_moves(&mut board, color).is_empty();
+            assert_eq!(
+                generator.has_legal_move(&mut board, color),
+                expected,
+                "{}",
+                fen
+            );
+            assert_eq!(FenParser::new().export_fen(&board), fen);
+        }
+    }
+    #[test]
+    fn attack_map_covers_every_attacked_square() {
+        let generator = MoveGenerator::new();
+        let board = Board::new();
+        let white = generator.attack_map(&board, Color::White);
+        assert_eq!(white.count(), 22);
+        assert!(white.contains(16) && !white.contains(0) && !white.contains(24));
+        assert_eq!(generator.attacks_from(&board, 1), vec![11, 16, 18]);
     }
 }
@@ -0,0 +1,258 @@
+use crate::types::*;
+
+pub const MAX_PLY: usize = 128;
+
+const TT_MOVE_SCORE: i32 = 1_000_000;
+const CAPTURE_SCORE: i32 = 100_000;
+const PROMOTION_SCORE: i32 = 90_000;
+const KILLER_SCORE: i32 = 80_000;
+// History scores stay within +/- this, below the killers.
+const HISTORY_MAX: i32 = 16_384;
+
+// TT move first, then captures by MVV-LVA, promotions, the two killer moves of the ply, and the
+// remaining quiet moves by history: how often the same piece moving to the same square has
+// caused a cutoff, for each side.
+pub struct MoveOrderer {
+    killers: Vec<[Option<Move>; 2]>,
+    history: [[[i32; 64]; 6]; 2],
+}
+
+impl MoveOrderer {
+    pub fn new() -> Self {
+        Self {
+            killers: vec![[None, None]; MAX_PLY],
+            history: [[[0; 64]; 6]; 2],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.killers
+            .iter_mut()
+            .for_each(|slot| *slot = [None, None]);
+        self.history = [[[0; 64]; 6]; 2];
+    }
+
+    // Killers belong to one search's plies, so they go; history carries over at half weight so
+    // that what was learned keeps helping without outvoting the new position.
+    pub fn new_search(&mut self) {
+        self.killers
+            .iter_mut()
+            .for_each(|slot| *slot = [None, None]);
+        self.history
+            .iter_mut()
+            .flatten()
+            .flatten()
+            .for_each(|score| *score /= 2);
+    }
+
+    pub fn order_moves(
+        &self,
+        moves: &mut [Move],
+        tt_move: Option<&Move>,
+        ply: usize,
+        color: Color,
+    ) {
+        moves.sort_by_cached_key(|chess_move| -self.score_move(chess_move, tt_move, ply, color));
+    }
+
+    pub fn record_killer(&mut self, chess_move: &Move, ply: usize) {
+        if chess_move.captured.is_some() || ply >= MAX_PLY {
+            return;
+        }
+        let slot = &mut self.killers[ply];
+        if slot[0].as_ref() != Some(chess_move) {
+            slot[1] = slot[0].take();
+            slot[0] = Some(chess_move.clone());
+        }
+    }
+
+    pub fn killers(&self, ply: usize) -> [Option<Move>; 2] {
+        self.killers.get(ply).cloned().unwrap_or([None, None])
+    }
+
+    // A quiet move that caused a cutoff gains depth^2; the quiet moves searched before it lose
+    // as much. The update shrinks as a score nears the cap, which also makes old results fade.
+    pub fn record_history(&mut self, color: Color, cutoff: &Move, earlier: &[Move], depth: u8) {
+        if !is_quiet(cutoff) {
+            return;
+        }
+        let bonus = (i32::from(depth) * i32::from(depth)).min(HISTORY_MAX);
+        self.update_history(color, cutoff, bonus);
+        for chess_move in earlier.iter().filter(|chess_move| is_quiet(chess_move)) {
+            self.update_history(color, chess_move, -bonus);
+        }
+    }
+
+    fn update_history(&mut self, color: Color, chess_move: &Move, bonus: i32) {
+        let score = &mut self.history[color as usize][chess_move.piece as usize][chess_move.to];
+        *score += bonus - *score * bonus.abs() / HISTORY_MAX;
+    }
+
+    fn history(&self, color: Color, chess_move: &Move) -> i32 {
+        self.history[color as usize][chess_move.piece as usize][chess_move.to]
+    }
+
+    fn score_move(
+        &self,
+        chess_move: &Move,
+        tt_move: Option<&Move>,
+        ply: usize,
+        color: Color,
+    ) -> i32 {
+        self.score_breakdown(chess_move, tt_move, ply, color)
+            .total()
+    }
+
+    // Every heuristic's opinion of the move, including ones outranked by a higher tier.
+    pub fn score_breakdown(
+        &self,
+        chess_move: &Move,
+        tt_move: Option<&Move>,
+        ply: usize,
+        color: Color,
+    ) -> OrderingScore {
+        let tt = if tt_move == Some(chess_move) {
+            TT_MOVE_SCORE
+        } else {
+            0
+        };
+        let mvv_lva = chess_move.captured.map_or(0, |victim| {
+            // The king's nominal 20000 would swamp the victim term, so rank it just above a queen.
+            let attacker = chess_move.piece.value().min(PieceType::Queen.value() + 100);
+            CAPTURE_SCORE + victim.value() * 10 - attacker
+        });
+        let promotion = chess_move
+            .promotion
+            .map_or(0, |promotion| PROMOTION_SCORE + promotion.value());
+        let killer = match self.killers.get(ply) {
+            Some([Some(first), _]) if first == chess_move => KILLER_SCORE,
+            Some([_, Some(second)]) if second == chess_move => KILLER_SCORE - 1,
+            _ => 0,
+        };
+        let history = if is_quiet(chess_move) {
+            self.history(color, chess_move)
+        } else {
+            0
+        };
+        OrderingScore {
+            tt,
+            mvv_lva,
+            promotion,
+            killer,
+            history,
+        }
+    }
+}
+
+fn is_quiet(chess_move: &Move) -> bool {
+    chess_move.captured.is_none() && chess_move.promotion.is_none()
+}
+
+impl Default for MoveOrderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderingScore {
+    pub tt: i32,
+    pub mvv_lva: i32,
+    pub promotion: i32,
+    pub killer: i32,
+    pub history: i32,
+}
+
+impl OrderingScore {
+    // The tiers don't add up: the first heuristic that applies decides the move's rank.
+    pub fn total(&self) -> i32 {
+        [
+            self.tt,
+            self.mvv_lva,
+            self.promotion,
+            self.killer,
+            self.history,
+        ]
+        .into_iter()
+        .find(|&score| score != 0)
+        .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_tt_move_captures_and_killers_ahead_of_quiet_moves() {
+        let quiet = Move::new(1, 18, PieceType::Knight);
+        let killer = Move::new(6, 21, PieceType::Knight);
+        let pawn_takes_queen = Move::new(12, 19, PieceType::Pawn).with_capture(PieceType::Queen);
+        let queen_takes_pawn = Move::new(3, 11, PieceType::Queen).with_capture(PieceType::Pawn);
+        let tt_move = Move::new(12, 28, PieceType::Pawn);
+
+        let mut orderer = MoveOrderer::new();
+        orderer.record_killer(&killer, 3);
+
+        let mut moves = vec![
+            quiet.clone(),
+            queen_takes_pawn.clone(),
+            killer.clone(),
+            tt_move.clone(),
+            pawn_takes_queen.clone(),
+        ];
+        orderer.order_moves(&mut moves, Some(&tt_move), 3, Color::White);
+        assert_eq!(
+            moves,
+            vec![tt_move, pawn_takes_queen, queen_takes_pawn, killer, quiet]
+        );
+    }
+
+    #[test]
+    fn breakdown_keeps_outranked_heuristics_visible() {
+        let capture_promotion = Move::new(52, 61, PieceType::Pawn)
+            .with_capture(PieceType::Rook)
+            .with_promotion(PieceType::Queen);
+        let score = MoveOrderer::new().score_breakdown(&capture_promotion, None, 0, Color::White);
+        assert_eq!(score.tt, 0);
+        assert_eq!(score.promotion, PROMOTION_SCORE + PieceType::Queen.value());
+        assert_eq!(score.total(), score.mvv_lva);
+    }
+
+    #[test]
+    fn history_sorts_quiet_moves_per_side_and_ages() {
+        let knight = Move::new(6, 21, PieceType::Knight);
+        let bishop = Move::new(5, 12, PieceType::Bishop);
+        let pawn = Move::new(12, 28, PieceType::Pawn);
+
+        let mut orderer = MoveOrderer::new();
+        orderer.record_history(Color::White, &knight, std::slice::from_ref(&pawn), 4);
+        orderer.record_history(Color::White, &bishop, &[], 2);
+
+        let mut moves = vec![pawn.clone(), bishop.clone(), knight.clone()];
+        orderer.order_moves(&mut moves, None, 0, Color::White);
+        assert_eq!(moves, vec![knight.clone(), bishop, pawn.clone()]);
+        assert!(
+            orderer
+                .score_breakdown(&pawn, None, 0, Color::White)
+                .history
+                < 0
+        );
+        // Black's table is separate
+        assert_eq!(
+            orderer
+                .score_breakdown(&knight, None, 0, Color::Black)
+                .history,
+            0
+        );
+
+        let before = orderer
+            .score_breakdown(&knight, None, 0, Color::White)
+            .history;
+        orderer.new_search();
+        let after = orderer
+            .score_breakdown(&knight, None, 0, Color::White)
+            .history;
+        assert_eq!(after, before / 2);
+    }
+}
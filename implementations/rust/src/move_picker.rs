@@ -0,0 +1,241 @@
+use crate::board::Board;
+use crate::move_generator::{is_noisy, MoveGenerator, MoveKind};
+use crate::move_ordering::MoveOrderer;
+use crate::types::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    TtMove,
+    GoodCaptures,
+    Killers,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+// Hands out a node's pseudo-legal moves a stage at a time: the TT move, captures and promotions
+// that don't obviously lose material, the killers, the quiet moves by history, and last the
+// captures that do. Each stage is only generated once the previous one is used up, so a cutoff
+// on the TT move or a capture never pays for the quiet moves. Legality is the caller's to check.
+pub struct MovePicker {
+    stage: Stage,
+    tt_move: Option<Move>,
+    // Killers actually handed out, kept out of the quiet stage
+    killers: [Option<Move>; 2],
+    ply: usize,
+    color: Color,
+    moves: Vec<Move>,
+    next: usize,
+    loaded: bool,
+    bad_captures: Vec<Move>,
+}
+
+impl MovePicker {
+    pub fn new(tt_move: Option<Move>, ply: usize, color: Color) -> Self {
+        Self {
+            stage: Stage::TtMove,
+            tt_move,
+            killers: [None, None],
+            ply,
+            color,
+            moves: Vec::new(),
+            next: 0,
+            loaded: false,
+            bad_captures: Vec::new(),
+        }
+    }
+
+    // The stage the last move handed out came from
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    pub fn next(
+        &mut self,
+        board: &Board,
+        generator: &MoveGenerator,
+        orderer: &MoveOrderer,
+    ) -> Option<Move> {
+        loop {
+            if !self.loaded {
+                self.load(board, generator, orderer);
+                self.loaded = true;
+            }
+            if let Some(chess_move) = self.moves.get(self.next) {
+                self.next += 1;
+                return Some(chess_move.clone());
+            }
+            if self.stage == Stage::Done {
+                return None;
+            }
+            self.stage = match self.stage {
+                Stage::TtMove => Stage::GoodCaptures,
+                Stage::GoodCaptures => Stage::Killers,
+                Stage::Killers => Stage::Quiets,
+                Stage::Quiets => Stage::BadCaptures,
+                Stage::BadCaptures | Stage::Done => Stage::Done,
+            };
+            self.moves.clear();
+            self.next = 0;
+            self.loaded = false;
+        }
+    }
+
+    fn load(&mut self, board: &Board, generator: &MoveGenerator, orderer: &MoveOrderer) {
+        match self.stage {
+            Stage::TtMove => {
+                if let Some(tt_move) = &self.tt_move {
+                    if generator.is_pseudo_legal(board, tt_move) {
+                        self.moves.push(tt_move.clone());
+                    } else {
+                        self.tt_move = None;
+                    }
+                }
+            }
+            Stage::GoodCaptures => {
+                let mut captures = generator.generate_moves_of(board, self.color, MoveKind::Noisy);
+                captures.retain(|chess_move| Some(chess_move) != self.tt_move.as_ref());
+                orderer.order_moves(&mut captures, None, self.ply, self.color);
+                for chess_move in captures {
+                    if self.loses_material(board, generator, &chess_move) {
+                        self.bad_captures.push(chess_move);
+                    } else {
+                        self.moves.push(chess_move);
+                    }
+                }
+            }
+            Stage::Killers => {
+                for (slot, killer) in orderer.killers(self.ply).into_iter().enumerate() {
+                    let Some(killer) = killer else {
+                        continue;
+                    };
+                    if !is_noisy(&killer)
+                        && Some(&killer) != self.tt_move.as_ref()
+                        && generator.is_pseudo_legal(board, &killer)
+                    {
+                        self.moves.push(killer.clone());
+                        self.killers[slot] = Some(killer);
+                    }
+                }
+            }
+            Stage::Quiets => {
+                let mut quiets = generator.generate_moves_of(board, self.color, MoveKind::Quiet);
+                quiets.retain(|chess_move| {
+                    Some(chess_move) != self.tt_move.as_ref()
+                        && !self
+                            .killers
+                            .iter()
+                            .any(|killer| killer.as_ref() == Some(chess_move))
+                });
+                orderer.order_moves(&mut quiets, None, self.ply, self.color);
+                self.moves = quiets;
+            }
+            Stage::BadCaptures => self.moves = std::mem::take(&mut self.bad_captures),
+            Stage::Done => {}
+        }
+    }
+
+    // Without an exchange evaluator, a capture counts as losing when a more valuable piece takes
+    // a defended one. Promotions and king captures (which can't be of a defended piece legally)
+    // are never put off.
+    fn loses_material(&self, board: &Board, generator: &MoveGenerator, chess_move: &Move) -> bool {
+        let Some(victim) = chess_move.captured else {
+            return false;
+        };
+        chess_move.promotion.is_none()
+            && chess_move.piece != PieceType::King
+            && chess_move.piece.value() > victim.value()
+            && generator.is_square_attacked(board, chess_move.to, self.color.opposite())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::board_from;
+
+    fn drain(picker: &mut MovePicker, board: &Board, orderer: &MoveOrderer) -> Vec<(Stage, Move)> {
+        let generator = MoveGenerator::new();
+        let mut moves = Vec::new();
+        while let Some(chess_move) = picker.next(board, &generator, orderer) {
+            moves.push((picker.stage(), chess_move));
+        }
+        moves
+    }
+
+    #[test]
+    fn yields_every_move_once_in_stage_order() {
+        let generator = MoveGenerator::new();
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        ] {
+            let board = board_from(fen);
+            let color = board.get_turn();
+            let all = generator.generate_moves(&board, color);
+            let mut orderer = MoveOrderer::new();
+            let killer = all.iter().find(|chess_move| !is_noisy(chess_move)).cloned();
+            if let Some(killer) = &killer {
+                orderer.record_killer(killer, 2);
+            }
+            let tt_move = all.last().cloned();
+
+            let picked = drain(
+                &mut MovePicker::new(tt_move.clone(), 2, color),
+                &board,
+                &orderer,
+            );
+            assert_eq!(picked.len(), all.len(), "{}", fen);
+            assert!(
+                all.iter()
+                    .all(|chess_move| picked.iter().any(|(_, picked)| picked == chess_move)),
+                "{}",
+                fen
+            );
+            assert_eq!(
+                picked.first().map(|(_, chess_move)| chess_move),
+                tt_move.as_ref()
+            );
+            assert!(
+                picked.windows(2).all(|pair| pair[0].0 <= pair[1].0),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn puts_off_losing_captures_and_ignores_stale_moves() {
+        // The e6 pawn guards d5, so Qxd5 waits until last while exd5 comes first
+        let board = board_from("4k3/8/4p3/3p4/4P3/8/3Q4/4K3 w - - 0 1");
+        let queen_takes = Move::new(11, 35, PieceType::Queen).with_capture(PieceType::Pawn);
+        let pawn_takes = Move::new(28, 35, PieceType::Pawn).with_capture(PieceType::Pawn);
+        // From another position: no knight stands on g1
+        let stale = Move::new(6, 21, PieceType::Knight);
+
+        let picked = drain(
+            &mut MovePicker::new(Some(stale.clone()), 0, Color::White),
+            &board,
+            &MoveOrderer::new(),
+        );
+        assert_eq!(picked[0], (Stage::GoodCaptures, pawn_takes));
+        assert_eq!(picked.last(), Some(&(Stage::BadCaptures, queen_takes)));
+        assert!(picked.iter().all(|(_, chess_move)| *chess_move != stale));
+    }
+
+    #[test]
+    fn a_cutoff_on_the_tt_move_generates_nothing_else() {
+        let board = Board::new();
+        let generator = MoveGenerator::new();
+        let tt_move = Move::new(12, 28, PieceType::Pawn);
+        let mut picker = MovePicker::new(Some(tt_move.clone()), 0, Color::White);
+        assert_eq!(
+            picker.next(&board, &generator, &MoveOrderer::new()),
+            Some(tt_move)
+        );
+        assert_eq!(picker.stage(), Stage::TtMove);
+        assert!(picker.moves.len() == 1 && picker.bad_captures.is_empty());
+    }
+}
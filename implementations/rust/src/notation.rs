@@ -0,0 +1,346 @@
+use crate::board::Board;
+use crate::move_generator::MoveGenerator;
+use crate::types::*;
+
+// How moves are written in output: coordinates as UCI sends them (`e7e8q`), or SAN (`e8=Q`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MoveNotation {
+    #[default]
+    Coordinate,
+    San,
+}
+
+impl MoveNotation {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "coordinate" | "lan" | "uci" => Some(MoveNotation::Coordinate),
+            "san" => Some(MoveNotation::San),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MoveNotation::Coordinate => "coordinate",
+            MoveNotation::San => "san",
+        }
+    }
+}
+
+pub struct SanParser {
+    move_generator: MoveGenerator,
+}
+
+impl SanParser {
+    pub fn new() -> Self {
+        Self {
+            move_generator: MoveGenerator::new(),
+        }
+    }
+
+    // Resolves `san` against the legal moves of the side to move. Capture marks and check
+    // suffixes are optional, and over-specified disambiguation (`Ngf3`) is accepted.
+    pub fn parse_san(&self, board: &mut Board, san: &str) -> Result<Move, &'static str> {
+        let turn = board.get_turn();
+        let legal_moves = self.move_generator.get_legal_moves(board, turn);
+        self.resolve_san(san, legal_moves)
+    }
+
+    // Resolves `san` against `moves`, which need not be legal: matching pseudo-legal moves tells
+    // which piece an illegal move was meant for.
+    pub fn resolve_san(&self, san: &str, moves: Vec<Move>) -> Result<Move, &'static str> {
+        let san = san.trim_end_matches(['+', '#', '!', '?']);
+
+        if let Some(kingside) = parse_castling(san) {
+            let target_file = if kingside { 6 } else { 2 };
+            return moves
+                .into_iter()
+                .find(|chess_move| chess_move.is_castling && chess_move.to % 8 == target_file)
+                .ok_or("Illegal move");
+        }
+
+        let mut chars: Vec<char> = san
+            .chars()
+            .filter(|ch| !matches!(ch, 'x' | ':' | '-'))
+            .collect();
+
+        let piece = match chars.first().copied() {
+            Some(ch @ ('K' | 'Q' | 'R' | 'B' | 'N')) => {
+                chars.remove(0);
+                PieceType::from_char(ch).ok_or("Invalid move format")?
+            }
+            _ => PieceType::Pawn,
+        };
+
+        let mut promotion = None;
+        if piece == PieceType::Pawn && chars.len() > 2 {
+            if let Some(&last) = chars.last().filter(|ch| ch.is_ascii_alphabetic()) {
+                promotion = Some(parse_promotion(last)?);
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+            }
+        }
+
+        if chars.len() < 2 || chars.len() > 4 {
+            return Err("Invalid move format");
+        }
+        let target: String = chars[chars.len() - 2..].iter().collect();
+        let to = algebraic_to_square(&target).map_err(|_| "Invalid move format")?;
+
+        let mut from_file = None;
+        let mut from_rank = None;
+        for &hint in &chars[..chars.len() - 2] {
+            if let Some(file) = FILES.iter().position(|&f| f == hint) {
+                from_file = Some(file);
+            } else if let Some(rank) = RANKS.iter().position(|&r| r == hint) {
+                from_rank = Some(rank);
+            } else {
+                return Err("Invalid move format");
+            }
+        }
+
+        let mut candidates = moves.into_iter().filter(|chess_move| {
+            chess_move.piece == piece
+                && chess_move.to == to
+                && !chess_move.is_castling
+                && from_file.is_none_or(|file| chess_move.from % 8 == file)
+                && from_rank.is_none_or(|rank| chess_move.from / 8 == rank)
+                && match chess_move.promotion {
+                    None => promotion.is_none(),
+                    Some(promoted) => promoted == promotion.unwrap_or(PieceType::Queen),
+                }
+        });
+
+        match (candidates.next(), candidates.next()) {
+            (Some(chess_move), None) => Ok(chess_move),
+            (Some(_), Some(_)) => Err("Ambiguous move"),
+            (None, _) => Err("Illegal move"),
+        }
+    }
+
+    // `chess_move` must be legal in `board`, which is restored before returning.
+    pub fn format_move(
+        &self,
+        board: &mut Board,
+        chess_move: &Move,
+        notation: MoveNotation,
+    ) -> String {
+        match notation {
+            MoveNotation::Coordinate => chess_move.to_string(),
+            MoveNotation::San => self.export_san(board, chess_move),
+        }
+    }
+
+    // A line played from `board`, one move after another.
+    pub fn format_line(&self, board: &Board, line: &[Move], notation: MoveNotation) -> Vec<String> {
+        let mut board = board.clone();
+        line.iter()
+            .map(|chess_move| {
+                let text = self.format_move(&mut board, chess_move, notation);
+                board.make_move(chess_move);
+                text
+            })
+            .collect()
+    }
+
+    // `chess_move` must be legal in `board`; the board is restored before returning.
+    pub fn export_san(&self, board: &mut Board, chess_move: &Move) -> String {
+        // The PGN convention for a pass, which the SAN standard itself lacks
+        if chess_move.is_null() {
+            return "--".to_string();
+        }
+        let mut san = if chess_move.is_castling {
+            if chess_move.to % 8 == 6 {
+                "O-O".to_string()
+            } else {
+                "O-O-O".to_string()
+            }
+        } else {
+            self.export_body(board, chess_move)
+        };
+
+        board.make_move(chess_move);
+        let opponent = board.get_turn();
+        if self.move_generator.is_in_check(board, opponent) {
+            let mated = !self.move_generator.has_legal_move(board, opponent);
+            san.push(if mated { '#' } else { '+' });
+        }
+        board.undo_move();
+
+        san
+    }
+
+    fn export_body(&self, board: &mut Board, chess_move: &Move) -> String {
+        let mut san = String::new();
+        let target = square_to_algebraic(chess_move.to);
+
+        if chess_move.piece == PieceType::Pawn {
+            if chess_move.captured.is_some() {
+                san.push(FILES[chess_move.from % 8]);
+                san.push('x');
+            }
+            san.push_str(&target);
+            if let Some(promotion) = chess_move.promotion {
+                san.push('=');
+                san.push_str(&promotion.to_string());
+            }
+            return san;
+        }
+
+        san.push_str(&chess_move.piece.to_string());
+
+        let turn = board.get_turn();
+        let rivals: Vec<Square> = self
+            .move_generator
+            .get_legal_moves(board, turn)
+            .into_iter()
+            .filter(|other| {
+                other.piece == chess_move.piece
+                    && other.to == chess_move.to
+                    && other.from != chess_move.from
+            })
+            .map(|other| other.from)
+            .collect();
+        if !rivals.is_empty() {
+            let from = square_to_algebraic(chess_move.from);
+            if rivals
+                .iter()
+                .all(|&square| square % 8 != chess_move.from % 8)
+            {
+                san.push_str(&from[..1]);
+            } else if rivals
+                .iter()
+                .all(|&square| square / 8 != chess_move.from / 8)
+            {
+                san.push_str(&from[1..]);
+            } else {
+                san.push_str(&from);
+            }
+        }
+
+        if chess_move.captured.is_some() {
+            san.push('x');
+        }
+        san.push_str(&target);
+        san
+    }
+}
+
+impl Default for SanParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn parse_castling(san: &str) -> Option<bool> {
+    match san {
+        "O-O" | "0-0" => Some(true),
+        "O-O-O" | "0-0-0" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_promotion(ch: char) -> Result<PieceType, &'static str> {
+    match PieceType::from_char(ch) {
+        Some(
+            piece @ (PieceType::Queen | PieceType::Rook | PieceType::Bishop | PieceType::Knight),
+        ) => Ok(piece),
+        _ => Err("Invalid move format"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::board_from;
+
+    fn round_trip(board: &mut Board, san: &str) -> String {
+        let parser = SanParser::new();
+        let chess_move = parser.parse_san(board, san).unwrap();
+        parser.export_san(board, &chess_move)
+    }
+
+    #[test]
+    fn parses_and_formats_opening_moves() {
+        let mut board = Board::new();
+        assert_eq!(round_trip(&mut board, "e4"), "e4");
+        assert_eq!(round_trip(&mut board, "Nf3"), "Nf3");
+        assert_eq!(round_trip(&mut board, "Ngf3"), "Nf3");
+        assert_eq!(
+            SanParser::new().parse_san(&mut board, "Qh5"),
+            Err("Illegal move")
+        );
+    }
+
+    #[test]
+    fn disambiguates_by_file_then_rank() {
+        let mut board = board_from("4k3/8/8/8/8/8/R6R/4K3 w - - 0 1");
+        assert_eq!(
+            SanParser::new().parse_san(&mut board, "Rd2"),
+            Err("Ambiguous move")
+        );
+        assert_eq!(round_trip(&mut board, "Rad2"), "Rad2");
+
+        let mut board = board_from("4k3/R7/8/8/8/8/R7/4K3 w - - 0 1");
+        assert_eq!(round_trip(&mut board, "R2a5"), "R2a5");
+    }
+
+    #[test]
+    fn handles_captures_castling_promotion_and_mate() {
+        let mut board = board_from("r3k2r/1P6/8/3p4/4P3/8/8/R3K2R w KQkq - 0 1");
+        assert_eq!(round_trip(&mut board, "exd5"), "exd5");
+        assert_eq!(round_trip(&mut board, "ed5"), "exd5");
+        assert_eq!(round_trip(&mut board, "O-O"), "O-O");
+        assert_eq!(round_trip(&mut board, "0-0-0"), "O-O-O");
+        assert_eq!(round_trip(&mut board, "bxa8=Q+"), "bxa8=Q+");
+        assert_eq!(round_trip(&mut board, "b8N"), "b8=N");
+
+        let mut board = board_from("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        assert_eq!(round_trip(&mut board, "Ra8"), "Ra8#");
+    }
+
+    #[test]
+    fn formats_lines_in_either_notation() {
+        let board = board_from("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+        let parser = SanParser::new();
+        let line = [
+            parser.parse_san(&mut board.clone(), "b8=N").unwrap(),
+            Move::new(60, 52, PieceType::King),
+        ];
+        assert_eq!(
+            parser.format_line(&board, &line, MoveNotation::Coordinate),
+            ["b7b8n", "e8e7"]
+        );
+        assert_eq!(
+            parser.format_line(&board, &line, MoveNotation::San),
+            ["b8=N", "Ke7"]
+        );
+    }
+
+    #[test]
+    fn a_pass_flips_the_turn_and_hash_and_undoes_cleanly() {
+        let mut board = board_from("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 5");
+        let before = board.get_hash();
+        let line = [Move::null()];
+        assert_eq!(
+            SanParser::new().format_line(&board, &line, MoveNotation::San),
+            ["--"]
+        );
+        assert_eq!(Move::null().to_string(), "0000");
+
+        board.make_null_move();
+        let passed = board_from("4k3/8/8/3pP3/8/8/8/4K3 b - - 1 5");
+        assert_eq!(board.get_turn(), Color::Black);
+        assert_eq!(board.get_en_passant_target(), None);
+        assert_eq!(board.get_hash(), passed.get_hash());
+
+        assert!(board.undo_move().unwrap().is_null());
+        assert_eq!(board.get_hash(), before);
+        assert_eq!(
+            board.get_en_passant_target(),
+            algebraic_to_square("d6").ok()
+        );
+    }
+}
@@ -0,0 +1,369 @@
+use crate::ai::{format_score, mate_in, AI};
+use crate::board::Board;
+use crate::eval::{ClassicalEvaluator, SCALE_NORMAL};
+use crate::fen::FenParser;
+use crate::i18n::tr;
+use crate::json_escape;
+use crate::move_generator::MoveGenerator;
+use crate::nodes_per_second;
+use crate::perft::Perft;
+use crate::search_limits::MAX_SEARCH_DEPTH;
+use std::time::Instant;
+
+// Exit codes for one-shot subcommands: scripts can tell a bad invocation from a position that
+// has no answer, or an answer other than the one they expected.
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const DEFAULT_BESTMOVE_DEPTH: u8 = 4;
+
+// What a one-shot subcommand asked for, from `--flag value` or `--flag=value` arguments.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Request {
+    fen: Option<String>,
+    // Checked by each subcommand, whose depth ranges differ
+    depth: Option<String>,
+    expect: Option<u64>,
+    detail: bool,
+    json: bool,
+}
+
+// `chess eval ...`, `chess bestmove ...` and `chess perft ...` answer once on stdout and exit,
+// without the REPL. Returns None when the arguments don't start with a subcommand, and the exit
+// code otherwise.
+pub fn run(args: &[String]) -> Option<i32> {
+    let (command, rest) = args.split_first()?;
+    if !matches!(command.as_str(), "eval" | "bestmove" | "perft") {
+        return None;
+    }
+    Some(match execute(command, rest) {
+        Ok((code, output)) => {
+            println!("{}", output);
+            code
+        }
+        Err((code, error)) => {
+            eprintln!("ERROR: {}", tr(error));
+            code
+        }
+    })
+}
+
+// The exit code and output line, or the exit code and error message.
+fn execute(command: &str, args: &[String]) -> Result<(i32, String), (i32, &'static str)> {
+    let request = parse_request(args).map_err(|error| (EXIT_USAGE, error))?;
+    let mut board = Board::new();
+    let fen = request.fen.as_deref().unwrap_or(START_FEN);
+    FenParser::new()
+        .parse_fen(&mut board, fen)
+        .map_err(|_| (EXIT_USAGE, "Invalid FEN string"))?;
+    match command {
+        "eval" if request.depth.is_none() && request.expect.is_none() => {
+            Ok((EXIT_OK, evaluation(&board, fen, &request)))
+        }
+        "bestmove" if !request.detail && request.expect.is_none() => {
+            best_move(&mut board, fen, &request).map(|output| (EXIT_OK, output))
+        }
+        "perft" if !request.detail => perft(&mut board, fen, &request),
+        _ => Err((EXIT_USAGE, "Unsupported option for this subcommand")),
+    }
+}
+
+fn parse_request(args: &[String]) -> Result<Request, &'static str> {
+    let mut request = Request::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+        let mut value = || inline.clone().or_else(|| args.next().cloned());
+        match flag {
+            "--fen" => request.fen = Some(value().ok_or("--fen requires a position")?),
+            "--depth" => request.depth = Some(value().unwrap_or_default()),
+            "--expect" => {
+                let expect = value()
+                    .and_then(|count| count.parse::<u64>().ok())
+                    .ok_or("Invalid expected node count")?;
+                request.expect = Some(expect);
+            }
+            "--detail" if inline.is_none() => request.detail = true,
+            "--json" if inline.is_none() => request.json = true,
+            // Global options, already applied before the subcommand runs
+            "--lang" => {
+                value();
+            }
+            _ => return Err("Unsupported option for this subcommand"),
+        }
+    }
+    Ok(request)
+}
+
+fn evaluation(board: &Board, fen: &str, request: &Request) -> String {
+    let breakdown = ClassicalEvaluator::new().breakdown(board);
+    let phase = if breakdown.endgame {
+        "endgame"
+    } else {
+        "middlegame"
+    };
+    match (request.json, request.detail) {
+        (false, false) => format!("EVALUATION: {}", breakdown.total),
+        (false, true) => format!(
+            "EVALUATION: {} (material={}, positional={}, phase={}, scale={}/{})",
+            breakdown.total, breakdown.material, breakdown.positional, phase, breakdown.scale, SCALE_NORMAL
+        ),
+        (true, false) => format!("{{\"fen\":\"{}\",\"score\":{}}}", json_escape(fen), breakdown.total),
+        (true, true) => format!(
+            "{{\"fen\":\"{}\",\"score\":{},\"material\":{},\"positional\":{},\"phase\":\"{}\",\"scale\":{}}}",
+            json_escape(fen),
+            breakdown.total,
+            breakdown.material,
+            breakdown.positional,
+            phase,
+            breakdown.scale
+        ),
+    }
+}
+
+fn best_move(
+    board: &mut Board,
+    fen: &str,
+    request: &Request,
+) -> Result<String, (i32, &'static str)> {
+    let color = board.get_turn();
+    if !MoveGenerator::new().has_legal_move(board, color) {
+        return Err((EXIT_FAILURE, "No legal moves available"));
+    }
+    let depth = match &request.depth {
+        None => DEFAULT_BESTMOVE_DEPTH,
+        Some(depth) => depth
+            .parse::<u8>()
+            .ok()
+            .filter(|depth| (1..=MAX_SEARCH_DEPTH).contains(depth))
+            .ok_or((EXIT_USAGE, "AI depth must be 1-64"))?,
+    };
+    let result = AI::new().find_best_move(board, depth);
+    let best_move = result
+        .best_move
+        .ok_or((EXIT_FAILURE, "No legal moves available"))?;
+    let pv: Vec<String> = result.pv.iter().map(ToString::to_string).collect();
+    Ok(if request.json {
+        let mate = mate_in(result.evaluation).map_or("null".to_string(), |moves| moves.to_string());
+        let pv: Vec<String> = pv
+            .iter()
+            .map(|chess_move| format!("\"{}\"", chess_move))
+            .collect();
+        format!(
+            "{{\"fen\":\"{}\",\"bestmove\":\"{}\",\"depth\":{},\"eval\":{},\"mate\":{},\"nodes\":{},\"pv\":[{}]}}",
+            json_escape(fen),
+            best_move,
+            result.depth,
+            result.evaluation,
+            mate,
+            result.nodes,
+            pv.join(",")
+        )
+    } else {
+        format!(
+            "BESTMOVE: {} (depth={}, eval={}, nodes={}, pv={})",
+            best_move,
+            result.depth,
+            format_score(result.evaluation),
+            result.nodes,
+            pv.join(" ")
+        )
+    })
+}
+
+// Counts the leaves `--depth` plies down. With `--expect`, a different count exits with
+// EXIT_FAILURE, so a CI script only has to check the exit code.
+fn perft(
+    board: &mut Board,
+    fen: &str,
+    request: &Request,
+) -> Result<(i32, String), (i32, &'static str)> {
+    let depth = request
+        .depth
+        .as_deref()
+        .and_then(|depth| depth.parse::<u8>().ok())
+        .filter(|&depth| depth >= 1)
+        .ok_or((EXIT_USAGE, "Invalid perft depth"))?;
+    let start_time = Instant::now();
+    let nodes = Perft::new().perft(board, depth);
+    let elapsed = start_time.elapsed();
+    let matches = request.expect.map(|expect| expect == nodes);
+    let code = if matches == Some(false) {
+        EXIT_FAILURE
+    } else {
+        EXIT_OK
+    };
+    let output = if request.json {
+        format!(
+            "{{\"fen\":\"{}\",\"depth\":{},\"nodes\":{},\"expected\":{},\"match\":{},\"time_ms\":{},\"nps\":{}}}",
+            json_escape(fen),
+            depth,
+            nodes,
+            request.expect.map_or("null".to_string(), |expect| expect.to_string()),
+            matches.map_or("null".to_string(), |matches| matches.to_string()),
+            elapsed.as_millis(),
+            nodes_per_second(nodes, elapsed)
+        )
+    } else {
+        let check = match (request.expect, matches) {
+            (Some(expect), Some(matches)) => {
+                format!(
+                    "; expected={}; match={}",
+                    expect,
+                    if matches { "yes" } else { "no" }
+                )
+            }
+            _ => String::new(),
+        };
+        format!(
+            "PERFT: depth={}; nodes={}{}; time={}ms; nps={}",
+            depth,
+            nodes,
+            check,
+            elapsed.as_millis(),
+            nodes_per_second(nodes, elapsed)
+        )
+    };
+    Ok((code, output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split(' ').map(str::to_string).collect()
+    }
+
+    #[test]
+    fn evaluates_positions_once_as_text_or_json() {
+        assert_eq!(
+            execute("eval", &[]),
+            Ok((EXIT_OK, "EVALUATION: 0".to_string()))
+        );
+        assert_eq!(
+            execute("eval", &args("--fen=garbage --detail")),
+            Err((EXIT_USAGE, "Invalid FEN string"))
+        );
+
+        let fen = "4k3/8/8/8/8/8/8/1N2K3 w - - 0 1".to_string();
+        let output = execute(
+            "eval",
+            &["--fen".to_string(), fen.clone(), "--detail".to_string()],
+        )
+        .unwrap()
+        .1;
+        assert!(output.starts_with("EVALUATION: 0 (material="), "{}", output);
+        assert!(output.ends_with("phase=endgame, scale=0/64)"), "{}", output);
+        let json = execute("eval", &[format!("--fen={}", fen), "--json".to_string()])
+            .unwrap()
+            .1;
+        assert_eq!(
+            json,
+            "{\"fen\":\"4k3/8/8/8/8/8/8/1N2K3 w - - 0 1\",\"score\":0}"
+        );
+
+        assert_eq!(
+            execute("eval", &args("--depth 3")),
+            Err((EXIT_USAGE, "Unsupported option for this subcommand"))
+        );
+        assert_eq!(
+            execute("eval", &args("--verbose")),
+            Err((EXIT_USAGE, "Unsupported option for this subcommand"))
+        );
+    }
+
+    #[test]
+    fn finds_best_moves_and_reports_positions_without_one() {
+        let mate_in_one = "--fen=6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+        let output = execute(
+            "bestmove",
+            &[
+                mate_in_one.to_string(),
+                "--depth".to_string(),
+                "2".to_string(),
+            ],
+        )
+        .unwrap()
+        .1;
+        // A found mate ends the deepening early
+        assert!(
+            output.starts_with("BESTMOVE: a1a8 (depth=1, eval=mate 1,"),
+            "{}",
+            output
+        );
+        let json = execute("bestmove", &[mate_in_one.to_string(), "--json".to_string()])
+            .unwrap()
+            .1;
+        assert!(
+            json.contains("\"bestmove\":\"a1a8\"") && json.contains("\"mate\":1"),
+            "{}",
+            json
+        );
+
+        let checkmated = "--fen=R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1".to_string();
+        assert_eq!(
+            execute("bestmove", &[checkmated]),
+            Err((EXIT_FAILURE, "No legal moves available"))
+        );
+        assert_eq!(
+            execute("bestmove", &args("--depth 0")),
+            Err((EXIT_USAGE, "AI depth must be 1-64"))
+        );
+        assert_eq!(run(&args("analyse --fen x")), None);
+    }
+
+    #[test]
+    fn counts_perft_leaves_and_checks_them_against_the_expected_count() {
+        let (code, output) = execute("perft", &args("--depth 3 --expect 8902")).unwrap();
+        assert_eq!(code, EXIT_OK);
+        assert!(
+            output.starts_with("PERFT: depth=3; nodes=8902; expected=8902; match=yes; time="),
+            "{}",
+            output
+        );
+
+        let kiwipete = "--fen=r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let (code, json) = execute(
+            "perft",
+            &[
+                kiwipete.to_string(),
+                "--depth=2".to_string(),
+                "--expect=2040".to_string(),
+                "--json".to_string(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(code, EXIT_FAILURE);
+        assert!(
+            json.contains("\"nodes\":2039,\"expected\":2040,\"match\":false"),
+            "{}",
+            json
+        );
+        let (code, output) = execute("perft", &args("--depth 1")).unwrap();
+        assert_eq!(code, EXIT_OK);
+        assert!(
+            output.starts_with("PERFT: depth=1; nodes=20; time="),
+            "{}",
+            output
+        );
+
+        assert_eq!(
+            execute("perft", &[]),
+            Err((EXIT_USAGE, "Invalid perft depth"))
+        );
+        assert_eq!(
+            execute("perft", &args("--depth 2 --expect lots")),
+            Err((EXIT_USAGE, "Invalid expected node count"))
+        );
+        assert_eq!(
+            execute("bestmove", &args("--expect 20")),
+            Err((EXIT_USAGE, "Unsupported option for this subcommand"))
+        );
+    }
+}
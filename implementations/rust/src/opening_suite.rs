@@ -0,0 +1,282 @@
+use crate::ai::AI;
+use crate::board::Board;
+use crate::fen::FenParser;
+use crate::notation::SanParser;
+use crate::pgn::{read_games, PgnGame};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+pub const DEFAULT_SUITE_PLIES: usize = 8;
+pub const DEFAULT_SUITE_WINDOW_CP: i32 = 100;
+// Enough to see a hanging piece; every candidate pays for one of these searches.
+pub const SUITE_EVAL_DEPTH: u8 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SuiteConfig {
+    pub count: usize,
+    // Opening moves played from each game before the position is taken
+    pub plies: usize,
+    // Positions scored further from equality than this are unbalanced and left out.
+    pub window_cp: i32,
+    pub depth: u8,
+}
+
+impl SuiteConfig {
+    pub fn new(count: usize) -> Self {
+        Self {
+            count,
+            plies: DEFAULT_SUITE_PLIES,
+            window_cp: DEFAULT_SUITE_WINDOW_CP,
+            depth: SUITE_EVAL_DEPTH,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Opening {
+    pub fen: String,
+    // The game's ECO tag, `?` without one
+    pub eco: String,
+    pub moves: Vec<String>,
+    // From White's point of view
+    pub eval: i32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SuiteReport {
+    pub games: usize,
+    // Distinct positions long enough and legal enough to consider
+    pub candidates: usize,
+    pub evaluated: usize,
+    pub openings: Vec<Opening>,
+}
+
+impl SuiteReport {
+    pub fn eco_codes(&self) -> usize {
+        self.openings
+            .iter()
+            .map(|opening| opening.eco.as_str())
+            .collect::<HashSet<_>>()
+            .len()
+    }
+}
+
+// Picks up to `config.count` balanced openings from a PGN database, spread over as many ECO
+// codes as it has: codes take turns, rotating through the volumes A-E, so a database heavy on
+// one opening still gives a varied suite. Only the candidates actually picked from are searched.
+pub fn generate(ai: &mut AI, pgn: &str, config: &SuiteConfig) -> SuiteReport {
+    let games = read_games(pgn);
+    let mut report = SuiteReport {
+        games: games.len(),
+        ..SuiteReport::default()
+    };
+
+    let mut seen = HashSet::new();
+    let mut groups: BTreeMap<String, VecDeque<(Board, Opening)>> = BTreeMap::new();
+    for game in &games {
+        let Some(board) = replay(game, config.plies) else {
+            continue;
+        };
+        let fen = FenParser::new().export_fen(&board);
+        // Transpositions count once, whatever the counters say
+        let position: Vec<&str> = fen.split_whitespace().take(4).collect();
+        if !seen.insert(position.join(" ")) {
+            continue;
+        }
+        let eco = game.tag("ECO").unwrap_or("?").to_string();
+        let opening = Opening {
+            fen,
+            eco: eco.clone(),
+            moves: game.moves[..config.plies].to_vec(),
+            eval: 0,
+        };
+        groups.entry(eco).or_default().push_back((board, opening));
+        report.candidates += 1;
+    }
+
+    // Each code's place within its volume, so the order runs A00, B00, C00, ..., A01, B01, ...
+    let mut volume_ranks = BTreeMap::new();
+    let mut order: Vec<(usize, char, String)> = groups
+        .keys()
+        .map(|eco| {
+            let volume = eco.chars().next().unwrap_or('?');
+            let rank = volume_ranks.entry(volume).or_insert(0);
+            *rank += 1;
+            (*rank, volume, eco.clone())
+        })
+        .collect();
+    order.sort();
+
+    while report.openings.len() < config.count && !groups.is_empty() {
+        for (_, _, eco) in &order {
+            if report.openings.len() >= config.count {
+                break;
+            }
+            let Some(group) = groups.get_mut(eco) else {
+                continue;
+            };
+            while let Some((mut board, mut opening)) = group.pop_front() {
+                report.evaluated += 1;
+                opening.eval = ai.find_best_move(&mut board, config.depth).evaluation;
+                if opening.eval.abs() <= config.window_cp {
+                    report.openings.push(opening);
+                    break;
+                }
+            }
+            if group.is_empty() {
+                groups.remove(eco);
+            }
+        }
+    }
+    report
+}
+
+// The position after the game's first `plies` moves, if it has that many legal ones.
+fn replay(game: &PgnGame, plies: usize) -> Option<Board> {
+    if game.moves.len() < plies || game.tag("FEN").is_some() {
+        return None;
+    }
+    let san_parser = SanParser::new();
+    let mut board = Board::new();
+    for san in &game.moves[..plies] {
+        let chess_move = san_parser.parse_san(&mut board, san).ok()?;
+        board.make_move(&chess_move);
+    }
+    Some(board)
+}
+
+// One EPD record per opening: the position, then `id`, `ce` (centipawns for the side to move,
+// as EPD has it) and the moves that reach it in `c0`.
+pub fn to_epd(openings: &[Opening]) -> String {
+    openings
+        .iter()
+        .enumerate()
+        .map(|(index, opening)| {
+            let position: Vec<&str> = opening.fen.split_whitespace().take(4).collect();
+            let ce = if position[1] == "w" {
+                opening.eval
+            } else {
+                -opening.eval
+            };
+            format!(
+                "{} id \"{} #{}\"; ce {}; c0 \"{}\";\n",
+                position.join(" "),
+                opening.eco,
+                index + 1,
+                ce,
+                numbered(&opening.moves)
+            )
+        })
+        .collect()
+}
+
+// One unfinished game per opening, tagged with its ECO code.
+pub fn to_pgn(openings: &[Opening]) -> String {
+    openings
+        .iter()
+        .enumerate()
+        .map(|(index, opening)| {
+            format!(
+                "[Event \"Opening suite\"]\n[Round \"{}\"]\n[ECO \"{}\"]\n[Result \"*\"]\n\n{} *\n\n",
+                index + 1,
+                opening.eco,
+                numbered(&opening.moves)
+            )
+        })
+        .collect()
+}
+
+// Start positions from a suite file, as `to_epd` or `to_pgn` write them; EPD lines may also be
+// plain FENs, and `#` starts a comment line. PGN games keep their moves, so repetitions count
+// from the start of the game.
+pub fn load_suite(text: &str) -> Result<Vec<Board>, String> {
+    let is_pgn = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .is_some_and(|line| line.starts_with('[') || line.starts_with("1."));
+    let boards: Vec<Board> = if is_pgn {
+        read_games(text)
+            .iter()
+            .map(|game| replay(game, game.moves.len()).ok_or("Invalid opening suite"))
+            .collect::<Result<_, _>>()?
+    } else {
+        let mut boards = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            // EPD stops at four fields; a FEN has counters where EPD has operations
+            let take = if fields.get(4).is_some_and(|field| field.parse::<u32>().is_ok()) {
+                6
+            } else {
+                4
+            };
+            let mut board = Board::new();
+            FenParser::new()
+                .parse_fen(&mut board, &fields[..take.min(fields.len())].join(" "))
+                .map_err(|_| "Invalid opening suite")?;
+            boards.push(board);
+        }
+        boards
+    };
+    if boards.is_empty() {
+        return Err("Invalid opening suite".to_string());
+    }
+    Ok(boards)
+}
+
+fn numbered(moves: &[String]) -> String {
+    moves
+        .iter()
+        .enumerate()
+        .map(|(index, san)| {
+            if index % 2 == 0 {
+                format!("{}. {}", index / 2 + 1, san)
+            } else {
+                san.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATABASE: &str = "\
+[ECO \"C50\"]\n\n1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 1-0\n\n\
+[ECO \"C50\"]\n\n1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 0-1\n\n\
+[ECO \"C44\"]\n\n1. e4 e5 2. Nf3 Nc6 3. d4 exd4 *\n\n\
+[ECO \"D06\"]\n\n1. d4 d5 2. c4 Nc6 3. Nc3 Nf6 *\n\n\
+[ECO \"A00\"]\n\n1. g4 e5 2. f3 Qh4# 0-1\n\n\
+[ECO \"B00\"]\n\n1. e4 b5 2. Bxb5 Na6 3. Bxa6 Bxa6 *\n";
+
+    #[test]
+    fn picks_balanced_openings_across_eco_codes() {
+        let mut config = SuiteConfig::new(10);
+        config.plies = 6;
+        config.depth = 2;
+        let report = generate(&mut AI::new(), DATABASE, &config);
+        assert_eq!(report.games, 6);
+        // The repeated game counts once and the four-ply mate line is too short for six plies
+        assert_eq!(report.candidates, 4);
+        assert!(report.openings.iter().all(|opening| opening.eval.abs() <= 100));
+        let ecos: Vec<&str> = report.openings.iter().map(|o| o.eco.as_str()).collect();
+        // Volumes take turns: B00, C44, D06, then C50. C44 is a pawn down for White after
+        // 3...exd4, outside the window
+        assert_eq!(ecos, ["B00", "D06", "C50"]);
+        assert_eq!(report.evaluated, 4);
+        assert_eq!(report.eco_codes(), 3);
+
+        let epd = to_epd(&report.openings);
+        assert!(epd.contains("id \"B00 #1\"; ce "));
+        assert_eq!(load_suite(&epd).unwrap().len(), report.openings.len());
+        let pgn = to_pgn(&report.openings);
+        assert!(pgn.contains("[ECO \"D06\"]\n[Result \"*\"]\n\n1. d4 d5 2. c4 Nc6 3. Nc3 Nf6 *"));
+        let boards = load_suite(&pgn).unwrap();
+        assert_eq!(boards[1].get_state().move_history.len(), 6);
+        assert!(load_suite("# nothing here\n").is_err());
+    }
+}
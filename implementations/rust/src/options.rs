@@ -0,0 +1,169 @@
+use crate::time_manager::TimePolicy;
+use crate::transposition::DEFAULT_TT_SIZE_MB;
+
+// Engine options, named the way UCI's `setoption` expects. Every option is currently an integer
+// within bounds ("spin" in UCI terms). Most shape the time allocation; the piece values feed the
+// evaluation, `MultiPV` sets how many root lines a search reports and `Hash`/`LargePages` size
+// the transposition table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OptionSpec {
+    pub name: &'static str,
+    pub default: i64,
+    pub min: i64,
+    pub max: i64,
+}
+
+const fn spin(name: &'static str, default: i64, min: i64, max: i64) -> OptionSpec {
+    OptionSpec {
+        name,
+        default,
+        min,
+        max,
+    }
+}
+
+pub const OPTIONS: [OptionSpec; 21] = [
+    spin("OpeningTimePercent", 70, 10, 200),
+    spin("MiddlegameTimePercent", 100, 10, 200),
+    spin("EndgameTimePercent", 80, 10, 200),
+    spin("EvalSwingCp", 40, 1, 1000),
+    spin("EvalSwingTimePercent", 50, 0, 300),
+    spin("CloseMoveMarginCp", 15, 0, 200),
+    spin("CloseMoveTimePercent", 10, 0, 100),
+    spin("EasyMoveLeadCp", 150, 0, 2000),
+    spin("EasyMoveTimePercent", 30, 0, 100),
+    spin("BestMoveChangeTimePercent", 25, 0, 200),
+    spin("FailLowTimePercent", 50, 0, 300),
+    spin("MoveOverhead", 30, 0, 5000),
+    spin("SharpnessTimePercent", 30, 0, 200),
+    spin("PawnValue", 100, 0, 3000),
+    spin("KnightValue", 320, 0, 3000),
+    spin("BishopValue", 330, 0, 3000),
+    spin("RookValue", 500, 0, 3000),
+    spin("QueenValue", 900, 0, 3000),
+    spin("MultiPV", 1, 1, 64),
+    spin("Hash", DEFAULT_TT_SIZE_MB as i64, 1, 4096),
+    // 1 tries large pages for the table and falls back to normal ones; 0 never tries.
+    spin("LargePages", 1, 0, 1),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    values: Vec<i64>,
+}
+
+impl Options {
+    pub fn new() -> Self {
+        Self {
+            values: OPTIONS.iter().map(|spec| spec.default).collect(),
+        }
+    }
+
+    // Names match case-insensitively, as UCI requires.
+    pub fn get(&self, name: &str) -> Option<i64> {
+        let index = OPTIONS
+            .iter()
+            .position(|spec| spec.name.eq_ignore_ascii_case(name))?;
+        Some(self.values[index])
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) -> Result<&'static OptionSpec, String> {
+        let index = OPTIONS
+            .iter()
+            .position(|spec| spec.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("Unknown option '{}'", name))?;
+        let spec = &OPTIONS[index];
+        let value = value
+            .parse::<i64>()
+            .ok()
+            .filter(|value| (spec.min..=spec.max).contains(value))
+            .ok_or_else(|| {
+                format!(
+                    "{} must be an integer from {} to {}",
+                    spec.name, spec.min, spec.max
+                )
+            })?;
+        self.values[index] = value;
+        Ok(spec)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'static OptionSpec, i64)> + '_ {
+        OPTIONS.iter().zip(self.values.iter().copied())
+    }
+
+    pub fn time_policy(&self) -> TimePolicy {
+        let value = |name| self.get(name).unwrap_or_default();
+        TimePolicy {
+            opening_percent: value("OpeningTimePercent") as u64,
+            middlegame_percent: value("MiddlegameTimePercent") as u64,
+            endgame_percent: value("EndgameTimePercent") as u64,
+            swing_cp: value("EvalSwingCp") as i32,
+            swing_percent: value("EvalSwingTimePercent") as u64,
+            close_margin_cp: value("CloseMoveMarginCp") as i32,
+            close_move_percent: value("CloseMoveTimePercent") as u64,
+            easy_move_lead_cp: value("EasyMoveLeadCp") as i32,
+            easy_move_percent: value("EasyMoveTimePercent") as u64,
+            best_move_change_percent: value("BestMoveChangeTimePercent") as u64,
+            fail_low_percent: value("FailLowTimePercent") as u64,
+            move_overhead_ms: value("MoveOverhead") as u64,
+            sharpness_percent: value("SharpnessTimePercent") as u64,
+        }
+    }
+
+    pub fn multi_pv(&self) -> usize {
+        self.get("MultiPV").unwrap_or(1) as usize
+    }
+
+    // Table size in megabytes, and whether to try large pages for it
+    pub fn hash(&self) -> (usize, bool) {
+        (
+            self.get("Hash").unwrap_or(DEFAULT_TT_SIZE_MB as i64) as usize,
+            self.get("LargePages") == Some(1),
+        )
+    }
+
+    // Pawn, knight, bishop, rook and queen, in `EvalConfig` order.
+    pub fn piece_values(&self) -> [i32; 5] {
+        [
+            "PawnValue",
+            "KnightValue",
+            "BishopValue",
+            "RookValue",
+            "QueenValue",
+        ]
+        .map(|name| self.get(name).unwrap_or_default() as i32)
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::EvalConfig;
+
+    #[test]
+    fn defaults_match_the_time_policy_and_values_are_bounded() {
+        let mut options = Options::new();
+        assert_eq!(options.time_policy(), TimePolicy::default());
+        assert_eq!(options.piece_values(), EvalConfig::default().piece_values);
+
+        assert!(options.set("openingtimepercent", "120").is_ok());
+        assert_eq!(options.time_policy().opening_percent, 120);
+        assert_eq!(options.get("OpeningTimePercent"), Some(120));
+
+        assert!(options.set("OpeningTimePercent", "5").is_err());
+        assert!(options.set("OpeningTimePercent", "lots").is_err());
+        assert!(options.set("Threads", "4").is_err());
+        assert_eq!(options.get("OpeningTimePercent"), Some(120));
+
+        assert_eq!(options.hash(), (DEFAULT_TT_SIZE_MB, true));
+        assert!(options.set("Hash", "64").is_ok());
+        assert!(options.set("LargePages", "0").is_ok());
+        assert_eq!(options.hash(), (64, false));
+    }
+}
@@ -0,0 +1,214 @@
+use crate::ai::MATE_SCORE;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How long the oracle may stay silent before it is given up on.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Disagreements smaller than this are search noise rather than something to look at.
+pub const DEFAULT_THRESHOLD_CP: i32 = 50;
+
+// An external UCI engine (Stockfish or any other) run as a subprocess and asked for its best
+// move and score, to check this engine's answers against. Its output is read on a separate
+// thread, so a hung or crashed oracle times out instead of blocking the REPL.
+pub struct UciOracle {
+    pub name: String,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+// The oracle's answer for one position, the score for the side to move in this engine's scale:
+// `mate N` becomes the matching mate-distance score.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OracleAnswer {
+    pub best_move: String,
+    pub score: i32,
+}
+
+impl UciOracle {
+    // Starts `program` with `args` and completes the `uci` / `isready` handshake.
+    pub fn start(program: &str, args: &[&str]) -> Result<Self, String> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|_| "Cannot start the oracle".to_string())?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut oracle = Self {
+            name: program.to_string(),
+            child,
+            stdin,
+            lines,
+        };
+        oracle.send("uci")?;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        loop {
+            let line = oracle.read_line(deadline)?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                oracle.name = name.trim().to_string();
+            }
+            if line.trim() == "uciok" {
+                break;
+            }
+        }
+        oracle.sync()?;
+        Ok(oracle)
+    }
+
+    // Searches `fen` to `depth` plies and returns the oracle's final answer.
+    pub fn analyse(&mut self, fen: &str, depth: u8) -> Result<OracleAnswer, String> {
+        self.send("ucinewgame")?;
+        self.sync()?;
+        self.send(&format!("position fen {}", fen))?;
+        self.send(&format!("go depth {}", depth))?;
+        let deadline = Instant::now() + SEARCH_TIMEOUT;
+        let mut score = None;
+        loop {
+            let line = self.read_line(deadline)?;
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("info") => {
+                    if let Some(info_score) = parse_info_score(&line) {
+                        score = Some(info_score);
+                    }
+                }
+                Some("bestmove") => {
+                    let best_move = tokens.next().unwrap_or("0000").to_string();
+                    return Ok(OracleAnswer {
+                        best_move,
+                        score: score.unwrap_or(0),
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn sync(&mut self) -> Result<(), String> {
+        self.send("isready")?;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        while self.read_line(deadline)?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{}", command)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|_| "The oracle stopped responding".to_string())
+    }
+
+    fn read_line(&self, deadline: Instant) -> Result<String, String> {
+        let wait = deadline.saturating_duration_since(Instant::now());
+        match self.lines.recv_timeout(wait) {
+            Ok(line) => Ok(line),
+            Err(RecvTimeoutError::Timeout) => Err("The oracle timed out".to_string()),
+            Err(RecvTimeoutError::Disconnected) => Err("The oracle exited".to_string()),
+        }
+    }
+}
+
+impl Drop for UciOracle {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + Duration::from_millis(500);
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// The score of an `info` line, if it has one. Bound scores (`lowerbound`/`upperbound`) are
+// skipped; the final exact score follows them.
+fn parse_info_score(line: &str) -> Option<i32> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let at = tokens.iter().position(|&token| token == "score")?;
+    if tokens
+        .get(at + 3)
+        .is_some_and(|&token| token == "lowerbound" || token == "upperbound")
+    {
+        return None;
+    }
+    let value = tokens.get(at + 2)?.parse::<i32>().ok()?;
+    match *tokens.get(at + 1)? {
+        "cp" => Some(value),
+        // Mating in N moves takes 2N - 1 plies; being mated in N takes 2N.
+        "mate" if value > 0 => Some(MATE_SCORE - (2 * value - 1)),
+        "mate" => Some(-MATE_SCORE + 2 * -value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::mate_in;
+
+    #[test]
+    fn reads_centipawn_and_mate_scores_from_info_lines() {
+        assert_eq!(
+            parse_info_score("info depth 12 seldepth 18 score cp -34 nodes 1000 pv e2e4"),
+            Some(-34)
+        );
+        let mate = parse_info_score("info depth 5 score mate 3 pv h5f7").unwrap();
+        assert_eq!(mate_in(mate), Some(3));
+        let mated = parse_info_score("info depth 5 score mate -2 pv a2a3").unwrap();
+        assert_eq!(mate_in(mated), Some(-2));
+        assert_eq!(parse_info_score("info depth 9 score cp 20 lowerbound"), None);
+        assert_eq!(parse_info_score("info string hello"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn talks_to_a_uci_engine_subprocess() {
+        // A scripted engine that always answers e2e4 with 31 centipawns
+        let script = r#"
+while read -r line; do
+  case "$line" in
+    uci) echo "id name Scripted"; echo "uciok" ;;
+    isready) echo "readyok" ;;
+    go*) echo "info depth 1 score cp 31 pv e2e4"; echo "bestmove e2e4" ;;
+    quit) exit 0 ;;
+  esac
+done
+"#;
+        let mut oracle = UciOracle::start("sh", &["-c", script]).unwrap();
+        assert_eq!(oracle.name, "Scripted");
+        let answer = oracle
+            .analyse(
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                3,
+            )
+            .unwrap();
+        assert_eq!(
+            answer,
+            OracleAnswer {
+                best_move: "e2e4".to_string(),
+                score: 31
+            }
+        );
+        assert!(UciOracle::start("/nonexistent/engine", &[]).is_err());
+    }
+}
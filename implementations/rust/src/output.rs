@@ -0,0 +1,83 @@
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+enum Message {
+    Line(String),
+    Flush(Sender<()>),
+}
+
+// Protocol output through a writer thread: the engine queues each line and carries on, so a GUI
+// slow to read its pipe never stalls a search or the command loop. Each line is flushed as soon
+// as it is written. Clones share the queue, so lines from the search thread and the command loop
+// come out whole and in the order they were queued.
+#[derive(Clone)]
+pub struct Output {
+    sender: Sender<Message>,
+}
+
+impl Output {
+    pub fn stdout() -> Self {
+        Self::new(io::stdout())
+    }
+
+    pub fn new(mut writer: impl Write + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for message in receiver {
+                match message {
+                    // A reader that went away is no reason to stop the engine; its lines are dropped.
+                    Message::Line(line) => {
+                        let _ = writeln!(writer, "{}", line).and_then(|_| writer.flush());
+                    }
+                    Message::Flush(done) => {
+                        let _ = done.send(());
+                    }
+                }
+            }
+        });
+        Self { sender }
+    }
+
+    pub fn line(&self, line: impl Into<String>) {
+        let _ = self.sender.send(Message::Line(line.into()));
+    }
+
+    // Waits until every line queued so far is written, e.g. before the process exits.
+    pub fn flush(&self) {
+        let (done, written) = mpsc::channel();
+        if self.sender.send(Message::Flush(done)).is_ok() {
+            let _ = written.recv();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writes_queued_lines_in_order_from_every_clone() {
+        let buffer = Shared::default();
+        let output = Output::new(buffer.clone());
+        let searcher = output.clone();
+        thread::spawn(move || searcher.line("info depth 1")).join().unwrap();
+        output.line("readyok");
+        output.flush();
+        assert_eq!(String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap(), "info depth 1\nreadyok\n");
+    }
+}
@@ -0,0 +1,356 @@
+use crate::bitboard::Bitboard;
+use crate::board::Board;
+use crate::types::*;
+use std::collections::HashMap;
+use std::fmt;
+
+// Classification only looks at pawns, so results are shared by every position with the same
+// pawn hash. The cap keeps a long analysis session from growing the cache without bound.
+const CACHE_LIMIT: usize = 4096;
+
+const FILE_A: usize = 0;
+const FILE_B: usize = 1;
+const FILE_C: usize = 2;
+const FILE_D: usize = 3;
+const FILE_E: usize = 4;
+const FILE_F: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructureKind {
+    IsolatedQueenPawn,
+    HangingPawns,
+    Carlsbad,
+    MaroczyBind,
+    Stonewall,
+    Hedgehog,
+}
+
+impl StructureKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            StructureKind::IsolatedQueenPawn => "iqp",
+            StructureKind::HangingPawns => "hanging_pawns",
+            StructureKind::Carlsbad => "carlsbad",
+            StructureKind::MaroczyBind => "maroczy",
+            StructureKind::Stonewall => "stonewall",
+            StructureKind::Hedgehog => "hedgehog",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            StructureKind::IsolatedQueenPawn => "isolated d-pawn with no c- or e-pawn beside it",
+            StructureKind::HangingPawns => "c- and d-pawns side by side with no b- or e-pawn",
+            StructureKind::Carlsbad => {
+                "d- and e-pawns facing a c- and d-pawn chain after the c-pawn exchange"
+            }
+            StructureKind::MaroczyBind => {
+                "c- and e-pawns on the fourth rank clamping the square between them"
+            }
+            StructureKind::Stonewall => "d- and f-pawns on the fourth rank with the e-pawn behind",
+            StructureKind::Hedgehog => {
+                "a-, b-, d- and e-pawns on the third rank behind a missing c-pawn"
+            }
+        }
+    }
+
+    // Typical plans for the side that owns the structure, then for its opponent.
+    pub fn plans(self) -> (&'static [&'static str], &'static [&'static str]) {
+        match self {
+            StructureKind::IsolatedQueenPawn => (
+                &[
+                    "use the outposts beside the pawn for knights",
+                    "attack on the kingside while the pieces are active",
+                    "prepare the d-pawn break to open the position",
+                ],
+                &[
+                    "blockade the square in front of the pawn with a knight",
+                    "trade minor pieces and head for an endgame",
+                    "pile up on the pawn along the d-file",
+                ],
+            ),
+            StructureKind::HangingPawns => (
+                &[
+                    "keep the pawns abreast and use the half-open b- and e-files",
+                    "advance one pawn at the right moment to open lines",
+                ],
+                &[
+                    "provoke an advance, then blockade the square in front",
+                    "attack the pawns with rooks on the c- and d-files",
+                ],
+            ),
+            StructureKind::Carlsbad => (
+                &[
+                    "minority attack with the b-pawn to create a weak c-pawn",
+                    "central break with the f-pawn and e-pawn",
+                ],
+                &[
+                    "kingside piece play against the castled king",
+                    "plant a knight on the e-file outpost",
+                ],
+            ),
+            StructureKind::MaroczyBind => (
+                &[
+                    "keep the b- and d-pawn breaks under control",
+                    "use the extra space to expand on either wing",
+                ],
+                &[
+                    "prepare the b-pawn or d-pawn break",
+                    "trade pieces to ease the cramp",
+                    "use the dark-square outpost on the d-file for a knight",
+                ],
+            ),
+            StructureKind::Stonewall => (
+                &[
+                    "post a knight on the e-file outpost",
+                    "attack the king with a rook lift or g-pawn advance",
+                ],
+                &[
+                    "occupy the hole on the e-file with a knight",
+                    "trade the light-squared bishops",
+                ],
+            ),
+            StructureKind::Hedgehog => (
+                &[
+                    "wait behind the pawns for the b-pawn or d-pawn break",
+                    "keep the pieces flexible on the back ranks",
+                ],
+                &[
+                    "keep the space advantage and stop both breaks",
+                    "build up slowly instead of attacking the pawn wall",
+                ],
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StructureMatch {
+    // The side whose pawns form the structure.
+    pub side: Color,
+    pub kind: StructureKind,
+}
+
+impl fmt::Display for StructureMatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let side = if self.side == Color::White {
+            "white"
+        } else {
+            "black"
+        };
+        write!(
+            f,
+            "{} {}: {}",
+            side,
+            self.kind.name(),
+            self.kind.description()
+        )
+    }
+}
+
+// Each side's pawns as square sets, so file and square queries are mask tests instead of board
+// scans.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PawnBitboards {
+    white: Bitboard,
+    black: Bitboard,
+}
+
+impl PawnBitboards {
+    pub fn from_board(board: &Board) -> Self {
+        Self {
+            white: board.pieces(PieceType::Pawn, Color::White),
+            black: board.pieces(PieceType::Pawn, Color::Black),
+        }
+    }
+
+    pub fn of(&self, color: Color) -> Bitboard {
+        match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        }
+    }
+
+    pub fn count_on_file(&self, color: Color, file: usize) -> u32 {
+        (self.of(color) & Bitboard::file(file)).count()
+    }
+
+    pub fn on_file(&self, color: Color, file: usize) -> bool {
+        self.count_on_file(color, file) > 0
+    }
+}
+
+// Everything derived from one pawn configuration.
+#[derive(Debug, Clone)]
+pub struct PawnEntry {
+    pub pawns: PawnBitboards,
+    pub structures: Vec<StructureMatch>,
+}
+
+pub struct PawnStructureAnalyzer {
+    cache: HashMap<u64, PawnEntry>,
+}
+
+impl PawnStructureAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn classify(&mut self, board: &Board) -> Vec<StructureMatch> {
+        self.entry(board).structures.clone()
+    }
+
+    pub fn entry(&mut self, board: &Board) -> &PawnEntry {
+        let key = board.pawn_hash();
+        if !self.cache.contains_key(&key) {
+            if self.cache.len() >= CACHE_LIMIT {
+                self.cache.clear();
+            }
+            self.cache.insert(key, Self::analyze(board));
+        }
+        &self.cache[&key]
+    }
+
+    fn analyze(board: &Board) -> PawnEntry {
+        let pawns = PawnBitboards::from_board(board);
+        let view = Pawns { pawns: &pawns };
+        let structures = [Color::White, Color::Black]
+            .into_iter()
+            .flat_map(|side| {
+                view.structures(side)
+                    .into_iter()
+                    .map(move |kind| StructureMatch { side, kind })
+            })
+            .collect();
+        PawnEntry { pawns, structures }
+    }
+}
+
+impl Default for PawnStructureAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Pawn queries with ranks counted from `side`'s own back rank (0 = first rank).
+struct Pawns<'a> {
+    pawns: &'a PawnBitboards,
+}
+
+impl Pawns<'_> {
+    fn at(&self, color: Color, file: usize, rank: usize, side: Color) -> bool {
+        let rank = if side == Color::White { rank } else { 7 - rank };
+        self.pawns.of(color).contains(rank * 8 + file)
+    }
+
+    fn on_file(&self, color: Color, file: usize) -> bool {
+        self.pawns.on_file(color, file)
+    }
+
+    fn structures(&self, side: Color) -> Vec<StructureKind> {
+        let them = side.opposite();
+        let own = |file, rank| self.at(side, file, rank, side);
+        let theirs = |file, rank| self.at(them, file, rank, side);
+        let mut found = Vec::new();
+
+        if self.on_file(side, FILE_D) && !self.on_file(side, FILE_C) && !self.on_file(side, FILE_E)
+        {
+            found.push(StructureKind::IsolatedQueenPawn);
+        }
+        if (1..7).any(|rank| own(FILE_C, rank) && own(FILE_D, rank))
+            && !self.on_file(side, FILE_B)
+            && !self.on_file(side, FILE_E)
+        {
+            found.push(StructureKind::HangingPawns);
+        }
+        if own(FILE_D, 3)
+            && own(FILE_E, 2)
+            && !self.on_file(side, FILE_C)
+            && theirs(FILE_D, 4)
+            && theirs(FILE_C, 5)
+            && !self.on_file(them, FILE_E)
+        {
+            found.push(StructureKind::Carlsbad);
+        }
+        if own(FILE_C, 3)
+            && own(FILE_E, 3)
+            && !self.on_file(side, FILE_D)
+            && self.on_file(them, FILE_D)
+            && !self.on_file(them, FILE_C)
+        {
+            found.push(StructureKind::MaroczyBind);
+        }
+        if own(FILE_D, 3) && own(FILE_E, 2) && own(FILE_F, 3) {
+            found.push(StructureKind::Stonewall);
+        }
+        if own(FILE_A, 2)
+            && own(FILE_B, 2)
+            && own(FILE_D, 2)
+            && own(FILE_E, 2)
+            && !self.on_file(side, FILE_C)
+        {
+            found.push(StructureKind::Hedgehog);
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::FenParser;
+
+    fn classify(fen: &str) -> Vec<StructureMatch> {
+        let mut board = Board::new();
+        FenParser::new().parse_fen(&mut board, fen).unwrap();
+        PawnStructureAnalyzer::new().classify(&board)
+    }
+
+    fn found(side: Color, kind: StructureKind) -> StructureMatch {
+        StructureMatch { side, kind }
+    }
+
+    #[test]
+    fn names_classic_structures_for_either_side() {
+        // Queen's Gambit Declined, Exchange Variation
+        assert_eq!(
+            classify("r1bq1rk1/pp1nbppp/2p2n2/3p2B1/3P4/2NBPN2/PPQ2PPP/R3K2R w KQ - 0 9"),
+            vec![found(Color::White, StructureKind::Carlsbad)]
+        );
+        // Black isolani after ...exd5 exd5 style trades
+        assert!(
+            classify("r1bq1rk1/pp3ppp/2n2n2/3p4/8/2N2N2/PP2BPPP/R2Q1RK1 b - - 0 10")
+                .contains(&found(Color::Black, StructureKind::IsolatedQueenPawn))
+        );
+        // Accelerated Dragon with the Maroczy bind
+        assert!(
+            classify("r1bqk2r/pp2ppbp/2np1np1/8/2P1P3/2N1B3/PP2BPPP/R2QK1NR w KQkq - 0 8")
+                .contains(&found(Color::White, StructureKind::MaroczyBind))
+        );
+        // Dutch Stonewall for Black
+        assert!(
+            classify("rnbq1rk1/pp2b1pp/2p1pn2/3p1p2/2PP4/5NP1/PP2PPBP/RNBQ1RK1 w - - 0 7")
+                .contains(&found(Color::Black, StructureKind::Stonewall))
+        );
+        assert!(classify("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_empty());
+    }
+
+    #[test]
+    fn caches_by_pawn_hash_across_piece_moves() {
+        let mut board = Board::new();
+        let mut analyzer = PawnStructureAnalyzer::new();
+        analyzer.classify(&board);
+        board.make_move(&Move::new(6, 21, PieceType::Knight));
+        analyzer.classify(&board);
+        assert_eq!(analyzer.cache.len(), 1);
+
+        board.make_move(&Move::new(52, 36, PieceType::Pawn));
+        let pawns = analyzer.entry(&board).pawns;
+        assert_eq!(analyzer.cache.len(), 2);
+        assert_eq!(pawns.count_on_file(Color::Black, FILE_E), 1);
+        assert!(pawns.of(Color::Black).contains(36));
+        assert_eq!(pawns.of(Color::White).count(), 8);
+    }
+}
@@ -1,7 +1,223 @@
 use crate::types::*;
 use crate::board::Board;
-use crate::move_generator::MoveGenerator;
+use crate::move_generator::{MoveGenerator, MoveKind};
+#[cfg(feature = "analysis")]
+use crate::topology::{pin_current_thread, Topology};
 use std::collections::HashMap;
+#[cfg(feature = "analysis")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "analysis")]
+use std::thread;
+
+// A well-known debugging position with its published perft counts from depth 1 up. The unit
+// tests check those that are cheap enough, `perft suite` the rest. Positions kept for play
+// rather than move generation have no counts.
+pub struct TestPosition {
+    pub name: &'static str,
+    pub fen: &'static str,
+    pub perft: &'static [u64],
+}
+
+pub const TEST_POSITIONS: [TestPosition; 8] = [
+    TestPosition {
+        name: "startpos",
+        fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        perft: &[20, 400, 8902, 197281, 4865609, 119060324],
+    },
+    TestPosition {
+        name: "kiwipete",
+        fen: "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        perft: &[48, 2039, 97862, 4085603, 193690690],
+    },
+    TestPosition {
+        name: "position3",
+        fen: "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        perft: &[14, 191, 2812, 43238, 674624, 11030083],
+    },
+    TestPosition {
+        name: "position4",
+        fen: "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        perft: &[6, 264, 9467, 422333, 15833292],
+    },
+    TestPosition {
+        name: "position5",
+        fen: "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        perft: &[44, 1486, 62379, 2103487, 89941194],
+    },
+    TestPosition {
+        name: "position6",
+        fen: "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        perft: &[46, 2079, 89890, 3894594, 164075551],
+    },
+    // Albin Countergambit after 7.Ke2; Black wins with 7...fxg1=N+
+    TestPosition {
+        name: "lasker-trap",
+        fen: "rnbqk1nr/ppp2ppp/8/4P3/1BP5/8/PP2KpPP/RN1Q1BNR b kq - 1 7",
+        perft: &[],
+    },
+    // White draws with 1.Kc6, a classic horizon test
+    TestPosition {
+        name: "behting-study",
+        fen: "8/8/7p/3KNN1k/2p4p/8/3P2p1/8 w - - 0 1",
+        perft: &[],
+    },
+];
+
+// Looks a position up by name, ignoring case, spaces and dashes: `lasker trap` finds
+// `lasker-trap`. The perft positions are numbered from the start position, so kiwipete is
+// also `position2`.
+pub fn test_position(name: &str) -> Option<&'static TestPosition> {
+    let key = |name: &str| -> String {
+        name.chars()
+            .filter(|ch| ch.is_ascii_alphanumeric())
+            .map(|ch| ch.to_ascii_lowercase())
+            .collect()
+    };
+    let wanted = match key(name).as_str() {
+        "position2" => "kiwipete".to_string(),
+        other => other.to_string(),
+    };
+    TEST_POSITIONS
+        .iter()
+        .find(|position| key(position.name) == wanted)
+}
+
+// A `perft suite` entry: a position and its known counts, by depth.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PerftCase {
+    pub name: String,
+    pub fen: String,
+    pub counts: Vec<(u8, u64)>,
+}
+
+// The test positions that have counts.
+pub fn standard_cases() -> Vec<PerftCase> {
+    TEST_POSITIONS
+        .iter()
+        .filter(|position| !position.perft.is_empty())
+        .map(|position| PerftCase {
+            name: position.name.to_string(),
+            fen: position.fen.to_string(),
+            counts: (1..).zip(position.perft.iter().copied()).collect(),
+        })
+        .collect()
+}
+
+// The EPD perft suite format: a position a line, `<fen> ;D1 20 ;D2 400 ... ;id <name>`, the
+// counts for any depths and the name optional (`line <n>` without one). Blank lines and `#`
+// comments are skipped. The FEN itself is checked by whoever sets it up. Lines are held to the
+// 16 KiB a command line may have; a real one is a FEN and a few counts.
+pub const MAX_SUITE_LINE_BYTES: usize = 16 * 1024;
+
+pub fn parse_suite(text: &str) -> Result<Vec<PerftCase>, String> {
+    let mut cases = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let invalid = || format!("Invalid perft suite line {}", index + 1);
+        if line.len() > MAX_SUITE_LINE_BYTES {
+            return Err(invalid());
+        }
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split(';').map(str::trim);
+        let fen = fields.next().unwrap_or_default().to_string();
+        let mut case = PerftCase {
+            name: format!("line {}", index + 1),
+            fen,
+            counts: Vec::new(),
+        };
+        for field in fields {
+            match field.split_once(' ') {
+                Some(("id", name)) => case.name = name.trim().to_string(),
+                Some((depth, nodes)) if depth.starts_with('D') => {
+                    let depth = depth[1..].parse::<u8>().ok().filter(|&depth| depth >= 1).ok_or_else(invalid)?;
+                    let nodes = nodes.trim().parse::<u64>().map_err(|_| invalid())?;
+                    case.counts.push((depth, nodes));
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        if case.counts.is_empty() {
+            return Err(invalid());
+        }
+        case.counts.sort_unstable();
+        cases.push(case);
+    }
+    Ok(cases)
+}
+
+// Which leaves a perft counts: every one, or only those reached by a capture (en passant and
+// capturing promotions included) or by a checking move. The filtered counts are the Captures and
+// Checks columns of the published perft tables, so they cross-check the move generator beyond the
+// plain totals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PerftMode {
+    All,
+    Captures,
+    Checks,
+}
+
+impl PerftMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            PerftMode::All => "all",
+            PerftMode::Captures => "captures",
+            PerftMode::Checks => "checks",
+        }
+    }
+}
+
+pub const DEFAULT_PERFT_HASH_MB: usize = 16;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct PerftEntry {
+    hash: u64,
+    depth: u8,
+    nodes: u64,
+}
+
+// Subtree counts by position and depth, for `perft_fast`: perft trees are full of transpositions
+// (1.e4 e5 2.Nf3 and 1.Nf3 e5 2.e4), and a count below a position never changes. One entry per
+// slot, always replaced; the full hash is kept, so only a true 64-bit collision could give a
+// wrong count.
+pub struct PerftTable {
+    entries: Vec<PerftEntry>,
+    hits: u64,
+}
+
+impl PerftTable {
+    pub fn new(size_mb: usize) -> Self {
+        let slots = (size_mb.max(1) * 1024 * 1024 / std::mem::size_of::<PerftEntry>()).max(1);
+        // A power of two, so the index is a mask of the hash
+        let slots = 1 << (usize::BITS - 1 - slots.leading_zeros());
+        Self {
+            entries: vec![PerftEntry::default(); slots],
+            hits: 0,
+        }
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    fn slot(&self, hash: u64) -> usize {
+        hash as usize & (self.entries.len() - 1)
+    }
+
+    // Depths 0 and 1 are never stored, so an empty slot can't match.
+    fn probe(&mut self, hash: u64, depth: u8) -> Option<u64> {
+        let entry = self.entries[self.slot(hash)];
+        let found = (entry.hash == hash && entry.depth == depth).then_some(entry.nodes);
+        self.hits += found.is_some() as u64;
+        found
+    }
+
+    fn store(&mut self, hash: u64, depth: u8, nodes: u64) {
+        let slot = self.slot(hash);
+        self.entries[slot] = PerftEntry { hash, depth, nodes };
+    }
+}
 
 pub struct Perft {
     move_generator: MoveGenerator,
@@ -32,26 +248,279 @@ impl Perft {
         nodes
     }
 
-    pub fn perft_divide(&self, board: &mut Board, depth: u8) -> HashMap<String, u64> {
-        let mut results = HashMap::new();
+    // `perft` with bulk counting, the last ply's legal moves counted rather than played, and
+    // with a table, subtrees already counted looked up instead of walked again.
+    pub fn perft_fast(&self, board: &mut Board, depth: u8, mut table: Option<&mut PerftTable>) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
         let color = board.get_turn();
         let moves = self.move_generator.get_legal_moves(board, color);
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        let hash = board.get_hash();
+        if let Some(nodes) = table.as_mut().and_then(|table| table.probe(hash, depth)) {
+            return nodes;
+        }
+
+        let mut nodes = 0;
+        for chess_move in &moves {
+            board.make_move(chess_move);
+            nodes += self.perft_fast(board, depth - 1, table.as_deref_mut());
+            board.undo_move();
+        }
+        if let Some(table) = table {
+            table.store(hash, depth, nodes);
+        }
+        nodes
+    }
+
+    // `perft_fast` with the root moves handed out to `threads` workers from a shared counter,
+    // and their counts summed. Each worker has its own table, `hash_mb` shared out between them
+    // (none when 0), so the memory used doesn't grow with the threads. With a topology each
+    // worker is pinned to its own CPU, as `analyze all`'s are. Returns the nodes and the table
+    // hits of all workers. Threads come with the `analysis` feature.
+    #[cfg(feature = "analysis")]
+    pub fn perft_parallel(
+        &self,
+        board: &Board,
+        depth: u8,
+        threads: usize,
+        hash_mb: usize,
+        pinning: Option<&Topology>,
+    ) -> (u64, u64) {
+        let mut root = board.clone();
+        if depth <= 1 {
+            return (self.perft_fast(&mut root, depth, None), 0);
+        }
+        let color = root.get_turn();
+        let moves = self.move_generator.get_legal_moves(&mut root, color);
+        let threads = threads.clamp(1, moves.len().max(1));
+        let worker_mb = (hash_mb > 0).then(|| (hash_mb / threads).max(1));
+        let next = AtomicUsize::new(0);
+
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|worker| {
+                    let (next, moves) = (&next, &moves);
+                    scope.spawn(move || {
+                        if let Some(topology) = pinning {
+                            pin_current_thread(topology.cpu_for_worker(worker));
+                        }
+                        let perft = Perft::new();
+                        let mut board = board.clone();
+                        let mut table = worker_mb.map(PerftTable::new);
+                        let mut nodes = 0;
+                        while let Some(chess_move) = moves.get(next.fetch_add(1, Ordering::Relaxed)) {
+                            board.make_move(chess_move);
+                            nodes += perft.perft_fast(&mut board, depth - 1, table.as_mut());
+                            board.undo_move();
+                        }
+                        (nodes, table.as_ref().map_or(0, PerftTable::hits))
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().expect("perft worker panicked"))
+                .fold((0, 0), |(nodes, hits), (worker_nodes, worker_hits)| (nodes + worker_nodes, hits + worker_hits))
+        })
+    }
+
+    // The tree is walked as `perft` walks it; only the last ply is filtered. Captures come from
+    // the noisy-move generator the search's quiescence uses, so this also times that generator.
+    pub fn perft_of(&self, board: &mut Board, depth: u8, mode: PerftMode) -> u64 {
+        if depth == 0 || mode == PerftMode::All {
+            return self.perft(board, depth);
+        }
 
+        let color = board.get_turn();
+        if depth > 1 {
+            let moves = self.move_generator.get_legal_moves(board, color);
+            let mut nodes = 0;
+            for chess_move in &moves {
+                board.make_move(chess_move);
+                nodes += self.perft_of(board, depth - 1, mode);
+                board.undo_move();
+            }
+            return nodes;
+        }
+
+        let moves = match mode {
+            PerftMode::Captures => self.move_generator.generate_moves_of(board, color, MoveKind::Noisy),
+            _ => self.move_generator.generate_moves(board, color),
+        };
+        let mut nodes = 0;
         for chess_move in &moves {
-            let from = square_to_algebraic(chess_move.from);
-            let to = square_to_algebraic(chess_move.to);
-            let move_str = match chess_move.promotion {
-                Some(promotion) => format!("{}{}{}", from, to, promotion),
-                None => format!("{}{}", from, to),
-            };
-            
+            if mode == PerftMode::Captures && chess_move.captured.is_none() {
+                continue;
+            }
             board.make_move(chess_move);
-            let count = self.perft(board, depth - 1);
+            let counted = !self.move_generator.is_in_check(board, color)
+                && (mode == PerftMode::Captures || self.move_generator.is_in_check(board, color.opposite()));
             board.undo_move();
-            
-            results.insert(move_str, count);
+            nodes += counted as u64;
+        }
+        nodes
+    }
+
+    pub fn perft_divide(&self, board: &mut Board, depth: u8) -> HashMap<String, u64> {
+        self.divide_moves(board, depth)
+            .into_iter()
+            .map(|(chess_move, count)| (chess_move.to_string(), count))
+            .collect()
+    }
+
+    // Leaf counts below each legal move, in generation order.
+    pub fn divide_moves(&self, board: &mut Board, depth: u8) -> Vec<(Move, u64)> {
+        let color = board.get_turn();
+        let moves = self.move_generator.get_legal_moves(board, color);
+
+        moves
+            .into_iter()
+            .map(|chess_move| {
+                board.make_move(&chess_move);
+                let count = self.perft(board, depth - 1);
+                board.undo_move();
+                (chess_move, count)
+            })
+            .collect()
+    }
+}
+
+impl Default for Perft {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::FenParser;
+
+    // Counts above this are left to `perft suite`
+    const CHEAP_PERFT: u64 = 100_000;
+
+    #[test]
+    fn test_positions_match_their_known_perft_counts() {
+        let perft = Perft::new();
+        for position in &TEST_POSITIONS {
+            let mut board = Board::new();
+            FenParser::new()
+                .parse_fen(&mut board, position.fen)
+                .unwrap();
+            for (depth, &expected) in position.perft.iter().enumerate().take_while(|(_, &nodes)| nodes <= CHEAP_PERFT) {
+                let nodes = perft.perft(&mut board, depth as u8 + 1);
+                assert_eq!(nodes, expected, "{} depth {}", position.name, depth + 1);
+            }
+        }
+        assert_eq!(
+            test_position("Lasker trap").map(|p| p.name),
+            Some("lasker-trap")
+        );
+        assert_eq!(test_position("position2").map(|p| p.name), Some("kiwipete"));
+        assert!(test_position("position7").is_none());
+    }
+
+    #[test]
+    fn reads_the_epd_suite_in_test_fixtures() {
+        // The fixtures are outside the crate, which the Docker image builds on its own
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../test/fixtures/perft/suite.epd");
+        let cases = std::fs::read_to_string(path).map_or_else(|_| Vec::new(), |text| parse_suite(&text).unwrap());
+        assert!(cases.is_empty() || cases.len() == 14);
+        let perft = Perft::new();
+        for case in &cases {
+            let mut board = Board::new();
+            FenParser::new().parse_fen(&mut board, &case.fen).unwrap();
+            for &(depth, expected) in case.counts.iter().filter(|(_, nodes)| *nodes <= CHEAP_PERFT / 10) {
+                assert_eq!(perft.perft_fast(&mut board, depth, None), expected, "{} depth {}", case.name, depth);
+            }
         }
 
-        results
+        assert!(cases.first().is_none_or(|case| case.name == "illegal-ep-1"));
+        let unnamed = parse_suite("# comment\n\n8/8/8/8/8/8/8/K6k w - - 0 1 ;D2 9 ;D1 3\n").unwrap();
+        assert_eq!(unnamed[0].name, "line 3");
+        assert_eq!(unnamed[0].counts, vec![(1, 3), (2, 9)]);
+        assert_eq!(standard_cases()[0].counts[2], (3, 8902));
+        for bad in ["8/8/8/8/8/8/8/K6k w - - 0 1", "8/8/8/8/8/8/8/K6k w - - 0 1 ;D0 1", "8/8/8/8/8/8/8/K6k w - - 0 1 ;D1 x", "fen ;bm e4"] {
+            assert_eq!(parse_suite(bad), Err("Invalid perft suite line 1".to_string()), "{}", bad);
+        }
+        let long = format!("8/8/8/8/8/8/8/K6k w - - 0 1 ;D1 3{}", " ;D1 3".repeat(MAX_SUITE_LINE_BYTES));
+        assert_eq!(parse_suite(&long), Err("Invalid perft suite line 1".to_string()));
+    }
+
+    #[test]
+    fn fast_perft_matches_the_plain_walk() {
+        let perft = Perft::new();
+        // A table small enough to be overwritten all the time still gives exact counts
+        let mut tables = [PerftTable::new(DEFAULT_PERFT_HASH_MB), PerftTable::new(0)];
+        for position in TEST_POSITIONS.iter().filter(|position| !position.perft.is_empty()) {
+            let mut board = Board::new();
+            FenParser::new()
+                .parse_fen(&mut board, position.fen)
+                .unwrap();
+            let fen = FenParser::new().export_fen(&board);
+            let cheap = position.perft.iter().filter(|&&nodes| nodes <= CHEAP_PERFT).count();
+            for depth in 0..=cheap as u8 {
+                let plain = perft.perft(&mut board, depth);
+                assert_eq!(perft.perft_fast(&mut board, depth, None), plain, "{} depth {}", position.name, depth);
+                for table in &mut tables {
+                    assert_eq!(perft.perft_fast(&mut board, depth, Some(table)), plain, "{} depth {}", position.name, depth);
+                }
+            }
+            assert_eq!(FenParser::new().export_fen(&board), fen);
+        }
+
+        // Counting the same tree again is one lookup
+        let mut board = Board::new();
+        let mut table = PerftTable::new(DEFAULT_PERFT_HASH_MB);
+        assert_eq!(perft.perft_fast(&mut board, 4, Some(&mut table)), 197281);
+        let hits = table.hits();
+        assert_eq!(perft.perft_fast(&mut board, 4, Some(&mut table)), 197281);
+        assert_eq!(table.hits(), hits + 1);
+    }
+
+    // Any number of threads, with or without tables, count the same tree
+    #[cfg(feature = "analysis")]
+    #[test]
+    fn parallel_perft_matches_the_plain_walk() {
+        let perft = Perft::new();
+        let kiwipete = test_position("kiwipete").unwrap();
+        let mut board = Board::new();
+        FenParser::new().parse_fen(&mut board, kiwipete.fen).unwrap();
+        for (threads, hash_mb) in [(1, 0), (3, 0), (4, 16), (64, 1)] {
+            for depth in 0..=3 {
+                let (nodes, _) = perft.perft_parallel(&board, depth, threads, hash_mb, None);
+                let expected = if depth == 0 { 1 } else { kiwipete.perft[depth as usize - 1] };
+                assert_eq!(nodes, expected, "{} threads, depth {}", threads, depth);
+            }
+        }
+    }
+
+    #[test]
+    fn counts_the_captures_and_checks_columns_of_the_perft_tables() {
+        // (name, captures by depth, checks by depth)
+        let tables: [(&str, &[u64], &[u64]); 4] = [
+            ("startpos", &[0, 0, 34, 1576], &[0, 0, 12, 469]),
+            ("kiwipete", &[8, 351, 17102], &[0, 3, 993]),
+            ("position3", &[1, 14, 209, 3348], &[2, 10, 267, 1680]),
+            ("position4", &[0, 87, 1021], &[0, 10, 38]),
+        ];
+        let perft = Perft::new();
+        for (name, captures, checks) in tables {
+            let mut board = Board::new();
+            FenParser::new()
+                .parse_fen(&mut board, test_position(name).unwrap().fen)
+                .unwrap();
+            for (mode, expected) in [(PerftMode::Captures, captures), (PerftMode::Checks, checks)] {
+                for (depth, &expected) in expected.iter().enumerate() {
+                    let nodes = perft.perft_of(&mut board, depth as u8 + 1, mode);
+                    assert_eq!(nodes, expected, "{} {} depth {}", name, mode.name(), depth + 1);
+                }
+            }
+        }
     }
-}
\ No newline at end of file
+}
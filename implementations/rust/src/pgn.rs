@@ -0,0 +1,394 @@
+use crate::board::Board;
+use crate::fen::FenParser;
+use crate::move_generator::MoveGenerator;
+use crate::notation::SanParser;
+use crate::types::Color;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+// PGN export format keeps movetext lines below 80 characters.
+const LINE_WIDTH: usize = 79;
+
+// The Seven Tag Roster minus the result, which is read off the board when exporting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnHeaders {
+    pub event: String,
+    pub site: String,
+    pub date: String,
+    pub round: String,
+    pub white: String,
+    pub black: String,
+}
+
+impl PgnHeaders {
+    // Headers for a game starting now; unknown tags hold PGN's "?".
+    pub fn new() -> Self {
+        Self {
+            event: "Casual game".to_string(),
+            site: "?".to_string(),
+            date: today(),
+            round: "-".to_string(),
+            white: "?".to_string(),
+            black: "?".to_string(),
+        }
+    }
+}
+
+impl Default for PgnHeaders {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct PgnWriter {
+    san_parser: SanParser,
+    move_generator: MoveGenerator,
+    fen_parser: FenParser,
+}
+
+impl PgnWriter {
+    pub fn new() -> Self {
+        Self {
+            san_parser: SanParser::new(),
+            move_generator: MoveGenerator::new(),
+            fen_parser: FenParser::new(),
+        }
+    }
+
+    // The game so far as a PGN game: headers, numbered SAN movetext and the result. A game that
+    // did not start from the initial position carries SetUp and FEN tags.
+    pub fn export(&self, board: &Board, headers: &PgnHeaders) -> String {
+        let result = self.result(board);
+        let mut start = board.clone();
+        while start.undo_move().is_some() {}
+        let start_fen = self.fen_parser.export_fen(&start);
+
+        let mut pgn = String::new();
+        for (tag, value) in [
+            ("Event", headers.event.as_str()),
+            ("Site", &headers.site),
+            ("Date", &headers.date),
+            ("Round", &headers.round),
+            ("White", &headers.white),
+            ("Black", &headers.black),
+            ("Result", result),
+        ] {
+            pgn.push_str(&format!("[{} \"{}\"]\n", tag, escape(value)));
+        }
+        if start_fen != START_FEN {
+            pgn.push_str("[SetUp \"1\"]\n");
+            pgn.push_str(&format!("[FEN \"{}\"]\n", start_fen));
+        }
+        pgn.push('\n');
+
+        let mut tokens = self.movetext(&start, board);
+        tokens.push(result.to_string());
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+        pgn
+    }
+
+    // The game's moves in SAN, without move numbers.
+    pub fn san_moves(&self, board: &Board) -> Vec<String> {
+        let mut replay = board.clone();
+        while replay.undo_move().is_some() {}
+        board
+            .get_state()
+            .move_history
+            .iter()
+            .map(|chess_move| {
+                let san = self.san_parser.export_san(&mut replay, chess_move);
+                replay.make_move(chess_move);
+                san
+            })
+            .collect()
+    }
+
+    pub fn result(&self, board: &Board) -> &'static str {
+        let mut board = board.clone();
+        let color = board.get_turn();
+        if !self.move_generator.has_legal_move(&mut board, color) {
+            if !self.move_generator.is_in_check(&board, color) {
+                "1/2-1/2"
+            } else if color == Color::White {
+                "0-1"
+            } else {
+                "1-0"
+            }
+        } else if board.is_draw() {
+            "1/2-1/2"
+        } else {
+            "*"
+        }
+    }
+
+    fn movetext(&self, start: &Board, board: &Board) -> Vec<String> {
+        let mut number = start.get_state().fullmove_number;
+        let mut white_to_move = start.get_turn() == Color::White;
+        let mut tokens = Vec::new();
+        for (index, san) in self.san_moves(board).into_iter().enumerate() {
+            if white_to_move {
+                tokens.push(format!("{}.", number));
+            } else if index == 0 {
+                tokens.push(format!("{}...", number));
+            }
+            tokens.push(san);
+            if !white_to_move {
+                number += 1;
+            }
+            white_to_move = !white_to_move;
+        }
+        tokens
+    }
+}
+
+impl Default for PgnWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// One game read from a PGN database: its tags and its mainline moves in SAN, with comments,
+// variations, NAGs, move numbers and the result stripped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Far longer than any real PGN line (export format wraps at 80 columns), the same bound as a
+// command line. A game with a longer one is dropped whole rather than read in part.
+pub const MAX_PGN_LINE_BYTES: usize = 16 * 1024;
+
+// Splits a PGN database into games. Moves are not checked here; replaying them against a board
+// is where an illegal one shows.
+pub fn read_games(text: &str) -> Vec<PgnGame> {
+    let mut games = Vec::new();
+    let mut game = PgnGame::default();
+    let mut in_movetext = false;
+    let mut oversized = false;
+    // Open `{` comments and `(` variations carry over from one line to the next.
+    let mut comment = false;
+    let mut variations = 0usize;
+
+    for line in text.lines() {
+        if line.len() > MAX_PGN_LINE_BYTES {
+            oversized = true;
+            in_movetext = true;
+            continue;
+        }
+        let trimmed = line.trim();
+        if !comment && variations == 0 && trimmed.starts_with('[') {
+            // A tag after movetext starts the next game
+            if in_movetext {
+                let finished = std::mem::take(&mut game);
+                if !std::mem::take(&mut oversized) {
+                    games.push(finished);
+                }
+                in_movetext = false;
+            }
+            if let Some(tag) = parse_tag(trimmed) {
+                game.tags.push(tag);
+            }
+            continue;
+        }
+        if trimmed.starts_with('%') {
+            continue;
+        }
+
+        let mut token = String::new();
+        let push_token = |token: &mut String, game: &mut PgnGame| {
+            let text = std::mem::take(token);
+            if is_result(&text) {
+                return;
+            }
+            let san = strip_move_number(&text).trim_end_matches(['!', '?']);
+            if !san.is_empty() && !san.starts_with('$') {
+                game.moves.push(san.to_string());
+            }
+        };
+        for ch in trimmed.chars() {
+            if comment {
+                comment = ch != '}';
+                continue;
+            }
+            match ch {
+                '{' => comment = true,
+                ';' => break,
+                '(' => variations += 1,
+                ')' => variations = variations.saturating_sub(1),
+                _ if variations > 0 => {}
+                _ if ch.is_whitespace() => push_token(&mut token, &mut game),
+                _ => token.push(ch),
+            }
+            if matches!(ch, '{' | '(' | ')') {
+                push_token(&mut token, &mut game);
+            }
+        }
+        push_token(&mut token, &mut game);
+        if !game.moves.is_empty() {
+            in_movetext = true;
+        }
+    }
+    if !oversized && (!game.moves.is_empty() || !game.tags.is_empty()) {
+        games.push(game);
+    }
+    games
+}
+
+// `12.e4` and `12...e5` to the move. Only digits followed by dots are a move number, so castling
+// written with zeros (`0-0`) is left alone.
+fn strip_move_number(token: &str) -> &str {
+    let digits = token.len()
+        - token
+            .trim_start_matches(|ch: char| ch.is_ascii_digit())
+            .len();
+    let rest = &token[digits..];
+    if digits > 0 && rest.starts_with('.') {
+        rest.trim_start_matches('.')
+    } else {
+        token
+    }
+}
+
+// `[Name "value"]`
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (name, value) = inner.split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((
+        name.to_string(),
+        value.replace("\\\"", "\"").replace("\\\\", "\\"),
+    ))
+}
+
+fn is_result(token: &str) -> bool {
+    matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*")
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Today's UTC date in PGN's YYYY.MM.DD form.
+pub fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (year, month, day) = civil_date(seconds / 86_400);
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+// Days since 1970-01-01 to a proleptic Gregorian date (Howard Hinnant's algorithm).
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(board: &mut Board, moves: &[&str]) {
+        let san_parser = SanParser::new();
+        for san in moves {
+            let chess_move = san_parser.parse_san(board, san).unwrap();
+            board.make_move(&chess_move);
+        }
+    }
+
+    #[test]
+    fn exports_headers_movetext_and_result() {
+        let mut board = Board::new();
+        play(&mut board, &["f3", "e5", "g4", "Qh4"]);
+        let headers = PgnHeaders {
+            date: "2024.01.31".to_string(),
+            ..PgnHeaders::new()
+        };
+        let pgn = PgnWriter::new().export(&board, &headers);
+        assert!(pgn.starts_with("[Event \"Casual game\"]\n[Site \"?\"]\n[Date \"2024.01.31\"]\n"));
+        assert!(pgn.contains("[Result \"0-1\"]\n\n1. f3 e5 2. g4 Qh4# 0-1\n"));
+        assert!(!pgn.contains("[FEN"));
+    }
+
+    #[test]
+    fn games_from_a_position_carry_the_fen_and_black_move_numbers() {
+        let mut board = Board::new();
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 40";
+        FenParser::new().parse_fen(&mut board, fen).unwrap();
+        play(&mut board, &["Kd7", "e4"]);
+        let pgn = PgnWriter::new().export(&board, &PgnHeaders::new());
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 40\"]\n"));
+        assert!(pgn.ends_with("\n40... Kd7 41. e4 *\n"));
+        assert_eq!(PgnWriter::new().result(&board), "*");
+    }
+
+    #[test]
+    fn reads_mainlines_and_tags_from_a_database() {
+        let text = "[Event \"One\"]\n[ECO \"C20\"]\n\n1. e4 {best by test} e5 2. Nf3!? (2. f4 exf4) Nc6 $1\n3. Bb5 a6 1-0\n\n[Event \"Two\"]\n\n1. d4 d5 ; a comment\n2. c4 *\n";
+        let games = read_games(text);
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].tag("ECO"), Some("C20"));
+        assert_eq!(games[0].moves, ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]);
+        assert_eq!(games[1].tag("Event"), Some("Two"));
+        assert_eq!(games[1].moves, ["d4", "d5", "c4"]);
+
+        let games = read_games(
+            "1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4.0-0 Nf6 5. d3 d6 6. Nc3 Be6 7. Be3 Qd7 8. a3 0-0-0 *",
+        );
+        assert_eq!(games[0].moves[6..8], ["0-0", "Nf6"]);
+        assert_eq!(games[0].moves[15], "0-0-0");
+        let mut board = Board::new();
+        let moves: Vec<&str> = games[0].moves.iter().map(String::as_str).collect();
+        play(&mut board, &moves);
+        assert_eq!(board.get_state().move_history.len(), 16);
+
+        let long = format!("1. e4 {{{}}} e5 *", "x".repeat(MAX_PGN_LINE_BYTES));
+        let games = read_games(&format!(
+            "[Event \"Long\"]\n\n{}\n\n[Event \"Short\"]\n\n1. d4 *\n",
+            long
+        ));
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].tag("Event"), Some("Short"));
+    }
+
+    #[test]
+    fn converts_days_to_calendar_dates() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(19_753), (2024, 1, 31));
+    }
+}
@@ -0,0 +1,218 @@
+use crate::board::Board;
+use crate::move_generator::MoveGenerator;
+use crate::types::*;
+use std::collections::HashMap;
+
+// Problems are solved by exhaustive search, which grows with every move; five covers most
+// published helpmates and selfmates.
+pub const MAX_PROBLEM_MOVES: u8 = 5;
+
+// What a problem asks for, always counted in moves of the side to move:
+// - `Mate`: the side to move mates whatever the defence does (#n)
+// - `Helpmate`: both sides cooperate so the side to move gets mated (h#n; Black usually starts)
+// - `Selfmate`: the side to move forces the other side to mate it (s#n)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stipulation {
+    Mate,
+    Helpmate,
+    Selfmate,
+}
+
+impl Stipulation {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "mate" | "#" => Some(Stipulation::Mate),
+            "helpmate" | "h#" => Some(Stipulation::Helpmate),
+            "selfmate" | "s#" => Some(Stipulation::Selfmate),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stipulation::Mate => "mate",
+            Stipulation::Helpmate => "helpmate",
+            Stipulation::Selfmate => "selfmate",
+        }
+    }
+}
+
+pub struct ProblemSolver {
+    move_generator: MoveGenerator,
+    // Whether the side to move achieves the aim from a position with this many moves left. Only
+    // failures are stored for helpmates, whose successes are collected as lines instead.
+    known: HashMap<(u64, u8), bool>,
+    nodes: u64,
+}
+
+impl ProblemSolver {
+    pub fn new() -> Self {
+        Self {
+            move_generator: MoveGenerator::new(),
+            known: HashMap::new(),
+            nodes: 0,
+        }
+    }
+
+    // Every solution in move generation order. With a defence to beat, as in mates and
+    // selfmates, a solution is a key move; in a helpmate it is the whole cooperative line. More
+    // than one solution means the problem is cooked.
+    pub fn solve(
+        &mut self,
+        board: &mut Board,
+        stipulation: Stipulation,
+        moves: u8,
+    ) -> Vec<Vec<Move>> {
+        self.known.clear();
+        self.nodes = 0;
+        if stipulation == Stipulation::Helpmate {
+            let mated = board.get_turn();
+            return self.helpmate_lines(board, mated, moves * 2);
+        }
+
+        let color = board.get_turn();
+        let mut keys = Vec::new();
+        for chess_move in self.move_generator.get_legal_moves(board, color) {
+            board.make_move(&chess_move);
+            let solves = self.defence_fails(board, stipulation, moves);
+            board.undo_move();
+            if solves {
+                keys.push(vec![chess_move]);
+            }
+        }
+        keys
+    }
+
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    // The attacker has just played with `moves` left, including the one played; true when every
+    // defence still loses.
+    fn defence_fails(&mut self, board: &mut Board, stipulation: Stipulation, moves: u8) -> bool {
+        self.nodes += 1;
+        let defender = board.get_turn();
+        let attacker = defender.opposite();
+        let defences = self.move_generator.get_legal_moves(board, defender);
+        if defences.is_empty() {
+            // Mate ends a direct mate; a selfmate needs the defender to be the one mating.
+            return stipulation == Stipulation::Mate
+                && self.move_generator.is_in_check(board, defender);
+        }
+        defences.iter().all(|defence| {
+            board.make_move(defence);
+            let fails = (stipulation == Stipulation::Selfmate
+                && self.is_checkmated(board, attacker))
+                || (moves > 1 && self.attack_succeeds(board, stipulation, moves - 1));
+            board.undo_move();
+            fails
+        })
+    }
+
+    fn attack_succeeds(&mut self, board: &mut Board, stipulation: Stipulation, moves: u8) -> bool {
+        let key = (board.get_hash(), moves);
+        if let Some(&known) = self.known.get(&key) {
+            return known;
+        }
+        let color = board.get_turn();
+        let attacks = self.move_generator.get_legal_moves(board, color);
+        let succeeds = attacks.iter().any(|attack| {
+            board.make_move(attack);
+            let succeeds = self.defence_fails(board, stipulation, moves);
+            board.undo_move();
+            succeeds
+        });
+        self.known.insert(key, succeeds);
+        succeeds
+    }
+
+    // Lines of exactly `plies` moves ending with `mated` checkmated.
+    fn helpmate_lines(&mut self, board: &mut Board, mated: Color, plies: u8) -> Vec<Vec<Move>> {
+        self.nodes += 1;
+        if plies == 0 {
+            return if self.is_checkmated(board, mated) {
+                vec![Vec::new()]
+            } else {
+                Vec::new()
+            };
+        }
+        let key = (board.get_hash(), plies);
+        if self.known.contains_key(&key) {
+            return Vec::new();
+        }
+
+        let color = board.get_turn();
+        let mut lines = Vec::new();
+        for chess_move in self.move_generator.get_legal_moves(board, color) {
+            board.make_move(&chess_move);
+            for mut line in self.helpmate_lines(board, mated, plies - 1) {
+                line.insert(0, chess_move.clone());
+                lines.push(line);
+            }
+            board.undo_move();
+        }
+        if lines.is_empty() {
+            self.known.insert(key, false);
+        }
+        lines
+    }
+
+    fn is_checkmated(&self, board: &mut Board, color: Color) -> bool {
+        self.move_generator.is_in_check(board, color)
+            && !self.move_generator.has_legal_move(board, color)
+    }
+}
+
+impl Default for ProblemSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::FenParser;
+
+    fn solve(fen: &str, stipulation: Stipulation, moves: u8) -> Vec<String> {
+        let mut board = Board::new();
+        FenParser::new().parse_fen(&mut board, fen).unwrap();
+        ProblemSolver::new()
+            .solve(&mut board, stipulation, moves)
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|chess_move| chess_move.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_direct_mate_keys() {
+        assert_eq!(
+            solve("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", Stipulation::Mate, 1),
+            ["a1a8"]
+        );
+        assert!(solve("6k1/5ppp/8/8/8/8/8/R5K1 b - - 0 1", Stipulation::Mate, 1).is_empty());
+    }
+
+    #[test]
+    fn solves_helpmates_and_selfmates() {
+        // Black walks into the corner for the rook
+        assert_eq!(
+            solve("7k/5K2/8/8/8/8/8/6R1 b - - 0 1", Stipulation::Helpmate, 1),
+            ["h8h7 g1h1"]
+        );
+        // Qf4 takes the black king's last squares, leaving only g2 mate
+        assert_eq!(
+            solve("8/8/8/8/8/5Qpk/8/6BK w - - 0 1", Stipulation::Selfmate, 1),
+            ["f3f4"]
+        );
+        // A cooked helpmate lists every cooperative line
+        let lines = solve("7k/8/5K2/8/8/8/8/6R1 b - - 0 1", Stipulation::Helpmate, 2);
+        assert!(lines.contains(&"h8h7 f6f7 h7h8 g1h1".to_string()));
+        assert_eq!(lines.len(), 11);
+    }
+}
@@ -0,0 +1,235 @@
+use crate::display::{DisplayOptions, DISPLAY_OPTIONS};
+use crate::types::Color;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+// Wins, draws and losses of the user playing one color against the engine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Record {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Record {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Win(Color),
+    Draw,
+}
+
+// Preferences and results that outlive the session. Saved as `key=value` lines after every
+// change when a file is configured; without one the profile lasts as long as the session.
+pub struct Profile {
+    pub path: Option<PathBuf>,
+    // Applied at startup
+    pub blindfold: bool,
+    // Limits `ai` uses when given none, e.g. `movetime 1000`
+    pub time_control: Option<String>,
+    // How the board is drawn
+    pub display: DisplayOptions,
+    pub white: Record,
+    pub black: Record,
+    game: GameTracker,
+}
+
+// Who moved which color in the game being played. A game counts against the engine when the
+// engine played one color and the user the other.
+#[derive(Debug, Clone, Copy, Default)]
+struct GameTracker {
+    engine: [bool; 2],
+    user: [bool; 2],
+    recorded: bool,
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self {
+            path: None,
+            blindfold: false,
+            time_control: None,
+            display: DisplayOptions::default(),
+            white: Record::default(),
+            black: Record::default(),
+            game: GameTracker::default(),
+        }
+    }
+
+    // A missing file is a fresh profile that will be created on the first save.
+    pub fn load(path: PathBuf) -> io::Result<Self> {
+        let mut profile = Self::new();
+        match fs::read_to_string(&path) {
+            Ok(text) => profile.parse(&text),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error),
+        }
+        profile.path = Some(path);
+        Ok(profile)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        match &self.path {
+            Some(path) => fs::write(path, self.serialize()),
+            None => Ok(()),
+        }
+    }
+
+    pub fn new_game(&mut self) {
+        self.game = GameTracker::default();
+    }
+
+    pub fn record_move(&mut self, color: Color, by_engine: bool) {
+        let played = if by_engine {
+            &mut self.game.engine
+        } else {
+            &mut self.game.user
+        };
+        played[side_index(color)] = true;
+    }
+
+    // A game with a pass in it is analysis, not a result.
+    pub fn record_pass(&mut self) {
+        self.game.recorded = true;
+    }
+
+    // Counts a finished game once, and only if it was the user against the engine. Returns
+    // whether it was counted.
+    pub fn finish_game(&mut self, outcome: Outcome) -> bool {
+        let user = match (self.game.user, self.game.engine) {
+            ([true, false], [false, true]) => Color::White,
+            ([false, true], [true, false]) => Color::Black,
+            _ => return false,
+        };
+        if self.game.recorded {
+            return false;
+        }
+        self.game.recorded = true;
+        let record = match user {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        };
+        match outcome {
+            Outcome::Win(winner) if winner == user => record.wins += 1,
+            Outcome::Win(_) => record.losses += 1,
+            Outcome::Draw => record.draws += 1,
+        }
+        true
+    }
+
+    fn parse(&mut self, text: &str) {
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "blindfold" => self.blindfold = value == "on",
+                "time" if !value.is_empty() => self.time_control = Some(value.to_string()),
+                "white" => self.white = parse_record(value),
+                "black" => self.black = parse_record(value),
+                // `display.<option>`; a value this version doesn't know keeps the default
+                key if key.starts_with("display.") => {
+                    let _ = self.display.set(&key["display.".len()..], value);
+                }
+                // Unknown keys come from newer versions; keep going.
+                _ => {}
+            }
+        }
+    }
+
+    fn serialize(&self) -> String {
+        let mut text = format!(
+            "blindfold={}\ntime={}\nwhite={}\nblack={}\n",
+            if self.blindfold { "on" } else { "off" },
+            self.time_control.as_deref().unwrap_or(""),
+            format_record(&self.white),
+            format_record(&self.black)
+        );
+        for name in DISPLAY_OPTIONS {
+            text.push_str(&format!("display.{}={}\n", name, self.display.get(name).unwrap_or_default()));
+        }
+        text
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn side_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+// `wins draws losses`
+fn parse_record(value: &str) -> Record {
+    let counts: Vec<u32> = value
+        .split_whitespace()
+        .map(|count| count.parse().unwrap_or(0))
+        .collect();
+    match counts[..] {
+        [wins, draws, losses] => Record {
+            wins,
+            draws,
+            losses,
+        },
+        _ => Record::default(),
+    }
+}
+
+fn format_record(record: &Record) -> String {
+    format!("{} {} {}", record.wins, record.draws, record.losses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_only_games_against_the_engine_once() {
+        let mut profile = Profile::new();
+        profile.record_move(Color::White, false);
+        profile.record_move(Color::Black, true);
+        assert!(profile.finish_game(Outcome::Win(Color::Black)));
+        assert!(!profile.finish_game(Outcome::Win(Color::Black)));
+        assert_eq!(profile.white.losses, 1);
+
+        // Both sides moved by hand
+        profile.new_game();
+        profile.record_move(Color::White, false);
+        profile.record_move(Color::Black, false);
+        assert!(!profile.finish_game(Outcome::Draw));
+        assert_eq!(profile.white.games() + profile.black.games(), 1);
+    }
+
+    #[test]
+    fn round_trips_through_its_file_format() {
+        let mut profile = Profile::new();
+        profile.blindfold = true;
+        profile.time_control = Some("movetime 1000".to_string());
+        profile.display.set("perspective", "black").unwrap();
+        profile.display.set("fen", "on").unwrap();
+        profile.black = Record {
+            wins: 2,
+            draws: 1,
+            losses: 4,
+        };
+        let mut loaded = Profile::new();
+        loaded.parse(&profile.serialize());
+        assert!(loaded.blindfold);
+        assert_eq!(loaded.time_control.as_deref(), Some("movetime 1000"));
+        assert_eq!(loaded.black, profile.black);
+        assert_eq!(loaded.white, Record::default());
+        assert_eq!(loaded.display, profile.display);
+    }
+}
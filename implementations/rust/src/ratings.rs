@@ -0,0 +1,275 @@
+use crate::selfplay::GameResult;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+// Virtual draws every player is given against an average opponent, as BayesElo's prior does:
+// it keeps a player who won or lost every game at a finite rating, and pulls players with few
+// games towards the middle.
+const PRIOR_DRAWS: f64 = 2.0;
+const MAX_ITERATIONS: usize = 10_000;
+const TOLERANCE: f64 = 1e-9;
+
+// One finished self-play or tournament game, a line of the results file. `white` and `black`
+// name the settings each side played with; `build` is the engine build that played both.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchRecord {
+    pub timestamp: u64,
+    pub build: String,
+    pub white: String,
+    pub black: String,
+    pub result: GameResult,
+    pub termination: String,
+    pub plies: usize,
+    pub start_fen: String,
+}
+
+impl MatchRecord {
+    // The rated player behind a side: the same settings on another build are another player.
+    pub fn white_player(&self) -> String {
+        format!("{} {}", self.build, self.white)
+    }
+
+    pub fn black_player(&self) -> String {
+        format!("{} {}", self.build, self.black)
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"ts\":{},\"build\":\"{}\",\"white\":\"{}\",\"black\":\"{}\",\"result\":\"{}\",\"termination\":\"{}\",\"plies\":{},\"fen\":\"{}\"}}",
+            self.timestamp,
+            escape(&self.build),
+            escape(&self.white),
+            escape(&self.black),
+            self.result,
+            escape(&self.termination),
+            self.plies,
+            escape(&self.start_fen)
+        )
+    }
+
+    // Reads back what `to_json` wrote; a line missing the players or the result gives None.
+    pub fn from_json(line: &str) -> Option<Self> {
+        Some(Self {
+            timestamp: json_field(line, "ts")?.parse().ok()?,
+            build: json_field(line, "build").unwrap_or_default(),
+            white: json_field(line, "white")?,
+            black: json_field(line, "black")?,
+            result: GameResult::parse(&json_field(line, "result")?)?,
+            termination: json_field(line, "termination").unwrap_or_default(),
+            plies: json_field(line, "plies").and_then(|plies| plies.parse().ok()).unwrap_or(0),
+            start_fen: json_field(line, "fen").unwrap_or_default(),
+        })
+    }
+}
+
+// Every recorded game. With a file each new result is appended as a JSON line as soon as it is
+// known, so an interrupted match keeps the games it finished; without one the results last as
+// long as the session.
+pub struct ResultsDb {
+    pub path: Option<PathBuf>,
+    pub records: Vec<MatchRecord>,
+}
+
+impl ResultsDb {
+    pub fn new() -> Self {
+        Self {
+            path: None,
+            records: Vec::new(),
+        }
+    }
+
+    // A missing file is an empty database that will be created on the first result. Lines that
+    // don't parse are skipped.
+    pub fn load(path: PathBuf) -> io::Result<Self> {
+        let records = match fs::read_to_string(&path) {
+            Ok(text) => text.lines().filter_map(MatchRecord::from_json).collect(),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(error) => return Err(error),
+        };
+        Ok(Self {
+            path: Some(path),
+            records,
+        })
+    }
+
+    pub fn record(&mut self, record: MatchRecord) -> io::Result<()> {
+        let line = record.to_json();
+        self.records.push(record);
+        match &self.path {
+            Some(path) => {
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                writeln!(file, "{}", line)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for ResultsDb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rating {
+    pub player: String,
+    // Relative to the average player, who is at 0.
+    pub elo: f64,
+    // Half-width of the 95% confidence interval.
+    pub error: f64,
+    pub games: u32,
+    // Points scored, draws counting one half.
+    pub points: f64,
+}
+
+// Maximum-likelihood Bradley-Terry ratings over every recorded game, with BayesElo's prior of
+// virtual draws, found with Hunter's MM iteration. Best first.
+pub fn compute_ratings(records: &[MatchRecord]) -> Vec<Rating> {
+    let mut index = BTreeMap::new();
+    for record in records {
+        for player in [record.white_player(), record.black_player()] {
+            let next = index.len();
+            index.entry(player).or_insert(next);
+        }
+    }
+    let count = index.len();
+    // Games and points between each pair, and each player's totals
+    let mut games = vec![vec![0.0; count]; count];
+    let mut points = vec![0.0; count];
+    let mut played = vec![0u32; count];
+    for record in records {
+        let white = index[&record.white_player()];
+        let black = index[&record.black_player()];
+        if white == black {
+            continue;
+        }
+        games[white][black] += 1.0;
+        games[black][white] += 1.0;
+        points[white] += record.result.white_score();
+        points[black] += 1.0 - record.result.white_score();
+        played[white] += 1;
+        played[black] += 1;
+    }
+
+    // The prior is a virtual opponent fixed at gamma 1 (0 Elo).
+    let mut gamma = vec![1.0f64; count];
+    for _ in 0..MAX_ITERATIONS {
+        let mut change = 0.0f64;
+        for player in 0..count {
+            let mut denominator = PRIOR_DRAWS / (gamma[player] + 1.0);
+            for opponent in 0..count {
+                if games[player][opponent] > 0.0 {
+                    denominator += games[player][opponent] / (gamma[player] + gamma[opponent]);
+                }
+            }
+            let updated = (points[player] + PRIOR_DRAWS / 2.0) / denominator;
+            change = change.max((updated.ln() - gamma[player].ln()).abs());
+            gamma[player] = updated;
+        }
+        if change < TOLERANCE {
+            break;
+        }
+    }
+
+    let to_elo = 400.0 / std::f64::consts::LN_10;
+    let mean = gamma.iter().map(|g| g.ln()).sum::<f64>() / count.max(1) as f64;
+    let mut ratings: Vec<Rating> = index
+        .into_iter()
+        .map(|(player, i)| {
+            // Fisher information of the player's log-strength, prior included
+            let mut information = PRIOR_DRAWS * expected_variance(gamma[i], 1.0);
+            for j in 0..count {
+                information += games[i][j] * expected_variance(gamma[i], gamma[j]);
+            }
+            Rating {
+                player,
+                elo: (gamma[i].ln() - mean) * to_elo,
+                error: 1.96 * to_elo / information.sqrt(),
+                games: played[i],
+                points: points[i],
+            }
+        })
+        .collect();
+    ratings.sort_by(|a, b| b.elo.total_cmp(&a.elo));
+    ratings
+}
+
+// p(1 - p) for the expected score p of `gamma` against `opponent`
+fn expected_variance(gamma: f64, opponent: f64) -> f64 {
+    let p = gamma / (gamma + opponent);
+    p * (1.0 - p)
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// The value of `"key":` in a flat JSON object written by `to_json`: a string (unescaped) or a
+// bare number.
+fn json_field(line: &str, key: &str) -> Option<String> {
+    let start = line.find(&format!("\"{}\":", key))? + key.len() + 3;
+    let rest = &line[start..];
+    if let Some(string) = rest.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = string.chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' => value.push(chars.next()?),
+                '"' => return Some(value),
+                _ => value.push(ch),
+            }
+        }
+        None
+    } else {
+        let end = rest.find([',', '}']).unwrap_or(rest.len());
+        Some(rest[..end].trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(white: &str, black: &str, result: GameResult) -> MatchRecord {
+        MatchRecord {
+            timestamp: 1,
+            build: "0.1.0".to_string(),
+            white: white.to_string(),
+            black: black.to_string(),
+            result,
+            termination: "checkmate".to_string(),
+            plies: 40,
+            start_fen: "8/8/8/8/8/8/8/8 w - - 0 1".to_string(),
+        }
+    }
+
+    #[test]
+    fn records_round_trip_through_json_lines() {
+        let record = game("depth \"3\"", "depth 2", GameResult::Draw);
+        assert_eq!(MatchRecord::from_json(&record.to_json()), Some(record));
+        assert_eq!(MatchRecord::from_json("{\"ts\":1}"), None);
+    }
+
+    #[test]
+    fn stronger_players_rate_higher_and_sweeps_stay_finite() {
+        let mut records = Vec::new();
+        for _ in 0..3 {
+            records.push(game("depth 3", "depth 1", GameResult::WhiteWins));
+            records.push(game("depth 1", "depth 3", GameResult::BlackWins));
+        }
+        records.push(game("depth 3", "depth 2", GameResult::Draw));
+        records.push(game("depth 2", "depth 1", GameResult::WhiteWins));
+
+        let ratings = compute_ratings(&records);
+        let names: Vec<&str> = ratings.iter().map(|rating| rating.player.as_str()).collect();
+        assert_eq!(names, ["0.1.0 depth 3", "0.1.0 depth 2", "0.1.0 depth 1"]);
+        assert!(ratings[0].elo.is_finite() && ratings[0].elo > 100.0);
+        assert!(ratings.iter().map(|rating| rating.elo).sum::<f64>().abs() < 1e-6);
+        assert_eq!(ratings[0].games, 7);
+        assert_eq!(ratings[0].points, 6.5);
+        assert!(ratings[2].error > 0.0);
+    }
+}
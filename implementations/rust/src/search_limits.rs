@@ -0,0 +1,395 @@
+use crate::time_manager::TimeControl;
+use crate::types::Color;
+
+pub const MAX_SEARCH_DEPTH: u8 = 64;
+
+pub const USAGE: [(&str, &str); 5] = [
+    (
+        "ai <depth> | ai depth <n>",
+        "Fixed-depth search (depth 1-64, no time limit)",
+    ),
+    (
+        "ai movetime <ms>",
+        "Iterative deepening until the time budget runs out",
+    ),
+    (
+        "ai depth <n> movetime <ms>",
+        "Whichever limit is reached first",
+    ),
+    (
+        "ai wtime <ms> btime <ms> [winc <ms>] [binc <ms>] [movestogo <n>]",
+        "Budget from the side to move's clock",
+    ),
+    (
+        "ai nodes <n>",
+        "Iterative deepening until about n nodes are searched",
+    ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub movetime_ms: Option<u64>,
+    // Remaining clock time and increment per side, and moves until the next time control.
+    pub wtime_ms: Option<u64>,
+    pub btime_ms: Option<u64>,
+    pub winc_ms: Option<u64>,
+    pub binc_ms: Option<u64>,
+    pub moves_to_go: Option<u32>,
+    pub nodes: Option<u64>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u8) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Self::default()
+        }
+    }
+
+    // Accepts a bare depth (`5`) for compatibility, or key-value pairs in any order: `depth`,
+    // `movetime`, `nodes` and the UCI clock keys `wtime`/`btime`/`winc`/`binc`/`movestogo`.
+    pub fn parse(args: &[&str]) -> Result<Self, String> {
+        let mut limits = Self::default();
+
+        if let [depth] = args {
+            if depth.chars().all(|ch| ch.is_ascii_digit()) {
+                limits.depth = Some(parse_depth(depth)?);
+                return Ok(limits);
+            }
+        }
+
+        let mut tokens = args.iter();
+        while let Some(&key) = tokens.next() {
+            let value = tokens.next().copied();
+            match key.to_lowercase().as_str() {
+                "depth" => limits.depth = Some(parse_depth(value.unwrap_or(""))?),
+                "movetime" => {
+                    limits.movetime_ms = Some(
+                        value
+                            .and_then(|ms| ms.parse::<u64>().ok())
+                            .filter(|&ms| ms > 0)
+                            .ok_or("movetime requires a positive integer")?,
+                    )
+                }
+                "wtime" => limits.wtime_ms = Some(parse_ms(key, value)?),
+                "btime" => limits.btime_ms = Some(parse_ms(key, value)?),
+                "winc" => limits.winc_ms = Some(parse_ms(key, value)?),
+                "binc" => limits.binc_ms = Some(parse_ms(key, value)?),
+                "movestogo" => {
+                    limits.moves_to_go = Some(
+                        value
+                            .and_then(|moves| moves.parse::<u32>().ok())
+                            .filter(|&moves| moves > 0)
+                            .ok_or("movestogo requires a positive integer")?,
+                    )
+                }
+                "nodes" => {
+                    limits.nodes = Some(
+                        value
+                            .and_then(|nodes| nodes.parse::<u64>().ok())
+                            .filter(|&nodes| nodes > 0)
+                            .ok_or("nodes requires a positive integer")?,
+                    )
+                }
+                _ => return Err(format!("Unsupported search limit '{}'", key)),
+            }
+        }
+
+        if limits.depth.is_none()
+            && limits.movetime_ms.is_none()
+            && limits.wtime_ms.is_none()
+            && limits.btime_ms.is_none()
+            && limits.nodes.is_none()
+        {
+            return Err(format!("AI depth must be 1-{}", MAX_SEARCH_DEPTH));
+        }
+        Ok(limits)
+    }
+
+    pub fn max_depth(&self) -> u8 {
+        self.depth.unwrap_or(MAX_SEARCH_DEPTH)
+    }
+
+    // A node budget wins, being the reproducible limit, then an explicit movetime, then the clock;
+    // without any of them the search is unbounded in time.
+    pub fn time_control(&self, side: Color) -> TimeControl {
+        if let Some(nodes) = self.nodes {
+            return TimeControl::Nodes(nodes);
+        }
+        let (remaining, increment) = match side {
+            Color::White => (self.wtime_ms, self.winc_ms),
+            Color::Black => (self.btime_ms, self.binc_ms),
+        };
+        match (self.movetime_ms, remaining) {
+            (Some(ms), _) => TimeControl::MoveTime(ms),
+            (None, Some(remaining_ms)) => TimeControl::Clock {
+                remaining_ms,
+                increment_ms: increment.unwrap_or(0),
+                moves_to_go: self.moves_to_go,
+            },
+            (None, None) => TimeControl::Infinite,
+        }
+    }
+
+    // False when only the other side's clock was given, which would leave the search unbounded.
+    pub fn is_bounded(&self, side: Color) -> bool {
+        self.depth.is_some() || self.time_control(side) != TimeControl::Infinite
+    }
+
+    // Whether either side's search stops on time rather than on depth or nodes.
+    pub fn uses_time(&self) -> bool {
+        [Color::White, Color::Black].into_iter().any(|side| {
+            matches!(self.time_control(side), TimeControl::MoveTime(_) | TimeControl::Clock { .. })
+        })
+    }
+
+    // The same limits with every time in them, move time, clocks and increments, cut to
+    // `numerator / denominator`, for time odds.
+    pub fn with_time_share(self, numerator: u64, denominator: u64) -> Self {
+        // In u128, as the times are whatever the user typed
+        let share = |ms: Option<u64>| {
+            ms.map(|ms| {
+                let share = u128::from(ms) * u128::from(numerator) / u128::from(denominator);
+                u64::try_from(share).unwrap_or(u64::MAX)
+            })
+        };
+        Self {
+            movetime_ms: share(self.movetime_ms).map(|ms| ms.max(1)),
+            wtime_ms: share(self.wtime_ms),
+            btime_ms: share(self.btime_ms),
+            winc_ms: share(self.winc_ms),
+            binc_ms: share(self.binc_ms),
+            ..self
+        }
+    }
+}
+
+// Search features an analysis request may switch off, by the name `no-<name>` takes.
+pub const SEARCH_FEATURES: [&str; 4] = ["pvs", "ttcutoff", "matedistance", "correction"];
+
+// Per-request changes to how the search itself runs, for isolating the effect of one feature on
+// one position: a root window other than (-inf, +inf), for the side to move; a depth every root
+// move is searched to before the search may stop early (mate found, time soft limit); and
+// features switched off. The default changes nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOverrides {
+    pub window: Option<(i32, i32)>,
+    pub min_root_depth: u8,
+    // Principal variation search: zero-window searches of all but the first move
+    pub pvs: bool,
+    pub tt_cutoffs: bool,
+    pub mate_distance: bool,
+    // Correction history applied to static evaluations
+    pub correction: bool,
+}
+
+impl Default for SearchOverrides {
+    fn default() -> Self {
+        Self {
+            window: None,
+            min_root_depth: 0,
+            pvs: true,
+            tt_cutoffs: true,
+            mate_distance: true,
+            correction: true,
+        }
+    }
+}
+
+impl SearchOverrides {
+    // Takes `window <alpha> <beta>`, `min-rootdepth <n>` and `no-<feature>` out of `args`,
+    // leaving the search limits.
+    pub fn split<'a>(args: &[&'a str]) -> Result<(Self, Vec<&'a str>), String> {
+        let mut overrides = Self::default();
+        let mut limits = Vec::new();
+        let mut tokens = args.iter();
+        while let Some(&token) = tokens.next() {
+            match token.to_lowercase().as_str() {
+                "window" => {
+                    let mut bound = || tokens.next().and_then(|value| value.parse::<i32>().ok());
+                    match (bound(), bound()) {
+                        (Some(alpha), Some(beta)) if alpha < beta => overrides.window = Some((alpha, beta)),
+                        _ => return Err("window requires alpha and beta in centipawns, alpha below beta".to_string()),
+                    }
+                }
+                "min-rootdepth" => {
+                    overrides.min_root_depth = parse_depth(tokens.next().copied().unwrap_or(""))
+                        .map_err(|_| format!("min-rootdepth must be 1-{}", MAX_SEARCH_DEPTH))?;
+                }
+                feature if feature.starts_with("no-") => match &feature[3..] {
+                    "pvs" => overrides.pvs = false,
+                    "ttcutoff" => overrides.tt_cutoffs = false,
+                    "matedistance" => overrides.mate_distance = false,
+                    "correction" => overrides.correction = false,
+                    name => {
+                        return Err(format!(
+                            "Unknown search feature '{}'; this search has {}",
+                            name,
+                            SEARCH_FEATURES.join(", ")
+                        ))
+                    }
+                },
+                _ => limits.push(token),
+            }
+        }
+        Ok((overrides, limits))
+    }
+
+    // `window=-50..50; min_rootdepth=12; off=pvs,ttcutoff`, listing only what differs
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some((alpha, beta)) = self.window {
+            parts.push(format!("window={}..{}", alpha, beta));
+        }
+        if self.min_root_depth > 0 {
+            parts.push(format!("min_rootdepth={}", self.min_root_depth));
+        }
+        let off: Vec<&str> = SEARCH_FEATURES
+            .iter()
+            .zip([self.pvs, self.tt_cutoffs, self.mate_distance, self.correction])
+            .filter(|(_, on)| !on)
+            .map(|(name, _)| *name)
+            .collect();
+        if !off.is_empty() {
+            parts.push(format!("off={}", off.join(",")));
+        }
+        if parts.is_empty() {
+            "none".to_string()
+        } else {
+            parts.join("; ")
+        }
+    }
+}
+
+fn parse_ms(key: &str, value: Option<&str>) -> Result<u64, String> {
+    value
+        .and_then(|ms| ms.parse::<u64>().ok())
+        .ok_or_else(|| format!("{} requires a number of milliseconds", key))
+}
+
+fn parse_depth(value: &str) -> Result<u8, String> {
+    match value.parse::<u8>() {
+        Ok(depth) if (1..=MAX_SEARCH_DEPTH).contains(&depth) => Ok(depth),
+        _ => Err(format!("AI depth must be 1-{}", MAX_SEARCH_DEPTH)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bare_depth_and_key_value_limits() {
+        assert_eq!(SearchLimits::parse(&["5"]), Ok(SearchLimits::depth(5)));
+        assert_eq!(
+            SearchLimits::parse(&["depth", "20"]),
+            Ok(SearchLimits::depth(20))
+        );
+        assert_eq!(
+            SearchLimits::parse(&["movetime", "5000"]),
+            Ok(SearchLimits {
+                movetime_ms: Some(5000),
+                ..SearchLimits::default()
+            })
+        );
+        assert_eq!(
+            SearchLimits::parse(&["movetime", "250", "depth", "8"]),
+            Ok(SearchLimits {
+                depth: Some(8),
+                movetime_ms: Some(250),
+                ..SearchLimits::default()
+            })
+        );
+    }
+
+    #[test]
+    fn clock_limits_pick_the_side_to_move() {
+        let limits = SearchLimits::parse(&[
+            "wtime",
+            "60000",
+            "btime",
+            "30000",
+            "binc",
+            "500",
+            "movestogo",
+            "12",
+        ])
+        .unwrap();
+        assert_eq!(
+            limits.time_control(Color::Black),
+            TimeControl::Clock {
+                remaining_ms: 30000,
+                increment_ms: 500,
+                moves_to_go: Some(12),
+            }
+        );
+        assert_eq!(
+            limits.time_control(Color::White),
+            TimeControl::Clock {
+                remaining_ms: 60000,
+                increment_ms: 0,
+                moves_to_go: Some(12),
+            }
+        );
+        assert!(limits.is_bounded(Color::Black));
+        let white_only = SearchLimits::parse(&["wtime", "1000"]).unwrap();
+        assert!(!white_only.is_bounded(Color::Black));
+        assert!(SearchLimits::parse(&["wtime", "soon"]).is_err());
+        assert!(SearchLimits::parse(&["wtime", "100", "movestogo", "0"]).is_err());
+
+        let nodes = SearchLimits::parse(&["nodes", "5000", "movetime", "100"]).unwrap();
+        assert_eq!(nodes.time_control(Color::White), TimeControl::Nodes(5000));
+        assert!(SearchLimits::parse(&["nodes", "0"]).is_err());
+
+        // Time odds cut the times, not the moves to go or a node budget
+        let half = limits.with_time_share(1, 2);
+        assert_eq!((half.wtime_ms, half.btime_ms, half.binc_ms, half.moves_to_go), (Some(30000), Some(15000), Some(250), Some(12)));
+        assert!(limits.uses_time());
+        assert!(!nodes.uses_time());
+        assert!(!SearchLimits::depth(5).uses_time());
+        assert_eq!(SearchLimits::parse(&["movetime", "1"]).unwrap().with_time_share(1, 3).movetime_ms, Some(1));
+        let huge = SearchLimits::parse(&["movetime", "18446744073709551615"]).unwrap();
+        assert_eq!(huge.with_time_share(99, 100).movetime_ms, Some(18_262_276_632_972_456_098));
+    }
+
+    #[test]
+    fn splits_search_overrides_from_the_limits() {
+        let (overrides, limits) =
+            SearchOverrides::split(&["depth", "20", "min-rootdepth", "12", "no-pvs", "window", "-30", "40", "no-correction"])
+                .unwrap();
+        assert_eq!(limits, vec!["depth", "20"]);
+        assert_eq!(
+            overrides,
+            SearchOverrides {
+                window: Some((-30, 40)),
+                min_root_depth: 12,
+                pvs: false,
+                correction: false,
+                ..SearchOverrides::default()
+            }
+        );
+        assert_eq!(overrides.describe(), "window=-30..40; min_rootdepth=12; off=pvs,correction");
+        assert_eq!(SearchOverrides::default().describe(), "none");
+
+        assert_eq!(
+            SearchOverrides::split(&["depth", "9", "no-nullmove"]),
+            Err("Unknown search feature 'nullmove'; this search has pvs, ttcutoff, matedistance, correction".to_string())
+        );
+        assert!(SearchOverrides::split(&["window", "50", "-50"]).is_err());
+        assert!(SearchOverrides::split(&["min-rootdepth", "0"]).is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_and_unknown_limits() {
+        assert!(SearchLimits::parse(&[]).is_err());
+        assert!(SearchLimits::parse(&["0"]).is_err());
+        assert!(SearchLimits::parse(&["65"]).is_err());
+        assert!(SearchLimits::parse(&["depth"]).is_err());
+        assert!(SearchLimits::parse(&["movetime", "0"]).is_err());
+        assert_eq!(
+            SearchLimits::parse(&["mate", "3"]),
+            Err("Unsupported search limit 'mate'".to_string())
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use crate::ai::MATE_SCORE;
+use crate::types::Move;
+
+// What became of a node, as the search left it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    // The root of the iteration.
+    Root,
+    // Depth ran out: a static evaluation, or a mate seen on the horizon.
+    Leaf,
+    // Answered by the transposition table without searching any move.
+    TableCutoff,
+    // The window was already out of reach of any mate from here.
+    MateDistance,
+    // Checkmate or stalemate on the board.
+    GameEnd,
+    // Score inside the window.
+    Exact,
+    // Score at or above beta: a cutoff.
+    FailHigh,
+    // Score at or below alpha: every move was refuted.
+    FailLow,
+    // Abandoned when the search ran out of time or was stopped.
+    Stopped,
+}
+
+impl NodeKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            NodeKind::Root => "root",
+            NodeKind::Leaf => "leaf",
+            NodeKind::TableCutoff => "tt_cutoff",
+            NodeKind::MateDistance => "mate_distance",
+            NodeKind::GameEnd => "game_end",
+            NodeKind::Exact => "exact",
+            NodeKind::FailHigh => "fail_high",
+            NodeKind::FailLow => "fail_low",
+            NodeKind::Stopped => "stopped",
+        }
+    }
+
+    // Graphviz fill colour: cutoffs red, refuted nodes grey, exact nodes green.
+    fn color(self) -> &'static str {
+        match self {
+            NodeKind::Root => "#ffffff",
+            NodeKind::Leaf => "#f5f5f5",
+            NodeKind::TableCutoff | NodeKind::MateDistance => "#cfe2ff",
+            NodeKind::GameEnd => "#fff3cd",
+            NodeKind::Exact => "#d1e7dd",
+            NodeKind::FailHigh => "#f8d7da",
+            NodeKind::FailLow => "#dee2e6",
+            NodeKind::Stopped => "#ffe5b4",
+        }
+    }
+}
+
+// One node the search visited. Scores and the window are from the side to move's point of
+// view at that node; a zero-width window (beta = alpha + 1) is a PVS scout search, and a move
+// that appears twice under the same parent was re-searched with the full window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    pub parent: Option<usize>,
+    // The move that led here; None at the root.
+    pub chess_move: Option<Move>,
+    pub ply: usize,
+    pub depth: u8,
+    pub alpha: i32,
+    pub beta: i32,
+    pub score: i32,
+    pub kind: NodeKind,
+    // Nodes visited in this subtree, this one included.
+    pub visits: u64,
+}
+
+// The tree one search iteration walked, recorded node by node for `debug tree`. Nodes are
+// stored in visiting order, so a parent always comes before its children.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchTree {
+    nodes: Vec<TreeNode>,
+    // Nodes entered but not yet left, innermost last.
+    open: Vec<usize>,
+}
+
+impl SearchTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Starts over with a root searched to `depth`.
+    pub fn reset(&mut self, depth: u8) {
+        self.nodes.clear();
+        self.open.clear();
+        self.enter(None, 0, depth, -MATE_SCORE - 1, MATE_SCORE + 1);
+        self.nodes[0].kind = NodeKind::Root;
+    }
+
+    pub fn enter(&mut self, chess_move: Option<Move>, ply: usize, depth: u8, alpha: i32, beta: i32) {
+        let index = self.nodes.len();
+        self.nodes.push(TreeNode {
+            parent: self.open.last().copied(),
+            chess_move,
+            ply,
+            depth,
+            alpha,
+            beta,
+            score: 0,
+            kind: NodeKind::Exact,
+            visits: 1,
+        });
+        self.open.push(index);
+    }
+
+    // Closes the innermost open node. `kind` is what the search knows about how it ended; without
+    // it the score is placed against the window the node was entered with.
+    pub fn leave(&mut self, score: i32, kind: Option<NodeKind>) {
+        let Some(index) = self.open.pop() else {
+            return;
+        };
+        let node = &mut self.nodes[index];
+        node.score = score;
+        node.kind = kind.unwrap_or(if score >= node.beta {
+            NodeKind::FailHigh
+        } else if score <= node.alpha {
+            NodeKind::FailLow
+        } else {
+            NodeKind::Exact
+        });
+        let visits = node.visits;
+        if let Some(parent) = node.parent {
+            self.nodes[parent].visits += visits;
+        }
+    }
+
+    // The root is left open while its moves are searched, and scored once they all are.
+    pub fn finish_root(&mut self, score: i32) {
+        if let Some(root) = self.nodes.first_mut() {
+            root.score = score;
+        }
+    }
+
+    pub fn nodes(&self) -> &[TreeNode] {
+        &self.nodes
+    }
+
+    // Nodes whose subtree saw at least `min_visits` nodes, the root always included. A dropped
+    // node's children are dropped with it, so what is kept stays a connected tree.
+    pub fn kept(&self, min_visits: u64) -> Vec<usize> {
+        let mut keep = vec![false; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            keep[index] = match node.parent {
+                None => true,
+                Some(parent) => keep[parent] && node.visits >= min_visits,
+            };
+        }
+        (0..self.nodes.len()).filter(|&index| keep[index]).collect()
+    }
+
+    pub fn to_dot(&self, min_visits: u64) -> String {
+        let mut dot = String::from("digraph search {\n");
+        dot.push_str("  node [shape=box, style=filled, fontname=\"monospace\", fontsize=10];\n");
+        let kept = self.kept(min_visits);
+        for &index in &kept {
+            let node = &self.nodes[index];
+            let name = node
+                .chess_move
+                .as_ref()
+                .map_or("root".to_string(), |chess_move| chess_move.to_string());
+            dot.push_str(&format!(
+                "  n{} [label=\"{}\\nd={} [{}, {}]\\n{} {}\\nvisits={}\", fillcolor=\"{}\"];\n",
+                index,
+                name,
+                node.depth,
+                window_bound(node.alpha),
+                window_bound(node.beta),
+                node.kind.name(),
+                node.score,
+                node.visits,
+                node.kind.color()
+            ));
+        }
+        for &index in &kept {
+            if let Some(parent) = self.nodes[index].parent {
+                dot.push_str(&format!("  n{} -> n{};\n", parent, index));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn to_json(&self, min_visits: u64) -> String {
+        let nodes: Vec<String> = self
+            .kept(min_visits)
+            .into_iter()
+            .map(|index| {
+                let node = &self.nodes[index];
+                format!(
+                    "{{\"id\":{},\"parent\":{},\"move\":{},\"ply\":{},\"depth\":{},\"alpha\":{},\"beta\":{},\"score\":{},\"kind\":\"{}\",\"visits\":{}}}",
+                    index,
+                    node.parent.map_or("null".to_string(), |parent| parent.to_string()),
+                    node.chess_move
+                        .as_ref()
+                        .map_or("null".to_string(), |chess_move| format!("\"{}\"", chess_move)),
+                    node.ply,
+                    node.depth,
+                    node.alpha,
+                    node.beta,
+                    node.score,
+                    node.kind.name(),
+                    node.visits
+                )
+            })
+            .collect();
+        format!(
+            "{{\"min_visits\":{},\"total\":{},\"nodes\":[{}]}}\n",
+            min_visits,
+            self.nodes.len(),
+            nodes.join(",")
+        )
+    }
+}
+
+// The search's infinite bounds print as infinities rather than as their sentinel values.
+fn window_bound(bound: i32) -> String {
+    if bound.abs() > MATE_SCORE {
+        if bound > 0 { "inf" } else { "-inf" }.to_string()
+    } else {
+        bound.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::AI;
+    use crate::board::Board;
+
+    #[test]
+    fn records_the_last_iteration_and_truncates_small_subtrees() {
+        let mut ai = AI::new();
+        ai.record_tree(true);
+        let mut board = Board::new();
+        let result = ai.find_best_move(&mut board, 3);
+        let tree = ai.take_tree().unwrap();
+        let nodes = tree.nodes();
+
+        assert_eq!(nodes[0].kind, NodeKind::Root);
+        assert_eq!(nodes[0].depth, 3);
+        assert_eq!(nodes[0].score, result.evaluation);
+        // The root plus every node the last iteration visited
+        assert_eq!(nodes[0].visits, nodes.len() as u64);
+        let root_moves = nodes.iter().filter(|node| node.parent == Some(0)).count();
+        assert!(root_moves >= 20, "{}", root_moves);
+        assert!(nodes.iter().any(|node| node.kind == NodeKind::FailHigh));
+        assert!(nodes.iter().all(|node| node.parent.is_none_or(|parent| parent < nodes.len())));
+
+        let kept = tree.kept(50);
+        assert!(kept.len() < nodes.len());
+        assert!(kept
+            .iter()
+            .all(|&index| index == 0 || nodes[index].visits >= 50));
+        let dot = tree.to_dot(50);
+        assert!(dot.starts_with("digraph search {"));
+        assert_eq!(dot.matches(" -> ").count(), kept.len() - 1);
+        assert!(tree.to_json(50).contains("\"kind\":\"root\""));
+    }
+}
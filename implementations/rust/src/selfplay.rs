@@ -0,0 +1,482 @@
+use crate::ai::AI;
+use crate::board::Board;
+use crate::move_generator::MoveGenerator;
+use crate::search_limits::SearchLimits;
+use crate::types::*;
+use std::fmt;
+
+// Games still going after this many plies are scored as draws.
+pub const MAX_GAME_PLIES: usize = 400;
+
+// Identifies the engine that played a game, so results from different builds are rated as
+// different players. Set `CHESS_BUILD_ID` when compiling (a commit hash, say) to tell apart
+// builds of the same version.
+pub fn build_id() -> String {
+    match option_env!("CHESS_BUILD_ID") {
+        Some(id) if !id.is_empty() => format!("{}+{}", env!("CARGO_PKG_VERSION"), id),
+        _ => env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl GameResult {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            _ => None,
+        }
+    }
+
+    // White's share of the point: 1, 0.5 or 0.
+    pub fn white_score(self) -> f64 {
+        match self {
+            GameResult::WhiteWins => 1.0,
+            GameResult::BlackWins => 0.0,
+            GameResult::Draw => 0.5,
+        }
+    }
+}
+
+// PGN's spelling: `1-0`, `0-1`, `1/2-1/2`
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+        })
+    }
+}
+
+// One side of a self-play game: its own engine (table, history and options included) and the
+// limits it searches every move with.
+pub struct Player {
+    pub name: String,
+    pub ai: AI,
+    pub limits: SearchLimits,
+}
+
+impl Player {
+    pub fn new(name: &str, ai: AI, limits: SearchLimits) -> Self {
+        Self {
+            name: name.to_string(),
+            ai,
+            limits,
+        }
+    }
+}
+
+// Time odds between the two players of a match, as shares of thinking time: `2:1` gives the
+// first player twice the second's. The larger share keeps the time in its limits and the
+// other's is cut in proportion, so the stronger side plays as configured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeOdds {
+    pub first: u32,
+    pub second: u32,
+}
+
+// Odds past this are no longer a handicap so much as a different time control.
+pub const MAX_TIME_ODDS: u32 = 100;
+
+impl TimeOdds {
+    // `<first>:<second>`, each 1-100
+    pub fn parse(text: &str) -> Option<Self> {
+        let (first, second) = text.split_once(':')?;
+        let share = |share: &str| share.parse::<u32>().ok().filter(|share| (1..=MAX_TIME_ODDS).contains(share));
+        Some(Self {
+            first: share(first)?,
+            second: share(second)?,
+        })
+    }
+
+    // Cuts the time of the player with the smaller share, which is then named with its share
+    // (`movetime 100 time 1/2`), so the results database rates it apart.
+    pub fn apply(self, first: &mut Player, second: &mut Player) {
+        let larger = self.first.max(self.second);
+        for (player, share) in [(first, self.first), (second, self.second)] {
+            if share < larger {
+                player.limits = player.limits.with_time_share(u64::from(share), u64::from(larger));
+                player.name = format!("{} time {}/{}", player.name, share, larger);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRecord {
+    pub result: GameResult,
+    // `checkmate`, `stalemate`, `repetition`, `fifty_moves`, `insufficient_material`,
+    // `move_limit`, `adjudicated_win` or `adjudicated_draw`
+    pub termination: &'static str,
+    pub moves: Vec<Move>,
+}
+
+// Draws are only adjudicated from this move on, past the opening's level scores.
+pub const ADJUDICATION_DRAW_FROM_MOVE: u32 = 40;
+
+// When to end a game before it is over, on the scores the players report. A result needs every
+// score for `moves` moves of each side (2 * `moves` plies in a row) to agree: White-relative
+// scores of at least `win_cp` for the same side, or within `draw_cp` of zero. So neither engine
+// alone can call a game, and one search's misjudgement resets the count. A referee, when given,
+// must also agree on every one of those positions. No thresholds, no adjudication.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Adjudication {
+    pub win_cp: Option<i32>,
+    pub draw_cp: Option<i32>,
+    pub moves: u32,
+}
+
+// A third opinion for adjudication, such as an external engine: the White-relative score of a
+// position, or None when it has no answer, which counts as disagreeing.
+pub type Referee<'a> = &'a mut dyn FnMut(&Board) -> Option<i32>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    Win(Color),
+    Draw,
+}
+
+impl Adjudication {
+    pub fn is_enabled(&self) -> bool {
+        self.moves > 0 && (self.win_cp.is_some() || self.draw_cp.is_some())
+    }
+
+    fn verdict(&self, board: &Board, score: i32) -> Option<Verdict> {
+        match self.win_cp {
+            Some(win_cp) if score >= win_cp => return Some(Verdict::Win(Color::White)),
+            Some(win_cp) if score <= -win_cp => return Some(Verdict::Win(Color::Black)),
+            _ => {}
+        }
+        self.draw_cp
+            .filter(|&draw_cp| score.abs() <= draw_cp)
+            .filter(|_| board.get_state().fullmove_number >= ADJUDICATION_DRAW_FROM_MOVE)
+            .map(|_| Verdict::Draw)
+    }
+}
+
+// Plays `start` out between two engines. Both start the game with fresh tables, so a game
+// doesn't depend on what the players searched before it.
+pub fn play_game(start: &Board, white: &mut Player, black: &mut Player) -> GameRecord {
+    play_adjudicated_game(start, white, black, &Adjudication::default(), None)
+}
+
+// `play_game`, ended early once the players (and the referee) agree on the result.
+pub fn play_adjudicated_game(
+    start: &Board,
+    white: &mut Player,
+    black: &mut Player,
+    adjudication: &Adjudication,
+    mut referee: Option<Referee>,
+) -> GameRecord {
+    let move_generator = MoveGenerator::new();
+    let mut board = start.clone();
+    let mut moves = Vec::new();
+    // The verdict the last plies agreed on, and for how many plies in a row
+    let mut agreed: Option<(Verdict, u32)> = None;
+    white.ai.new_game();
+    black.ai.new_game();
+
+    loop {
+        let color = board.get_turn();
+        if !move_generator.has_legal_move(&mut board, color) {
+            let (result, termination) = if move_generator.is_in_check(&board, color) {
+                let result = match color {
+                    Color::White => GameResult::BlackWins,
+                    Color::Black => GameResult::WhiteWins,
+                };
+                (result, "checkmate")
+            } else {
+                (GameResult::Draw, "stalemate")
+            };
+            return GameRecord {
+                result,
+                termination,
+                moves,
+            };
+        }
+        let termination = if crate::draw_detection::is_draw_by_repetition(board.get_state()) {
+            Some("repetition")
+        } else if crate::draw_detection::is_draw_by_fifty_moves(board.get_state()) {
+            Some("fifty_moves")
+        } else if is_insufficient_material(&board) {
+            Some("insufficient_material")
+        } else if moves.len() >= MAX_GAME_PLIES {
+            Some("move_limit")
+        } else {
+            None
+        };
+        if let Some(termination) = termination {
+            return GameRecord {
+                result: GameResult::Draw,
+                termination,
+                moves,
+            };
+        }
+
+        let player = match color {
+            Color::White => &mut *white,
+            Color::Black => &mut *black,
+        };
+        let limits = player.limits;
+        let result = player.ai.search(&mut board, &limits);
+        let Some(chess_move) = result.best_move else {
+            // A side with legal moves always gets one back; treat anything else as a draw.
+            return GameRecord {
+                result: GameResult::Draw,
+                termination: "move_limit",
+                moves,
+            };
+        };
+
+        // The mover's score is for the position it searched, as is the referee's
+        if adjudication.is_enabled() {
+            let verdict = adjudication.verdict(&board, result.evaluation).filter(|&verdict| {
+                referee.as_mut().is_none_or(|referee| {
+                    referee(&board).and_then(|score| adjudication.verdict(&board, score)) == Some(verdict)
+                })
+            });
+            agreed = match (verdict, agreed) {
+                (Some(verdict), Some((previous, plies))) if verdict == previous => Some((verdict, plies + 1)),
+                (Some(verdict), _) => Some((verdict, 1)),
+                (None, _) => None,
+            };
+        }
+        board.make_move(&chess_move);
+        moves.push(chess_move);
+
+        if let Some((verdict, _)) = agreed.filter(|&(_, plies)| plies >= 2 * adjudication.moves) {
+            let (result, termination) = match verdict {
+                Verdict::Win(Color::White) => (GameResult::WhiteWins, "adjudicated_win"),
+                Verdict::Win(Color::Black) => (GameResult::BlackWins, "adjudicated_win"),
+                Verdict::Draw => (GameResult::Draw, "adjudicated_draw"),
+            };
+            return GameRecord {
+                result,
+                termination,
+                moves,
+            };
+        }
+    }
+}
+
+// A match result from the first player's side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    // The first player's share of the points, 0 to 1.
+    pub fn score(&self) -> f64 {
+        (f64::from(self.wins) + f64::from(self.draws) / 2.0) / f64::from(self.games().max(1))
+    }
+
+    // The Elo difference the score implies, first player minus second. A clean sweep has no
+    // finite answer, so scores are kept off 0 and 1 by half a game.
+    pub fn elo(&self) -> f64 {
+        let half_game = 0.5 / f64::from(self.games().max(1));
+        elo_from_score(self.score().clamp(half_game, 1.0 - half_game))
+    }
+
+    // Half-width of the 95% confidence interval on `elo`, from the spread of the game results.
+    pub fn error(&self) -> f64 {
+        let games = f64::from(self.games().max(1));
+        let score = self.score();
+        let variance = (f64::from(self.wins) * (1.0 - score).powi(2)
+            + f64::from(self.draws) * (0.5 - score).powi(2)
+            + f64::from(self.losses) * score.powi(2))
+            / games;
+        let margin = 1.96 * (variance / games).sqrt();
+        let half_game = 0.5 / games;
+        let bound = |score: f64| elo_from_score(score.clamp(half_game, 1.0 - half_game));
+        (bound(score + margin) - bound(score - margin)) / 2.0
+    }
+
+    pub fn add(&mut self, first_score: f64) {
+        match first_score {
+            score if score > 0.5 => self.wins += 1,
+            score if score < 0.5 => self.losses += 1,
+            _ => self.draws += 1,
+        }
+    }
+}
+
+fn elo_from_score(score: f64) -> f64 {
+    400.0 * (score / (1.0 - score)).log10()
+}
+
+// Plays `games` games between two players, colours alternating with the first player White in
+// odd games, and the openings in turn, each played once with either colour, adjudicated as
+// `play_adjudicated_game`. `on_game` sees each game as it finishes, with its number, start
+// position and players as White and Black.
+pub fn play_match<F>(
+    first: &mut Player,
+    second: &mut Player,
+    games: u32,
+    openings: &[Board],
+    adjudication: &Adjudication,
+    mut referee: Option<Referee>,
+    mut on_game: F,
+) -> MatchScore
+where
+    F: FnMut(u32, &Board, &Player, &Player, &GameRecord),
+{
+    let mut score = MatchScore::default();
+    for game in 1..=games {
+        let first_is_white = game % 2 == 1;
+        let start = &openings[(game as usize - 1) / 2 % openings.len()];
+        let (white, black) = if first_is_white {
+            (&mut *first, &mut *second)
+        } else {
+            (&mut *second, &mut *first)
+        };
+        let referee = referee.as_mut().map(|referee| &mut **referee as Referee);
+        let record = play_adjudicated_game(start, white, black, adjudication, referee);
+        score.add(if first_is_white {
+            record.result.white_score()
+        } else {
+            1.0 - record.result.white_score()
+        });
+        on_game(game, start, white, black, &record);
+    }
+    score
+}
+
+// Bare kings, or a lone knight or bishop against a bare king: no mate is possible.
+fn is_insufficient_material(board: &Board) -> bool {
+    let heavy = [PieceType::Pawn, PieceType::Rook, PieceType::Queen];
+    if [Color::White, Color::Black]
+        .iter()
+        .any(|&color| heavy.iter().any(|&piece| !board.pieces(piece, color).is_empty()))
+    {
+        return false;
+    }
+    let minors = [Color::White, Color::Black]
+        .iter()
+        .map(|&color| {
+            board.pieces(PieceType::Knight, color).count()
+                + board.pieces(PieceType::Bishop, color).count()
+        })
+        .sum::<u32>();
+    minors <= 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::board_from;
+
+    #[test]
+    fn plays_games_to_a_result() {
+        let mut white = Player::new("depth 2", AI::new(), SearchLimits::depth(2));
+        let mut black = Player::new("depth 1", AI::new(), SearchLimits::depth(1));
+
+        // A queen up with the king in a corner: White mates
+        let board = board_from("7k/8/5K2/8/8/8/8/6Q1 w - - 0 1");
+        let game = play_game(&board, &mut white, &mut black);
+        assert_eq!(game.result, GameResult::WhiteWins);
+        assert_eq!(game.termination, "checkmate");
+        assert!(game.moves.len() % 2 == 1);
+
+        let board = board_from("7k/8/5K2/8/8/8/8/6N1 w - - 0 1");
+        let game = play_game(&board, &mut white, &mut black);
+        assert_eq!(game.termination, "insufficient_material");
+        assert_eq!(game.result.white_score(), 0.5);
+        assert_eq!(GameResult::parse(&game.result.to_string()), Some(game.result));
+    }
+
+    #[test]
+    fn adjudicates_once_every_score_agrees_for_long_enough() {
+        let mut white = Player::new("white", AI::new(), SearchLimits::depth(2));
+        let mut black = Player::new("black", AI::new(), SearchLimits::depth(2));
+        let rules = Adjudication {
+            win_cp: Some(600),
+            draw_cp: Some(30),
+            moves: 2,
+        };
+
+        // A queen up: both sides see it after every move, so the game ends after four plies
+        let queen_up = board_from("4k3/8/8/8/8/8/3QPPP1/6K1 w - - 0 50");
+        let game = play_adjudicated_game(&queen_up, &mut white, &mut black, &rules, None);
+        assert_eq!((game.result, game.termination, game.moves.len()), (GameResult::WhiteWins, "adjudicated_win", 4));
+
+        // A referee that never agrees keeps the game going to its real end; one that agrees is
+        // asked about every position
+        let mut never = |_: &Board| None;
+        let game = play_adjudicated_game(&queen_up, &mut white, &mut black, &rules, Some(&mut never));
+        assert!(!game.termination.starts_with("adjudicated"), "{}", game.termination);
+        let mut referee_calls = 0;
+        let mut agrees = |_: &Board| {
+            referee_calls += 1;
+            Some(1000)
+        };
+        let game = play_adjudicated_game(&queen_up, &mut white, &mut black, &rules, Some(&mut agrees));
+        assert_eq!((game.termination, referee_calls), ("adjudicated_win", 4));
+
+        // Level rook endings are drawn, but not before move 40
+        let rooks = board_from("4k3/7r/8/8/8/8/R7/4K3 w - - 0 45");
+        let game = play_adjudicated_game(&rooks, &mut white, &mut black, &rules, None);
+        assert_eq!((game.result, game.termination, game.moves.len()), (GameResult::Draw, "adjudicated_draw", 4));
+        let early = board_from("4k3/7r/8/8/8/8/R7/4K3 w - - 0 1");
+        assert_eq!(rules.verdict(&early, 0), None);
+        assert_eq!(rules.verdict(&rooks, 0), Some(Verdict::Draw));
+        assert_eq!(rules.verdict(&early, -700), Some(Verdict::Win(Color::Black)));
+    }
+
+    #[test]
+    fn match_scores_convert_to_elo_with_an_error_bar() {
+        let even = MatchScore { wins: 10, draws: 20, losses: 10 };
+        assert_eq!(even.score(), 0.5);
+        assert!(even.elo().abs() < 1e-9);
+        assert!(even.error() > 0.0);
+
+        // 75% is about +191 Elo
+        let ahead = MatchScore { wins: 60, draws: 30, losses: 10 };
+        assert!((ahead.elo() - 190.8).abs() < 0.1, "{}", ahead.elo());
+        let behind = MatchScore { wins: 10, draws: 30, losses: 60 };
+        assert!((ahead.elo() + behind.elo()).abs() < 1e-9);
+        // More games, a tighter interval
+        let longer = MatchScore { wins: 600, draws: 300, losses: 100 };
+        assert!(longer.error() < ahead.error() / 3.0);
+        // A sweep stays finite
+        assert!(MatchScore { wins: 4, draws: 0, losses: 0 }.elo().is_finite());
+
+        let mut white = Player::new("a", AI::new(), SearchLimits::depth(1));
+        let mut black = Player::new("b", AI::new(), SearchLimits::depth(1));
+        let board = board_from("7k/8/5K2/8/8/8/8/6N1 w - - 0 1");
+        let mut colours = Vec::new();
+        let score = play_match(&mut white, &mut black, 3, &[board], &Adjudication::default(), None, |game, _, white, black, _| {
+            colours.push((game, white.name.clone(), black.name.clone()));
+        });
+        assert_eq!(score, MatchScore { wins: 0, draws: 3, losses: 0 });
+        assert_eq!(colours[1], (2, "b".to_string(), "a".to_string()));
+    }
+    #[test]
+    fn time_odds_cut_the_smaller_share() {
+        assert_eq!(TimeOdds::parse("2:1"), Some(TimeOdds { first: 2, second: 1 }));
+        for text in ["2", "0:1", "2:1:1", "101:1", "a:b"] {
+            assert_eq!(TimeOdds::parse(text), None, "{}", text);
+        }
+
+        let limits = SearchLimits::parse(&["movetime", "300"]).unwrap();
+        let mut first = Player::new("movetime 300", AI::new(), limits);
+        let mut second = Player::new("movetime 300", AI::new(), limits);
+        TimeOdds::parse("2:3").unwrap().apply(&mut first, &mut second);
+        assert_eq!((first.name.as_str(), first.limits.movetime_ms), ("movetime 300 time 2/3", Some(200)));
+        assert_eq!((second.name.as_str(), second.limits), ("movetime 300", limits));
+    }
+}
@@ -0,0 +1,127 @@
+use crate::ai::AI;
+use crate::analysis::{score_loss, score_replies, RootScore};
+use crate::attack_tables::KING_ATTACKS;
+use crate::bitboard::Bitboard;
+use crate::board::Board;
+use crate::move_generator::MoveGenerator;
+use crate::tactics::TacticsDetector;
+use crate::types::*;
+use std::fmt;
+
+// Deep enough to see a piece hang or a simple tactic go wrong, shallow enough to run before
+// every timed search.
+pub const SHARPNESS_DEPTH: u8 = 2;
+// The spread is taken over the best few moves; the rest of a long move list is mostly noise.
+const TOP_MOVES: usize = 5;
+// Mates and won pieces all mean "only one move holds"; beyond this the size doesn't matter.
+const LOSS_CAP_CP: i32 = 500;
+
+// How hard a position is to play, from 0 (quiet, many moves are fine) to 100 (one slip loses).
+// Three things go into it: how far the best few moves' scores spread, how many tactical motifs
+// are on the board for either side, and how much more one king is under fire than the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sharpness {
+    pub score: u32,
+    // Standard deviation of the best few moves' scores, in centipawns.
+    pub spread_cp: i32,
+    pub motifs: usize,
+    // Squares next to the White king attacked by Black, minus the same for the Black king.
+    pub king_imbalance: i32,
+}
+
+impl Sharpness {
+    // Scores every legal move with a shallow search on `ai`, sharing its table.
+    pub fn measure(ai: &mut AI, board: &Board) -> Self {
+        let scores = score_replies(ai, board, SHARPNESS_DEPTH);
+        Self::from_scores(board, &scores)
+    }
+
+    // `scores` are root moves best first for the side to move, as `analyze_all` and
+    // `score_replies` return them.
+    pub fn from_scores(board: &Board, scores: &[RootScore]) -> Self {
+        let spread_cp = spread(board.get_turn(), scores);
+        let tactics = TacticsDetector::new();
+        let motifs = tactics.detect_all(board, Color::White).len()
+            + tactics.detect_all(board, Color::Black).len();
+        let king_imbalance =
+            king_pressure(board, Color::White) as i32 - king_pressure(board, Color::Black) as i32;
+
+        // Up to 40 points for the spread, 30 for motifs and 30 for the king imbalance
+        let score = (spread_cp.min(200) / 5) as u32
+            + (motifs.min(6) * 5) as u32
+            + king_imbalance.unsigned_abs().min(6) * 5;
+        Self {
+            score,
+            spread_cp,
+            motifs,
+            king_imbalance,
+        }
+    }
+}
+
+impl fmt::Display for Sharpness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "score={}; spread={}cp; motifs={}; king_imbalance={:+}",
+            self.score, self.spread_cp, self.motifs, self.king_imbalance
+        )
+    }
+}
+
+// Losses are measured against the best move and capped, so a single mate in the list doesn't
+// swamp everything else.
+fn spread(color: Color, scores: &[RootScore]) -> i32 {
+    let Some(best) = scores.first() else {
+        return 0;
+    };
+    let losses: Vec<i64> = scores
+        .iter()
+        .take(TOP_MOVES)
+        .map(|root_score| i64::from(score_loss(best, root_score, color).clamp(0, LOSS_CAP_CP)))
+        .collect();
+    let count = losses.len() as i64;
+    let mean = losses.iter().sum::<i64>() / count;
+    let variance = losses.iter().map(|loss| (loss - mean).pow(2)).sum::<i64>() / count;
+    (variance as f64).sqrt().round() as i32
+}
+
+// The king's square and its neighbours that the other side attacks.
+fn king_pressure(board: &Board, color: Color) -> u32 {
+    let Some(king) = board.king_square(color) else {
+        return 0;
+    };
+    let zone: Bitboard = KING_ATTACKS[king]
+        .as_slice()
+        .iter()
+        .copied()
+        .chain([king])
+        .collect();
+    (MoveGenerator::new().attack_map(board, color.opposite()) & zone).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::board_from;
+
+    #[test]
+    fn tactical_positions_are_sharper_than_the_opening() {
+        let start = Sharpness::measure(&mut AI::new(), &Board::new());
+        assert_eq!(start.motifs, 0);
+        assert_eq!(start.king_imbalance, 0);
+        assert!(start.score < 20, "{}", start);
+
+        // The black queen hangs on d4 and only exd4 takes it: one move scores a queen above the
+        // rest, which is a wide spread, and the hanging queen counts as a motif
+        let board = board_from("rnb1kbnr/pppp1ppp/8/4p3/3q4/4P3/PPPP1PPP/RNBQKBNR w KQkq - 0 3");
+        let sharp = Sharpness::measure(&mut AI::new(), &board);
+        assert!(sharp.spread_cp >= 150, "{}", sharp);
+        assert!(sharp.motifs > 0, "{}", sharp);
+        assert!(sharp.score > start.score + 30, "{}", sharp);
+
+        // Black must parry the mate on f7, and it is Black's king that is under fire
+        let board = board_from("r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 3 3");
+        assert!(Sharpness::measure(&mut AI::new(), &board).king_imbalance < 0);
+    }
+}
@@ -0,0 +1,296 @@
+use crate::attack_tables::ray_table;
+use crate::bitboard::Bitboard;
+use crate::cpu;
+use crate::timing::Instant;
+use crate::types::Square;
+use std::hint::black_box;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const ROOK_DIRECTIONS: [i32; 4] = [-8, -1, 1, 8];
+const BISHOP_DIRECTIONS: [i32; 4] = [-9, -7, 7, 9];
+
+// Multipliers that send blocker sets with different attacks to different slots (a table of
+// 2^bits entries for a mask of `bits` squares), found by trying sparse random numbers from a
+// fixed-seed generator. The tests check every one against walking the rays.
+#[rustfmt::skip]
+const ROOK_MAGICS: [u64; 64] = [
+    0x1080_0040_0880_1020, 0x0040_0242_2003_b000, 0x0100_2001_0010_4008, 0x0180_0800_8010_010c,
+    0x6d80_0400_0280_2800, 0x1e00_9004_0008_4200, 0x0200_4408_1081_2200, 0x0100_0080_4200_2100,
+    0x2080_8020_8000_4000, 0x8004_4020_1004_4000, 0x0008_8020_0210_0680, 0x2210_8010_0080_0804,
+    0x0000_8008_0004_0080, 0x0904_8002_0004_0080, 0x0102_8080_0200_0100, 0x0120_8008_8010_4100,
+    0x0040_0080_0020_9040, 0x2200_8100_4000_2100, 0x0020_0440_1000_4800, 0x0400_8480_1000_0800,
+    0x8222_0200_2008_1004, 0x8504_8080_0400_0201, 0x0094_0400_5982_1008, 0x0400_4a00_0084_0041,
+    0x0080_0042_4000_2000, 0x0280_4001_8020_0088, 0x0040_1000_8020_0080, 0x0000_1001_0021_0008,
+    0x2020_0800_8004_0080, 0x8000_0400_8002_0080, 0x0064_0401_0100_0200, 0x0013_8000_8000_4100,
+    0x0010_4000_2080_1080, 0x00a0_0040_2040_1000, 0x1022_8010_0480_2002, 0x0000_0810_0100_2100,
+    0x0088_8004_0080_0800, 0x0400_0400_8080_0200, 0x0000_8001_0080_0200, 0xa810_2040_8200_0104,
+    0x0000_8000_4004_8020, 0x0820_5000_2000_4000, 0x0004_2003_0211_0040, 0x0410_1000_0800_8080,
+    0x9004_0008_0080_8005, 0x0101_0002_0401_0008, 0x0811_0002_0001_0004, 0x2408_1051_0082_0004,
+    0x0040_2100_8000_5100, 0x0000_2000_8040_0080, 0x8810_0080_2000_1080, 0x0000_8010_0008_0080,
+    0x9040_0400_8008_0080, 0x0100_0400_0200_8080, 0x0341_0002_0004_0100, 0x8081_0420_8041_0200,
+    0x0801_800a_2011_0141, 0x0082_8a53_00a0_4001, 0xa000_0810_4100_2001, 0x0144_0500_1000_0821,
+    0x9202_0020_0410_0902, 0x8001_0008_0a24_0005, 0x0000_4081_0810_0204, 0x1082_0400_4021_1082,
+];
+#[rustfmt::skip]
+const BISHOP_MAGICS: [u64; 64] = [
+    0x1010_2002_004a_1420, 0x4020_2204_0100_2250, 0x4010_0148_4108_b002, 0x2804_104a_0000_2404,
+    0x0044_0504_01c0_2206, 0x0004_8804_c100_0000, 0x0010_8410_0904_8820, 0x0100_1202_1002_0898,
+    0x0220_0508_0818_0080, 0x0000_1809_1104_0111, 0x4904_2200_8102_0000, 0x00c0_0404_1080_22a0,
+    0x9080_0848_4080_4080, 0x0000_6101_2010_1800, 0x4008_1848_1404_2204, 0x000a_1882_0801_4480,
+    0x0230_0024_2012_0444, 0x0510_0044_500a_2060, 0x1881_0068_0202_2a00, 0x1014_2042_0202_00b0,
+    0x0223_0004_9040_0084, 0x8243_4008_0802_1008, 0x4004_0c00_9e01_1004, 0x010a_00c5_0080_c428,
+    0x1404_9000_2003_1002, 0x0002_2040_1014_06b0, 0x0028_1800_0102_00a0, 0x0009_0400_0044_0080,
+    0x0002_0400_0600_8222, 0x003c_5100_0203_010a, 0x8498_0880_0100_8804, 0x04c2_2200_048c_4101,
+    0x0802_3090_0005_2002, 0x4018_0824_0103_0408, 0x8820_c502_4810_0400, 0x8009_0200_8008_0080,
+    0x4040_0502_0009_0048, 0x1008_0800_2081_1004, 0x2001_0612_8002_1829, 0x0004_0442_0202_9084,
+    0x00c0_a208_2140_4000, 0x0446_0250_0440_4209, 0x0810_0828_0801_8400, 0x4030_0451_4400_0800,
+    0x8070_0404_0820_0400, 0x0021_0208_8100_0600, 0x5c04_0124_0411_4100, 0x4088_1840_4243_8084,
+    0x2021_4804_1158_0200, 0x0202_0121_0110_2000, 0x3001_1020_8410_1402, 0x0881_0200_8404_5100,
+    0x0020_0010_0202_2044, 0x1840_2114_505c_8200, 0x8004_1002_1843_1000, 0x0010_1003_2700_6100,
+    0x0008_2205_1028_0208, 0x0420_2602_1202_4204, 0x0010_0020_4402_2100, 0x0003_9102_2084_0448,
+    0x0001_0800_0610_4400, 0x0082_c840_8408_0082, 0x0200_108c_4888_0540, 0x0051_0110_0400_8024,
+];
+
+// How a blocker set becomes an index into a square's slice of the table. Both give every
+// subset of the square's mask its own slot in a slice of 2^bits entries, so the layout (and the
+// memory) is the same either way; only the index function, and so the order inside each slice,
+// differs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indexing {
+    // Multiply by a magic number and keep the top bits
+    Magic,
+    // Gather the mask's bits with BMI2's PEXT (or its portable equivalent)
+    Pext,
+}
+
+impl Indexing {
+    pub fn name(self) -> &'static str {
+        match self {
+            Indexing::Magic => "magic",
+            Indexing::Pext => "pext",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct SquareEntry {
+    // The squares whose occupancy matters: the rays without their last square
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+// Rook and bishop attacks for every square and blocker set, looked up instead of walked.
+pub struct SlidingAttacks {
+    indexing: Indexing,
+    // Whether PEXT indexing can use the instruction itself
+    #[cfg_attr(not(target_arch = "x86_64"), allow(dead_code))]
+    bmi2: bool,
+    rook: [SquareEntry; 64],
+    bishop: [SquareEntry; 64],
+    attacks: Vec<u64>,
+}
+
+impl SlidingAttacks {
+    pub fn new(indexing: Indexing) -> Self {
+        let mut tables = Self {
+            indexing,
+            bmi2: cpu::features().bmi2,
+            rook: [SquareEntry::default(); 64],
+            bishop: [SquareEntry::default(); 64],
+            attacks: Vec::new(),
+        };
+        for square in 0..64 {
+            tables.rook[square] = tables.fill(square, &ROOK_DIRECTIONS, ROOK_MAGICS[square]);
+            tables.bishop[square] = tables.fill(square, &BISHOP_DIRECTIONS, BISHOP_MAGICS[square]);
+        }
+        tables
+    }
+
+    pub fn indexing(&self) -> Indexing {
+        self.indexing
+    }
+
+    pub fn rook(&self, square: Square, occupied: Bitboard) -> Bitboard {
+        Bitboard(self.lookup(&self.rook[square], occupied.0))
+    }
+
+    pub fn bishop(&self, square: Square, occupied: Bitboard) -> Bitboard {
+        Bitboard(self.lookup(&self.bishop[square], occupied.0))
+    }
+
+    pub fn queen(&self, square: Square, occupied: Bitboard) -> Bitboard {
+        self.rook(square, occupied) | self.bishop(square, occupied)
+    }
+
+    #[inline]
+    fn lookup(&self, entry: &SquareEntry, occupied: u64) -> u64 {
+        #[cfg(target_arch = "x86_64")]
+        if self.indexing == Indexing::Pext && self.bmi2 {
+            // SAFETY: `bmi2` is only set when the CPU reports BMI2.
+            return unsafe { self.lookup_bmi2(entry, occupied) };
+        }
+        self.attacks[self.index(entry, occupied)]
+    }
+
+    // The whole lookup compiled for BMI2, so PEXT is inlined rather than called through the
+    // kernel table.
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "bmi2")]
+    fn lookup_bmi2(&self, entry: &SquareEntry, occupied: u64) -> u64 {
+        self.attacks[entry.offset + std::arch::x86_64::_pext_u64(occupied, entry.mask) as usize]
+    }
+
+    fn index(&self, entry: &SquareEntry, occupied: u64) -> usize {
+        entry.offset
+            + match self.indexing {
+                Indexing::Magic => {
+                    ((occupied & entry.mask).wrapping_mul(entry.magic) >> entry.shift) as usize
+                }
+                Indexing::Pext => (cpu::kernels().pext)(occupied, entry.mask) as usize,
+            }
+    }
+
+    // Appends the square's slice of the table and returns how to index it.
+    fn fill(&mut self, square: Square, directions: &[i32; 4], magic: u64) -> SquareEntry {
+        let mask = directions
+            .iter()
+            .flat_map(|&direction| {
+                let ray = ray_table(direction)[square].as_slice();
+                ray[..ray.len().saturating_sub(1)].iter()
+            })
+            .fold(0u64, |mask, &target| mask | 1 << target);
+        let bits = mask.count_ones();
+        let entry = SquareEntry {
+            mask,
+            magic,
+            shift: 64 - bits,
+            offset: self.attacks.len(),
+        };
+
+        // Every subset of the mask, by the carry-rippler trick
+        self.attacks.resize(entry.offset + (1 << bits), 0);
+        let mut subset = 0u64;
+        loop {
+            let index = self.index(&entry, subset);
+            self.attacks[index] = walk(square, directions, subset);
+            subset = subset.wrapping_sub(mask) & mask;
+            if subset == 0 {
+                break;
+            }
+        }
+        entry
+    }
+}
+
+// Attacks along the rays from `square`, each stopping at its first blocker
+fn walk(square: Square, directions: &[i32; 4], occupied: u64) -> u64 {
+    let mut attacks = 0u64;
+    for &direction in directions {
+        for &target in ray_table(direction)[square].as_slice() {
+            attacks |= 1 << target;
+            if occupied & (1 << target) != 0 {
+                break;
+            }
+        }
+    }
+    attacks
+}
+
+// The tables the engine uses, indexed by PEXT where the CPU has BMI2 and by magics elsewhere.
+pub fn tables() -> &'static SlidingAttacks {
+    static TABLES: OnceLock<SlidingAttacks> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        SlidingAttacks::new(if cpu::features().bmi2 {
+            Indexing::Pext
+        } else {
+            Indexing::Magic
+        })
+    })
+}
+
+// Times `lookups` rook-plus-bishop lookups over varied blocker sets: walking the rays, each
+// indexing called directly, and the engine's own tables through `tables()`. The last against its
+// direct row is what the run-time dispatch costs.
+pub fn benchmark(lookups: u64) -> Vec<(&'static str, Duration)> {
+    let mut state = 0x0123_4567_89ab_cdefu64;
+    let blockers: Vec<u64> = (0..256)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state & state.rotate_left(23)
+        })
+        .collect();
+    let time = |lookup: &dyn Fn(Square, u64) -> u64| {
+        let start = Instant::now();
+        let mut sum = 0u64;
+        for index in 0..lookups as usize {
+            let occupied = blockers[index % blockers.len()];
+            sum = sum.wrapping_add(lookup(index % 64, black_box(occupied)));
+        }
+        black_box(sum);
+        start.elapsed()
+    };
+
+    let magic = SlidingAttacks::new(Indexing::Magic);
+    let pext = SlidingAttacks::new(Indexing::Pext);
+    vec![
+        (
+            "walk",
+            time(&|square, occupied| {
+                walk(square, &ROOK_DIRECTIONS, occupied)
+                    | walk(square, &BISHOP_DIRECTIONS, occupied)
+            }),
+        ),
+        (
+            "magic",
+            time(&|square, occupied| magic.queen(square, Bitboard(occupied)).0),
+        ),
+        (
+            "pext",
+            time(&|square, occupied| pext.queen(square, Bitboard(occupied)).0),
+        ),
+        (
+            "engine",
+            time(&|square, occupied| tables().queen(square, Bitboard(occupied)).0),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn both_indexings_match_walking_the_rays() {
+        let magic = SlidingAttacks::new(Indexing::Magic);
+        let pext = SlidingAttacks::new(Indexing::Pext);
+        assert_eq!(magic.attacks.len(), pext.attacks.len());
+        assert_eq!(magic.attacks.len(), 102_400 + 5_248);
+
+        let mut state = 0x1234_5678_9abc_def0u64;
+        for _ in 0..2_000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let occupied = state & state.rotate_left(17);
+            for square in 0..64 {
+                let rook = walk(square, &ROOK_DIRECTIONS, occupied);
+                let bishop = walk(square, &BISHOP_DIRECTIONS, occupied);
+                for tables in [&magic, &pext] {
+                    assert_eq!(tables.rook(square, Bitboard(occupied)).0, rook);
+                    assert_eq!(tables.bishop(square, Bitboard(occupied)).0, bishop);
+                }
+            }
+        }
+        // A rook on a1 with b1 and a3 occupied
+        let occupied = Bitboard(1 << 1 | 1 << 16);
+        assert_eq!(
+            tables().rook(0, occupied),
+            Bitboard(1 << 1 | 1 << 8 | 1 << 16)
+        );
+    }
+}
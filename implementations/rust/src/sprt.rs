@@ -0,0 +1,265 @@
+use crate::board::Board;
+use crate::selfplay::{play_match, Adjudication, GameRecord, MatchScore, Player};
+
+// The hypotheses a patch is tested between, in Elo of the patched engine over the base, and the
+// error rates: `alpha` is the chance of accepting a patch worth only `elo0`, `beta` that of
+// rejecting one worth `elo1`. The defaults are a common gainer test.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SprtBounds {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for SprtBounds {
+    fn default() -> Self {
+        Self {
+            elo0: 0.0,
+            elo1: 5.0,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
+
+impl SprtBounds {
+    pub fn is_valid(&self) -> bool {
+        let probability = |p: f64| p > 0.0 && p < 0.5;
+        self.elo0 < self.elo1 && probability(self.alpha) && probability(self.beta)
+    }
+
+    // The log-likelihood ratio at or below which H0 (`elo0`) is accepted
+    pub fn lower(&self) -> f64 {
+        (self.beta / (1.0 - self.alpha)).ln()
+    }
+
+    // The log-likelihood ratio at or above which H1 (`elo1`) is accepted
+    pub fn upper(&self) -> f64 {
+        ((1.0 - self.beta) / self.alpha).ln()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprtDecision {
+    AcceptH0,
+    AcceptH1,
+    Continue,
+}
+
+impl SprtDecision {
+    pub fn name(self) -> &'static str {
+        match self {
+            SprtDecision::AcceptH0 => "H0",
+            SprtDecision::AcceptH1 => "H1",
+            SprtDecision::Continue => "inconclusive",
+        }
+    }
+}
+
+fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+// The log-likelihood ratio of `elo1` against `elo0` given the games so far, in the normal
+// approximation to the trinomial (win/draw/loss) model: the score's mean and variance come from
+// the results, and the two hypotheses differ only in where they put the mean. Until the
+// results have some spread there is no evidence either way.
+pub fn llr(score: &MatchScore, bounds: &SprtBounds) -> f64 {
+    let games = f64::from(score.games());
+    if games == 0.0 {
+        return 0.0;
+    }
+    let (wins, draws) = (
+        f64::from(score.wins) / games,
+        f64::from(score.draws) / games,
+    );
+    let mean = wins + draws / 2.0;
+    let variance = (wins + draws / 4.0 - mean * mean) / games;
+    if variance <= 0.0 {
+        return 0.0;
+    }
+    let (s0, s1) = (expected_score(bounds.elo0), expected_score(bounds.elo1));
+    (s1 - s0) * (2.0 * mean - s0 - s1) / (2.0 * variance)
+}
+
+pub fn decide(llr: f64, bounds: &SprtBounds) -> SprtDecision {
+    if llr >= bounds.upper() {
+        SprtDecision::AcceptH1
+    } else if llr <= bounds.lower() {
+        SprtDecision::AcceptH0
+    } else {
+        SprtDecision::Continue
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SprtResult {
+    // From the test player's side
+    pub score: MatchScore,
+    pub llr: f64,
+    pub decision: SprtDecision,
+}
+
+// Plays the test player against the base in pairs of games, one with each colour from the same
+// opening, until the log-likelihood ratio crosses a bound or `max_games` are played. A test
+// only stops after a whole pair, so neither colour gets an extra game. `on_game` sees each game
+// with the score and LLR so far, for live progress.
+pub fn run<F>(
+    test: &mut Player,
+    base: &mut Player,
+    bounds: &SprtBounds,
+    max_games: u32,
+    openings: &[Board],
+    mut on_game: F,
+) -> SprtResult
+where
+    F: FnMut(u32, &GameRecord, &MatchScore, f64),
+{
+    let mut score = MatchScore::default();
+    let mut llr_now = 0.0;
+    let mut played = 0;
+    for pair in 0..max_games.div_ceil(2) {
+        let games = 2.min(max_games - 2 * pair);
+        let opening = pair as usize % openings.len();
+        // The test player is White in the first game of each pair
+        play_match(
+            test,
+            base,
+            games,
+            &openings[opening..],
+            &Adjudication::default(),
+            None,
+            |game, _, _, _, record| {
+                let white_score = record.result.white_score();
+                score.add(if game % 2 == 1 {
+                    white_score
+                } else {
+                    1.0 - white_score
+                });
+                llr_now = llr(&score, bounds);
+                played += 1;
+                on_game(played, record, &score, llr_now);
+            },
+        );
+        if decide(llr_now, bounds) != SprtDecision::Continue {
+            break;
+        }
+    }
+    SprtResult {
+        score,
+        llr: llr_now,
+        decision: decide(llr_now, bounds),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::AI;
+    use crate::search_limits::SearchLimits;
+
+    #[test]
+    fn the_llr_follows_the_score_between_the_bounds() {
+        let bounds = SprtBounds::default();
+        assert!((bounds.upper() - 2.944).abs() < 0.001);
+        assert!((bounds.lower() + 2.944).abs() < 0.001);
+        assert!(bounds.is_valid());
+        assert!(!SprtBounds {
+            elo0: 5.0,
+            elo1: 0.0,
+            ..bounds
+        }
+        .is_valid());
+        assert!(!SprtBounds {
+            alpha: 0.0,
+            ..bounds
+        }
+        .is_valid());
+
+        // No spread, no evidence
+        assert_eq!(
+            llr(
+                &MatchScore {
+                    wins: 0,
+                    draws: 10,
+                    losses: 0
+                },
+                &bounds
+            ),
+            0.0
+        );
+        // An even score is what `elo0` predicts
+        let even = MatchScore {
+            wins: 300,
+            draws: 400,
+            losses: 300,
+        };
+        assert!(llr(&even, &bounds) < 0.0);
+        let strong = MatchScore {
+            wins: 600,
+            draws: 300,
+            losses: 100,
+        };
+        assert_eq!(
+            decide(llr(&strong, &bounds), &bounds),
+            SprtDecision::AcceptH1
+        );
+        let weak = MatchScore {
+            wins: 100,
+            draws: 300,
+            losses: 600,
+        };
+        assert_eq!(decide(llr(&weak, &bounds), &bounds), SprtDecision::AcceptH0);
+        let early = MatchScore {
+            wins: 2,
+            draws: 1,
+            losses: 1,
+        };
+        assert_eq!(
+            decide(llr(&early, &bounds), &bounds),
+            SprtDecision::Continue
+        );
+    }
+
+    #[test]
+    fn stops_after_a_whole_pair_once_a_bound_is_crossed() {
+        // From a mate in one, whoever has White wins, so the pairs split evenly and the test
+        // never decides within the cap
+        let board = {
+            let mut board = Board::new();
+            crate::fen::FenParser::new()
+                .parse_fen(&mut board, "7k/8/5K2/8/8/8/8/6Q1 w - - 0 1")
+                .unwrap();
+            board
+        };
+        let mut test = Player::new("depth 3", AI::new(), SearchLimits::depth(3));
+        let mut base = Player::new("depth 1", AI::new(), SearchLimits::depth(1));
+        let mut progress = Vec::new();
+        let result = run(
+            &mut test,
+            &mut base,
+            &SprtBounds::default(),
+            3,
+            std::slice::from_ref(&board),
+            |game, _, score, _| {
+                progress.push((game, score.games()));
+            },
+        );
+        assert_eq!(result.score.games(), 3);
+        assert_eq!(progress, vec![(1, 1), (2, 2), (3, 3)]);
+        assert_eq!(result.decision, SprtDecision::Continue);
+
+        // Against hypotheses that far apart an even score soon decides, and only after a pair
+        let loose = SprtBounds {
+            elo0: -400.0,
+            elo1: -300.0,
+            alpha: 0.45,
+            beta: 0.45,
+        };
+        let result = run(&mut test, &mut base, &loose, 20, &[board], |_, _, _, _| {});
+        assert_eq!(result.decision, SprtDecision::AcceptH1);
+        assert_eq!(result.score.games() % 2, 0);
+        assert!(result.score.games() < 20);
+    }
+}
@@ -0,0 +1,528 @@
+use crate::attack_tables::ray_table;
+use crate::board::Board;
+use crate::move_generator::MoveGenerator;
+use crate::types::*;
+use std::fmt;
+
+const DIAGONALS: [i32; 4] = [-9, -7, 7, 9];
+const ORTHOGONALS: [i32; 4] = [-8, -1, 1, 8];
+const ALL_DIRECTIONS: [i32; 8] = [-9, -8, -7, -1, 1, 7, 8, 9];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlacedPiece {
+    pub square: Square,
+    pub piece: Piece,
+}
+
+impl fmt::Display for PlacedPiece {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            self.piece.to_char(),
+            square_to_algebraic(self.square)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MotifKind {
+    Pin {
+        pinner: PlacedPiece,
+        pinned: PlacedPiece,
+        target: PlacedPiece,
+    },
+    Skewer {
+        attacker: PlacedPiece,
+        front: PlacedPiece,
+        back: PlacedPiece,
+    },
+    Fork {
+        attacker: PlacedPiece,
+        targets: Vec<PlacedPiece>,
+    },
+    DiscoveredAttack {
+        slider: PlacedPiece,
+        blocker: PlacedPiece,
+        target: PlacedPiece,
+    },
+    Overload {
+        defender: PlacedPiece,
+        duties: Vec<PlacedPiece>,
+    },
+    Hanging {
+        target: PlacedPiece,
+        attackers: Vec<PlacedPiece>,
+    },
+}
+
+// A motif is reported from the point of view of the side that can exploit it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Motif {
+    pub side: Color,
+    pub kind: MotifKind,
+}
+
+impl Motif {
+    // The tag output keys on, and the words explanations use
+    fn names(&self) -> (&'static str, &'static str) {
+        match self.kind {
+            MotifKind::Pin { .. } => ("pin", "pin"),
+            MotifKind::Skewer { .. } => ("skewer", "skewer"),
+            MotifKind::Fork { .. } => ("fork", "fork"),
+            MotifKind::DiscoveredAttack { .. } => ("discovered_attack", "discovered attack"),
+            MotifKind::Overload { .. } => ("overload", "overloaded defender"),
+            MotifKind::Hanging { .. } => ("hanging", "hanging piece"),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.names().0
+    }
+
+    pub fn label(&self) -> &'static str {
+        self.names().1
+    }
+
+    pub fn description(&self) -> String {
+        match &self.kind {
+            MotifKind::Pin {
+                pinner,
+                pinned,
+                target,
+            } => {
+                let absolute = if target.piece.piece_type == PieceType::King {
+                    " (absolute)"
+                } else {
+                    ""
+                };
+                format!("{} pins {} to {}{}", pinner, pinned, target, absolute)
+            }
+            MotifKind::Skewer {
+                attacker,
+                front,
+                back,
+            } => format!("{} skewers {} and {}", attacker, front, back),
+            MotifKind::Fork { attacker, targets } => {
+                format!("{} forks {}", attacker, join_pieces(targets))
+            }
+            MotifKind::DiscoveredAttack {
+                slider,
+                blocker,
+                target,
+            } => format!("moving {} uncovers {} on {}", blocker, slider, target),
+            MotifKind::Overload { defender, duties } => {
+                format!(
+                    "{} is overloaded defending {}",
+                    defender,
+                    join_pieces(duties)
+                )
+            }
+            MotifKind::Hanging { target, attackers } => {
+                format!("{} is hanging to {}", target, join_pieces(attackers))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Motif {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let side = if self.side == Color::White {
+            "white"
+        } else {
+            "black"
+        };
+        write!(f, "{} {}: {}", side, self.name(), self.description())
+    }
+}
+
+fn join_pieces(pieces: &[PlacedPiece]) -> String {
+    pieces
+        .iter()
+        .map(|piece| piece.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub struct TacticsDetector {
+    move_generator: MoveGenerator,
+}
+
+impl TacticsDetector {
+    pub fn new() -> Self {
+        Self {
+            move_generator: MoveGenerator::new(),
+        }
+    }
+
+    pub fn detect_all(&self, board: &Board, side: Color) -> Vec<Motif> {
+        let mut motifs = Vec::new();
+        motifs.extend(self.find_pins(board, side));
+        motifs.extend(self.find_skewers(board, side));
+        motifs.extend(self.find_forks(board, side));
+        motifs.extend(self.find_discovered_attacks(board, side));
+        motifs.extend(self.find_overloads(board, side));
+        motifs.extend(self.find_hanging_pieces(board, side));
+        motifs
+    }
+
+    pub fn find_pins(&self, board: &Board, side: Color) -> Vec<Motif> {
+        self.line_candidates(board, side)
+            .into_iter()
+            .filter(|(_, front, back)| {
+                back.piece.piece_type == PieceType::King
+                    || back.piece.piece_type.value() > front.piece.piece_type.value()
+            })
+            .map(|(pinner, pinned, target)| Motif {
+                side,
+                kind: MotifKind::Pin {
+                    pinner,
+                    pinned,
+                    target,
+                },
+            })
+            .collect()
+    }
+
+    pub fn find_skewers(&self, board: &Board, side: Color) -> Vec<Motif> {
+        self.line_candidates(board, side)
+            .into_iter()
+            .filter(|(attacker, front, back)| {
+                let front_outranks_back = front.piece.piece_type == PieceType::King
+                    || front.piece.piece_type.value() > back.piece.piece_type.value();
+                let back_is_winnable = !self.is_defended(board, back.square, back.piece.color)
+                    || back.piece.piece_type.value() > attacker.piece.piece_type.value();
+                front_outranks_back && back_is_winnable
+            })
+            .map(|(attacker, front, back)| Motif {
+                side,
+                kind: MotifKind::Skewer {
+                    attacker,
+                    front,
+                    back,
+                },
+            })
+            .collect()
+    }
+
+    pub fn find_forks(&self, board: &Board, side: Color) -> Vec<Motif> {
+        let mut motifs = Vec::new();
+
+        for square in 0..64 {
+            let Some(piece) = board.get_piece(square) else {
+                continue;
+            };
+            if piece.color != side {
+                continue;
+            }
+
+            let targets: Vec<PlacedPiece> = self
+                .move_generator
+                .attacks_from(board, square)
+                .into_iter()
+                .filter_map(|target| placed(board, target))
+                .filter(|target| {
+                    target.piece.color != side
+                        && (target.piece.piece_type == PieceType::King
+                            || target.piece.piece_type.value() > piece.piece_type.value()
+                            || !self.is_defended(board, target.square, target.piece.color))
+                })
+                .collect();
+
+            if targets.len() >= 2 {
+                motifs.push(Motif {
+                    side,
+                    kind: MotifKind::Fork {
+                        attacker: PlacedPiece { square, piece },
+                        targets,
+                    },
+                });
+            }
+        }
+
+        motifs
+    }
+
+    pub fn find_discovered_attacks(&self, board: &Board, side: Color) -> Vec<Motif> {
+        let mut scratch = board.clone();
+        scratch.set_turn(side);
+        let legal_moves = self.move_generator.get_legal_moves(&mut scratch, side);
+        let mut motifs = Vec::new();
+
+        for square in 0..64 {
+            let Some(slider) = placed(board, square) else {
+                continue;
+            };
+            if slider.piece.color != side {
+                continue;
+            }
+
+            for &direction in slider_directions(slider.piece.piece_type) {
+                let ray = ray_table(direction)[square].as_slice();
+                let mut occupied = ray
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &target)| board.get_piece(target).is_some());
+                let (Some((_, &blocker_square)), Some((target_index, &target_square))) =
+                    (occupied.next(), occupied.next())
+                else {
+                    continue;
+                };
+                let (Some(blocker), Some(target)) =
+                    (placed(board, blocker_square), placed(board, target_square))
+                else {
+                    continue;
+                };
+                if blocker.piece.color != side
+                    || blocker.piece.piece_type == PieceType::King
+                    || target.piece.color == side
+                {
+                    continue;
+                }
+                let worth_uncovering = target.piece.piece_type == PieceType::King
+                    || target.piece.piece_type.value() > slider.piece.piece_type.value()
+                    || !self.is_defended(board, target.square, target.piece.color);
+                if !worth_uncovering {
+                    continue;
+                }
+
+                let line = &ray[..target_index];
+                let can_step_off = legal_moves.iter().any(|chess_move| {
+                    chess_move.from == blocker_square && !line.contains(&chess_move.to)
+                });
+                if can_step_off {
+                    motifs.push(Motif {
+                        side,
+                        kind: MotifKind::DiscoveredAttack {
+                            slider,
+                            blocker,
+                            target,
+                        },
+                    });
+                }
+            }
+        }
+
+        motifs
+    }
+
+    pub fn find_overloads(&self, board: &Board, side: Color) -> Vec<Motif> {
+        let defender_color = side.opposite();
+        let mut duties: Vec<(Square, Vec<PlacedPiece>)> = Vec::new();
+
+        for square in 0..64 {
+            let Some(target) = placed(board, square) else {
+                continue;
+            };
+            if target.piece.color != defender_color || target.piece.piece_type == PieceType::King {
+                continue;
+            }
+            if self
+                .move_generator
+                .attackers_of(board, square, side)
+                .is_empty()
+            {
+                continue;
+            }
+
+            let defenders = self
+                .move_generator
+                .attackers_of(board, square, defender_color);
+            if let [defender] = defenders.as_slice() {
+                match duties.iter_mut().find(|(square, _)| square == defender) {
+                    Some((_, defended)) => defended.push(target),
+                    None => duties.push((*defender, vec![target])),
+                }
+            }
+        }
+
+        duties
+            .into_iter()
+            .filter(|(_, defended)| defended.len() >= 2)
+            .filter_map(|(square, defended)| {
+                Some(Motif {
+                    side,
+                    kind: MotifKind::Overload {
+                        defender: placed(board, square)?,
+                        duties: defended,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    pub fn find_hanging_pieces(&self, board: &Board, side: Color) -> Vec<Motif> {
+        let mut motifs = Vec::new();
+
+        for square in 0..64 {
+            let Some(target) = placed(board, square) else {
+                continue;
+            };
+            if target.piece.color == side || target.piece.piece_type == PieceType::King {
+                continue;
+            }
+
+            let attackers: Vec<PlacedPiece> = self
+                .move_generator
+                .attackers_of(board, square, side)
+                .into_iter()
+                .filter_map(|attacker| placed(board, attacker))
+                .collect();
+            let Some(cheapest) = attackers
+                .iter()
+                .map(|attacker| attacker.piece.piece_type.value())
+                .min()
+            else {
+                continue;
+            };
+
+            if !self.is_defended(board, square, target.piece.color)
+                || cheapest < target.piece.piece_type.value()
+            {
+                motifs.push(Motif {
+                    side,
+                    kind: MotifKind::Hanging { target, attackers },
+                });
+            }
+        }
+
+        motifs
+    }
+
+    fn is_defended(&self, board: &Board, square: Square, color: Color) -> bool {
+        !self
+            .move_generator
+            .attackers_of(board, square, color)
+            .is_empty()
+    }
+
+    // Every slider line of `side` whose first two occupants are enemy pieces:
+    // the shared shape of pins and skewers.
+    fn line_candidates(
+        &self,
+        board: &Board,
+        side: Color,
+    ) -> Vec<(PlacedPiece, PlacedPiece, PlacedPiece)> {
+        let mut candidates = Vec::new();
+
+        for square in 0..64 {
+            let Some(attacker) = placed(board, square) else {
+                continue;
+            };
+            if attacker.piece.color != side {
+                continue;
+            }
+
+            for &direction in slider_directions(attacker.piece.piece_type) {
+                let mut occupied = ray_table(direction)[square]
+                    .as_slice()
+                    .iter()
+                    .filter_map(|&target| placed(board, target));
+                if let (Some(front), Some(back)) = (occupied.next(), occupied.next()) {
+                    if front.piece.color != side && back.piece.color != side {
+                        candidates.push((attacker, front, back));
+                    }
+                }
+            }
+        }
+
+        candidates
+    }
+}
+
+impl Default for TacticsDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn placed(board: &Board, square: Square) -> Option<PlacedPiece> {
+    board
+        .get_piece(square)
+        .map(|piece| PlacedPiece { square, piece })
+}
+
+fn slider_directions(piece_type: PieceType) -> &'static [i32] {
+    match piece_type {
+        PieceType::Bishop => &DIAGONALS,
+        PieceType::Rook => &ORTHOGONALS,
+        PieceType::Queen => &ALL_DIRECTIONS,
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::board_from;
+
+    fn names(motifs: &[Motif]) -> Vec<String> {
+        motifs.iter().map(|motif| motif.to_string()).collect()
+    }
+
+    #[test]
+    fn detects_absolute_and_relative_pins() {
+        let board = board_from("4k3/8/2n5/1B6/8/8/8/4K3 w - - 0 1");
+        let pins = TacticsDetector::new().find_pins(&board, Color::White);
+        assert_eq!(
+            names(&pins),
+            vec!["white pin: Bb5 pins nc6 to ke8 (absolute)"]
+        );
+
+        let board = board_from("4k3/8/8/3q4/8/3n4/8/3RK3 w - - 0 1");
+        let pins = TacticsDetector::new().find_pins(&board, Color::White);
+        assert_eq!(names(&pins), vec!["white pin: Rd1 pins nd3 to qd5"]);
+    }
+
+    #[test]
+    fn detects_knight_fork_of_king_and_rook() {
+        let board = board_from("r3k3/2N5/8/8/8/8/8/4K3 b - - 0 1");
+        let forks = TacticsDetector::new().find_forks(&board, Color::White);
+        assert_eq!(names(&forks), vec!["white fork: Nc7 forks ra8, ke8"]);
+    }
+
+    #[test]
+    fn detects_skewer_through_the_king() {
+        let board = board_from("8/8/8/8/8/1k6/8/R1K4q w - - 0 1");
+        let skewers = TacticsDetector::new().find_skewers(&board, Color::Black);
+        assert_eq!(
+            names(&skewers),
+            vec!["black skewer: qh1 skewers Kc1 and Ra1"]
+        );
+
+        let board = board_from("8/8/8/8/8/1k6/8/K1R4q w - - 0 1");
+        let detector = TacticsDetector::new();
+        assert!(detector.find_skewers(&board, Color::Black).is_empty());
+        assert_eq!(
+            names(&detector.find_pins(&board, Color::Black)),
+            vec!["black pin: qh1 pins Rc1 to Ka1 (absolute)"]
+        );
+    }
+
+    #[test]
+    fn detects_hanging_and_overloaded_pieces() {
+        let board = board_from("4k3/8/8/3n4/8/8/8/3RK3 w - - 0 1");
+        let hanging = TacticsDetector::new().find_hanging_pieces(&board, Color::White);
+        assert_eq!(
+            names(&hanging),
+            vec!["white hanging: nd5 is hanging to Rd1"]
+        );
+
+        let board = board_from("4k3/7p/5n2/3p4/8/8/8/3RK2R w - - 0 1");
+        let overloads = TacticsDetector::new().find_overloads(&board, Color::White);
+        assert_eq!(
+            names(&overloads),
+            vec!["white overload: nf6 is overloaded defending pd5, ph7"]
+        );
+    }
+
+    #[test]
+    fn detects_discovered_attack_on_the_queen() {
+        let board = board_from("3qk3/8/8/8/8/8/3N4/3RK3 w - - 0 1");
+        let discovered = TacticsDetector::new().find_discovered_attacks(&board, Color::White);
+        assert_eq!(
+            names(&discovered),
+            vec!["white discovered_attack: moving Nd2 uncovers Rd1 on qd8"]
+        );
+    }
+}
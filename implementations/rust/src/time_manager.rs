@@ -0,0 +1,748 @@
+use crate::timing::Instant;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeControl {
+    Infinite,
+    MoveTime(u64),
+    // Time left on the mover's clock, the increment per move and, for classical controls, the
+    // moves remaining until more time is added.
+    Clock {
+        remaining_ms: u64,
+        increment_ms: u64,
+        moves_to_go: Option<u32>,
+    },
+    // A budget of searched nodes instead of wall-clock time.
+    Nodes(u64),
+}
+
+// Sudden-death clocks are budgeted as if this many moves remained.
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+// Never plan to use the last tenth of the clock: it absorbs overhead and misjudged iterations.
+const CLOCK_RESERVE_DIVISOR: u64 = 10;
+// Node-based searches convert millisecond budgets at this nominal speed, so the same limits stop
+// at the same point on every machine.
+pub const NODES_PER_MS: u64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamePhase {
+    Opening,
+    Middlegame,
+    Endgame,
+}
+
+impl GamePhase {
+    // Knights, bishops, rooks and queens left on the board (14 at the start), and the move number.
+    pub fn classify(pieces: u32, fullmove_number: u32) -> Self {
+        if pieces <= 6 {
+            GamePhase::Endgame
+        } else if pieces >= 12 && fullmove_number <= 12 {
+            GamePhase::Opening
+        } else {
+            GamePhase::Middlegame
+        }
+    }
+}
+
+// How the soft limit is shaped per position; percentages are of the plain half-budget soft limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimePolicy {
+    // Openings are mostly development and endgames mostly technique, so both get less than the
+    // middlegame, where most games are decided.
+    pub opening_percent: u64,
+    pub middlegame_percent: u64,
+    pub endgame_percent: u64,
+    // A score that moves by more than `swing_cp` between iterations means the position is not
+    // understood yet, so the soft limit grows by `swing_percent`.
+    pub swing_cp: i32,
+    pub swing_percent: u64,
+    // Each root move besides the best that may be within `close_margin_cp` of it adds
+    // `close_move_percent`, up to `MAX_CLOSE_MOVES` moves.
+    pub close_margin_cp: i32,
+    pub close_move_percent: u64,
+    // A best move that has lasted `EASY_MOVE_ITERATIONS` iterations and leads every alternative
+    // by `easy_move_lead_cp` is an easy move: the soft limit drops to `easy_move_percent`, so
+    // the time saved is left on the clock for harder decisions. Score swings don't matter here;
+    // with that lead they rarely change the move.
+    pub easy_move_lead_cp: i32,
+    pub easy_move_percent: u64,
+    // Every change of best move adds `best_move_change_percent`; changes count half as much
+    // with each later iteration, so only recent churn keeps the soft limit raised.
+    pub best_move_change_percent: u64,
+    // When the previous best move fails low by more than `swing_cp` in the middle of an
+    // iteration, the hard limit grows by `fail_low_percent` (within the clock's reserve) so the
+    // iteration can find the replacement.
+    pub fail_low_percent: u64,
+    // Time lost per move between the GUI or server and the engine; it comes off every budget,
+    // including the hard limit, so the clock never runs out while a move is in transit.
+    pub move_overhead_ms: u64,
+    // A sharp position (see `Sharpness`, scored 0-100) extends the soft limit by up to
+    // `sharpness_percent`, in proportion to its score: there are more ways to go wrong.
+    pub sharpness_percent: u64,
+}
+
+const MAX_CLOSE_MOVES: u64 = 4;
+pub const EASY_MOVE_ITERATIONS: u32 = 4;
+
+// What one completed iteration found at the root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IterationReport {
+    pub score: i32,
+    // Other root moves that may be close to the best one.
+    pub close_moves: usize,
+    // Completed iterations in a row, this one included, that chose the same best move.
+    pub stable_iterations: u32,
+    // The best score minus the highest fail-soft upper bound among the other root moves, so
+    // the real lead is at least this much; None when there is only one legal move.
+    pub lead: Option<i32>,
+    // How many times a new move took the lead during this iteration.
+    pub best_move_changes: u32,
+}
+
+impl Default for TimePolicy {
+    fn default() -> Self {
+        Self {
+            opening_percent: 70,
+            middlegame_percent: 100,
+            endgame_percent: 80,
+            swing_cp: 40,
+            swing_percent: 50,
+            close_margin_cp: 15,
+            close_move_percent: 10,
+            easy_move_lead_cp: 150,
+            easy_move_percent: 30,
+            best_move_change_percent: 25,
+            fail_low_percent: 50,
+            move_overhead_ms: 30,
+            sharpness_percent: 30,
+        }
+    }
+}
+
+impl TimePolicy {
+    fn phase_percent(&self, phase: GamePhase) -> u64 {
+        match phase {
+            GamePhase::Opening => self.opening_percent,
+            GamePhase::Middlegame => self.middlegame_percent,
+            GamePhase::Endgame => self.endgame_percent,
+        }
+    }
+}
+
+// Splits a budget into a soft limit (don't start another iteration) and a hard limit (abort now).
+// The soft limit starts from the phase allocation and is re-derived after every iteration from
+// how unsettled the search looks; it never exceeds the hard limit. Limits are in milliseconds,
+// or in nodes when the manager counts nodes.
+pub struct TimeManager {
+    start: Instant,
+    policy: TimePolicy,
+    counts_nodes: bool,
+    base_soft_limit: Option<u64>,
+    soft_limit: Option<u64>,
+    base_hard_limit: Option<u64>,
+    hard_limit: Option<u64>,
+    // How far a root fail-low may push the hard limit.
+    max_limit: Option<u64>,
+    last_score: Option<i32>,
+    // Recent best-move changes, in hundredths, halved after every iteration.
+    best_move_changes: u64,
+    failing_low: bool,
+}
+
+impl TimeManager {
+    pub fn new(control: TimeControl) -> Self {
+        Self::with_policy(control, TimePolicy::default(), GamePhase::Middlegame)
+    }
+
+    pub fn with_policy(control: TimeControl, policy: TimePolicy, phase: GamePhase) -> Self {
+        let (soft_limit, hard_limit) = Self::allocate_time(control, &policy, phase);
+        // Only a clock has time to spare beyond the hard limit; movetime and node budgets are
+        // exact.
+        let max_limit = match control {
+            TimeControl::Clock { remaining_ms, .. } => Some(usable_ms(remaining_ms, &policy)),
+            _ => hard_limit,
+        };
+        Self {
+            start: Instant::now(),
+            policy,
+            counts_nodes: matches!(control, TimeControl::Nodes(_)),
+            base_soft_limit: soft_limit,
+            soft_limit,
+            base_hard_limit: hard_limit,
+            hard_limit,
+            max_limit,
+            last_score: None,
+            best_move_changes: 0,
+            failing_low: false,
+        }
+    }
+
+    // Measures every budget in nodes, converting time limits at NODES_PER_MS, so iteration
+    // decisions depend only on the search itself and not on the machine's speed.
+    pub fn node_based(control: TimeControl, policy: TimePolicy, phase: GamePhase) -> Self {
+        let mut manager = Self::with_policy(control, policy, phase);
+        if !manager.counts_nodes {
+            let to_nodes = |limit: Option<u64>| limit.map(|ms| ms.saturating_mul(NODES_PER_MS));
+            manager.counts_nodes = true;
+            manager.base_soft_limit = to_nodes(manager.base_soft_limit);
+            manager.soft_limit = manager.base_soft_limit;
+            manager.base_hard_limit = to_nodes(manager.base_hard_limit);
+            manager.hard_limit = manager.base_hard_limit;
+            manager.max_limit = to_nodes(manager.max_limit);
+        }
+        manager
+    }
+
+    pub fn allocate_time(
+        control: TimeControl,
+        policy: &TimePolicy,
+        phase: GamePhase,
+    ) -> (Option<u64>, Option<u64>) {
+        match control {
+            TimeControl::Infinite => (None, None),
+            // The next iteration usually costs more than everything before it, so stop at half.
+            TimeControl::MoveTime(ms) => {
+                let hard = ms.saturating_sub(policy.move_overhead_ms);
+                let soft = percent_of(hard / 2, policy.phase_percent(phase));
+                (Some(soft.min(hard)), Some(hard))
+            }
+            // A fair share of what is left until the next control, plus the increment, is the
+            // target; the hard limit allows twice that but never touches the reserve.
+            TimeControl::Clock {
+                remaining_ms,
+                increment_ms,
+                moves_to_go,
+            } => {
+                let moves = moves_to_go.map_or(DEFAULT_MOVES_TO_GO, u64::from).max(1);
+                let usable = usable_ms(remaining_ms, policy);
+                let share = (remaining_ms / moves).saturating_add(increment_ms);
+                let target = share.saturating_sub(policy.move_overhead_ms).min(usable);
+                let hard = target.saturating_mul(2).min(usable);
+                let soft = percent_of(target / 2, policy.phase_percent(phase));
+                (Some(soft.min(hard)), Some(hard))
+            }
+            // Shaped like a movetime; nodes have no transit latency to allow for.
+            TimeControl::Nodes(nodes) => {
+                let soft = percent_of(nodes / 2, policy.phase_percent(phase));
+                (Some(soft.min(nodes)), Some(nodes))
+            }
+        }
+    }
+
+    // Called before the first iteration with the position's sharpness (0-100). It raises the base
+    // soft limit, so the adjustments after each iteration start from the extended allocation.
+    pub fn report_sharpness(&mut self, sharpness: u32) {
+        let percent = 100 + u64::from(sharpness.min(100)) * self.policy.sharpness_percent / 100;
+        self.base_soft_limit = self.base_soft_limit.map(|base| {
+            let extended = percent_of(base, percent);
+            self.hard_limit.map_or(extended, |hard| extended.min(hard))
+        });
+        self.soft_limit = self.base_soft_limit;
+    }
+
+    // Called after each completed iteration.
+    pub fn report_iteration(&mut self, report: IterationReport) {
+        let swing = self
+            .last_score
+            .is_some_and(|last| (report.score - last).abs() > self.policy.swing_cp);
+        self.last_score = Some(report.score);
+        self.best_move_changes =
+            self.best_move_changes / 2 + u64::from(report.best_move_changes) * 100;
+        self.failing_low = false;
+
+        let easy_move = report.stable_iterations >= EASY_MOVE_ITERATIONS
+            && report
+                .lead
+                .is_none_or(|lead| lead >= self.policy.easy_move_lead_cp);
+        let mut percent =
+            100 + (report.close_moves as u64).min(MAX_CLOSE_MOVES) * self.policy.close_move_percent;
+        if swing {
+            percent += self.policy.swing_percent;
+        }
+        percent += self.best_move_changes * self.policy.best_move_change_percent / 100;
+        if easy_move {
+            percent = self.policy.easy_move_percent;
+        }
+        self.soft_limit = self.base_soft_limit.map(|base| {
+            let extended = percent_of(base, percent);
+            self.hard_limit.map_or(extended, |hard| extended.min(hard))
+        });
+    }
+
+    // Called as soon as the previous iteration's best move fails low, before the iteration ends.
+    // The extension is taken from the original hard limit, so fail-lows in several iterations
+    // don't compound.
+    pub fn report_fail_low(&mut self) {
+        if self.failing_low {
+            return;
+        }
+        self.failing_low = true;
+        self.hard_limit = self.base_hard_limit.map(|hard| {
+            let extended = percent_of(hard, 100 + self.policy.fail_low_percent);
+            self.max_limit
+                .map_or(extended, |max| extended.min(max).max(hard))
+        });
+    }
+
+    pub fn hard_limit(&self) -> Option<u64> {
+        self.hard_limit
+    }
+
+    pub fn soft_limit(&self) -> Option<u64> {
+        self.soft_limit
+    }
+
+    pub fn counts_nodes(&self) -> bool {
+        self.counts_nodes
+    }
+
+    pub fn elapsed_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
+    // `nodes` is the number searched so far; it is only read when the manager counts nodes.
+    pub fn should_stop(&self, nodes: u64) -> bool {
+        self.hard_limit
+            .is_some_and(|limit| self.spent(nodes) >= limit)
+    }
+
+    pub fn should_continue_iteration(&self, nodes: u64) -> bool {
+        self.soft_limit
+            .is_none_or(|limit| self.spent(nodes) < limit)
+    }
+
+    fn spent(&self, nodes: u64) -> u64 {
+        if self.counts_nodes {
+            nodes
+        } else {
+            self.elapsed_ms()
+        }
+    }
+}
+
+// `percent`% of a budget, which may be as large as the user cares to type, so without overflow
+fn percent_of(value: u64, percent: u64) -> u64 {
+    u64::try_from(u128::from(value) * u128::from(percent) / 100).unwrap_or(u64::MAX)
+}
+
+// The part of a clock a single move may ever use.
+fn usable_ms(remaining_ms: u64, policy: &TimePolicy) -> u64 {
+    (remaining_ms - remaining_ms / CLOCK_RESERVE_DIVISOR).saturating_sub(policy.move_overhead_ms)
+}
+
+// One stage of a tournament control: `moves` moves (None = the rest of the game) in `base_ms`,
+// with `increment_ms` added after every move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    pub moves: Option<u32>,
+    pub base_ms: u64,
+    pub increment_ms: u64,
+}
+
+// Classical controls such as `40/90+30` (40 moves in 90 minutes, 30 s increment, repeating) or
+// `40/120:20/60:30` (then 20 moves in 60 minutes, then 30 minutes for the rest). Minutes for the
+// base, seconds for the increment; a final period with a move count repeats.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TournamentControl {
+    periods: Vec<Period>,
+}
+
+impl TournamentControl {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid time control '{}'", text);
+        let periods = text
+            .split(':')
+            .map(|period| {
+                let (moves, rest) = match period.split_once('/') {
+                    Some((moves, rest)) => (
+                        Some(moves.parse::<u32>().ok().filter(|&moves| moves > 0)?),
+                        rest,
+                    ),
+                    None => (None, period),
+                };
+                let (minutes, increment) = rest.split_once('+').unwrap_or((rest, "0"));
+                Some(Period {
+                    moves,
+                    base_ms: (minutes.parse::<f64>().ok().filter(|m| *m >= 0.0)? * 60_000.0) as u64,
+                    increment_ms: (increment.parse::<f64>().ok().filter(|s| *s >= 0.0)? * 1000.0)
+                        as u64,
+                })
+            })
+            .collect::<Option<Vec<Period>>>()
+            .ok_or_else(invalid)?;
+        // Only the last period may be sudden death.
+        if periods[..periods.len() - 1]
+            .iter()
+            .any(|period| period.moves.is_none())
+        {
+            return Err(invalid());
+        }
+        Ok(Self { periods })
+    }
+
+    pub fn initial_ms(&self) -> u64 {
+        self.periods[0].base_ms
+    }
+
+    // The period in force for a side that has already made `moves_made` moves, and how many of
+    // its moves remain before the next control.
+    pub fn period(&self, moves_made: u32) -> (&Period, Option<u32>) {
+        let mut start: u32 = 0;
+        for period in &self.periods {
+            match period.moves {
+                Some(moves) if moves_made >= start.saturating_add(moves) => {
+                    start = start.saturating_add(moves)
+                }
+                Some(moves) => return (period, Some(start.saturating_add(moves) - moves_made)),
+                None => return (period, None),
+            }
+        }
+        // Past the listed periods the last one, which has a move count, repeats.
+        let last = &self.periods[self.periods.len() - 1];
+        let length = last.moves.unwrap_or(1);
+        (last, Some(length - (moves_made - start) % length))
+    }
+
+    // Time to add to a clock after its `moves_made`-th move: the increment, plus the next
+    // period's base when that move completed a control.
+    pub fn time_added_after(&self, moves_made: u32) -> u64 {
+        let Some(previous) = moves_made.checked_sub(1) else {
+            return 0;
+        };
+        let (period, moves_to_go) = self.period(previous);
+        let next_base = if moves_to_go == Some(1) {
+            self.period(moves_made).0.base_ms
+        } else {
+            0
+        };
+        period.increment_ms.saturating_add(next_base)
+    }
+
+    pub fn time_control(&self, remaining_ms: u64, moves_made: u32) -> TimeControl {
+        let (period, moves_to_go) = self.period(moves_made);
+        TimeControl::Clock {
+            remaining_ms,
+            increment_ms: period.increment_ms,
+            moves_to_go,
+        }
+    }
+}
+
+// Elapsed time comes from `Instant`, which never runs backwards; sub-millisecond runs still get
+// a finite rate.
+pub fn nodes_per_second(nodes: u64, elapsed: Duration) -> u64 {
+    let micros = elapsed.as_micros().max(1);
+    (u128::from(nodes) * 1_000_000 / micros) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn movetime_reserves_half_the_budget_for_the_last_iteration() {
+        let policy = TimePolicy::default();
+        assert_eq!(
+            TimeManager::allocate_time(TimeControl::Infinite, &policy, GamePhase::Middlegame),
+            (None, None)
+        );
+        assert_eq!(
+            TimeManager::allocate_time(TimeControl::MoveTime(1000), &policy, GamePhase::Middlegame),
+            (Some(485), Some(970))
+        );
+
+        // Any budget a user can type is shaped without overflowing
+        let huge = TimeControl::MoveTime(u64::MAX);
+        let (soft, hard) = TimeManager::allocate_time(huge, &policy, GamePhase::Middlegame);
+        assert_eq!(hard, Some(u64::MAX - policy.move_overhead_ms));
+        assert!(soft < hard);
+        let mut manager = TimeManager::with_policy(huge, policy, GamePhase::Opening);
+        manager.report_sharpness(100);
+        manager.report_iteration(IterationReport {
+            close_moves: 4,
+            best_move_changes: 3,
+            ..IterationReport::default()
+        });
+        manager.report_fail_low();
+        assert!(manager.soft_limit() > soft && manager.soft_limit() <= hard);
+
+        let manager = TimeManager::new(TimeControl::Infinite);
+        assert!(!manager.should_stop(u64::MAX));
+        assert!(manager.should_continue_iteration(u64::MAX));
+    }
+
+    #[test]
+    fn policy_shapes_the_soft_limit_by_phase_and_instability() {
+        let policy = TimePolicy::default();
+        let control = TimeControl::MoveTime(1000);
+        assert_eq!(
+            TimeManager::allocate_time(control, &policy, GamePhase::Opening),
+            (Some(339), Some(970))
+        );
+        assert_eq!(GamePhase::classify(14, 1), GamePhase::Opening);
+        assert_eq!(GamePhase::classify(14, 30), GamePhase::Middlegame);
+        assert_eq!(GamePhase::classify(4, 5), GamePhase::Endgame);
+
+        let report = |score, close_moves| IterationReport {
+            score,
+            close_moves,
+            ..IterationReport::default()
+        };
+        let mut manager = TimeManager::with_policy(control, policy, GamePhase::Middlegame);
+        manager.report_iteration(report(20, 0));
+        assert_eq!(manager.soft_limit(), Some(485));
+        manager.report_iteration(report(25, 2));
+        assert_eq!(manager.soft_limit(), Some(582));
+        manager.report_iteration(report(-200, 9));
+        assert_eq!(manager.soft_limit(), Some(921));
+
+        let greedy = TimePolicy {
+            swing_percent: 500,
+            ..policy
+        };
+        let mut manager = TimeManager::with_policy(control, greedy, GamePhase::Middlegame);
+        manager.report_iteration(report(0, 0));
+        manager.report_iteration(report(300, 0));
+        assert_eq!(manager.soft_limit(), Some(970));
+    }
+
+    #[test]
+    fn easy_moves_give_back_most_of_the_soft_limit() {
+        let control = TimeControl::MoveTime(1000);
+        let mut manager =
+            TimeManager::with_policy(control, TimePolicy::default(), GamePhase::Middlegame);
+        let easy = IterationReport {
+            score: 900,
+            close_moves: 0,
+            stable_iterations: 4,
+            lead: Some(600),
+            best_move_changes: 0,
+        };
+        manager.report_iteration(easy);
+        assert_eq!(manager.soft_limit(), Some(145));
+
+        // Not yet stable, or not far enough ahead
+        manager.report_iteration(IterationReport {
+            stable_iterations: 3,
+            ..easy
+        });
+        assert_eq!(manager.soft_limit(), Some(485));
+        manager.report_iteration(IterationReport {
+            lead: Some(100),
+            ..easy
+        });
+        assert_eq!(manager.soft_limit(), Some(485));
+
+        // The only legal move is always easy, even while its score moves
+        manager.report_iteration(IterationReport {
+            score: 0,
+            lead: None,
+            ..easy
+        });
+        assert_eq!(manager.soft_limit(), Some(145));
+    }
+
+    #[test]
+    fn clock_budgets_split_the_remaining_time() {
+        let policy = TimePolicy::default();
+        let clock = |remaining_ms, increment_ms, moves_to_go| TimeControl::Clock {
+            remaining_ms,
+            increment_ms,
+            moves_to_go,
+        };
+        let allocate =
+            |control| TimeManager::allocate_time(control, &policy, GamePhase::Middlegame);
+        // 60 s sudden death with a 1 s increment: target 3 s per move, less the overhead
+        assert_eq!(
+            allocate(clock(60_000, 1000, None)),
+            (Some(1485), Some(5940))
+        );
+        // Ten moves to the control: target 6 s
+        assert_eq!(
+            allocate(clock(60_000, 0, Some(10))),
+            (Some(2985), Some(11_940))
+        );
+        // The last move before the control cannot spend the reserve
+        assert_eq!(
+            allocate(clock(10_000, 0, Some(1))),
+            (Some(4485), Some(8970))
+        );
+        // Clocks and increments as large as a user can type stay within the usable time
+        for control in [
+            clock(1000, u64::MAX, None),
+            clock(u64::MAX, 0, Some(1)),
+            clock(u64::MAX, u64::MAX, None),
+        ] {
+            let (soft, hard) = allocate(control);
+            assert!(soft <= hard, "{:?}", control);
+        }
+        assert_eq!(allocate(clock(1000, u64::MAX, None)).1, Some(870));
+        let (soft, hard) = allocate(TimeControl::Nodes(u64::MAX));
+        assert!(soft < hard && hard == Some(u64::MAX));
+    }
+
+    #[test]
+    fn move_overhead_comes_off_the_hard_limit() {
+        let policy = TimePolicy {
+            move_overhead_ms: 200,
+            ..TimePolicy::default()
+        };
+        let allocate =
+            |control| TimeManager::allocate_time(control, &policy, GamePhase::Middlegame);
+        assert_eq!(
+            allocate(TimeControl::MoveTime(1000)),
+            (Some(400), Some(800))
+        );
+        // Bullet: 2 s left is mostly overhead, and the hard limit stays inside what remains
+        let (_, hard) = allocate(TimeControl::Clock {
+            remaining_ms: 2000,
+            increment_ms: 0,
+            moves_to_go: None,
+        });
+        assert_eq!(hard, Some(0));
+        assert_eq!(allocate(TimeControl::MoveTime(150)), (Some(0), Some(0)));
+    }
+
+    #[test]
+    fn best_move_changes_and_root_fail_lows_buy_time() {
+        let policy = TimePolicy::default();
+        let report = |best_move_changes| IterationReport {
+            best_move_changes,
+            ..IterationReport::default()
+        };
+        let mut manager =
+            TimeManager::with_policy(TimeControl::MoveTime(1000), policy, GamePhase::Middlegame);
+        manager.report_iteration(report(2));
+        assert_eq!(manager.soft_limit(), Some(727));
+        // Older changes fade: 200 / 2 + 100 hundredths
+        manager.report_iteration(report(1));
+        assert_eq!(manager.soft_limit(), Some(727));
+        manager.report_iteration(report(0));
+        manager.report_iteration(report(0));
+        assert_eq!(manager.soft_limit(), Some(543));
+
+        // A movetime is exact, so a fail-low cannot extend it
+        manager.report_fail_low();
+        assert_eq!(manager.hard_limit(), Some(970));
+
+        let clock = TimeControl::Clock {
+            remaining_ms: 60_000,
+            increment_ms: 0,
+            moves_to_go: None,
+        };
+        let mut manager = TimeManager::with_policy(clock, policy, GamePhase::Middlegame);
+        assert_eq!(manager.hard_limit(), Some(3940));
+        manager.report_fail_low();
+        manager.report_fail_low();
+        assert_eq!(manager.hard_limit(), Some(5910));
+        manager.report_iteration(IterationReport::default());
+        manager.report_fail_low();
+        assert_eq!(manager.hard_limit(), Some(5910));
+
+        let last_move = TimeControl::Clock {
+            remaining_ms: 10_000,
+            increment_ms: 0,
+            moves_to_go: Some(1),
+        };
+        let mut manager = TimeManager::with_policy(last_move, policy, GamePhase::Middlegame);
+        manager.report_fail_low();
+        assert_eq!(manager.hard_limit(), Some(8970));
+    }
+
+    #[test]
+    fn sharp_positions_extend_the_soft_limit_before_the_first_iteration() {
+        let policy = TimePolicy::default();
+        let control = TimeControl::MoveTime(1000);
+        let mut manager = TimeManager::with_policy(control, policy, GamePhase::Middlegame);
+        manager.report_sharpness(50);
+        assert_eq!(manager.soft_limit(), Some(557));
+        manager.report_iteration(IterationReport {
+            close_moves: 2,
+            ..IterationReport::default()
+        });
+        assert_eq!(manager.soft_limit(), Some(668));
+
+        // Never past the hard limit, however sharp
+        let greedy = TimePolicy {
+            sharpness_percent: 200,
+            ..policy
+        };
+        let mut manager = TimeManager::with_policy(control, greedy, GamePhase::Middlegame);
+        manager.report_sharpness(100);
+        assert_eq!(manager.soft_limit(), Some(970));
+    }
+
+    #[test]
+    fn node_budgets_ignore_the_wall_clock() {
+        let policy = TimePolicy::default();
+        let manager =
+            TimeManager::with_policy(TimeControl::Nodes(10_000), policy, GamePhase::Middlegame);
+        assert!(manager.counts_nodes());
+        assert_eq!(manager.soft_limit(), Some(5000));
+        assert!(manager.should_continue_iteration(4999));
+        assert!(!manager.should_continue_iteration(5000));
+        assert!(!manager.should_stop(9999));
+        assert!(manager.should_stop(10_000));
+
+        // Time limits become node budgets at the nominal speed
+        let manager =
+            TimeManager::node_based(TimeControl::MoveTime(1000), policy, GamePhase::Middlegame);
+        assert_eq!(manager.soft_limit(), Some(485 * NODES_PER_MS));
+        assert!(manager.should_stop(970 * NODES_PER_MS));
+        assert!(!manager.should_stop(0));
+    }
+
+    #[test]
+    fn tournament_controls_reset_moves_to_go_at_each_boundary() {
+        let control = TournamentControl::parse("40/90+30").unwrap();
+        assert_eq!(control.initial_ms(), 90 * 60_000);
+        assert_eq!(control.period(0).1, Some(40));
+        assert_eq!(control.period(39).1, Some(1));
+        assert_eq!(control.period(40).1, Some(40));
+        assert_eq!(control.time_added_after(39), 30_000);
+        assert_eq!(control.time_added_after(40), 90 * 60_000 + 30_000);
+        assert_eq!(control.time_added_after(80), 90 * 60_000 + 30_000);
+
+        let control = TournamentControl::parse("40/120:20/60:30").unwrap();
+        assert_eq!(control.period(45).1, Some(15));
+        assert_eq!(control.time_added_after(40), 60 * 60_000);
+        assert_eq!(control.time_added_after(60), 30 * 60_000);
+        assert_eq!(
+            control.time_control(5000, 70),
+            TimeControl::Clock {
+                remaining_ms: 5000,
+                increment_ms: 0,
+                moves_to_go: None,
+            }
+        );
+
+        assert_eq!(
+            TournamentControl::parse("5+3")
+                .unwrap()
+                .period(100)
+                .0
+                .increment_ms,
+            3000
+        );
+        // Move counts near u32::MAX saturate rather than overflow
+        let control = TournamentControl::parse("4294967295/90+30:4294967295/60").unwrap();
+        assert_eq!(control.period(10).1, Some(u32::MAX - 10));
+        assert_eq!(control.period(u32::MAX).1, Some(u32::MAX));
+        assert_eq!(control.time_added_after(u32::MAX), 30_000 + 60 * 60_000);
+        assert_eq!(
+            TournamentControl::parse("1/1e300+1e300")
+                .unwrap()
+                .time_added_after(1),
+            u64::MAX
+        );
+        for invalid in ["", "40/", "0/90", "90:40/30", "40/90+x", "-5"] {
+            assert!(TournamentControl::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn nodes_per_second_scales_by_elapsed_time() {
+        assert_eq!(nodes_per_second(5000, Duration::from_millis(250)), 20_000);
+        assert_eq!(nodes_per_second(3, Duration::ZERO), 3_000_000);
+    }
+}
@@ -0,0 +1,38 @@
+// The clock searches are timed by. `std::time::Instant` panics in the browser
+// (wasm32-unknown-unknown has no system clock), so with the `wasm` feature there it reads the
+// page's monotonic `performance.now()` instead; everywhere else it is std's.
+#[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+pub use std::time::Instant;
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+pub use browser::Instant;
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+mod browser {
+    use std::time::Duration;
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = performance, js_name = now)]
+        fn performance_now() -> f64;
+    }
+
+    // Milliseconds since the page loaded
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+    pub struct Instant(f64);
+
+    impl Instant {
+        pub fn now() -> Self {
+            Instant(performance_now())
+        }
+
+        pub fn duration_since(&self, earlier: Instant) -> Duration {
+            Duration::from_secs_f64((self.0 - earlier.0).max(0.0) / 1000.0)
+        }
+
+        pub fn elapsed(&self) -> Duration {
+            Instant::now().duration_since(*self)
+        }
+    }
+}
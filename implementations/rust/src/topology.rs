@@ -0,0 +1,119 @@
+use std::fs;
+use std::thread;
+
+// The machine's CPUs grouped by NUMA node, as Linux lists them under /sys. Elsewhere, or when
+// /sys is unreadable, every CPU is taken to sit on one node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Topology {
+    // CPU numbers per node, in node order
+    pub nodes: Vec<Vec<usize>>,
+}
+
+impl Topology {
+    pub fn detect() -> Self {
+        let mut nodes = Vec::new();
+        for node in 0.. {
+            let path = format!("/sys/devices/system/node/node{}/cpulist", node);
+            match fs::read_to_string(path) {
+                Ok(list) => nodes.push(parse_cpu_list(&list)),
+                Err(_) => break,
+            }
+        }
+        nodes.retain(|cpus| !cpus.is_empty());
+        if nodes.is_empty() {
+            let cpus = thread::available_parallelism().map_or(1, |cpus| cpus.get());
+            nodes.push((0..cpus).collect());
+        }
+        Self { nodes }
+    }
+
+    pub fn cpus(&self) -> usize {
+        self.nodes.iter().map(Vec::len).sum()
+    }
+
+    // The CPU for the `worker`th search thread: workers go round the nodes first, so a few
+    // threads already spread over every socket's memory, then through each node's CPUs.
+    pub fn cpu_for_worker(&self, worker: usize) -> usize {
+        let node = &self.nodes[worker % self.nodes.len()];
+        node[(worker / self.nodes.len()) % node.len()]
+    }
+}
+
+// `0-3,8,10-11` as Linux writes CPU lists
+pub fn parse_cpu_list(list: &str) -> Vec<usize> {
+    let mut cpus = Vec::new();
+    for range in list.trim().split(',').filter(|range| !range.is_empty()) {
+        let (first, last) = range.split_once('-').unwrap_or((range, range));
+        if let (Ok(first), Ok(last)) = (first.parse::<usize>(), last.parse::<usize>()) {
+            cpus.extend(first..=last);
+        }
+    }
+    cpus
+}
+
+// The reverse of `parse_cpu_list`, runs collapsed: `0-3,8`
+pub fn format_cpu_list(cpus: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &cpu in cpus {
+        match ranges.last_mut() {
+            Some((_, last)) if *last + 1 == cpu => *last = cpu,
+            _ => ranges.push((cpu, cpu)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(first, last)| {
+            if first == last {
+                first.to_string()
+            } else {
+                format!("{}-{}", first, last)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// Binds the calling thread to `cpu`. Memory the thread touches first is then placed on that
+// CPU's node, so a pinned worker's hash table is node-local without any NUMA allocation calls.
+// Returns false where pinning isn't supported or the CPU isn't available to this process.
+#[cfg(target_os = "linux")]
+pub fn pin_current_thread(cpu: usize) -> bool {
+    // glibc's and musl's cpu_set_t: 1024 bits
+    const SET_WORDS: usize = 16;
+    extern "C" {
+        fn sched_setaffinity(pid: i32, cpusetsize: usize, mask: *const u64) -> i32;
+    }
+    if cpu >= SET_WORDS * 64 {
+        return false;
+    }
+    let mut mask = [0u64; SET_WORDS];
+    mask[cpu / 64] |= 1 << (cpu % 64);
+    // SAFETY: `mask` is a live cpu_set_t-sized buffer for the duration of the call, and pid 0
+    // names the calling thread.
+    unsafe { sched_setaffinity(0, std::mem::size_of_val(&mask), mask.as_ptr()) == 0 }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_current_thread(_cpu: usize) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spreads_workers_over_nodes_first() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), [0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpu_list(""), Vec::<usize>::new());
+        assert_eq!(format_cpu_list(&[0, 1, 2, 3, 8, 10, 11]), "0-3,8,10-11");
+
+        let topology = Topology {
+            nodes: vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7]],
+        };
+        assert_eq!(topology.cpus(), 8);
+        let cpus: Vec<usize> = (0..10).map(|worker| topology.cpu_for_worker(worker)).collect();
+        assert_eq!(cpus, [0, 4, 1, 5, 2, 6, 3, 7, 0, 4]);
+        assert!(Topology::detect().cpus() >= 1);
+    }
+}
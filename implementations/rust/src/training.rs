@@ -0,0 +1,333 @@
+use crate::attack_tables::KNIGHT_ATTACKS;
+use crate::types::*;
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quiz {
+    SquareColor { square: Square },
+    KnightPath { from: Square, to: Square },
+}
+
+impl Quiz {
+    pub fn prompt(&self) -> String {
+        match self {
+            Quiz::SquareColor { square } => {
+                format!(
+                    "what color is {}? (light|dark)",
+                    square_to_algebraic(*square)
+                )
+            }
+            Quiz::KnightPath { from, to } => format!(
+                "how many moves does a knight need from {} to {}?",
+                square_to_algebraic(*from),
+                square_to_algebraic(*to)
+            ),
+        }
+    }
+
+    pub fn solution(&self) -> String {
+        match self {
+            Quiz::SquareColor { square } => square_shade(*square).to_string(),
+            Quiz::KnightPath { from, to } => {
+                let path = knight_path(*from, *to);
+                let squares: Vec<String> = path.iter().map(|&sq| square_to_algebraic(sq)).collect();
+                format!("{} ({})", path.len() - 1, squares.join("-"))
+            }
+        }
+    }
+
+    pub fn check(&self, answer: &str) -> Result<bool, &'static str> {
+        match self {
+            Quiz::SquareColor { square } => match answer.to_lowercase().as_str() {
+                "light" | "white" | "l" | "w" => Ok(square_shade(*square) == "light"),
+                "dark" | "black" | "d" | "b" => Ok(square_shade(*square) == "dark"),
+                _ => Err("answer must be light or dark"),
+            },
+            Quiz::KnightPath { from, to } => match answer.parse::<usize>() {
+                Ok(moves) => Ok(moves == knight_path(*from, *to).len() - 1),
+                Err(_) => Err("answer must be a number of moves"),
+            },
+        }
+    }
+}
+
+pub struct TrainingSession {
+    pub blindfold: bool,
+    pub peeks: u32,
+    pub asked: u32,
+    pub correct: u32,
+    pub quiz: Option<Quiz>,
+    pub drill: Drill,
+    rng_state: u64,
+}
+
+// An opening line played from the initial position, in SAN, with its place in the review
+// schedule. Lines sit in Leitner boxes: a clean run moves a line up a box and doubles the
+// rounds before it comes back; a mistake sends it back to the first box, due next round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrillLine {
+    pub moves: Vec<String>,
+    pub user: Color,
+    pub level: u32,
+    pub due: u32,
+    pub runs: u32,
+    pub clean_runs: u32,
+}
+
+// Depth of the searches that justify the theory move after a mistake; shallow enough to answer
+// at once.
+pub const DRILL_REVIEW_DEPTH: u8 = 3;
+// A wrong move this much worse than theory is called worse; closer ones are a matter of taste.
+pub const DRILL_MISTAKE_MARGIN_CP: i32 = 50;
+
+// The lines drilled this session. A round is one run through a line; the engine plays the
+// line's moves for the other side and the user has to find theirs.
+#[derive(Debug, Clone, Default)]
+pub struct Drill {
+    pub lines: Vec<DrillLine>,
+    pub current: Option<usize>,
+    pub round: u32,
+    pub run_mistakes: u32,
+}
+
+impl Drill {
+    // Adds a line, due at once; returns its index.
+    pub fn add(&mut self, moves: Vec<String>, user: Color) -> usize {
+        self.lines.push(DrillLine {
+            moves,
+            user,
+            level: 0,
+            due: self.round,
+            runs: 0,
+            clean_runs: 0,
+        });
+        self.lines.len() - 1
+    }
+
+    // Starts a run of the line due soonest, the least learned first among equals.
+    pub fn start_next(&mut self) -> Option<usize> {
+        let next = (0..self.lines.len()).min_by_key(|&index| {
+            let line = &self.lines[index];
+            (line.due, line.level, index)
+        })?;
+        self.current = Some(next);
+        self.run_mistakes = 0;
+        Some(next)
+    }
+
+    pub fn line(&self) -> Option<&DrillLine> {
+        self.current.map(|index| &self.lines[index])
+    }
+
+    // What theory plays after `ply` moves of the current line, or None once the line is over.
+    pub fn expected(&self, ply: usize) -> Option<&str> {
+        self.line()?.moves.get(ply).map(String::as_str)
+    }
+
+    pub fn record_mistake(&mut self) {
+        self.run_mistakes += 1;
+    }
+
+    // Schedules the finished line and returns how many rounds it waits before coming back.
+    pub fn finish_run(&mut self) -> u32 {
+        let Some(index) = self.current.take() else {
+            return 0;
+        };
+        self.round += 1;
+        let clean = self.run_mistakes == 0;
+        let line = &mut self.lines[index];
+        line.runs += 1;
+        if clean {
+            line.clean_runs += 1;
+            line.level += 1;
+        } else {
+            line.level = 0;
+        }
+        let wait = 1 << line.level.min(10);
+        line.due = self.round + wait - 1;
+        wait
+    }
+
+    pub fn stop(&mut self) {
+        self.current = None;
+    }
+}
+
+impl TrainingSession {
+    pub fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or(0);
+        Self::with_seed(seed)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            blindfold: false,
+            peeks: 0,
+            asked: 0,
+            correct: 0,
+            quiz: None,
+            drill: Drill::default(),
+            // xorshift never leaves the zero state, so force a bit on.
+            rng_state: seed | 1,
+        }
+    }
+
+    pub fn start_blindfold(&mut self) {
+        self.blindfold = true;
+        self.peeks = 0;
+    }
+
+    pub fn stop_blindfold(&mut self) {
+        self.blindfold = false;
+    }
+
+    pub fn record_peek(&mut self) -> u32 {
+        self.peeks += 1;
+        self.peeks
+    }
+
+    pub fn next_square_color_quiz(&mut self) -> Quiz {
+        let square = self.random_square();
+        self.ask(Quiz::SquareColor { square })
+    }
+
+    pub fn next_knight_path_quiz(&mut self) -> Quiz {
+        let from = self.random_square();
+        let mut to = self.random_square();
+        while to == from {
+            to = self.random_square();
+        }
+        self.ask(Quiz::KnightPath { from, to })
+    }
+
+    // Returns whether the answer was right; the quiz stays open on a malformed answer.
+    pub fn answer(&mut self, answer: &str) -> Result<(bool, Quiz), &'static str> {
+        let quiz = self.quiz.ok_or("no quiz in progress")?;
+        let correct = quiz.check(answer)?;
+        self.quiz = None;
+        if correct {
+            self.correct += 1;
+        }
+        Ok((correct, quiz))
+    }
+
+    fn ask(&mut self, quiz: Quiz) -> Quiz {
+        self.asked += 1;
+        self.quiz = Some(quiz);
+        quiz
+    }
+
+    fn random_square(&mut self) -> Square {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 7;
+        self.rng_state ^= self.rng_state << 17;
+        (self.rng_state % 64) as Square
+    }
+}
+
+pub fn square_shade(square: Square) -> &'static str {
+    if (square / 8 + square % 8) % 2 == 0 {
+        "dark"
+    } else {
+        "light"
+    }
+}
+
+// Shortest knight route including both endpoints; every square is reachable on an empty board.
+pub fn knight_path(from: Square, to: Square) -> Vec<Square> {
+    let mut previous = [None; 64];
+    let mut queue = VecDeque::from([from]);
+    previous[from] = Some(from);
+
+    while let Some(square) = queue.pop_front() {
+        if square == to {
+            break;
+        }
+        for &next in KNIGHT_ATTACKS[square].as_slice() {
+            if previous[next].is_none() {
+                previous[next] = Some(square);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    let mut path = vec![to];
+    let mut square = to;
+    while square != from {
+        square = previous[square].expect("knight graph is connected");
+        path.push(square);
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(name: &str) -> Square {
+        algebraic_to_square(name).unwrap()
+    }
+
+    #[test]
+    fn square_colors_match_the_board() {
+        assert_eq!(square_shade(square("a1")), "dark");
+        assert_eq!(square_shade(square("h1")), "light");
+        assert_eq!(square_shade(square("d1")), "light");
+        assert_eq!(square_shade(square("e4")), "light");
+        assert_eq!(square_shade(square("d4")), "dark");
+    }
+
+    #[test]
+    fn knight_paths_are_shortest_routes() {
+        assert_eq!(knight_path(square("g1"), square("f3")).len(), 2);
+        assert_eq!(knight_path(square("a1"), square("b2")).len(), 5);
+        assert_eq!(knight_path(square("a1"), square("h8")).len(), 7);
+        let path = knight_path(square("b1"), square("e6"));
+        assert_eq!(path.first(), Some(&square("b1")));
+        assert_eq!(path.last(), Some(&square("e6")));
+    }
+
+    #[test]
+    fn answers_update_the_score() {
+        let mut session = TrainingSession::with_seed(42);
+        let quiz = session.next_square_color_quiz();
+        let Quiz::SquareColor { square } = quiz else {
+            panic!("expected a square color quiz");
+        };
+        assert!(session.answer("purple").is_err());
+        assert_eq!(session.answer(square_shade(square)), Ok((true, quiz)));
+        assert_eq!(session.answer("dark"), Err("no quiz in progress"));
+
+        let quiz = session.next_knight_path_quiz();
+        assert_eq!(session.answer("99"), Ok((false, quiz)));
+        assert_eq!((session.asked, session.correct), (2, 1));
+    }
+
+    #[test]
+    fn missed_lines_come_back_before_learned_ones() {
+        let mut drill = Drill::default();
+        let line = |moves: &[&str]| moves.iter().map(|san| san.to_string()).collect();
+        drill.add(line(&["e4", "e5", "Nf3"]), Color::White);
+        drill.add(line(&["d4", "d5", "c4"]), Color::White);
+
+        assert_eq!(drill.start_next(), Some(0));
+        assert_eq!(drill.expected(2), Some("Nf3"));
+        assert_eq!(drill.expected(3), None);
+        assert_eq!(drill.finish_run(), 2);
+
+        assert_eq!(drill.start_next(), Some(1));
+        drill.record_mistake();
+        assert_eq!(drill.finish_run(), 1);
+
+        // The missed line is due again before the clean one
+        assert_eq!(drill.start_next(), Some(1));
+        assert_eq!(drill.finish_run(), 2);
+        assert_eq!(drill.start_next(), Some(0));
+        assert_eq!(drill.lines[0].clean_runs, 1);
+    }
+}
@@ -0,0 +1,341 @@
+use crate::board::Board;
+use crate::types::{Move, PieceType};
+use std::io::{self, Read, Write};
+use std::mem;
+
+pub const DEFAULT_TT_SIZE_MB: usize = 16;
+
+// Saved tables start with this header: magic, format version, a fingerprint of the Zobrist keys
+// (the start position hash) and the slot count. Each stored entry then takes ENTRY_BYTES.
+const FILE_MAGIC: &[u8; 4] = b"CETT";
+const FILE_VERSION: u32 = 1;
+const ENTRY_BYTES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Debug, Clone)]
+pub struct TtEntry {
+    pub key: u64,
+    pub depth: u8,
+    pub score: i32,
+    pub bound: Bound,
+    pub best_move: Option<Move>,
+}
+
+pub struct TranspositionTable {
+    entries: Vec<Option<TtEntry>>,
+    size_mb: usize,
+    large_pages: bool,
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> Self {
+        Self::allocate(size_mb, false)
+    }
+
+    // Asks for the table to be backed by huge pages where the platform offers them (Linux
+    // transparent huge pages), falling back to normal pages; `uses_large_pages` tells which.
+    pub fn with_large_pages(size_mb: usize) -> Self {
+        Self::allocate(size_mb, true)
+    }
+
+    fn allocate(size_mb: usize, large_pages: bool) -> Self {
+        let bytes = size_mb.max(1) * 1024 * 1024;
+        let slots = (bytes / mem::size_of::<Option<TtEntry>>()).max(1);
+        // Round down to a power of two so the index is a mask of the key.
+        let slots = 1 << (usize::BITS - 1 - slots.leading_zeros());
+        // The advice has to come before the first write faults the pages in.
+        let mut entries = Vec::with_capacity(slots);
+        let large_pages = large_pages
+            && advise_huge_pages(
+                entries.as_mut_ptr() as *mut u8,
+                slots * mem::size_of::<Option<TtEntry>>(),
+            );
+        entries.resize(slots, None);
+        Self {
+            entries,
+            size_mb: size_mb.max(1),
+            large_pages,
+        }
+    }
+
+    pub fn size_mb(&self) -> usize {
+        self.size_mb
+    }
+
+    pub fn slots(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn uses_large_pages(&self) -> bool {
+        self.large_pages
+    }
+
+    pub fn probe(&self, key: u64) -> Option<&TtEntry> {
+        self.entries[self.index(key)]
+            .as_ref()
+            .filter(|entry| entry.key == key)
+    }
+
+    // Keeps the deeper result when the same position is stored twice, otherwise always replaces.
+    pub fn store(&mut self, entry: TtEntry) {
+        let index = self.index(entry.key);
+        if let Some(existing) = &self.entries[index] {
+            if existing.key == entry.key && existing.depth > entry.depth {
+                return;
+            }
+        }
+        self.entries[index] = Some(entry);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key as usize) & (self.entries.len() - 1)
+    }
+
+    // Returns the number of entries written.
+    pub fn save(&self, writer: &mut impl Write) -> io::Result<usize> {
+        let stored: Vec<&TtEntry> = self.entries.iter().flatten().collect();
+        writer.write_all(FILE_MAGIC)?;
+        writer.write_all(&FILE_VERSION.to_le_bytes())?;
+        writer.write_all(&Board::new().get_hash().to_le_bytes())?;
+        writer.write_all(&(self.entries.len() as u64).to_le_bytes())?;
+        writer.write_all(&(stored.len() as u64).to_le_bytes())?;
+        for entry in &stored {
+            writer.write_all(&encode_entry(entry))?;
+        }
+        writer.flush()?;
+        Ok(stored.len())
+    }
+
+    // Replaces the table with a saved one of the same size. Nothing changes unless the whole
+    // file validates. Returns the number of entries loaded.
+    pub fn load(&mut self, reader: &mut impl Read) -> Result<usize, &'static str> {
+        let mut header = [0u8; 32];
+        read_exact(reader, &mut header)?;
+        if &header[0..4] != FILE_MAGIC {
+            return Err("Not a hash file");
+        }
+        if u32::from_le_bytes(header[4..8].try_into().unwrap()) != FILE_VERSION {
+            return Err("Unsupported hash file version");
+        }
+        if u64::from_le_bytes(header[8..16].try_into().unwrap()) != Board::new().get_hash() {
+            return Err("Hash file uses different Zobrist keys");
+        }
+        if u64::from_le_bytes(header[16..24].try_into().unwrap()) != self.entries.len() as u64 {
+            return Err("Hash file size does not match the table");
+        }
+        let count = u64::from_le_bytes(header[24..32].try_into().unwrap());
+        if count > self.entries.len() as u64 {
+            return Err("Corrupt hash file");
+        }
+
+        let mut entries = vec![None; self.entries.len()];
+        let mut record = [0u8; ENTRY_BYTES];
+        for _ in 0..count {
+            read_exact(reader, &mut record)?;
+            let entry = decode_entry(&record).ok_or("Corrupt hash file")?;
+            let index = self.index(entry.key);
+            entries[index] = Some(entry);
+        }
+        if reader
+            .read(&mut [0u8; 1])
+            .map_err(|_| "Cannot read hash file")?
+            != 0
+        {
+            return Err("Corrupt hash file");
+        }
+        // Copied in place, so the table keeps its pages
+        self.entries.clone_from_slice(&entries);
+        Ok(count as usize)
+    }
+}
+
+// Transparent huge pages: the kernel backs each 2 MB-aligned stretch of the range with one huge
+// page instead of 512 small ones, cutting TLB misses on random table probes. The ends of the
+// buffer outside a whole huge page stay on small pages. False when the kernel has them switched
+// off or refuses the advice.
+#[cfg(target_os = "linux")]
+fn advise_huge_pages(start: *mut u8, len: usize) -> bool {
+    const MADV_HUGEPAGE: i32 = 14;
+    const HUGE_PAGE: usize = 2 * 1024 * 1024;
+    extern "C" {
+        fn madvise(addr: *mut u8, len: usize, advice: i32) -> i32;
+    }
+    let enabled = std::fs::read_to_string("/sys/kernel/mm/transparent_hugepage/enabled")
+        .is_ok_and(|mode| !mode.contains("[never]"));
+    let first = (start as usize).next_multiple_of(HUGE_PAGE);
+    let end = (start as usize + len) / HUGE_PAGE * HUGE_PAGE;
+    if !enabled || first >= end {
+        return false;
+    }
+    // SAFETY: [first, end) lies inside the caller's live allocation, and MADV_HUGEPAGE only
+    // changes how the kernel backs the range, never its contents.
+    unsafe { madvise(first as *mut u8, end - first, MADV_HUGEPAGE) == 0 }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn advise_huge_pages(_start: *mut u8, _len: usize) -> bool {
+    false
+}
+
+fn read_exact(reader: &mut impl Read, buffer: &mut [u8]) -> Result<(), &'static str> {
+    reader
+        .read_exact(buffer)
+        .map_err(|error| match error.kind() {
+            io::ErrorKind::UnexpectedEof => "Corrupt hash file",
+            _ => "Cannot read hash file",
+        })
+}
+
+// key, depth, score, bound, then from, to, piece, captured, promotion and flags; a zero piece
+// byte means no best move.
+fn encode_entry(entry: &TtEntry) -> [u8; ENTRY_BYTES] {
+    let mut record = [0u8; ENTRY_BYTES];
+    record[0..8].copy_from_slice(&entry.key.to_le_bytes());
+    record[8] = entry.depth;
+    record[9..13].copy_from_slice(&entry.score.to_le_bytes());
+    record[13] = entry.bound as u8;
+    if let Some(chess_move) = &entry.best_move {
+        let piece_byte =
+            |piece: Option<PieceType>| piece.map_or(0, |piece| piece.to_string().as_bytes()[0]);
+        record[14] = chess_move.from as u8;
+        record[15] = chess_move.to as u8;
+        record[16] = piece_byte(Some(chess_move.piece));
+        record[17] = piece_byte(chess_move.captured);
+        record[18] = piece_byte(chess_move.promotion);
+        record[19] = u8::from(chess_move.is_castling) | (u8::from(chess_move.is_en_passant) << 1);
+    }
+    record
+}
+
+fn decode_entry(record: &[u8; ENTRY_BYTES]) -> Option<TtEntry> {
+    let bound = match record[13] {
+        0 => Bound::Exact,
+        1 => Bound::Lower,
+        2 => Bound::Upper,
+        _ => return None,
+    };
+    let piece = |byte: u8| match byte {
+        0 => Some(None),
+        _ => PieceType::from_char(byte as char)
+            .filter(|_| byte.is_ascii_uppercase())
+            .map(Some),
+    };
+    let best_move = match piece(record[16])? {
+        None => None,
+        Some(moved) => {
+            if record[14] >= 64 || record[15] >= 64 || record[19] > 3 {
+                return None;
+            }
+            let mut chess_move = Move::new(record[14] as usize, record[15] as usize, moved);
+            chess_move.captured = piece(record[17])?;
+            chess_move.promotion = piece(record[18])?;
+            chess_move.is_castling = record[19] & 1 != 0;
+            chess_move.is_en_passant = record[19] & 2 != 0;
+            Some(chess_move)
+        }
+    };
+    Some(TtEntry {
+        key: u64::from_le_bytes(record[0..8].try_into().unwrap()),
+        depth: record[8],
+        score: i32::from_le_bytes(record[9..13].try_into().unwrap()),
+        bound,
+        best_move,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PieceType;
+
+    fn entry(key: u64, depth: u8, score: i32) -> TtEntry {
+        TtEntry {
+            key,
+            depth,
+            score,
+            bound: Bound::Exact,
+            best_move: Some(Move::new(12, 28, PieceType::Pawn)),
+        }
+    }
+
+    #[test]
+    fn stores_probes_and_prefers_depth_for_the_same_key() {
+        let mut table = TranspositionTable::new(1);
+        assert!(table.entries.len().is_power_of_two());
+        assert!(table.probe(42).is_none());
+
+        table.store(entry(42, 4, 30));
+        table.store(entry(42, 2, -10));
+        assert_eq!(table.probe(42).map(|hit| hit.score), Some(30));
+
+        table.store(entry(42, 5, 15));
+        assert_eq!(table.probe(42).map(|hit| hit.depth), Some(5));
+
+        let colliding = 42 + table.entries.len() as u64;
+        table.store(entry(colliding, 1, 0));
+        assert!(table.probe(42).is_none());
+
+        table.clear();
+        assert!(table.probe(colliding).is_none());
+    }
+
+    #[test]
+    fn large_page_tables_fall_back_and_behave_the_same() {
+        let mut table = TranspositionTable::with_large_pages(8);
+        assert_eq!(table.size_mb(), 8);
+        assert_eq!(table.slots(), TranspositionTable::new(8).slots());
+        table.store(entry(42, 4, 30));
+        assert_eq!(table.probe(42).map(|hit| hit.score), Some(30));
+        assert!(!TranspositionTable::new(8).uses_large_pages());
+    }
+
+    #[test]
+    fn saves_and_reloads_with_validation() {
+        let mut table = TranspositionTable::new(1);
+        table.store(entry(42, 4, 30));
+        let mut promotion = entry(7, 1, -5);
+        promotion.bound = Bound::Lower;
+        promotion.best_move = Some(Move {
+            captured: Some(PieceType::Rook),
+            promotion: Some(PieceType::Queen),
+            ..Move::new(52, 61, PieceType::Pawn)
+        });
+        table.store(promotion.clone());
+        let mut file = Vec::new();
+        assert_eq!(table.save(&mut file).unwrap(), 2);
+
+        let mut restored = TranspositionTable::new(1);
+        assert_eq!(restored.load(&mut file.as_slice()), Ok(2));
+        assert_eq!(restored.probe(42).map(|hit| hit.score), Some(30));
+        let hit = restored.probe(7).unwrap();
+        assert_eq!(
+            (hit.bound, &hit.best_move),
+            (Bound::Lower, &promotion.best_move)
+        );
+
+        assert_eq!(
+            TranspositionTable::new(2).load(&mut file.as_slice()),
+            Err("Hash file size does not match the table")
+        );
+        assert_eq!(
+            restored.load(&mut &file[..file.len() - 1]),
+            Err("Corrupt hash file")
+        );
+        assert_eq!(
+            restored.load(&mut &b"nonsense"[..]),
+            Err("Corrupt hash file")
+        );
+        assert_eq!(restored.load(&mut &[b'X'; 64][..]), Err("Not a hash file"));
+        assert!(restored.probe(42).is_some());
+    }
+}
@@ -77,7 +77,7 @@ impl Piece {
         Self { piece_type, color }
     }
 
-    pub fn to_char(&self) -> char {
+    pub fn to_char(self) -> char {
         let ch = match self.piece_type {
             PieceType::King => 'K',
             PieceType::Queen => 'Q',
@@ -147,6 +147,30 @@ impl Move {
         self.is_en_passant = true;
         self
     }
+
+    // Passing the turn: nothing moves. Only analysis makes one, never move generation.
+    pub fn null() -> Self {
+        Self::new(0, 0, PieceType::King)
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.from == self.to
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_null() {
+            // UCI's spelling
+            return write!(f, "0000");
+        }
+        write!(f, "{}{}", square_to_algebraic(self.from), square_to_algebraic(self.to))?;
+        // Lowercase promotions, as UCI writes them: e7e8q
+        if let Some(promotion) = self.promotion {
+            write!(f, "{}", promotion.to_string().to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -177,12 +201,19 @@ impl CastlingRights {
     }
 }
 
+impl Default for CastlingRights {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct IrreversibleState {
     pub castling_rights: CastlingRights,
     pub en_passant_target: Option<Square>,
     pub halfmove_clock: u32,
     pub zobrist_hash: u64,
+    pub pawn_hash: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -195,6 +226,8 @@ pub struct GameState {
     pub fullmove_number: u32,
     pub move_history: Vec<Move>,
     pub zobrist_hash: u64,
+    // Pawns only (see `ZobristKeys::pawn_hash`), kept up to date alongside `zobrist_hash`
+    pub pawn_hash: u64,
     pub position_history: Vec<u64>,
     pub irreversible_history: Vec<IrreversibleState>,
 }
@@ -213,13 +246,13 @@ impl GameState {
         board[6] = Some(Piece::new(PieceType::Knight, Color::White));
         board[7] = Some(Piece::new(PieceType::Rook, Color::White));
         
-        for i in 8..16 {
-            board[i] = Some(Piece::new(PieceType::Pawn, Color::White));
+        for square in board.iter_mut().take(16).skip(8) {
+            *square = Some(Piece::new(PieceType::Pawn, Color::White));
         }
         
         // Black pieces
-        for i in 48..56 {
-            board[i] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        for square in board.iter_mut().take(56).skip(48) {
+            *square = Some(Piece::new(PieceType::Pawn, Color::Black));
         }
         
         board[56] = Some(Piece::new(PieceType::Rook, Color::Black));
@@ -240,12 +273,19 @@ impl GameState {
             fullmove_number: 1,
             move_history: Vec::new(),
             zobrist_hash: 0,
+            pawn_hash: 0,
             position_history: Vec::new(),
             irreversible_history: Vec::new(),
         }
     }
 }
 
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub const FILES: [char; 8] = ['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h'];
 pub const RANKS: [char; 8] = ['1', '2', '3', '4', '5', '6', '7', '8'];
 
@@ -0,0 +1,430 @@
+use crate::ai::{mate_in, SearchResult, AI};
+use crate::board::Board;
+use crate::eval::EvalConfig;
+use crate::fen::FenParser;
+use crate::input::{self, InputLine};
+use crate::move_generator::MoveGenerator;
+use crate::options::{Options, OPTIONS};
+use crate::output::Output;
+use crate::search_limits::SearchLimits;
+use crate::sharpness::Sharpness;
+use crate::time_manager::TimeControl;
+use crate::types::{Color, Move};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// The engine side of the UCI protocol, for GUIs such as Arena or CuteChess and for bots. A search
+// runs on its own thread so `stop` and `isready` are answered while it thinks; the AI moves to
+// that thread and comes back, hash table included, when the search ends. Commands are read on
+// their own thread and output goes through a writer thread, so neither a busy engine nor a slow
+// GUI holds the other up.
+pub struct UciSession {
+    board: Board,
+    options: Options,
+    output: Output,
+    ai: Option<AI>,
+    search: Option<JoinHandle<AI>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl UciSession {
+    pub fn new(options: Options) -> Self {
+        Self::with_output(options, Output::stdout())
+    }
+
+    pub fn with_output(options: Options, output: Output) -> Self {
+        let ai = configured_ai(&options);
+        Self::with_ai(options, output, ai)
+    }
+
+    // A session around an engine that outlives it, already configured for `options`; take both
+    // back with `into_parts`.
+    pub fn with_ai(options: Options, output: Output, mut ai: AI) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        ai.set_stop_signal(Arc::clone(&stop));
+        Self {
+            board: Board::new(),
+            options,
+            output,
+            ai: Some(ai),
+            search: None,
+            stop,
+        }
+    }
+
+    pub fn into_parts(mut self) -> (Options, AI) {
+        self.finish_search(true);
+        let ai = self.ai.take().expect("no search is running");
+        (self.options, ai)
+    }
+
+    pub fn run(&mut self) {
+        self.run_lines(input::next_stdin_line);
+    }
+
+    // Reads commands until `quit` or the end of input. At the end of input a running search is
+    // allowed to finish, so piped sessions still get their `bestmove`. Blank lines are skipped.
+    pub fn run_lines(&mut self, mut next_line: impl FnMut() -> InputLine) {
+        loop {
+            match next_line() {
+                InputLine::Line(line) => {
+                    if !self.handle(line.trim()) {
+                        break;
+                    }
+                }
+                InputLine::Rejected(error) => self.output.line(format!("info string {}", error)),
+                InputLine::Eof => break,
+            }
+        }
+        self.finish_search(false);
+        self.output.flush();
+    }
+
+    // Returns false on `quit`.
+    pub fn handle(&mut self, line: &str) -> bool {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = parts.split_first() else {
+            return true;
+        };
+        match command {
+            "uci" => identify(&self.output),
+            "isready" => self.output.line("readyok"),
+            "ucinewgame" => {
+                self.finish_search(true);
+                self.ai().new_game();
+                self.board = Board::new();
+            }
+            "setoption" => self.set_option(args),
+            "position" => {
+                self.finish_search(true);
+                if let Err(error) = self.set_position(args) {
+                    self.output.line(format!("info string {}", error));
+                }
+            }
+            "go" => self.go(args),
+            "stop" => self.finish_search(true),
+            "quit" => {
+                self.finish_search(true);
+                return false;
+            }
+            // The protocol says to ignore unknown commands; saying so helps when typing by hand.
+            _ => self
+                .output
+                .line(format!("info string Unknown command '{}'", command)),
+        }
+        true
+    }
+
+    // `setoption name <name> value <value>`
+    fn set_option(&mut self, args: &[&str]) {
+        self.finish_search(true);
+        let value_at = args.iter().position(|&arg| arg == "value");
+        let name = match args.first() {
+            Some(&"name") => args[1..value_at.unwrap_or(args.len())].join(" "),
+            _ => String::new(),
+        };
+        let value = value_at.map(|index| args[index + 1..].join(" "));
+        match self.options.set(&name, value.as_deref().unwrap_or("")) {
+            Ok(spec) => {
+                let policy = self.options.time_policy();
+                let piece_values = self.options.piece_values();
+                let multi_pv = self.options.multi_pv();
+                let (hash_mb, large_pages) = self.options.hash();
+                let ai = self.ai();
+                ai.set_hash(hash_mb, large_pages);
+                ai.set_time_policy(policy);
+                ai.set_multi_pv(multi_pv);
+                ai.set_eval_config(EvalConfig {
+                    piece_values,
+                    ..ai.eval_config()
+                });
+                if matches!(spec.name, "Hash" | "LargePages") {
+                    let table = ai.transposition_table();
+                    let line = format!(
+                        "info string hash {} MB large pages {}",
+                        table.size_mb(),
+                        if table.uses_large_pages() { "on" } else { "off" }
+                    );
+                    self.output.line(line);
+                }
+            }
+            Err(error) => self.output.line(format!("info string {}", error)),
+        }
+    }
+
+    // `position startpos|fen <fen> [moves <move>...]`; the board is only replaced when the whole
+    // command is valid.
+    fn set_position(&mut self, args: &[&str]) -> Result<(), String> {
+        let moves_at = args
+            .iter()
+            .position(|&arg| arg == "moves")
+            .unwrap_or(args.len());
+        let mut board = Board::new();
+        match args.first() {
+            Some(&"startpos") => {}
+            Some(&"fen") => FenParser::new().parse_fen(&mut board, &args[1..moves_at].join(" "))?,
+            _ => return Err("position needs startpos or fen".to_string()),
+        }
+
+        let generator = MoveGenerator::new();
+        for text in args.iter().skip(moves_at + 1) {
+            let color = board.get_turn();
+            let chess_move = generator
+                .get_legal_moves(&mut board, color)
+                .into_iter()
+                .find(|chess_move| uci_move(chess_move).eq_ignore_ascii_case(text))
+                .ok_or_else(|| format!("Illegal move '{}'", text))?;
+            board.make_move(&chess_move);
+        }
+        self.board = board;
+        Ok(())
+    }
+
+    // `go` takes the same limits as the REPL's `ai`; `infinite` (or no limit at all) searches
+    // until `stop`, and `ponder` is accepted but not acted on.
+    fn go(&mut self, args: &[&str]) {
+        self.finish_search(true);
+        let infinite = args.contains(&"infinite");
+        let args: Vec<&str> = args
+            .iter()
+            .copied()
+            .filter(|&arg| arg != "infinite" && arg != "ponder")
+            .collect();
+        let limits = if args.is_empty() {
+            SearchLimits::default()
+        } else {
+            match SearchLimits::parse(&args) {
+                Ok(limits) => limits,
+                Err(error) => {
+                    self.output.line(format!("info string {}", error));
+                    return;
+                }
+            }
+        };
+        // Without a bound only `stop` ends the search, so hold `bestmove` until then as well.
+        let wait_for_stop = infinite || !limits.is_bounded(self.board.get_turn());
+
+        self.stop.store(false, Ordering::Relaxed);
+        let stop = Arc::clone(&self.stop);
+        let output = self.output.clone();
+        let mut ai = self.ai.take().expect("no search is running");
+        let mut board = self.board.clone();
+        self.search = Some(thread::spawn(move || {
+            let side = board.get_turn();
+            if limits.time_control(side) != TimeControl::Infinite {
+                let sharpness = Sharpness::measure(&mut ai, &board);
+                ai.set_sharpness(sharpness.score);
+            }
+            let result = ai.search_with_progress(&mut board, &limits, &mut |result| {
+                for line in info_lines(result, side) {
+                    output.line(line);
+                }
+            });
+            while wait_for_stop && !stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(5));
+            }
+            output.line(bestmove_line(&result));
+            ai
+        }));
+    }
+
+    // Waits for a running search, asking it to stop first when `stop` is set, and takes the AI
+    // back.
+    fn finish_search(&mut self, stop: bool) {
+        if let Some(search) = self.search.take() {
+            if stop {
+                self.stop.store(true, Ordering::Relaxed);
+            }
+            self.ai = Some(search.join().expect("search thread panicked"));
+        }
+    }
+
+    fn ai(&mut self) -> &mut AI {
+        self.ai.as_mut().expect("no search is running")
+    }
+}
+
+pub fn configured_ai(options: &Options) -> AI {
+    let mut ai = AI::new();
+    let (hash_mb, large_pages) = options.hash();
+    ai.set_hash(hash_mb, large_pages);
+    ai.set_time_policy(options.time_policy());
+    ai.set_multi_pv(options.multi_pv());
+    ai.set_eval_config(EvalConfig {
+        piece_values: options.piece_values(),
+        ..EvalConfig::default()
+    });
+    ai
+}
+
+fn identify(output: &Output) {
+    output.line("id name Rust Chess Engine");
+    output.line("id author The Great Analysis Challenge");
+    for spec in OPTIONS.iter() {
+        output.line(format!(
+            "option name {} type spin default {} min {} max {}",
+            spec.name, spec.default, spec.min, spec.max
+        ));
+    }
+    output.line("uciok");
+}
+
+// Moves display the way UCI wants them, promotion letters included: e7e8q.
+fn uci_move(chess_move: &Move) -> String {
+    chess_move.to_string()
+}
+
+// Scores are from the side to move's point of view.
+// One line per MultiPV line, numbered with `multipv` when there is more than one.
+fn info_lines(result: &SearchResult, side: Color) -> Vec<String> {
+    let numbered = result.lines.len() > 1;
+    result
+        .lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let score = if side == Color::White {
+                line.score
+            } else {
+                -line.score
+            };
+            let score = match mate_in(score) {
+                Some(moves) => format!("mate {}", moves),
+                None => format!("cp {}", score),
+            };
+            let multipv = if numbered {
+                format!(" multipv {}", index + 1)
+            } else {
+                String::new()
+            };
+            let pv: Vec<String> = line.pv.iter().map(uci_move).collect();
+            format!(
+                "info depth {}{} score {} nodes {} nps {} time {} pv {}",
+                result.depth,
+                multipv,
+                score,
+                result.nodes,
+                result.nps,
+                result.time_ms,
+                pv.join(" ")
+            )
+        })
+        .collect()
+}
+
+fn bestmove_line(result: &SearchResult) -> String {
+    match (result.pv.first(), result.pv.get(1)) {
+        (Some(best), Some(reply)) => {
+            format!("bestmove {} ponder {}", uci_move(best), uci_move(reply))
+        }
+        (Some(best), None) => format!("bestmove {}", uci_move(best)),
+        // No legal move: the protocol's null move.
+        _ => "bestmove 0000".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{self, Write};
+    use std::sync::mpsc::{self, Sender};
+
+    struct Pipe(Sender<Vec<u8>>);
+
+    impl Write for Pipe {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            let _ = self.0.send(bytes.to_vec());
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn answers_while_searching_and_ends_with_bestmove() {
+        let (sender, receiver) = mpsc::channel();
+        let mut session = UciSession::with_output(Options::new(), Output::new(Pipe(sender)));
+        session.handle("go infinite");
+        session.handle("");
+        session.handle("isready");
+        session.handle("stop");
+        session.output.flush();
+
+        let text: Vec<u8> = receiver.try_iter().flatten().collect();
+        let text = String::from_utf8(text).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines.contains(&"readyok"), "{}", text);
+        assert!(lines.last().unwrap().starts_with("bestmove "), "{}", text);
+        assert_eq!(lines.iter().filter(|line| line.starts_with("bestmove")).count(), 1);
+    }
+
+    #[test]
+    fn position_commands_replace_the_board_only_when_valid() {
+        let mut session = UciSession::new(Options::new());
+        session.handle("position startpos moves e2e4 e7e5 g1f3");
+        let fen = FenParser::new().export_fen(&session.board);
+        assert!(fen.starts_with("rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b"));
+
+        assert!(session
+            .set_position(&["startpos", "moves", "e2e5"])
+            .is_err());
+        assert!(session.set_position(&["fen", "not", "a", "fen"]).is_err());
+        assert_eq!(FenParser::new().export_fen(&session.board), fen);
+
+        session.handle("position fen 8/4P1k1/8/8/8/8/8/K7 w - - 0 1 moves e7e8N");
+        let fen = FenParser::new().export_fen(&session.board);
+        assert!(fen.starts_with("4N3/6k1/8/8/8/8/8/K7 b"), "{}", fen);
+    }
+
+    #[test]
+    fn reports_side_relative_scores_mates_and_the_null_move() {
+        let mut ai = AI::new();
+        let mut board = Board::new();
+        // Black mates with Qh4 (fool's mate)
+        FenParser::new()
+            .parse_fen(
+                &mut board,
+                "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2",
+            )
+            .unwrap();
+        let result = ai.find_best_move(&mut board, 2);
+        assert!(info_lines(&result, Color::Black)[0].contains("score mate 1 "));
+        assert_eq!(bestmove_line(&result), "bestmove d8h4");
+
+        let mut board = Board::new();
+        let result = ai.find_best_move(&mut board, 3);
+        let line = &info_lines(&result, Color::White)[0];
+        assert!(line.starts_with("info depth 3 score cp "), "{}", line);
+        assert!(bestmove_line(&result).contains(" ponder "));
+
+        FenParser::new()
+            .parse_fen(&mut board, "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1")
+            .unwrap();
+        assert_eq!(
+            bestmove_line(&ai.find_best_move(&mut board, 2)),
+            "bestmove 0000"
+        );
+    }
+
+    #[test]
+    fn options_are_set_by_name_and_value() {
+        let mut session = UciSession::new(Options::new());
+        session.handle("setoption name MoveOverhead value 250");
+        assert_eq!(session.options.get("MoveOverhead"), Some(250));
+        session.handle("setoption name MoveOverhead value -1");
+        assert_eq!(session.options.get("MoveOverhead"), Some(250));
+        session.handle("setoption name KnightValue value 280");
+        assert_eq!(session.ai().eval_config().piece_values[1], 280);
+
+        session.handle("setoption name MultiPV value 3");
+        let mut board = Board::new();
+        let result = session.ai().find_best_move(&mut board, 2);
+        let lines = info_lines(&result, Color::White);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[2].starts_with("info depth 2 multipv 3 score cp "), "{}", lines[2]);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::ai::{mate_in, AI};
+use crate::board::Board;
+use crate::fen::FenParser;
+use crate::game_state::{self, move_json};
+use crate::move_generator::MoveGenerator;
+use crate::notation::SanParser;
+use crate::search_limits::{SearchLimits, MAX_SEARCH_DEPTH};
+use crate::types::Move;
+use wasm_bindgen::prelude::*;
+
+// The engine for browser GUIs, through wasm-bindgen (the `wasm` feature; see the README for the
+// build). A GUI keeps one `Engine` for the game; everything comes back as the JSON `state json`
+// prints, so the page never needs the engine's types. Errors reject with the message the REPL
+// would print.
+#[wasm_bindgen]
+pub struct Engine {
+    board: Board,
+    ai: AI,
+    move_generator: MoveGenerator,
+    san_parser: SanParser,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl Engine {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Engine {
+        Engine {
+            board: Board::new(),
+            ai: AI::new(),
+            move_generator: MoveGenerator::new(),
+            san_parser: SanParser::new(),
+        }
+    }
+
+    pub fn new_game(&mut self) {
+        self.board.reset();
+        self.ai.new_game();
+    }
+
+    pub fn load_fen(&mut self, fen: &str) -> Result<(), String> {
+        FenParser::new().parse_fen(&mut self.board, fen)
+    }
+
+    pub fn fen(&self) -> String {
+        FenParser::new().export_fen(&self.board)
+    }
+
+    // The whole game state, as `state json` prints it
+    pub fn state(&self) -> String {
+        game_state::to_json(&self.board)
+    }
+
+    // `[{"uci":"e2e4","san":"e4"},...]`
+    pub fn legal_moves(&mut self) -> String {
+        let moves: Vec<String> = self
+            .legal()
+            .iter()
+            .map(|chess_move| move_json(&self.san_parser, &mut self.board, chess_move))
+            .collect();
+        format!("[{}]", moves.join(","))
+    }
+
+    // A move in UCI coordinates (`e7e8q`) or SAN (`Nf3`); returns the state after it.
+    pub fn make_move(&mut self, text: &str) -> Result<String, String> {
+        let uci = text.to_ascii_lowercase();
+        let chess_move = match self.legal().into_iter().find(|chess_move| chess_move.to_string() == uci) {
+            Some(chess_move) => chess_move,
+            None => self.san_parser.parse_san(&mut self.board, text)?,
+        };
+        self.board.make_move(&chess_move);
+        Ok(self.state())
+    }
+
+    // The best move to `depth`, without playing it:
+    // `{"best_move":{"uci":..,"san":..}|null,"score":<cp>,"mate":<moves>|null,"depth":..,
+    // "nodes":..,"time_ms":..,"pv":["e2e4",...]}`, scores from White's point of view.
+    pub fn search(&mut self, depth: u8) -> Result<String, String> {
+        if !(1..=MAX_SEARCH_DEPTH).contains(&depth) {
+            return Err(format!("AI depth must be 1-{}", MAX_SEARCH_DEPTH));
+        }
+        let result = self.ai.search(&mut self.board, &SearchLimits::depth(depth));
+        let best_move = match &result.best_move {
+            Some(chess_move) => move_json(&self.san_parser, &mut self.board, chess_move),
+            None => "null".to_string(),
+        };
+        let pv: Vec<String> = result.pv.iter().map(|chess_move| format!("\"{}\"", chess_move)).collect();
+        Ok(format!(
+            "{{\"best_move\":{},\"score\":{},\"mate\":{},\"depth\":{},\"nodes\":{},\"time_ms\":{},\"pv\":[{}]}}",
+            best_move,
+            result.evaluation,
+            mate_in(result.evaluation).map_or("null".to_string(), |moves| moves.to_string()),
+            result.depth,
+            result.nodes,
+            result.time_ms,
+            pv.join(",")
+        ))
+    }
+}
+
+impl Engine {
+    fn legal(&mut self) -> Vec<Move> {
+        let turn = self.board.get_turn();
+        self.move_generator.get_legal_moves(&mut self.board, turn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_and_searches_through_json() {
+        let mut engine = Engine::new();
+        assert!(engine.legal_moves().starts_with("[{\"uci\":"));
+        assert_eq!(engine.legal_moves().matches("\"uci\"").count(), 20);
+
+        let state = engine.make_move("e2e4").unwrap();
+        assert!(state.contains("\"turn\":\"black\""), "{}", state);
+        engine.make_move("e5").unwrap();
+        engine.make_move("Nf3").unwrap();
+        assert!(engine.make_move("e2e4").is_err());
+        assert_eq!(engine.fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+
+        engine.load_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let search = engine.search(3).unwrap();
+        assert!(search.starts_with("{\"best_move\":{\"uci\":\"a1a8\",\"san\":\"Ra8#\"}"), "{}", search);
+        assert!(search.contains("\"mate\":1,"), "{}", search);
+        assert!(engine.search(0).is_err());
+        assert!(engine.load_fen("not a fen").is_err());
+
+        engine.new_game();
+        assert_eq!(engine.fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    }
+}
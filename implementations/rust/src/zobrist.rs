@@ -66,6 +66,23 @@ impl ZobristKeys {
         
         hash
     }
+
+    // Pawns only, so pawn-structure data can be cached across piece moves
+    pub fn pawn_hash(&self, board: &[Option<Piece>; 64]) -> u64 {
+        let mut hash = 0u64;
+        for (i, piece_opt) in board.iter().enumerate() {
+            if let Some(piece) = piece_opt.filter(|p| p.piece_type == PieceType::Pawn) {
+                hash ^= self.pieces[piece_to_index(piece)][i];
+            }
+        }
+        hash
+    }
+}
+
+impl Default for ZobristKeys {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 fn xorshift64(mut state: u64) -> u64 {